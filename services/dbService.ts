@@ -108,8 +108,19 @@ export const getTableStructure = async (
                 character_maximum_length,
                 is_nullable,
                 column_default,
-                is_identity
-            FROM information_schema.columns 
+                is_identity,
+                EXISTS (
+                    SELECT 1
+                    FROM information_schema.table_constraints tc
+                    JOIN information_schema.key_column_usage kcu
+                      ON kcu.constraint_name = tc.constraint_name
+                     AND kcu.table_schema = tc.table_schema
+                    WHERE tc.constraint_type = 'PRIMARY KEY'
+                      AND tc.table_schema = c.table_schema
+                      AND tc.table_name = c.table_name
+                      AND kcu.column_name = c.column_name
+                ) AS is_primary_key
+            FROM information_schema.columns c
             WHERE table_name = '${tableName}'
             ORDER BY ordinal_position
         `;
@@ -164,7 +175,7 @@ export const getTableStructure = async (
             const isNullable = row[3] === 'YES';
             const defaultValue = row[4];
             const extra = row[5]; // is_identity for PG, EXTRA for MySQL
-            const columnKey = row[6]; // COLUMN_KEY for MySQL
+            const columnKey = row[6]; // is_primary_key for PG, COLUMN_KEY for MySQL
             const columnComment = row[7]; // COLUMN_COMMENT for MySQL
 
             return {
@@ -173,7 +184,7 @@ export const getTableStructure = async (
                 name: name,
                 type: dataType,
                 length: maxLength ? String(maxLength) : '',
-                isPrimaryKey: isPostgres ? false : (columnKey === 'PRI'),
+                isPrimaryKey: isPostgres ? columnKey === true : (columnKey === 'PRI'),
                 isNotNull: !isNullable,
                 isAutoIncrement: isPostgres ? (extra === 'YES' || (defaultValue && defaultValue.includes('nextval'))) : (extra && extra.includes('auto_increment')),
                 defaultValue: defaultValue !== null ? String(defaultValue) : '',
@@ -0,0 +1,842 @@
+use sqlx::{Pool, Postgres, MySql, Sqlite, Row};
+use crate::db::dialect::{identifier_offsets, Dialect};
+use crate::db::types::{
+    ColumnDef, ConstraintInfo, IndexDef, ForeignKeyDef, RowStatus, TableDesign, TableChangeRequest, TableChangePlan,
+};
+
+/// Catalog facts the planner needs that the designer state does not carry
+#[derive(Debug, Default)]
+pub struct PlanContext {
+    /// Name of the existing primary key constraint (Postgres)
+    pub primary_key_name: Option<String>,
+    /// CREATE TRIGGER statements to restore after a SQLite table rebuild
+    pub triggers: Vec<String>,
+    /// UNIQUE and CHECK constraints a SQLite table rebuild has to carry over
+    pub constraints: Vec<ConstraintInfo>,
+    /// Generated columns of a SQLite table, which a rebuild cannot keep
+    pub generated_columns: Vec<String>,
+    /// Whether SQLite enforces foreign keys, to restore after a rebuild
+    pub foreign_keys: bool,
+}
+
+const REFERENTIAL_ACTIONS: [&str; 5] = ["RESTRICT", "CASCADE", "SET NULL", "NO ACTION", "SET DEFAULT"];
+
+/// Rows present on both sides (matched by id), plus the ones only on one side
+struct Diff<'a, T> {
+    removed: Vec<&'a T>,
+    added: Vec<&'a T>,
+    changed: Vec<(&'a T, &'a T)>,
+}
+
+impl<'a, T> Diff<'a, T> {
+    fn new(
+        original: &'a [T],
+        edited: &'a [T],
+        key: impl Fn(&T) -> (&str, RowStatus),
+        same: impl Fn(&T, &T) -> bool,
+    ) -> Self {
+        let original: Vec<&T> = original.iter().filter(|r| key(r).1 != RowStatus::Deleted).collect();
+        let edited: Vec<&T> = edited.iter().filter(|r| key(r).1 != RowStatus::Deleted).collect();
+        let find = |rows: &[&'a T], id: &str| rows.iter().copied().find(|r| key(r).0 == id);
+
+        let removed = original.iter().copied().filter(|o| find(&edited, key(o).0).is_none()).collect();
+        let added = edited.iter().copied().filter(|e| find(&original, key(e).0).is_none()).collect();
+        let changed = original
+            .iter()
+            .filter_map(|o| find(&edited, key(o).0).map(|e| (*o, e)))
+            .filter(|(o, e)| !same(o, e))
+            .collect();
+        Diff { removed, added, changed }
+    }
+
+    /// Rows that have to be dropped: removed ones and the old side of changed ones
+    fn to_drop(&self) -> impl Iterator<Item = &'a T> + '_ {
+        self.removed.iter().copied().chain(self.changed.iter().map(|(o, _)| *o))
+    }
+
+    /// Rows that have to be created: added ones and the new side of changed ones
+    fn to_create(&self) -> impl Iterator<Item = &'a T> + '_ {
+        self.added.iter().copied().chain(self.changed.iter().map(|(_, e)| *e))
+    }
+}
+
+fn live<T>(rows: &[T], status: impl Fn(&T) -> RowStatus) -> Vec<&T> {
+    rows.iter().filter(|r| status(r) != RowStatus::Deleted).collect()
+}
+
+fn column_diff<'a>(original: &'a TableDesign, design: &'a TableDesign) -> Diff<'a, ColumnDef> {
+    Diff::new(&original.columns, &design.columns, |c| (c.id.as_str(), c.status), |a, b| {
+        a.name == b.name
+            && column_type(a) == column_type(b)
+            && a.is_not_null == b.is_not_null
+            && a.is_auto_increment == b.is_auto_increment
+            && a.default_value.trim() == b.default_value.trim()
+            && a.comment == b.comment
+    })
+}
+
+fn index_diff<'a>(original: &'a TableDesign, design: &'a TableDesign) -> Diff<'a, IndexDef> {
+    Diff::new(&original.indexes, &design.indexes, |i| (i.id.as_str(), i.status), |a, b| {
+        a.name == b.name && a.index_type.eq_ignore_ascii_case(&b.index_type) && a.columns == b.columns
+    })
+}
+
+fn foreign_key_diff<'a>(original: &'a TableDesign, design: &'a TableDesign) -> Diff<'a, ForeignKeyDef> {
+    Diff::new(&original.foreign_keys, &design.foreign_keys, |f| (f.id.as_str(), f.status), |a, b| {
+        a.name == b.name
+            && a.source_column == b.source_column
+            && a.ref_table == b.ref_table
+            && a.ref_column == b.ref_column
+            && a.on_delete.eq_ignore_ascii_case(&b.on_delete)
+            && a.on_update.eq_ignore_ascii_case(&b.on_update)
+    })
+}
+
+/// Primary key column names of a design, in column order
+fn primary_key(design: &TableDesign) -> Vec<&str> {
+    live(&design.columns, |c| c.status)
+        .into_iter()
+        .filter(|c| c.is_primary_key)
+        .map(|c| c.name.as_str())
+        .collect()
+}
+
+/// Whether the key is made of other columns; compared by column identity, so
+/// renaming a key column leaves the key itself alone
+fn primary_key_changed(original: &TableDesign, design: &TableDesign) -> bool {
+    fn ids(design: &TableDesign) -> Vec<&str> {
+        live(&design.columns, |c| c.status).into_iter().filter(|c| c.is_primary_key).map(|c| c.id.as_str()).collect()
+    }
+    ids(original) != ids(design)
+}
+
+/// Column type with the designer's length/precision appended when not already spelled out
+fn column_type(column: &ColumnDef) -> String {
+    let base = column.data_type.trim();
+    let length = column.length.trim();
+    if length.is_empty() || base.contains('(') {
+        base.to_string()
+    } else {
+        format!("{}({})", base, length)
+    }
+}
+
+/// SQLite only accepts literals unparenthesized in DEFAULT clauses
+fn sqlite_default(expr: &str) -> String {
+    let upper = expr.to_ascii_uppercase();
+    let is_literal = expr.starts_with('\'')
+        || expr.starts_with('(')
+        || expr.parse::<f64>().is_ok()
+        || matches!(
+            upper.as_str(),
+            "NULL" | "TRUE" | "FALSE" | "CURRENT_TIME" | "CURRENT_DATE" | "CURRENT_TIMESTAMP"
+        );
+    if is_literal {
+        expr.to_string()
+    } else {
+        format!("({})", expr)
+    }
+}
+
+/// Column definition as used in CREATE TABLE / ADD COLUMN.
+///
+/// `inline_primary_key` is only used by SQLite, where AUTOINCREMENT requires
+/// the primary key to be declared on the column itself.
+fn column_definition(dialect: Dialect, column: &ColumnDef, inline_primary_key: bool) -> String {
+    let mut def = format!("{} {}", dialect.quote_ident(&column.name), column_type(column));
+    let default = column.default_value.trim();
+
+    match dialect {
+        Dialect::Postgres => {
+            if column.is_auto_increment {
+                def.push_str(" GENERATED BY DEFAULT AS IDENTITY");
+            }
+            if column.is_not_null {
+                def.push_str(" NOT NULL");
+            }
+            if !default.is_empty() && !column.is_auto_increment {
+                def.push_str(&format!(" DEFAULT {}", default));
+            }
+        }
+        Dialect::MySql => {
+            def.push_str(if column.is_not_null || column.is_primary_key { " NOT NULL" } else { " NULL" });
+            if !default.is_empty() && !column.is_auto_increment {
+                def.push_str(&format!(" DEFAULT {}", default));
+            }
+            if column.is_auto_increment {
+                def.push_str(" AUTO_INCREMENT");
+            }
+            if !column.comment.is_empty() {
+                def.push_str(&format!(" COMMENT {}", dialect.quote_literal(&column.comment)));
+            }
+        }
+        Dialect::Sqlite => {
+            if inline_primary_key {
+                def.push_str(" PRIMARY KEY");
+                if column.is_auto_increment {
+                    def.push_str(" AUTOINCREMENT");
+                }
+            }
+            if column.is_not_null {
+                def.push_str(" NOT NULL");
+            }
+            if !default.is_empty() {
+                def.push_str(&format!(" DEFAULT {}", sqlite_default(default)));
+            }
+        }
+    }
+    def
+}
+
+fn column_list(dialect: Dialect, columns: &[&str]) -> String {
+    columns.iter().map(|c| dialect.quote_ident(c)).collect::<Vec<_>>().join(", ")
+}
+
+fn index_name(table: &str, index: &IndexDef) -> String {
+    if index.name.trim().is_empty() {
+        format!("idx_{}_{}", table, index.columns.join("_"))
+    } else {
+        index.name.trim().to_string()
+    }
+}
+
+fn foreign_key_name(table: &str, fk: &ForeignKeyDef) -> String {
+    if fk.name.trim().is_empty() {
+        format!("fk_{}_{}", table, fk.source_column)
+    } else {
+        fk.name.trim().to_string()
+    }
+}
+
+fn referential_action(action: &str) -> Result<Option<String>, String> {
+    let action = action.trim().to_ascii_uppercase();
+    if action.is_empty() {
+        return Ok(None);
+    }
+    if REFERENTIAL_ACTIONS.contains(&action.as_str()) {
+        Ok(Some(action))
+    } else {
+        Err(format!("Unsupported referential action: {}", action))
+    }
+}
+
+/// `CONSTRAINT ... FOREIGN KEY ... REFERENCES ...` clause
+fn foreign_key_clause(dialect: Dialect, table: &str, fk: &ForeignKeyDef) -> Result<String, String> {
+    // Referenced tables may be given as `schema.table`
+    let referenced = match fk.ref_table.split_once('.') {
+        Some((schema, name)) => dialect.qualified(Some(schema), name),
+        None => dialect.quote_ident(&fk.ref_table),
+    };
+    let mut clause = format!(
+        "CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+        dialect.quote_ident(&foreign_key_name(table, fk)),
+        dialect.quote_ident(&fk.source_column),
+        referenced,
+        dialect.quote_ident(&fk.ref_column),
+    );
+    if let Some(action) = referential_action(&fk.on_delete)? {
+        clause.push_str(&format!(" ON DELETE {}", action));
+    }
+    if let Some(action) = referential_action(&fk.on_update)? {
+        clause.push_str(&format!(" ON UPDATE {}", action));
+    }
+    Ok(clause)
+}
+
+/// CREATE INDEX statement, or None (with a warning) when the engine has no equivalent
+fn create_index(
+    dialect: Dialect,
+    schema: Option<&str>,
+    table: &str,
+    index: &IndexDef,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let name = index_name(table, index);
+    let kind = match index.index_type.to_ascii_uppercase().as_str() {
+        "UNIQUE" => "UNIQUE ",
+        "FULLTEXT" if dialect == Dialect::MySql => "FULLTEXT ",
+        "FULLTEXT" => {
+            warnings.push(format!("Skipped FULLTEXT index {}: not supported by this database", name));
+            return None;
+        }
+        _ => "",
+    };
+    let columns: Vec<&str> = index.columns.iter().map(String::as_str).collect();
+    let statement = match dialect {
+        // SQLite qualifies the index name rather than the table
+        Dialect::Sqlite => format!(
+            "CREATE {}INDEX {} ON {} ({});",
+            kind,
+            dialect.qualified(schema, &name),
+            dialect.quote_ident(table),
+            column_list(dialect, &columns),
+        ),
+        _ => format!(
+            "CREATE {}INDEX {} ON {} ({});",
+            kind,
+            dialect.quote_ident(&name),
+            dialect.qualified(schema, table),
+            column_list(dialect, &columns),
+        ),
+    };
+    Some(statement)
+}
+
+fn drop_index(dialect: Dialect, schema: Option<&str>, table: &str, index: &IndexDef) -> String {
+    let name = index_name(table, index);
+    match dialect {
+        Dialect::MySql => format!("DROP INDEX {} ON {};", dialect.quote_ident(&name), dialect.qualified(schema, table)),
+        Dialect::Postgres | Dialect::Sqlite => format!("DROP INDEX {};", dialect.qualified(schema, &name)),
+    }
+}
+
+/// CREATE TABLE statement for a full design (SQLite includes foreign keys inline)
+fn create_table_statement(
+    dialect: Dialect,
+    qualified: &str,
+    table: &str,
+    design: &TableDesign,
+    constraints: &[String],
+) -> Result<String, String> {
+    let columns = live(&design.columns, |c| c.status);
+    if columns.is_empty() {
+        return Err("A table needs at least one column".to_string());
+    }
+    let pk = primary_key(design);
+    let inline_pk = dialect == Dialect::Sqlite
+        && pk.len() == 1
+        && columns.iter().any(|c| c.is_primary_key && c.is_auto_increment);
+
+    let mut parts: Vec<String> = columns
+        .iter()
+        .map(|c| column_definition(dialect, c, inline_pk && c.is_primary_key))
+        .collect();
+    if !pk.is_empty() && !inline_pk {
+        parts.push(format!("PRIMARY KEY ({})", column_list(dialect, &pk)));
+    }
+    if dialect == Dialect::Sqlite {
+        for fk in live(&design.foreign_keys, |f| f.status) {
+            parts.push(foreign_key_clause(dialect, table, fk)?);
+        }
+    }
+    parts.extend(constraints.iter().cloned());
+    Ok(format!("CREATE TABLE {} (\n    {}\n);", qualified, parts.join(",\n    ")))
+}
+
+/// Plan the statements turning `request.original` into `request.design`
+pub fn plan(dialect: Dialect, request: &TableChangeRequest, context: &PlanContext) -> Result<TableChangePlan, String> {
+    if request.table.trim().is_empty() {
+        return Err("Table name is required".to_string());
+    }
+    match &request.original {
+        None => plan_create(dialect, request),
+        Some(original) => match dialect {
+            Dialect::Postgres => plan_postgres(request, original, context),
+            Dialect::MySql => plan_mysql(request, original),
+            Dialect::Sqlite => plan_sqlite(request, original, context),
+        },
+    }
+}
+
+fn plan_create(dialect: Dialect, request: &TableChangeRequest) -> Result<TableChangePlan, String> {
+    let schema = request.schema.as_deref();
+    let table = request.table.as_str();
+    let qualified = dialect.qualified(schema, table);
+    let mut plan = TableChangePlan::default();
+
+    plan.statements.push(create_table_statement(dialect, &qualified, table, &request.design, &[])?);
+    for index in live(&request.design.indexes, |i| i.status) {
+        if let Some(statement) = create_index(dialect, schema, table, index, &mut plan.warnings) {
+            plan.statements.push(statement);
+        }
+    }
+    if dialect != Dialect::Sqlite {
+        for fk in live(&request.design.foreign_keys, |f| f.status) {
+            plan.statements.push(format!("ALTER TABLE {} ADD {};", qualified, foreign_key_clause(dialect, table, fk)?));
+        }
+    }
+    if dialect == Dialect::Postgres {
+        for column in live(&request.design.columns, |c| c.status) {
+            if !column.comment.is_empty() {
+                plan.statements.push(format!(
+                    "COMMENT ON COLUMN {}.{} IS {};",
+                    qualified,
+                    dialect.quote_ident(&column.name),
+                    dialect.quote_literal(&column.comment),
+                ));
+            }
+        }
+    }
+    Ok(plan)
+}
+
+fn warn_not_null_without_default(column: &ColumnDef, warnings: &mut Vec<String>) {
+    if column.is_not_null && column.default_value.trim().is_empty() && !column.is_auto_increment {
+        warnings.push(format!(
+            "Column {} is NOT NULL without a default; adding it fails if the table has rows",
+            column.name
+        ));
+    }
+}
+
+fn plan_postgres(
+    request: &TableChangeRequest,
+    original: &TableDesign,
+    context: &PlanContext,
+) -> Result<TableChangePlan, String> {
+    let dialect = Dialect::Postgres;
+    let schema = request.schema.as_deref();
+    let table = request.table.as_str();
+    let qualified = dialect.qualified(schema, table);
+    let design = &request.design;
+    let mut plan = TableChangePlan::default();
+
+    let columns = column_diff(original, design);
+    let indexes = index_diff(original, design);
+    let foreign_keys = foreign_key_diff(original, design);
+    let old_pk = primary_key(original);
+    let new_pk = primary_key(design);
+    let pk_changed = primary_key_changed(original, design);
+
+    for fk in foreign_keys.to_drop() {
+        plan.statements.push(format!(
+            "ALTER TABLE {} DROP CONSTRAINT {};",
+            qualified,
+            dialect.quote_ident(&foreign_key_name(table, fk)),
+        ));
+    }
+    for index in indexes.to_drop() {
+        plan.statements.push(drop_index(dialect, schema, table, index));
+    }
+    if pk_changed && !old_pk.is_empty() {
+        let name = context.primary_key_name.clone().unwrap_or_else(|| format!("{}_pkey", table));
+        plan.statements.push(format!("ALTER TABLE {} DROP CONSTRAINT {};", qualified, dialect.quote_ident(&name)));
+    }
+    for column in &columns.removed {
+        plan.statements.push(format!("ALTER TABLE {} DROP COLUMN {};", qualified, dialect.quote_ident(&column.name)));
+        plan.warnings.push(format!("Column {} and its data will be dropped", column.name));
+    }
+
+    for (old, new) in &columns.changed {
+        let column = dialect.quote_ident(&new.name);
+        let alter = |action: String| format!("ALTER TABLE {} ALTER COLUMN {} {};", qualified, column, action);
+
+        if old.name != new.name {
+            plan.statements.push(format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                qualified,
+                dialect.quote_ident(&old.name),
+                column,
+            ));
+        }
+        let new_type = column_type(new);
+        if column_type(old) != new_type {
+            plan.statements.push(alter(format!("TYPE {} USING {}::{}", new_type, column, new_type)));
+            plan.warnings.push(format!("Column {} changes type from {} to {}", new.name, column_type(old), new_type));
+        }
+        if old.is_auto_increment != new.is_auto_increment {
+            plan.statements.push(alter(if new.is_auto_increment {
+                "ADD GENERATED BY DEFAULT AS IDENTITY".to_string()
+            } else {
+                "DROP IDENTITY IF EXISTS".to_string()
+            }));
+        }
+        if old.is_not_null != new.is_not_null {
+            plan.statements.push(alter(if new.is_not_null { "SET NOT NULL" } else { "DROP NOT NULL" }.to_string()));
+        }
+        let default = new.default_value.trim();
+        if old.default_value.trim() != default {
+            plan.statements.push(alter(if default.is_empty() {
+                "DROP DEFAULT".to_string()
+            } else {
+                format!("SET DEFAULT {}", default)
+            }));
+        }
+        if old.comment != new.comment {
+            let comment = if new.comment.is_empty() { "NULL".to_string() } else { dialect.quote_literal(&new.comment) };
+            plan.statements.push(format!("COMMENT ON COLUMN {}.{} IS {};", qualified, column, comment));
+        }
+    }
+
+    for column in &columns.added {
+        plan.statements.push(format!("ALTER TABLE {} ADD COLUMN {};", qualified, column_definition(dialect, column, false)));
+        if !column.comment.is_empty() {
+            plan.statements.push(format!(
+                "COMMENT ON COLUMN {}.{} IS {};",
+                qualified,
+                dialect.quote_ident(&column.name),
+                dialect.quote_literal(&column.comment),
+            ));
+        }
+        warn_not_null_without_default(column, &mut plan.warnings);
+    }
+
+    if pk_changed && !new_pk.is_empty() {
+        plan.statements.push(format!("ALTER TABLE {} ADD PRIMARY KEY ({});", qualified, column_list(dialect, &new_pk)));
+    }
+    for index in indexes.to_create() {
+        if let Some(statement) = create_index(dialect, schema, table, index, &mut plan.warnings) {
+            plan.statements.push(statement);
+        }
+    }
+    for fk in foreign_keys.to_create() {
+        plan.statements.push(format!("ALTER TABLE {} ADD {};", qualified, foreign_key_clause(dialect, table, fk)?));
+    }
+    Ok(plan)
+}
+
+fn plan_mysql(request: &TableChangeRequest, original: &TableDesign) -> Result<TableChangePlan, String> {
+    let dialect = Dialect::MySql;
+    let schema = request.schema.as_deref();
+    let table = request.table.as_str();
+    let qualified = dialect.qualified(schema, table);
+    let design = &request.design;
+    let mut plan = TableChangePlan::default();
+
+    let columns = column_diff(original, design);
+    let indexes = index_diff(original, design);
+    let foreign_keys = foreign_key_diff(original, design);
+    let old_pk = primary_key(original);
+    let new_pk = primary_key(design);
+    let pk_changed = primary_key_changed(original, design);
+
+    // Foreign keys go first and last so the indexes backing them can be changed in between
+    let dropped_fks: Vec<String> = foreign_keys
+        .to_drop()
+        .map(|fk| format!("DROP FOREIGN KEY {}", dialect.quote_ident(&foreign_key_name(table, fk))))
+        .collect();
+    if !dropped_fks.is_empty() {
+        plan.statements.push(format!("ALTER TABLE {}\n    {};", qualified, dropped_fks.join(",\n    ")));
+    }
+
+    let mut clauses = Vec::new();
+    for index in indexes.to_drop() {
+        clauses.push(format!("DROP INDEX {}", dialect.quote_ident(&index_name(table, index))));
+    }
+    if pk_changed && !old_pk.is_empty() {
+        clauses.push("DROP PRIMARY KEY".to_string());
+    }
+    for column in &columns.removed {
+        clauses.push(format!("DROP COLUMN {}", dialect.quote_ident(&column.name)));
+        plan.warnings.push(format!("Column {} and its data will be dropped", column.name));
+    }
+    for (old, new) in &columns.changed {
+        clauses.push(format!(
+            "CHANGE COLUMN {} {}",
+            dialect.quote_ident(&old.name),
+            column_definition(dialect, new, false),
+        ));
+        if column_type(old) != column_type(new) {
+            plan.warnings.push(format!("Column {} changes type from {} to {}", new.name, column_type(old), column_type(new)));
+        }
+    }
+    for column in &columns.added {
+        clauses.push(format!("ADD COLUMN {}", column_definition(dialect, column, false)));
+        warn_not_null_without_default(column, &mut plan.warnings);
+    }
+    if pk_changed && !new_pk.is_empty() {
+        clauses.push(format!("ADD PRIMARY KEY ({})", column_list(dialect, &new_pk)));
+    }
+    for index in indexes.to_create() {
+        let kind = match index.index_type.to_ascii_uppercase().as_str() {
+            "UNIQUE" => "UNIQUE ",
+            "FULLTEXT" => "FULLTEXT ",
+            _ => "",
+        };
+        let index_columns: Vec<&str> = index.columns.iter().map(String::as_str).collect();
+        clauses.push(format!(
+            "ADD {}INDEX {} ({})",
+            kind,
+            dialect.quote_ident(&index_name(table, index)),
+            column_list(dialect, &index_columns),
+        ));
+    }
+    if !clauses.is_empty() {
+        plan.statements.push(format!("ALTER TABLE {}\n    {};", qualified, clauses.join(",\n    ")));
+    }
+
+    let added_fks = foreign_keys
+        .to_create()
+        .map(|fk| foreign_key_clause(dialect, table, fk).map(|clause| format!("ADD {}", clause)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !added_fks.is_empty() {
+        plan.statements.push(format!("ALTER TABLE {}\n    {};", qualified, added_fks.join(",\n    ")));
+    }
+
+    if plan.statements.len() > 1 {
+        plan.warnings.push(
+            "MySQL commits each ALTER TABLE implicitly; a failure part-way leaves earlier statements applied".to_string(),
+        );
+    }
+    Ok(plan)
+}
+
+/// UNIQUE and CHECK constraints of a SQLite table, restated for its rebuilt
+/// copy under the new column names. A constraint of a dropped column goes with
+/// it; a CHECK expression naming a dropped or renamed column cannot be
+/// rewritten safely, so the rebuild is refused.
+fn carried_constraints(
+    table: &str,
+    original: &TableDesign,
+    design: &TableDesign,
+    context: &PlanContext,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<String>, String> {
+    let dialect = Dialect::Sqlite;
+    let original_columns = live(&original.columns, |c| c.status);
+    let design_columns = live(&design.columns, |c| c.status);
+    // Name in the design of an original column; None when it is dropped
+    let new_name = |name: &str| match original_columns.iter().find(|c| c.name.eq_ignore_ascii_case(name)) {
+        Some(old) => design_columns.iter().find(|c| c.id == old.id).map(|c| c.name.clone()),
+        None => Some(name.to_string()),
+    };
+
+    let mut carried = Vec::new();
+    for constraint in &context.constraints {
+        match constraint.constraint_type.as_str() {
+            "UNIQUE" => {
+                // The designer lists the backing index; deleting it drops the constraint
+                let index = original.indexes.iter().find(|i| i.name == constraint.name);
+                if index.is_some_and(|index| !live(&design.indexes, |i| i.status).iter().any(|i| i.id == index.id)) {
+                    continue;
+                }
+                let columns: Option<Vec<String>> = constraint.columns.iter().map(|c| new_name(c)).collect();
+                match columns {
+                    Some(columns) => {
+                        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+                        carried.push(format!("UNIQUE ({})", column_list(dialect, &columns)));
+                    }
+                    None => warnings.push(format!(
+                        "UNIQUE constraint on ({}) will be dropped with its column",
+                        constraint.columns.join(", ")
+                    )),
+                }
+            }
+            "CHECK" => {
+                let Some(body) = &constraint.definition else { continue };
+                if constraint.columns.first().is_some_and(|c| new_name(c).is_none()) {
+                    warnings.push(format!("CHECK ({}) will be dropped with column {}", body, constraint.columns[0]));
+                    continue;
+                }
+                for column in &original_columns {
+                    let renamed = new_name(&column.name);
+                    if renamed.as_deref() != Some(column.name.as_str()) && !identifier_offsets(body, &column.name).is_empty() {
+                        return Err(format!(
+                            "SQLite has to rebuild {} for these changes, but CHECK ({}) refers to column {}, which is {}; change the table with SQL instead",
+                            table,
+                            body,
+                            column.name,
+                            if renamed.is_some() { "renamed" } else { "dropped" },
+                        ));
+                    }
+                }
+                // Unnamed constraints are listed under a made-up name
+                let unnamed = constraint
+                    .name
+                    .strip_prefix(&format!("{}_check_", table))
+                    .is_some_and(|n| n.parse::<usize>().is_ok());
+                carried.push(if unnamed {
+                    format!("CHECK ({})", body)
+                } else {
+                    format!("CONSTRAINT {} CHECK ({})", dialect.quote_ident(&constraint.name), body)
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(carried)
+}
+
+fn plan_sqlite(
+    request: &TableChangeRequest,
+    original: &TableDesign,
+    context: &PlanContext,
+) -> Result<TableChangePlan, String> {
+    let dialect = Dialect::Sqlite;
+    let schema = request.schema.as_deref();
+    let table = request.table.as_str();
+    let qualified = dialect.qualified(schema, table);
+    let design = &request.design;
+    let mut plan = TableChangePlan::default();
+
+    let columns = column_diff(original, design);
+    let indexes = index_diff(original, design);
+    let foreign_keys = foreign_key_diff(original, design);
+
+    // ALTER TABLE in SQLite can only rename columns and append simple ones
+    let rename_only = |old: &ColumnDef, new: &ColumnDef| {
+        column_type(old) == column_type(new)
+            && old.is_not_null == new.is_not_null
+            && old.is_auto_increment == new.is_auto_increment
+            && old.default_value.trim() == new.default_value.trim()
+    };
+    let simple_add = |column: &ColumnDef| {
+        let default = column.default_value.trim();
+        // Added columns may only default to a constant
+        let constant_default = default.is_empty()
+            || (sqlite_default(default) == default
+                && !default.starts_with('(')
+                && !default.to_ascii_uppercase().starts_with("CURRENT_"));
+        let needs_value = column.is_not_null && default.is_empty();
+        !(column.is_primary_key || column.is_auto_increment || needs_value) && constant_default
+    };
+    let needs_rebuild = !columns.removed.is_empty()
+        || columns.changed.iter().any(|(old, new)| !rename_only(old, new))
+        || columns.added.iter().any(|c| !simple_add(c))
+        || primary_key_changed(original, design)
+        || !foreign_keys.removed.is_empty()
+        || !foreign_keys.added.is_empty()
+        || !foreign_keys.changed.is_empty();
+
+    if !needs_rebuild {
+        for index in indexes.to_drop() {
+            plan.statements.push(drop_index(dialect, schema, table, index));
+        }
+        for (old, new) in columns.changed.iter().filter(|(old, new)| old.name != new.name) {
+            plan.statements.push(format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                qualified,
+                dialect.quote_ident(&old.name),
+                dialect.quote_ident(&new.name),
+            ));
+        }
+        for column in &columns.added {
+            plan.statements.push(format!("ALTER TABLE {} ADD COLUMN {};", qualified, column_definition(dialect, column, false)));
+        }
+        for index in indexes.to_create() {
+            if let Some(statement) = create_index(dialect, schema, table, index, &mut plan.warnings) {
+                plan.statements.push(statement);
+            }
+        }
+        return Ok(plan);
+    }
+
+    // Table rebuild: https://www.sqlite.org/lang_altertable.html#otheralter
+    if let Some(column) = context.generated_columns.first() {
+        return Err(format!(
+            "SQLite has to rebuild {} for these changes, which would turn generated column {} into a plain one; change the table with SQL instead",
+            table, column
+        ));
+    }
+    let constraints = carried_constraints(table, original, design, context, &mut plan.warnings)?;
+    let temp_name = format!("_nebula_new_{}", table);
+    let temp = dialect.qualified(schema, &temp_name);
+    plan.pre_statements.push("PRAGMA foreign_keys = OFF;".to_string());
+    plan.pre_statements.push("PRAGMA legacy_alter_table = ON;".to_string());
+
+    plan.statements.push(create_table_statement(dialect, &temp, table, design, &constraints)?);
+
+    // Copy the columns that survive, reading them under their original names
+    let original_columns = live(&original.columns, |c| c.status);
+    let (targets, sources): (Vec<&str>, Vec<&str>) = live(&design.columns, |c| c.status)
+        .into_iter()
+        .filter_map(|new| {
+            original_columns
+                .iter()
+                .find(|old| old.id == new.id)
+                .map(|old| (new.name.as_str(), old.name.as_str()))
+        })
+        .unzip();
+    if !targets.is_empty() {
+        plan.statements.push(format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {};",
+            temp,
+            column_list(dialect, &targets),
+            column_list(dialect, &sources),
+            qualified,
+        ));
+    }
+    plan.statements.push(format!("DROP TABLE {};", qualified));
+    plan.statements.push(format!("ALTER TABLE {} RENAME TO {};", temp, dialect.quote_ident(table)));
+    // Indexes backing PRIMARY KEY and UNIQUE constraints come back with the constraints
+    for index in live(&design.indexes, |i| i.status).into_iter().filter(|i| !i.name.starts_with("sqlite_autoindex_")) {
+        if let Some(statement) = create_index(dialect, schema, table, index, &mut plan.warnings) {
+            plan.statements.push(statement);
+        }
+    }
+    for trigger in &context.triggers {
+        let trigger = trigger.trim().trim_end_matches(';');
+        plan.statements.push(format!("{};", trigger));
+    }
+
+    plan.post_statements.push("PRAGMA legacy_alter_table = OFF;".to_string());
+    plan.post_statements.push(format!("PRAGMA foreign_keys = {};", if context.foreign_keys { "ON" } else { "OFF" }));
+
+    plan.warnings.push(format!(
+        "SQLite cannot alter {} in place; the table will be rebuilt (copied, dropped and renamed)",
+        table
+    ));
+    for column in &columns.removed {
+        plan.warnings.push(format!("Column {} and its data will be dropped", column.name));
+    }
+    if !context.triggers.is_empty() {
+        plan.warnings.push(format!("{} trigger(s) will be recreated", context.triggers.len()));
+    }
+    Ok(plan)
+}
+
+// ============================================================================
+// Applying a plan
+// ============================================================================
+
+pub async fn apply_postgres(pool: &Pool<Postgres>, plan: &TableChangePlan) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for statement in &plan.statements {
+        sqlx::raw_sql(statement).execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// MySQL DDL is not transactional, so statements simply run in order
+pub async fn apply_mysql(pool: &Pool<MySql>, plan: &TableChangePlan) -> Result<(), String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    for statement in &plan.statements {
+        sqlx::raw_sql(statement).execute(&mut *conn).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Run the plan on a single connection, since the rebuild pragmas are per connection
+pub async fn apply_sqlite(pool: &Pool<Sqlite>, plan: &TableChangePlan) -> Result<(), String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    for statement in &plan.pre_statements {
+        sqlx::raw_sql(statement).execute(&mut *conn).await.map_err(|e| e.to_string())?;
+    }
+
+    let result = async {
+        sqlx::raw_sql("BEGIN").execute(&mut *conn).await.map_err(|e| e.to_string())?;
+        let body = async {
+            for statement in &plan.statements {
+                sqlx::raw_sql(statement).execute(&mut *conn).await.map_err(|e| e.to_string())?;
+            }
+            if !plan.pre_statements.is_empty() {
+                // Foreign keys were off during the rebuild; make sure nothing was left dangling
+                let violations = sqlx::query("PRAGMA foreign_key_check")
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Some(row) = violations.first() {
+                    let table: String = row.try_get(0).unwrap_or_default();
+                    return Err(format!(
+                        "Rebuild would leave {} foreign key violation(s) (first in table {})",
+                        violations.len(),
+                        table
+                    ));
+                }
+            }
+            sqlx::raw_sql("COMMIT").execute(&mut *conn).await.map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        .await;
+        if body.is_err() {
+            let _ = sqlx::raw_sql("ROLLBACK").execute(&mut *conn).await;
+        }
+        body
+    }
+    .await;
+
+    for statement in &plan.post_statements {
+        sqlx::raw_sql(statement).execute(&mut *conn).await.map_err(|e| e.to_string())?;
+    }
+    result
+}
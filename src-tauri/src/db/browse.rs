@@ -0,0 +1,464 @@
+// Table browsing
+//
+// `browse_table` builds the table view's query from a filter tree, sort keys
+// and a page request, binding every value as a parameter. Sorting always ends
+// with the table's key so that pages do not overlap, and NULLs sort after
+// every value on all engines, which keyset paging relies on: the next page
+// starts after the ordering values of the previous page's last row.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use serde_json::{Map, Value};
+use sqlx::Row;
+
+use crate::db::dialect::Dialect;
+use crate::db::edit::{bind, pg_cast_type, value_cell, Params};
+use crate::db::introspection;
+use crate::db::stream::Cell;
+use crate::db::types::{
+    BrowseRequest, BrowseResult, ColumnInfo, CountMode, FilterJoin, FilterNode, FilterOperator, Paging, SortKey,
+    SqlPool,
+};
+use crate::db::{mysql, postgres, sqlite};
+
+/// Postgres column types whose values the grid's row conversion reads
+/// exactly; other columns are selected as text. Numbers and times in
+/// particular have to survive the trip to a keyset cursor and back.
+const PG_DECODED: &[&str] = &[
+    "smallint", "integer", "bigint", "real", "double precision", "boolean", "text", "character varying", "character",
+    "name", "json", "jsonb", "bytea",
+];
+
+/// MySQL column types selected as text, for the same reason
+const MYSQL_AS_TEXT: &[&str] = &["decimal", "numeric", "datetime", "timestamp", "time", "bit", "year"];
+
+/// Escape character of the LIKE patterns built for contains / starts with /
+/// ends with; not a backslash, which MySQL string literals treat specially
+const LIKE_ESCAPE: char = '!';
+
+fn like_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == LIKE_ESCAPE || c == '%' || c == '_' {
+            escaped.push(LIKE_ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Text of a filter value that is used as (part of) a LIKE pattern
+fn pattern_text(column: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(format!("{}: the pattern must be text", column)),
+    }
+}
+
+struct Browser<'a> {
+    dialect: Dialect,
+    table: String,
+    columns: HashMap<&'a str, &'a ColumnInfo>,
+}
+
+impl Browser<'_> {
+    fn new<'a>(dialect: Dialect, schema: Option<&str>, table: &str, columns: &'a [ColumnInfo]) -> Browser<'a> {
+        Browser {
+            dialect,
+            table: dialect.qualified(schema, table),
+            columns: columns.iter().map(|c| (c.name.as_str(), c)).collect(),
+        }
+    }
+
+    fn column(&self, name: &str) -> Result<&ColumnInfo, String> {
+        self.columns
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Column '{}' does not exist in {}", name, self.table))
+    }
+
+    /// Select list entry of a column
+    fn select_expr(&self, column: &ColumnInfo) -> String {
+        let name = self.dialect.quote_ident(&column.name);
+        match self.dialect {
+            Dialect::Postgres if !PG_DECODED.contains(&pg_cast_type(&column.data_type).as_str()) => {
+                format!("{}::text", name)
+            }
+            Dialect::MySql => {
+                let base = column.data_type.split(['(', ' ']).next().unwrap_or_default();
+                if MYSQL_AS_TEXT.contains(&base.to_ascii_lowercase().as_str()) {
+                    format!("CAST({} AS CHAR)", name)
+                } else {
+                    name
+                }
+            }
+            _ => name,
+        }
+    }
+
+    /// `column <operator> value`, failing for types without that comparison
+    fn compare(&self, column: &ColumnInfo, operator: &str, value: &Value, params: &mut Params) -> Result<String, String> {
+        params
+            .compare(column, operator, value_cell(column, value)?)
+            .ok_or_else(|| format!("Column '{}' cannot be compared", column.name))
+    }
+
+    /// The column as text, for LIKE
+    fn text_expr(&self, column: &ColumnInfo) -> String {
+        let name = self.dialect.quote_ident(&column.name);
+        match self.dialect {
+            Dialect::Postgres => format!("{}::text", name),
+            Dialect::MySql | Dialect::Sqlite => name,
+        }
+    }
+
+    fn filter(&self, node: &FilterNode, params: &mut Params) -> Result<String, String> {
+        match node {
+            FilterNode::Group { join, children } => {
+                if children.is_empty() {
+                    return Ok(match join {
+                        FilterJoin::And => "1 = 1",
+                        FilterJoin::Or => "1 = 0",
+                    }
+                    .to_string());
+                }
+                let parts = children
+                    .iter()
+                    .map(|child| self.filter(child, params).map(|sql| format!("({})", sql)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                let join = match join {
+                    FilterJoin::And => " AND ",
+                    FilterJoin::Or => " OR ",
+                };
+                Ok(parts.join(join))
+            }
+            FilterNode::Condition { column, operator, value } => self.condition(self.column(column)?, *operator, value, params),
+        }
+    }
+
+    fn condition(&self, column: &ColumnInfo, operator: FilterOperator, value: &Value, params: &mut Params) -> Result<String, String> {
+        let name = self.dialect.quote_ident(&column.name);
+        let comparison = match operator {
+            FilterOperator::Eq => "=",
+            FilterOperator::Ne => "<>",
+            FilterOperator::Lt => "<",
+            FilterOperator::Lte => "<=",
+            FilterOperator::Gt => ">",
+            FilterOperator::Gte => ">=",
+            _ => "",
+        };
+        match operator {
+            FilterOperator::IsNull => Ok(format!("{} IS NULL", name)),
+            FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", name)),
+            FilterOperator::Eq if value.is_null() => Ok(format!("{} IS NULL", name)),
+            FilterOperator::Ne if value.is_null() => Ok(format!("{} IS NOT NULL", name)),
+            _ if value.is_null() => Err(format!("{}: the filter needs a value", column.name)),
+            FilterOperator::Eq | FilterOperator::Ne | FilterOperator::Lt | FilterOperator::Lte | FilterOperator::Gt
+            | FilterOperator::Gte => self.compare(column, comparison, value, params),
+            FilterOperator::Like | FilterOperator::NotLike => {
+                let not = if operator == FilterOperator::NotLike { "NOT " } else { "" };
+                let placeholder = params.push_text(pattern_text(&column.name, value)?);
+                Ok(format!("{} {}LIKE {}", self.text_expr(column), not, placeholder))
+            }
+            FilterOperator::Contains | FilterOperator::StartsWith | FilterOperator::EndsWith => {
+                let text = like_escape(&pattern_text(&column.name, value)?);
+                let pattern = match operator {
+                    FilterOperator::StartsWith => format!("{}%", text),
+                    FilterOperator::EndsWith => format!("%{}", text),
+                    _ => format!("%{}%", text),
+                };
+                // SQLite's LIKE ignores ASCII case, MySQL's follows the
+                // (usually case-insensitive) collation
+                let like = if self.dialect == Dialect::Postgres { "ILIKE" } else { "LIKE" };
+                let placeholder = params.push_text(pattern);
+                Ok(format!("{} {} {} ESCAPE '{}'", self.text_expr(column), like, placeholder, LIKE_ESCAPE))
+            }
+            FilterOperator::In | FilterOperator::NotIn => {
+                let values = value
+                    .as_array()
+                    .ok_or_else(|| format!("{}: IN needs a list of values", column.name))?;
+                if values.iter().any(Value::is_null) {
+                    return Err(format!("{}: use the null operators to filter on NULL", column.name));
+                }
+                let not_in = operator == FilterOperator::NotIn;
+                if values.is_empty() {
+                    return Ok(if not_in { "1 = 1" } else { "1 = 0" }.to_string());
+                }
+                let parts = values
+                    .iter()
+                    .map(|v| self.compare(column, "=", v, params))
+                    .collect::<Result<Vec<_>, String>>()?;
+                let matched = parts.join(" OR ");
+                Ok(if not_in { format!("NOT ({})", matched) } else { matched })
+            }
+            FilterOperator::Between => match value.as_array().map(Vec::as_slice) {
+                Some([low, high]) if !low.is_null() && !high.is_null() => {
+                    let low = self.compare(column, ">=", low, params)?;
+                    let high = self.compare(column, "<=", high, params)?;
+                    Ok(format!("{} AND {}", low, high))
+                }
+                _ => Err(format!("{}: BETWEEN needs a [low, high] pair", column.name)),
+            },
+        }
+    }
+
+    /// ORDER BY term placing NULLs after every value
+    fn order_term(&self, key: &SortKey) -> Result<String, String> {
+        let column = self.column(&key.column)?;
+        // Qualified, so that it names the column and not the select list
+        // entry, which may be the column as text
+        let mut name = format!("{}.{}", self.table, self.dialect.quote_ident(&column.name));
+        if self.dialect == Dialect::Postgres && column.data_type == "json" {
+            name = format!("{}::jsonb", name);
+        }
+        let direction = if key.descending { "DESC" } else { "ASC" };
+        Ok(match self.dialect {
+            // Postgres' defaults already sort NULLs as the largest value
+            Dialect::Postgres => format!("{} {}", name, direction),
+            _ if !column.is_nullable => format!("{} {}", name, direction),
+            Dialect::Sqlite => format!("{} {} NULLS {}", name, direction, if key.descending { "FIRST" } else { "LAST" }),
+            Dialect::MySql => format!("{} IS NULL {}, {} {}", name, direction, name, direction),
+        })
+    }
+
+    /// Rows after the cursor in `order`: equal on the first n - 1 ordering
+    /// columns and beyond on the n-th, for some n. Conditions are written out
+    /// again for each n because MySQL and SQLite placeholders are positional.
+    fn after(&self, order: &[SortKey], cursor: &Map<String, Value>, params: &mut Params) -> Result<String, String> {
+        let mut values = Vec::new();
+        for key in order {
+            let column = self.column(&key.column)?;
+            let value = cursor
+                .get(&key.column)
+                .ok_or_else(|| format!("The cursor lacks a value for '{}'", key.column))?;
+            values.push((key, column, value));
+        }
+
+        let mut alternatives = Vec::new();
+        for (n, (key, column, value)) in values.iter().enumerate() {
+            let name = self.dialect.quote_ident(&column.name);
+            // With NULLs last, nothing comes after a NULL ascending, and every value after it descending
+            if value.is_null() && !key.descending {
+                continue;
+            }
+            let mut parts = Vec::new();
+            for (_, earlier, earlier_value) in &values[..n] {
+                parts.push(if earlier_value.is_null() {
+                    format!("{} IS NULL", self.dialect.quote_ident(&earlier.name))
+                } else {
+                    self.compare(earlier, "=", earlier_value, params)?
+                });
+            }
+            parts.push(if value.is_null() {
+                format!("{} IS NOT NULL", name)
+            } else if key.descending {
+                self.compare(column, "<", value, params)?
+            } else if column.is_nullable {
+                format!("({} OR {} IS NULL)", self.compare(column, ">", value, params)?, name)
+            } else {
+                self.compare(column, ">", value, params)?
+            });
+            alternatives.push(format!("({})", parts.join(" AND ")));
+        }
+        if alternatives.is_empty() {
+            return Ok("1 = 0".to_string());
+        }
+        Ok(alternatives.join(" OR "))
+    }
+}
+
+/// Run a query with bound cells and convert its rows for the grid
+async fn fetch(pool: &SqlPool, sql: &str, cells: &[Cell]) -> Result<Vec<Vec<Value>>, String> {
+    Ok(match pool {
+        SqlPool::Postgres(pool) => {
+            let rows = cells.iter().fold(sqlx::query(sql), bind).fetch_all(pool).await.map_err(|e| e.to_string())?;
+            rows.iter().map(postgres::row_values).collect()
+        }
+        SqlPool::MySql(pool) => {
+            let rows = cells.iter().fold(sqlx::query(sql), bind).fetch_all(pool).await.map_err(|e| e.to_string())?;
+            rows.iter().map(mysql::row_values).collect()
+        }
+        SqlPool::Sqlite(pool) => {
+            let rows = cells.iter().fold(sqlx::query(sql), bind).fetch_all(pool).await.map_err(|e| e.to_string())?;
+            rows.iter().map(sqlite::row_values).collect()
+        }
+    })
+}
+
+/// Run a query with bound cells returning one integer
+async fn fetch_count(pool: &SqlPool, sql: &str, cells: &[Cell]) -> Result<i64, String> {
+    match pool {
+        SqlPool::Postgres(pool) => cells.iter().fold(sqlx::query(sql), bind).fetch_one(pool).await.and_then(|r| r.try_get(0)),
+        SqlPool::MySql(pool) => cells.iter().fold(sqlx::query(sql), bind).fetch_one(pool).await.and_then(|r| r.try_get(0)),
+        SqlPool::Sqlite(pool) => cells.iter().fold(sqlx::query(sql), bind).fetch_one(pool).await.and_then(|r| r.try_get(0)),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// The planner's row estimate, where the engine has a cheap one
+async fn estimate(pool: &SqlPool, request: &BrowseRequest, from: &str, cells: &[Cell]) -> Result<Option<u64>, String> {
+    match pool {
+        SqlPool::Postgres(pg) => {
+            let plan: Value = cells
+                .iter()
+                .fold(sqlx::query(&format!("EXPLAIN (FORMAT JSON) SELECT 1 FROM {}", from)), bind)
+                .fetch_one(pg)
+                .await
+                .and_then(|r| r.try_get(0))
+                .map_err(|e| e.to_string())?;
+            Ok(plan[0]["Plan"]["Plan Rows"].as_f64().map(|rows| rows as u64))
+        }
+        SqlPool::MySql(my) if request.filter.is_none() => {
+            let rows: Option<i64> = sqlx::query_scalar(
+                "SELECT CAST(TABLE_ROWS AS SIGNED) FROM information_schema.TABLES \
+                 WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ?",
+            )
+            .bind(&request.schema)
+            .bind(&request.table)
+            .fetch_optional(my)
+            .await
+            .map_err(|e| e.to_string())?
+            .flatten();
+            Ok(rows.map(|n| n.max(0) as u64))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Read one page of a table
+pub async fn browse_table(pool: &SqlPool, request: &BrowseRequest) -> Result<BrowseResult, String> {
+    let start = Instant::now();
+    if request.page_size == 0 {
+        return Err("The page size must be at least 1".to_string());
+    }
+    let dialect = pool.dialect();
+    let schema = request.schema.as_deref();
+    let columns = introspection::columns(pool, schema, &request.table).await?;
+    if columns.is_empty() {
+        return Err(format!("Table '{}' was not found", request.table));
+    }
+    let browser = Browser::new(dialect, schema, &request.table, &columns);
+
+    let mut order_by: Vec<SortKey> = Vec::new();
+    for key in &request.sort {
+        browser.column(&key.column)?;
+        if !order_by.iter().any(|k| k.column == key.column) {
+            order_by.push(key.clone());
+        }
+    }
+    match introspection::row_key(pool, schema, &request.table, &columns).await? {
+        Some(key) => {
+            for column in key {
+                if !order_by.iter().any(|k| k.column == column) {
+                    order_by.push(SortKey { column, descending: false });
+                }
+            }
+        }
+        None if matches!(request.paging, Paging::Keyset { .. }) => {
+            return Err(format!(
+                "{} has no primary or unique key; keyset paging needs one, use offset paging instead",
+                request.table
+            ));
+        }
+        None => {}
+    }
+
+    // The page
+    let mut params = Params::new(dialect);
+    let mut conditions = Vec::new();
+    if let Some(filter) = &request.filter {
+        conditions.push(format!("({})", browser.filter(filter, &mut params)?));
+    }
+    let offset = match &request.paging {
+        Paging::Offset { offset } => *offset,
+        Paging::Keyset { after: Some(cursor) } => {
+            conditions.push(format!("({})", browser.after(&order_by, cursor, &mut params)?));
+            0
+        }
+        Paging::Keyset { after: None } => 0,
+    };
+    let names: Vec<String> = columns.iter().map(|c| browser.select_expr(c)).collect();
+    let mut sql = format!("SELECT {} FROM {}", names.join(", "), browser.table);
+    if !conditions.is_empty() {
+        sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    if !order_by.is_empty() {
+        let terms = order_by.iter().map(|k| browser.order_term(k)).collect::<Result<Vec<_>, String>>()?;
+        sql.push_str(&format!(" ORDER BY {}", terms.join(", ")));
+    }
+    // One extra row tells whether there is a next page
+    sql.push_str(&format!(" LIMIT {}", request.page_size + 1));
+    if offset > 0 {
+        sql.push_str(&format!(" OFFSET {}", offset));
+    }
+    let mut rows = fetch(pool, &sql, &params.cells).await?;
+    let has_more = rows.len() as u64 > request.page_size;
+    rows.truncate(request.page_size as usize);
+
+    let next_cursor = match (&request.paging, rows.last()) {
+        (Paging::Keyset { .. }, Some(last)) if has_more => Some(
+            order_by
+                .iter()
+                .filter_map(|key| {
+                    let i = columns.iter().position(|c| c.name == key.column)?;
+                    Some((key.column.clone(), last[i].clone()))
+                })
+                .collect(),
+        ),
+        _ => None,
+    };
+
+    // The total, with the filter alone
+    let mut params = Params::new(dialect);
+    let mut from = browser.table.clone();
+    if let Some(filter) = &request.filter {
+        from.push_str(&format!(" WHERE {}", browser.filter(filter, &mut params)?));
+    }
+    let (total_rows, total_is_estimate) = match request.count {
+        CountMode::None => (None, false),
+        CountMode::Estimate => match estimate(pool, request, &from, &params.cells).await? {
+            Some(rows) => (Some(rows), true),
+            None => (Some(fetch_count(pool, &format!("SELECT COUNT(*) FROM {}", from), &params.cells).await? as u64), false),
+        },
+        CountMode::Exact => (Some(fetch_count(pool, &format!("SELECT COUNT(*) FROM {}", from), &params.cells).await? as u64), false),
+    };
+
+    Ok(BrowseResult {
+        columns: columns.iter().map(|c| c.name.clone()).collect(),
+        rows,
+        total_rows,
+        total_is_estimate,
+        has_more,
+        order_by,
+        next_cursor,
+        execution_time_ms: start.elapsed().as_millis(),
+    })
+}
+
+/// Count the rows of a table that match `filter`
+pub async fn count_rows(pool: &SqlPool, schema: Option<&str>, table: &str, filter: &FilterNode) -> Result<u64, String> {
+    let dialect = pool.dialect();
+    let columns = introspection::columns(pool, schema, table).await?;
+    if columns.is_empty() {
+        return Err(format!("Table '{}' was not found", table));
+    }
+    let browser = Browser::new(dialect, schema, table, &columns);
+    let mut params = Params::new(dialect);
+    let sql = format!("SELECT COUNT(*) FROM {} WHERE {}", browser.table, browser.filter(filter, &mut params)?);
+    Ok(fetch_count(pool, &sql, &params.cells).await? as u64)
+}
+
+/// The condition `filter` stands for on a table with `columns`, with its
+/// values added to `params`
+pub fn filter_condition(
+    dialect: Dialect,
+    schema: Option<&str>,
+    table: &str,
+    columns: &[ColumnInfo],
+    filter: &FilterNode,
+    params: &mut Params,
+) -> Result<String, String> {
+    Browser::new(dialect, schema, table, columns).filter(filter, params)
+}
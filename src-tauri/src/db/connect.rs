@@ -0,0 +1,194 @@
+// Connection pool setup
+//
+// Pragmas and extensions go into the SQLite connect options, which the driver
+// applies to each connection it opens before handing it out; pools cloned
+// from the options (such as dump snapshots) keep them. ATTACH has no connect
+// option, so attachments run in `after_connect` on every pooled connection.
+//
+// SQLCipher is bundled in place of plain SQLite; a key turns into the `key`
+// pragma, which the driver always sends first.
+
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Executor, Pool, Sqlite};
+
+use crate::db::dialect::Dialect;
+use crate::db::types::{SqliteAttachment, SqliteCipher, SqliteOptions};
+
+const LITE: Dialect = Dialect::Sqlite;
+
+/// `path` as a SQLite URI filename, so that query parameters can be added
+fn file_uri(path: &str) -> String {
+    let mut uri = String::from("file:");
+    let path = path.replace('\\', "/");
+    // Windows drive paths become `file:/C:/...`
+    if path.as_bytes().get(1) == Some(&b':') {
+        uri.push('/');
+    }
+    for c in path.chars() {
+        match c {
+            '%' => uri.push_str("%25"),
+            '?' => uri.push_str("%3f"),
+            '#' => uri.push_str("%23"),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
+/// The `ATTACH` statement for an attachment; the path is bound as `?1`
+pub fn attach_statement(attachment: &SqliteAttachment) -> (String, String) {
+    let path = if attachment.read_only {
+        format!("{}?mode=ro", file_uri(&attachment.path))
+    } else {
+        attachment.path.clone()
+    };
+    (format!("ATTACH DATABASE ?1 AS {}", LITE.quote_ident(&attachment.alias)), path)
+}
+
+/// `(pragma, value)` pairs for the cipher settings. A compatibility version
+/// is spelled out as the settings it stands for, because the driver sends
+/// `cipher_compatibility` after `kdf_iter`, which would undo an explicit one.
+pub fn cipher_settings(cipher: &SqliteCipher) -> Result<Vec<(&'static str, String)>, String> {
+    let mut settings = Vec::new();
+    let defaults = match cipher.compatibility {
+        None => None,
+        Some(1) => Some((4000, 1024, false, "SHA1")),
+        Some(2) => Some((4000, 1024, true, "SHA1")),
+        Some(3) => Some((64000, 1024, true, "SHA1")),
+        Some(4) => Some((256000, 4096, true, "SHA512")),
+        Some(version) => return Err(format!("Unknown SQLCipher compatibility version {}", version)),
+    };
+    let kdf_iter = cipher.kdf_iter.or(defaults.map(|(iterations, ..)| iterations));
+    let page_size = cipher.page_size.or(defaults.map(|(_, size, ..)| size));
+    if let Some(iterations) = kdf_iter {
+        settings.push(("kdf_iter", iterations.to_string()));
+    }
+    if let Some(size) = page_size {
+        settings.push(("cipher_page_size", size.to_string()));
+    }
+    if let Some((_, _, hmac, algorithm)) = defaults {
+        settings.push(("cipher_kdf_algorithm", format!("PBKDF2_HMAC_{}", algorithm)));
+        settings.push(("cipher_use_hmac", if hmac { "ON" } else { "OFF" }.to_string()));
+        if hmac {
+            settings.push(("cipher_hmac_algorithm", format!("HMAC_{}", algorithm)));
+        }
+    }
+    Ok(settings)
+}
+
+/// Add the SQLCipher key and settings; the driver sends them before any other pragma
+fn cipher_options(connect: SqliteConnectOptions, cipher: &SqliteCipher) -> Result<SqliteConnectOptions, String> {
+    if cipher.key.is_empty() {
+        return Err("The SQLCipher key is empty".to_string());
+    }
+    let mut connect = connect.pragma("key", LITE.quote_literal(&cipher.key));
+    for (pragma, value) in cipher_settings(cipher)? {
+        connect = connect.pragma(pragma, value);
+    }
+    Ok(connect)
+}
+
+/// Error of the first read from an encrypted database; a wrong key or wrong
+/// cipher settings surface as SQLITE_NOTADB
+pub fn cipher_error(error: sqlx::Error) -> String {
+    match &error {
+        sqlx::Error::Database(e) if e.code().as_deref() == Some("26") => {
+            "The key or cipher settings are wrong, or the file is not a SQLCipher database".to_string()
+        }
+        _ => error.to_string(),
+    }
+}
+
+/// Make sure the key opened the database. Without SQLCipher compiled in,
+/// `PRAGMA key` is silently ignored, so that is checked too.
+pub async fn check_cipher(pool: &Pool<Sqlite>) -> Result<(), String> {
+    let version: Option<String> = sqlx::query_scalar("PRAGMA cipher_version")
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if version.is_none() {
+        return Err("This build of SQLite does not include SQLCipher".to_string());
+    }
+    sqlx::query("SELECT count(*) FROM sqlite_master")
+        .execute(pool)
+        .await
+        .map_err(cipher_error)?;
+    Ok(())
+}
+
+/// Apply the pragmas, extensions and cipher settings of `options`
+pub fn apply_options(mut connect: SqliteConnectOptions, options: &SqliteOptions) -> Result<SqliteConnectOptions, String> {
+    if let Some(cipher) = &options.cipher {
+        connect = cipher_options(connect, cipher)?;
+    }
+    if let Some(mode) = &options.journal_mode {
+        let mode = SqliteJournalMode::from_str(mode).map_err(|_| format!("Unknown journal mode '{}'", mode))?;
+        connect = connect.journal_mode(mode);
+    }
+    if let Some(on) = options.foreign_keys {
+        connect = connect.foreign_keys(on);
+    }
+    if let Some(ms) = options.busy_timeout_ms {
+        connect = connect.busy_timeout(Duration::from_millis(ms));
+    }
+    if let Some(pages) = options.cache_size {
+        connect = connect.pragma("cache_size", pages.to_string());
+    }
+    for extension in &options.extensions {
+        // The driver reports a failed load as a crashed worker, so catch the
+        // common case of a wrong path here; SQLite also tries the platform suffix
+        let found = ["", ".so", ".dylib", ".dll"]
+            .iter()
+            .any(|suffix| Path::new(&format!("{}{}", extension.path, suffix)).is_file());
+        if !found {
+            return Err(format!("Extension '{}' was not found", extension.path));
+        }
+        connect = match &extension.entry_point {
+            Some(entry) => connect.extension_with_entrypoint(extension.path.clone(), entry.clone()),
+            None => connect.extension(extension.path.clone()),
+        };
+    }
+    Ok(connect)
+}
+
+/// Open a SQLite pool with `options` applied to every connection
+pub async fn sqlite_pool(connection_string: &str, options: &SqliteOptions) -> Result<Pool<Sqlite>, String> {
+    for (i, attachment) in options.attach.iter().enumerate() {
+        let alias = attachment.alias.to_lowercase();
+        if alias.is_empty() || alias == "main" || alias == "temp" {
+            return Err(format!("'{}' cannot be used as an attachment alias", attachment.alias));
+        }
+        if options.attach[..i].iter().any(|a| a.alias.to_lowercase() == alias) {
+            return Err(format!("Attachment alias '{}' is used twice", attachment.alias));
+        }
+    }
+
+    let connect = SqliteConnectOptions::from_str(connection_string).map_err(|e| e.to_string())?;
+    // REGEXP, which SQLite leaves to the application, for searches and queries
+    let connect = apply_options(connect, options)?.with_regexp();
+    let attachments: Vec<(String, String)> = options.attach.iter().map(attach_statement).collect();
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .after_connect(move |conn, _| {
+            let attachments = attachments.clone();
+            Box::pin(async move {
+                for (statement, path) in &attachments {
+                    conn.execute(sqlx::query(statement).bind(path)).await?;
+                }
+                Ok(())
+            })
+        })
+        .connect_with(connect)
+        .await
+        .map_err(|e| if options.cipher.is_some() { cipher_error(e) } else { e.to_string() })?;
+    if options.cipher.is_some() {
+        if let Err(e) = check_cipher(&pool).await {
+            pool.close().await;
+            return Err(e);
+        }
+    }
+    Ok(pool)
+}
@@ -0,0 +1,487 @@
+// Table data comparison
+//
+// `diff_table_data` compares the rows of two tables, on the same or different
+// connections, matched by key. The left table's key order is cut into chunks
+// of `chunk_size` rows. Between two Postgres or two MySQL tables each chunk is
+// first compared by a checksum computed in the database, and only chunks
+// whose checksums differ are read; other pairs read every chunk. Rows are
+// compared in a normalized text form, so that 1.50 and 1.5 or TRUE and 1 are
+// equal across engines. A row whose two sides land in different chunks (text
+// keys ordered differently by the two engines) is still paired at the end.
+// The sync script turns the right table into the left one.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use serde_json::{Map, Value};
+use sqlx::Row;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::dialect::Dialect;
+use crate::db::edit::Params;
+use crate::db::import::preview_value;
+use crate::db::introspection;
+use crate::db::stream::{self, Cell};
+use crate::db::tasks::CancelFlag;
+use crate::db::types::{
+    ChangedRow, ColumnInfo, DataDiff, DataDiffProgress, DataDiffRequest, SqlPool, ValueDiff,
+};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Key and compared columns of a row, in select order
+type Cells = Vec<Cell>;
+
+/// A value in the form compared across engines; None for NULL
+fn normalized(cell: &Cell) -> Option<String> {
+    match cell {
+        Cell::Null => None,
+        Cell::Bool(value) => Some(if *value { "1" } else { "0" }.to_string()),
+        Cell::Int(value) => Some(value.to_string()),
+        Cell::Float(value) if value.fract() == 0.0 && value.abs() < 1e15 => Some((*value as i64).to_string()),
+        Cell::Float(value) => Some(value.to_string()),
+        Cell::Decimal(text) => {
+            let text = text.trim();
+            let text = if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.') } else { text };
+            Some(if text == "-0" { "0".to_string() } else { text.to_string() })
+        }
+        Cell::Text(text) => Some(text.clone()),
+        Cell::Bytes(bytes) => Some(stream::encode_hex(bytes)),
+        Cell::Json(value) => Some(value.to_string()),
+    }
+}
+
+/// One of the two compared tables
+struct Side<'a> {
+    pool: &'a SqlPool,
+    dialect: Dialect,
+    table: String,
+    key: Vec<&'a ColumnInfo>,
+    /// Compared columns besides the key
+    columns: Vec<&'a ColumnInfo>,
+}
+
+impl Side<'_> {
+    fn select_list(&self) -> String {
+        self.key
+            .iter()
+            .chain(&self.columns)
+            .map(|c| self.dialect.quote_ident(&c.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Keys beyond `bound` in the direction of `operator` (`>` or `<`): equal
+    /// on the first n - 1 key columns and beyond on the n-th, for some n
+    fn beyond(&self, bound: &[Cell], operator: &str, params: &mut Params) -> Result<Vec<String>, String> {
+        let compare = |params: &mut Params, column: &ColumnInfo, operator: &str, cell: &Cell| {
+            params
+                .compare(column, operator, cell.clone())
+                .ok_or_else(|| format!("Key column '{}' cannot be compared", column.name))
+        };
+        let mut alternatives = Vec::new();
+        for n in 0..self.key.len() {
+            let mut parts = Vec::new();
+            for (column, cell) in self.key[..n].iter().zip(bound) {
+                parts.push(compare(params, column, "=", cell)?);
+            }
+            parts.push(compare(params, self.key[n], operator, &bound[n])?);
+            alternatives.push(format!("({})", parts.join(" AND ")));
+        }
+        Ok(alternatives)
+    }
+
+    /// Rows with keys in (lower, upper]; an open end when None
+    fn range(&self, lower: Option<&Cells>, upper: Option<&Cells>) -> Result<String, String> {
+        let mut params = Params::inline(self.dialect);
+        let mut conditions: Vec<String> = self
+            .key
+            .iter()
+            .map(|c| format!("{} IS NOT NULL", self.dialect.quote_ident(&c.name)))
+            .collect();
+        if let Some(lower) = lower {
+            conditions.push(format!("({})", self.beyond(lower, ">", &mut params)?.join(" OR ")));
+        }
+        if let Some(upper) = upper {
+            let mut alternatives = self.beyond(upper, "<", &mut params)?;
+            let equal = self
+                .key
+                .iter()
+                .zip(upper)
+                .map(|(column, cell)| {
+                    params
+                        .compare(column, "=", cell.clone())
+                        .ok_or_else(|| format!("Key column '{}' cannot be compared", column.name))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            alternatives.push(format!("({})", equal.join(" AND ")));
+            conditions.push(format!("({})", alternatives.join(" OR ")));
+        }
+        Ok(conditions.join(" AND "))
+    }
+
+    async fn rows(&self, condition: &str) -> Result<Vec<Cells>, String> {
+        let sql = format!("SELECT {} FROM {} WHERE {}", self.select_list(), self.table, condition);
+        stream::fetch_cells(self.pool, &sql).await
+    }
+
+    /// Row count and an order-independent sum of the rows' hashes
+    async fn checksum(&self, condition: &str) -> Result<(i64, String), String> {
+        let names: Vec<String> = self.key.iter().chain(&self.columns).map(|c| self.dialect.quote_ident(&c.name)).collect();
+        match self.pool {
+            SqlPool::Postgres(pool) => {
+                let sql = format!(
+                    "SELECT count(*), coalesce(sum(('x' || left(md5(ROW({})::text), 15))::bit(60)::bigint), 0)::text FROM {} WHERE {}",
+                    names.join(", "),
+                    self.table,
+                    condition
+                );
+                let row = sqlx::query(&sql).fetch_one(pool).await.map_err(|e| e.to_string())?;
+                Ok((row.try_get(0).map_err(|e| e.to_string())?, row.try_get(1).map_err(|e| e.to_string())?))
+            }
+            SqlPool::MySql(pool) => {
+                // CONCAT_WS skips NULLs, so where they are is hashed too
+                let nulls: Vec<String> = names.iter().map(|name| format!("ISNULL({})", name)).collect();
+                let sql = format!(
+                    "SELECT COUNT(*), CAST(COALESCE(SUM(CAST(CONV(LEFT(MD5(CONCAT_WS('|', {}, CONCAT({}))), 15), 16, 10) AS UNSIGNED)), 0) AS CHAR) FROM {} WHERE {}",
+                    names.join(", "),
+                    nulls.join(", "),
+                    self.table,
+                    condition
+                );
+                let row = sqlx::query(&sql).fetch_one(pool).await.map_err(|e| e.to_string())?;
+                Ok((row.try_get(0).map_err(|e| e.to_string())?, row.try_get(1).map_err(|e| e.to_string())?))
+            }
+            SqlPool::Sqlite(_) => Err("SQLite has no hash function to checksum rows with".to_string()),
+        }
+    }
+
+    /// `key = value AND ...` for the script
+    fn key_filter(&self, row: &Cells) -> String {
+        let mut params = Params::inline(self.dialect);
+        self.key
+            .iter()
+            .zip(row)
+            .map(|(column, cell)| format!("{} = {}", self.dialect.quote_ident(&column.name), params.push(column, cell.clone())))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+}
+
+/// Keys of every `chunk_size`-th row of the left table, the upper ends of the chunks
+async fn boundaries(side: &Side<'_>, chunk_size: usize) -> Result<Vec<Cells>, String> {
+    let keys: Vec<String> = side.key.iter().map(|c| side.dialect.quote_ident(&c.name)).collect();
+    let not_null: Vec<String> = keys.iter().map(|k| format!("{} IS NOT NULL", k)).collect();
+    let sql = format!(
+        "SELECT {keys} FROM (SELECT {keys}, row_number() OVER (ORDER BY {keys}) AS nebula_row FROM {table} WHERE {not_null}) numbered \
+         WHERE nebula_row % {chunk_size} = 0 ORDER BY {keys}",
+        keys = keys.join(", "),
+        table = side.table,
+        not_null = not_null.join(" AND "),
+    );
+    stream::fetch_cells(side.pool, &sql).await
+}
+
+/// Compares the chunks and collects the differences
+struct Comparer<'a> {
+    left: &'a Side<'a>,
+    right: &'a Side<'a>,
+    max_rows: usize,
+    result: DataDiff,
+    /// Rows not found in the same chunk on the other side, by normalized key
+    left_pending: HashMap<Vec<Option<String>>, Cells>,
+    right_pending: HashMap<Vec<Option<String>>, Cells>,
+    deletes: Vec<String>,
+    updates: Vec<String>,
+    inserts: Vec<String>,
+}
+
+impl Comparer<'_> {
+    fn key_of(&self, row: &Cells) -> Vec<Option<String>> {
+        row[..self.left.key.len()].iter().map(normalized).collect()
+    }
+
+    fn row_map(&self, side: &Side, row: &Cells) -> Map<String, Value> {
+        side.key.iter().chain(&side.columns).zip(row).map(|(c, cell)| (c.name.clone(), preview_value(cell))).collect()
+    }
+
+    fn compare(&mut self, left: &Cells, right: &Cells) {
+        let offset = self.left.key.len();
+        let mut differences = Vec::new();
+        let mut assignments = Vec::new();
+        let mut params = Params::inline(self.right.dialect);
+        for (i, (left_column, right_column)) in self.left.columns.iter().zip(&self.right.columns).enumerate() {
+            let (l, r) = (&left[offset + i], &right[offset + i]);
+            if normalized(l) == normalized(r) {
+                continue;
+            }
+            differences.push(ValueDiff { column: left_column.name.clone(), left: preview_value(l), right: preview_value(r) });
+            if right_column.generated.is_none() {
+                let value = params.push(right_column, l.clone());
+                assignments.push(format!("{} = {}", self.right.dialect.quote_ident(&right_column.name), value));
+            }
+        }
+        if differences.is_empty() {
+            self.result.matching_rows += 1;
+            return;
+        }
+        self.result.changed_count += 1;
+        if self.result.changed.len() < self.max_rows {
+            let key = self.left.key.iter().zip(left).map(|(c, cell)| (c.name.clone(), preview_value(cell))).collect();
+            self.result.changed.push(ChangedRow { key, differences });
+            if !assignments.is_empty() {
+                self.updates.push(format!(
+                    "UPDATE {} SET {} WHERE {};",
+                    self.right.table,
+                    assignments.join(", "),
+                    self.right.key_filter(left)
+                ));
+            }
+        } else {
+            self.result.truncated = true;
+        }
+    }
+
+    /// Compare the rows of one chunk, keeping the unmatched ones for the end
+    fn chunk(&mut self, left_rows: Vec<Cells>, right_rows: Vec<Cells>) {
+        self.result.left_rows += left_rows.len() as u64;
+        self.result.right_rows += right_rows.len() as u64;
+        let mut right: HashMap<_, _> = right_rows.into_iter().map(|row| (self.key_of(&row), row)).collect();
+        for row in left_rows {
+            let key = self.key_of(&row);
+            match right.remove(&key).or_else(|| self.right_pending.remove(&key)) {
+                Some(other) => self.compare(&row, &other),
+                None => {
+                    self.left_pending.insert(key, row);
+                }
+            }
+        }
+        for (key, row) in right {
+            match self.left_pending.remove(&key) {
+                Some(other) => self.compare(&other, &row),
+                None => {
+                    self.right_pending.insert(key, row);
+                }
+            }
+        }
+    }
+
+    /// Rows found on one side only
+    fn finish(&mut self) {
+        let (left, right) = (self.left, self.right);
+        let mut left_only: Vec<Cells> = self.left_pending.drain().map(|(_, row)| row).collect();
+        let mut right_only: Vec<Cells> = self.right_pending.drain().map(|(_, row)| row).collect();
+        let order = |a: &Cells, b: &Cells| {
+            a.iter().map(normalized).collect::<Vec<_>>().cmp(&b.iter().map(normalized).collect::<Vec<_>>())
+        };
+        left_only.sort_by(order);
+        right_only.sort_by(order);
+        self.result.left_only_count = left_only.len() as u64;
+        self.result.right_only_count = right_only.len() as u64;
+        self.result.truncated |= left_only.len() > self.max_rows || right_only.len() > self.max_rows;
+
+        let writable: Vec<(usize, &ColumnInfo)> = right
+            .key
+            .iter()
+            .chain(&right.columns)
+            .copied()
+            .enumerate()
+            .filter(|(_, c)| c.generated.is_none())
+            .collect();
+        let names: Vec<String> = writable.iter().map(|(_, c)| right.dialect.quote_ident(&c.name)).collect();
+        for row in left_only.iter().take(self.max_rows) {
+            let mut params = Params::inline(right.dialect);
+            let values: Vec<String> = writable.iter().map(|(i, c)| params.push(c, row[*i].clone())).collect();
+            self.inserts.push(format!("INSERT INTO {} ({}) VALUES ({});", right.table, names.join(", "), values.join(", ")));
+            self.result.left_only.push(self.row_map(left, row));
+        }
+        for row in right_only.iter().take(self.max_rows) {
+            self.deletes.push(format!("DELETE FROM {} WHERE {};", right.table, right.key_filter(row)));
+            self.result.right_only.push(self.row_map(right, row));
+        }
+        self.result.script = self
+            .deletes
+            .iter()
+            .chain(&self.updates)
+            .chain(&self.inserts)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
+/// Columns of `names` in `columns`, by exact or case-insensitive name
+fn pick<'a>(columns: &'a [ColumnInfo], names: &[String], table: &str) -> Result<Vec<&'a ColumnInfo>, String> {
+    names
+        .iter()
+        .map(|name| {
+            columns
+                .iter()
+                .find(|c| &c.name == name)
+                .or_else(|| columns.iter().find(|c| c.name.eq_ignore_ascii_case(name)))
+                .ok_or_else(|| format!("Column {} does not exist in table {}", name, table))
+        })
+        .collect()
+}
+
+/// Compare the rows of `request.left_table` on `left` with the right table on `right`
+pub async fn diff_table_data(
+    app: &AppHandle,
+    left: &SqlPool,
+    right: &SqlPool,
+    same_connection: bool,
+    request: &DataDiffRequest,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<DataDiff, String> {
+    let start = Instant::now();
+    let left_schema = request.left_schema.as_deref();
+    let right_schema = request.right_schema.as_deref();
+    let right_table = request.right_table.as_deref().unwrap_or(&request.left_table);
+    if same_connection && left_schema == right_schema && right_table == request.left_table {
+        return Err("The two sides are the same table".to_string());
+    }
+
+    let left_columns = introspection::columns(left, left_schema, &request.left_table).await?;
+    if left_columns.is_empty() {
+        return Err(format!("Table {} not found", request.left_table));
+    }
+    let right_columns = introspection::columns(right, right_schema, right_table).await?;
+    if right_columns.is_empty() {
+        return Err(format!("Table {} not found", right_table));
+    }
+
+    let key_names = if request.key_columns.is_empty() {
+        introspection::primary_key(left, left_schema, &request.left_table).await?.ok_or_else(|| {
+            format!("{} has no primary key; choose the columns to match rows by", request.left_table)
+        })?
+    } else {
+        request.key_columns.clone()
+    };
+    let mut warnings = Vec::new();
+    let names: Vec<String> = if request.columns.is_empty() {
+        left_columns
+            .iter()
+            .filter(|c| !key_names.contains(&c.name))
+            .filter(|c| {
+                let found = right_columns.iter().any(|r| r.name.eq_ignore_ascii_case(&c.name));
+                if !found {
+                    warnings.push(format!("Column {} is only in {} and is not compared", c.name, request.left_table));
+                }
+                found
+            })
+            .map(|c| c.name.clone())
+            .collect()
+    } else {
+        request.columns.iter().filter(|name| !key_names.contains(name)).cloned().collect()
+    };
+    for column in right_columns.iter().filter(|c| !left_columns.iter().any(|l| l.name.eq_ignore_ascii_case(&c.name))) {
+        warnings.push(format!("Column {} is only in {} and is not compared", column.name, right_table));
+    }
+
+    let left_side = Side {
+        pool: left,
+        dialect: left.dialect(),
+        table: left.dialect().qualified(left_schema, &request.left_table),
+        key: pick(&left_columns, &key_names, &request.left_table)?,
+        columns: pick(&left_columns, &names, &request.left_table)?,
+    };
+    let right_side = Side {
+        pool: right,
+        dialect: right.dialect(),
+        table: right.dialect().qualified(right_schema, right_table),
+        key: pick(&right_columns, &key_names, right_table)?,
+        columns: pick(&right_columns, &names, right_table)?,
+    };
+    // Checksums are only comparable when both engines render values alike
+    let hashed = left_side.dialect == right_side.dialect && left_side.dialect != Dialect::Sqlite;
+
+    let bounds = boundaries(&left_side, request.chunk_size.max(1)).await?;
+    let chunks_total = bounds.len() as u64 + 1;
+    let mut comparer = Comparer {
+        left: &left_side,
+        right: &right_side,
+        max_rows: request.max_rows,
+        result: DataDiff {
+            key_columns: left_side.key.iter().map(|c| c.name.clone()).collect(),
+            columns: left_side.columns.iter().map(|c| c.name.clone()).collect(),
+            left_rows: 0,
+            right_rows: 0,
+            matching_rows: 0,
+            left_only_count: 0,
+            right_only_count: 0,
+            changed_count: 0,
+            left_only: Vec::new(),
+            right_only: Vec::new(),
+            changed: Vec::new(),
+            truncated: false,
+            chunks: chunks_total,
+            chunks_skipped: 0,
+            script: String::new(),
+            warnings,
+            cancelled: false,
+            execution_time_ms: 0,
+        },
+        left_pending: HashMap::new(),
+        right_pending: HashMap::new(),
+        deletes: Vec::new(),
+        updates: Vec::new(),
+        inserts: Vec::new(),
+    };
+
+    let emit = |comparer: &Comparer, chunks_done: u64| {
+        let _ = app.emit(
+            "data-diff-progress",
+            DataDiffProgress {
+                task_id: task_id.to_string(),
+                chunks_done,
+                chunks_total,
+                rows_compared: comparer.result.left_rows + comparer.result.right_rows,
+            },
+        );
+    };
+    let mut last_progress = Instant::now();
+    let mut chunks_done = 0;
+    emit(&comparer, chunks_done);
+    for chunk in 0..bounds.len() + 1 {
+        if cancel.is_cancelled() {
+            comparer.result.cancelled = true;
+            break;
+        }
+        let lower = chunk.checked_sub(1).map(|i| &bounds[i]);
+        let upper = bounds.get(chunk);
+        let left_range = left_side.range(lower, upper)?;
+        let right_range = right_side.range(lower, upper)?;
+        if hashed {
+            let (left_checksum, right_checksum) =
+                futures::try_join!(left_side.checksum(&left_range), right_side.checksum(&right_range))?;
+            if left_checksum == right_checksum {
+                let rows = left_checksum.0 as u64;
+                comparer.result.left_rows += rows;
+                comparer.result.right_rows += rows;
+                comparer.result.matching_rows += rows;
+                comparer.result.chunks_skipped += 1;
+                chunks_done += 1;
+                continue;
+            }
+        }
+        let (left_rows, right_rows) = futures::try_join!(left_side.rows(&left_range), right_side.rows(&right_range))?;
+        comparer.chunk(left_rows, right_rows);
+        chunks_done += 1;
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            emit(&comparer, chunks_done);
+        }
+    }
+    comparer.finish();
+    emit(&comparer, chunks_done);
+
+    let mut result = comparer.result;
+    if result.truncated {
+        result.warnings.push(format!(
+            "Only the first {} rows of each kind of difference are listed and scripted",
+            request.max_rows
+        ));
+    }
+    result.execution_time_ms = start.elapsed().as_millis();
+    Ok(result)
+}
@@ -0,0 +1,9 @@
+// DDL scripting
+//
+// Produces CREATE statements for existing objects. MySQL can report these
+// itself through SHOW CREATE; Postgres and SQLite definitions are rebuilt from
+// the catalog and the introspection helpers.
+
+pub mod postgres;
+pub mod mysql;
+pub mod sqlite;
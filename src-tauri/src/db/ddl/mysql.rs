@@ -0,0 +1,31 @@
+use sqlx::{Pool, MySql, Executor};
+use crate::db::dialect::Dialect;
+use crate::db::introspection::mysql::text;
+use crate::db::types::ObjectType;
+
+/// Generate the CREATE statement for an object using the server's SHOW CREATE
+pub async fn generate_ddl(
+    pool: &Pool<MySql>,
+    object_type: ObjectType,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    let (keyword, column) = match object_type {
+        ObjectType::Table => ("TABLE", "Create Table"),
+        ObjectType::View => ("VIEW", "Create View"),
+        ObjectType::Function => ("FUNCTION", "Create Function"),
+        ObjectType::Procedure => ("PROCEDURE", "Create Procedure"),
+        ObjectType::Trigger => ("TRIGGER", "SQL Original Statement"),
+        // Only MariaDB has sequences; MySQL reports a syntax error
+        ObjectType::Sequence => ("SEQUENCE", "Create Table"),
+    };
+
+    let sql = format!("SHOW CREATE {} {}", keyword, Dialect::MySql.qualified(schema, name));
+    // SHOW statements go through the text protocol rather than a prepared statement
+    let row = pool.fetch_one(sql.as_str()).await.map_err(|e| e.to_string())?;
+
+    // The definition is NULL when the user lacks privileges on the routine body
+    let ddl = text(&row, column)?
+        .ok_or_else(|| format!("No definition available for '{}' (insufficient privileges?)", name))?;
+    Ok(format!("{};", ddl.trim_end()))
+}
@@ -0,0 +1,389 @@
+use sqlx::{Pool, Postgres, Row};
+use crate::db::dialect::Dialect;
+use crate::db::introspection;
+use crate::db::types::{ColumnInfo, ObjectType, SequenceInfo};
+
+const PG: Dialect = Dialect::Postgres;
+
+/// Generate the CREATE statement(s) for an object
+pub async fn generate_ddl(
+    pool: &Pool<Postgres>,
+    object_type: ObjectType,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    match object_type {
+        ObjectType::Table => table_ddl(pool, schema, name).await,
+        ObjectType::View => view_ddl(pool, schema, name).await,
+        ObjectType::Function | ObjectType::Procedure => routine_ddl(pool, object_type, schema, name).await,
+        ObjectType::Sequence => sequence_ddl(pool, schema, name).await,
+        ObjectType::Trigger => trigger_ddl(pool, schema, name).await,
+    }
+}
+
+pub(crate) fn column_definition(column: &ColumnInfo) -> String {
+    let mut def = format!("{} {}", PG.quote_ident(&column.name), column.data_type);
+    if let Some(expr) = &column.generated {
+        def.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expr));
+    }
+    if let Some(identity) = &column.identity {
+        def.push_str(&format!(" GENERATED {} AS IDENTITY", identity));
+    }
+    if let Some(default) = &column.default_value {
+        def.push_str(&format!(" DEFAULT {}", default));
+    }
+    if !column.is_nullable {
+        def.push_str(" NOT NULL");
+    }
+    def
+}
+
+/// CREATE INDEX statements for indexes that don't back a constraint
+async fn index_statements(pool: &Pool<Postgres>, schema: &str, table: &str) -> Result<Vec<String>, String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT pg_get_indexdef(ix.indexrelid) || ';'
+        FROM pg_index ix
+        JOIN pg_class t ON t.oid = ix.indrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        JOIN pg_class i ON i.oid = ix.indexrelid
+        WHERE n.nspname = $1
+          AND t.relname = $2
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_constraint k
+              WHERE k.conrelid = ix.indrelid AND k.conindid = ix.indexrelid
+                AND k.contype IN ('p', 'u', 'x')
+          )
+          AND NOT EXISTS (SELECT 1 FROM pg_inherits inh WHERE inh.inhrelid = ix.indexrelid)
+        ORDER BY i.relname
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn table_ddl(pool: &Pool<Postgres>, schema: Option<&str>, table: &str) -> Result<String, String> {
+    Ok(table_statements(pool, schema, table, true).await?.join("\n\n"))
+}
+
+/// CREATE TABLE with its indexes, comments and partitions. Without
+/// `foreign_keys`, FOREIGN KEY constraints are left out so they can be added
+/// once every table exists (see `foreign_key_statements`).
+pub(crate) async fn table_statements(
+    pool: &Pool<Postgres>,
+    schema: Option<&str>,
+    table: &str,
+    foreign_keys: bool,
+) -> Result<Vec<String>, String> {
+    let info = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            c.relispartition AS is_partition,
+            CASE WHEN c.relkind = 'p' THEN pg_get_partkeydef(c.oid) END AS partition_key,
+            CASE WHEN c.relispartition THEN pg_get_expr(c.relpartbound, c.oid) END AS partition_bound,
+            (
+                SELECT format('%I.%I', pn.nspname, pc.relname)
+                FROM pg_inherits inh
+                JOIN pg_class pc ON pc.oid = inh.inhparent
+                JOIN pg_namespace pn ON pn.oid = pc.relnamespace
+                WHERE inh.inhrelid = c.oid
+                ORDER BY inh.inhseqno
+                LIMIT 1
+            ) AS parent,
+            ARRAY(
+                SELECT format('CREATE TABLE %I.%I PARTITION OF %I.%I %s;',
+                              chn.nspname, ch.relname, n.nspname, c.relname,
+                              pg_get_expr(ch.relpartbound, ch.oid))
+                FROM pg_inherits inh
+                JOIN pg_class ch ON ch.oid = inh.inhrelid
+                JOIN pg_namespace chn ON chn.oid = ch.relnamespace
+                WHERE inh.inhparent = c.oid AND ch.relispartition
+                ORDER BY ch.relname
+            ) AS partitions,
+            obj_description(c.oid, 'pg_class') AS comment
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND c.relname = $2
+          AND c.relkind IN ('r', 'p')
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Table '{}' not found", table))?;
+
+    let schema: String = info.try_get("schema").map_err(|e| e.to_string())?;
+    let is_partition: bool = info.try_get("is_partition").map_err(|e| e.to_string())?;
+    let partition_key: Option<String> = info.try_get("partition_key").map_err(|e| e.to_string())?;
+    let partition_bound: Option<String> = info.try_get("partition_bound").map_err(|e| e.to_string())?;
+    let parent: Option<String> = info.try_get("parent").map_err(|e| e.to_string())?;
+    let partitions: Vec<String> = info.try_get("partitions").map_err(|e| e.to_string())?;
+    let comment: Option<String> = info.try_get("comment").map_err(|e| e.to_string())?;
+
+    let qualified = PG.qualified(Some(&schema), table);
+    let columns = introspection::postgres::list_columns(pool, Some(&schema), table).await?;
+
+    // Locally defined constraints only; partitions inherit theirs from the parent
+    let constraints: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT c.conname::text, pg_get_constraintdef(c.oid, true)
+        FROM pg_constraint c
+        JOIN pg_class t ON t.oid = c.conrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        WHERE n.nspname = $1
+          AND t.relname = $2
+          AND c.contype IN ('p', 'u', 'f', 'c', 'x')
+          AND (c.contype <> 'f' OR $3)
+          AND c.conislocal
+        ORDER BY array_position(ARRAY['p', 'u', 'f', 'c', 'x'], c.contype::text), c.conname
+        "#,
+    )
+    .bind(&schema)
+    .bind(table)
+    .bind(foreign_keys)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut statements = Vec::new();
+
+    let mut create = match (&parent, is_partition) {
+        (Some(parent), true) => format!("CREATE TABLE {} PARTITION OF {}", qualified, parent),
+        _ => format!("CREATE TABLE {} (", qualified),
+    };
+    let mut body: Vec<String> = Vec::new();
+    if !is_partition {
+        body.extend(columns.iter().map(column_definition));
+    }
+    body.extend(
+        constraints
+            .iter()
+            .map(|(name, def)| format!("CONSTRAINT {} {}", PG.quote_ident(name), def)),
+    );
+
+    if is_partition {
+        if !body.is_empty() {
+            create.push_str(&format!(" (\n    {}\n)", body.join(",\n    ")));
+        }
+        if let Some(bound) = &partition_bound {
+            create.push_str(&format!("\n{}", bound));
+        }
+    } else {
+        create.push_str(&format!("\n    {}\n)", body.join(",\n    ")));
+        if let Some(parent) = &parent {
+            create.push_str(&format!("\nINHERITS ({})", parent));
+        }
+    }
+    if let Some(key) = &partition_key {
+        create.push_str(&format!("\nPARTITION BY {}", key));
+    }
+    create.push(';');
+    statements.push(create);
+
+    statements.extend(index_statements(pool, &schema, table).await?);
+
+    if let Some(comment) = &comment {
+        statements.push(format!("COMMENT ON TABLE {} IS {};", qualified, PG.quote_literal(comment)));
+    }
+    for column in &columns {
+        if let Some(comment) = &column.comment {
+            statements.push(format!(
+                "COMMENT ON COLUMN {}.{} IS {};",
+                qualified,
+                PG.quote_ident(&column.name),
+                PG.quote_literal(comment)
+            ));
+        }
+    }
+
+    statements.extend(partitions);
+
+    Ok(statements)
+}
+
+/// ALTER TABLE ... ADD CONSTRAINT statements for the table's own foreign keys
+pub(crate) async fn foreign_key_statements(pool: &Pool<Postgres>, schema: &str, table: &str) -> Result<Vec<String>, String> {
+    let constraints: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT c.conname::text, pg_get_constraintdef(c.oid, true)
+        FROM pg_constraint c
+        JOIN pg_class t ON t.oid = c.conrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        WHERE n.nspname = $1
+          AND t.relname = $2
+          AND c.contype = 'f'
+          AND c.conislocal
+        ORDER BY c.conname
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let qualified = PG.qualified(Some(schema), table);
+    Ok(constraints
+        .iter()
+        .map(|(name, def)| format!("ALTER TABLE {} ADD CONSTRAINT {} {};", qualified, PG.quote_ident(name), def))
+        .collect())
+}
+
+async fn view_ddl(pool: &Pool<Postgres>, schema: Option<&str>, view: &str) -> Result<String, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            c.relkind = 'm' AS is_materialized,
+            pg_get_viewdef(c.oid, true) AS definition,
+            obj_description(c.oid, 'pg_class') AS comment
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND c.relname = $2
+          AND c.relkind IN ('v', 'm')
+        "#,
+    )
+    .bind(schema)
+    .bind(view)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("View '{}' not found", view))?;
+
+    let schema: String = row.try_get("schema").map_err(|e| e.to_string())?;
+    let is_materialized: bool = row.try_get("is_materialized").map_err(|e| e.to_string())?;
+    let definition: String = row.try_get("definition").map_err(|e| e.to_string())?;
+    let comment: Option<String> = row.try_get("comment").map_err(|e| e.to_string())?;
+
+    let qualified = PG.qualified(Some(&schema), view);
+    let select = definition.trim().trim_end_matches(';');
+
+    let mut statements = Vec::new();
+    if is_materialized {
+        statements.push(format!("CREATE MATERIALIZED VIEW {} AS\n{}\nWITH DATA;", qualified, select));
+        statements.extend(index_statements(pool, &schema, view).await?);
+    } else {
+        statements.push(format!("CREATE OR REPLACE VIEW {} AS\n{};", qualified, select));
+    }
+    if let Some(comment) = &comment {
+        let kind = if is_materialized { "MATERIALIZED VIEW" } else { "VIEW" };
+        statements.push(format!("COMMENT ON {} {} IS {};", kind, qualified, PG.quote_literal(comment)));
+    }
+
+    Ok(statements.join("\n\n"))
+}
+
+/// Every overload of the named function or procedure
+async fn routine_ddl(
+    pool: &Pool<Postgres>,
+    object_type: ObjectType,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    let prokind = if object_type == ObjectType::Procedure { "p" } else { "f" };
+    let definitions: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT pg_get_functiondef(p.oid)
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND p.proname = $2
+          AND p.prokind = $3::"char"
+        ORDER BY pg_get_function_identity_arguments(p.oid)
+        "#,
+    )
+    .bind(schema)
+    .bind(name)
+    .bind(prokind)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if definitions.is_empty() {
+        return Err(format!("Routine '{}' not found", name));
+    }
+
+    Ok(definitions
+        .iter()
+        .map(|def| format!("{};", def.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n\n"))
+}
+
+async fn sequence_ddl(pool: &Pool<Postgres>, schema: Option<&str>, name: &str) -> Result<String, String> {
+    let sequence = introspection::postgres::list_sequences(pool, schema)
+        .await?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Sequence '{}' not found", name))?;
+
+    let mut statements = vec![create_sequence_statement(&sequence)];
+    statements.extend(owned_by_statement(&sequence));
+
+    Ok(statements.join("\n\n"))
+}
+
+pub(crate) fn create_sequence_statement(sequence: &SequenceInfo) -> String {
+    let qualified = PG.qualified(sequence.schema.as_deref(), &sequence.name);
+    let mut create = format!("CREATE SEQUENCE {}", qualified);
+    if let Some(data_type) = &sequence.data_type {
+        create.push_str(&format!("\n    AS {}", data_type));
+    }
+    create.push_str(&format!("\n    INCREMENT BY {}", sequence.increment));
+    if let Some(min) = sequence.min_value {
+        create.push_str(&format!("\n    MINVALUE {}", min));
+    }
+    if let Some(max) = sequence.max_value {
+        create.push_str(&format!("\n    MAXVALUE {}", max));
+    }
+    if let Some(start) = sequence.start_value {
+        create.push_str(&format!("\n    START WITH {}", start));
+    }
+    create.push_str(if sequence.cycle { "\n    CYCLE;" } else { "\n    NO CYCLE;" });
+    create
+}
+
+/// `ALTER SEQUENCE ... OWNED BY`, which needs the owning table to exist
+pub(crate) fn owned_by_statement(sequence: &SequenceInfo) -> Option<String> {
+    let owned_by = sequence.owned_by.as_ref()?;
+    let schema = sequence.schema.clone().unwrap_or_default();
+    Some(format!(
+        "ALTER SEQUENCE {} OWNED BY {}.{};",
+        PG.qualified(Some(&schema), &sequence.name),
+        PG.quote_ident(&schema),
+        owned_by
+    ))
+}
+
+async fn trigger_ddl(pool: &Pool<Postgres>, schema: Option<&str>, name: &str) -> Result<String, String> {
+    let definitions: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT pg_get_triggerdef(t.oid, true) || ';'
+        FROM pg_trigger t
+        JOIN pg_class c ON c.oid = t.tgrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND t.tgname = $2
+          AND NOT t.tgisinternal
+        ORDER BY c.relname
+        "#,
+    )
+    .bind(schema)
+    .bind(name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if definitions.is_empty() {
+        return Err(format!("Trigger '{}' not found", name));
+    }
+    Ok(definitions.join("\n\n"))
+}
@@ -0,0 +1,49 @@
+use sqlx::{Pool, Sqlite, Row};
+use crate::db::dialect::Dialect;
+use crate::db::types::ObjectType;
+
+/// Generate the CREATE statement(s) for an object from `sqlite_master`.
+///
+/// Tables are scripted together with their explicitly created indexes.
+pub async fn generate_ddl(
+    pool: &Pool<Sqlite>,
+    object_type: ObjectType,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    if matches!(object_type, ObjectType::Function | ObjectType::Procedure | ObjectType::Sequence) {
+        return Err(format!("SQLite does not support {} objects", object_type.as_str()));
+    }
+    let object_kind = object_type.as_str();
+
+    let schema = schema.unwrap_or("main");
+    let master = format!("{}.sqlite_master", Dialect::Sqlite.quote_ident(schema));
+
+    let create: Option<String> = sqlx::query(&format!("SELECT sql FROM {} WHERE type = ?1 AND name = ?2", master))
+        .bind(object_kind)
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|row| row.try_get("sql"))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let create = create.ok_or_else(|| format!("{} '{}' not found", object_kind, name))?;
+
+    let mut statements = vec![format!("{};", create.trim_end())];
+
+    if object_type == ObjectType::Table {
+        // Auto-indexes for PRIMARY KEY / UNIQUE have NULL sql and are part of the table definition
+        let indexes: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT sql FROM {} WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL ORDER BY name",
+            master
+        ))
+        .bind(name)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        statements.extend(indexes.iter().map(|sql| format!("{};", sql.trim_end())));
+    }
+
+    Ok(statements.join("\n\n"))
+}
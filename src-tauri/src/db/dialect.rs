@@ -0,0 +1,73 @@
+use serde::{Serialize, Deserialize};
+
+/// SQL flavour of a connection, used wherever we have to build SQL text ourselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    Postgres,
+    #[serde(rename = "mysql")]
+    MySql,
+    Sqlite,
+}
+
+impl Dialect {
+    /// Quote an identifier (table, column, schema...) for this dialect
+    pub fn quote_ident(&self, ident: &str) -> String {
+        match self {
+            Dialect::MySql => format!("`{}`", ident.replace('`', "``")),
+            Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", ident.replace('"', "\"\"")),
+        }
+    }
+
+    /// Quote an optionally schema-qualified object name
+    pub fn qualified(&self, schema: Option<&str>, name: &str) -> String {
+        match schema {
+            Some(schema) if !schema.is_empty() => {
+                format!("{}.{}", self.quote_ident(schema), self.quote_ident(name))
+            }
+            _ => self.quote_ident(name),
+        }
+    }
+
+    /// Quote a string literal for this dialect
+    pub fn quote_literal(&self, value: &str) -> String {
+        match self {
+            // MySQL treats backslash as an escape character by default
+            Dialect::MySql => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''")),
+            Dialect::Postgres | Dialect::Sqlite => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+fn is_identifier_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || byte >= 0x80
+}
+
+/// Byte offsets at which `name` stands alone as an identifier in an SQL
+/// expression, bare or quoted, ignoring ASCII case and string literals
+pub fn identifier_offsets(sql: &str, name: &str) -> Vec<usize> {
+    let bytes = sql.as_bytes();
+    let name = name.as_bytes();
+    let mut offsets = Vec::new();
+    if name.is_empty() {
+        return offsets;
+    }
+    let mut in_literal = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\'' {
+            in_literal = !in_literal;
+        } else if !in_literal
+            && bytes[i..].len() >= name.len()
+            && bytes[i..i + name.len()].eq_ignore_ascii_case(name)
+            && (i == 0 || !is_identifier_byte(bytes[i - 1]))
+            && !bytes.get(i + name.len()).is_some_and(|b| is_identifier_byte(*b))
+        {
+            offsets.push(i);
+            i += name.len();
+            continue;
+        }
+        i += 1;
+    }
+    offsets
+}
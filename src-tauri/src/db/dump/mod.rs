@@ -0,0 +1,475 @@
+// Database dumps
+//
+// `dump_database` writes a schema (or a whole Postgres database) as a plain
+// SQL script that `restore_dump` replays through `script::execute_file`. The
+// dump reads through a one-connection pool whose connection sits inside a
+// single read-only snapshot transaction (see `snapshot_pool`), so every
+// catalog query and every table read sees the same state even while other
+// sessions keep writing. The engine-specific parts live in one module per
+// engine:
+// - postgres.rs: types, sequences, routines, tables, COPY or INSERT data,
+//   then foreign keys, views and triggers, all schema-qualified
+// - mysql.rs: SHOW CREATE output with foreign key checks off, INSERT data
+// - sqlite.rs: sqlite_master statements in creation order, INSERT data
+
+pub mod postgres;
+pub mod mysql;
+pub mod sqlite;
+
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sqlx::mysql::MySqlPoolOptions;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::Executor;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::connect;
+use crate::db::dialect::Dialect;
+use crate::db::export::text::{TextEncoding, TextWriter};
+use crate::db::script;
+use crate::db::tasks::CancelFlag;
+use crate::db::types::{
+    DumpDataFormat, DumpOptions, DumpProgress, DumpSummary, OnError, SqlFileRequest, SqlFileSummary, SqlPool,
+    SqliteAttachment,
+};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// First line of every dump
+const DUMP_HEADER: &str = "-- NebulaDB dump";
+
+fn dialect_name(dialect: Dialect) -> &'static str {
+    match dialect {
+        Dialect::Postgres => "postgres",
+        Dialect::MySql => "mysql",
+        Dialect::Sqlite => "sqlite",
+    }
+}
+
+/// Case-insensitive glob match supporting `*` and `?`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, at)) => {
+                    p = star + 1;
+                    t = at + 1;
+                    backtrack = Some((star, at + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// The include / exclude patterns of a dump
+pub struct TableFilter<'a> {
+    include: &'a [String],
+    exclude: &'a [String],
+}
+
+impl TableFilter<'_> {
+    pub fn new(options: &DumpOptions) -> TableFilter<'_> {
+        TableFilter { include: &options.include, exclude: &options.exclude }
+    }
+
+    pub fn matches(&self, schema: Option<&str>, name: &str) -> bool {
+        let qualified = schema.map(|schema| format!("{}.{}", schema, name));
+        let hit = |pattern: &String| {
+            glob_match(pattern, name) || qualified.as_deref().is_some_and(|q| glob_match(pattern, q))
+        };
+        (self.include.is_empty() || self.include.iter().any(hit)) && !self.exclude.iter().any(hit)
+    }
+}
+
+/// Order `names` so that every name comes after the names it depends on.
+/// `edges` holds `(name, dependency)` pairs; names caught in a cycle keep
+/// their original order at the end.
+pub fn dependency_order(names: Vec<String>, edges: &[(String, String)]) -> Vec<String> {
+    let mut pending = names;
+    let mut ordered: Vec<String> = Vec::with_capacity(pending.len());
+    loop {
+        let ready = pending.iter().position(|name| {
+            edges
+                .iter()
+                .filter(|(from, to)| from == name && to != name)
+                .all(|(_, to)| !pending.contains(to))
+        });
+        match ready {
+            Some(i) => ordered.push(pending.remove(i)),
+            None => break,
+        }
+    }
+    ordered.extend(pending);
+    ordered
+}
+
+/// Writes the dump file, batches INSERT statements and reports progress
+pub struct DumpWriter<'a> {
+    out: TextWriter,
+    app: &'a AppHandle,
+    task_id: &'a str,
+    cancel: &'a CancelFlag,
+    batch_size: usize,
+    /// `INSERT INTO ... VALUES` prefix of the current table and the rows
+    /// written under it so far
+    insert: Option<(String, usize)>,
+    tables_total: usize,
+    tables_done: usize,
+    table: Option<String>,
+    rows: u64,
+    cancelled: bool,
+    last_progress: Instant,
+}
+
+impl<'a> DumpWriter<'a> {
+    fn create(
+        path: &Path,
+        app: &'a AppHandle,
+        task_id: &'a str,
+        cancel: &'a CancelFlag,
+        batch_size: usize,
+    ) -> Result<DumpWriter<'a>, String> {
+        Ok(DumpWriter {
+            out: TextWriter::create(path, TextEncoding::Utf8 { bom: false })?,
+            app,
+            task_id,
+            cancel,
+            batch_size: batch_size.max(1),
+            insert: None,
+            tables_total: 0,
+            tables_done: 0,
+            table: None,
+            rows: 0,
+            cancelled: false,
+            last_progress: Instant::now(),
+        })
+    }
+
+    pub fn write(&mut self, text: &str) -> Result<(), String> {
+        self.out.write_str(text)
+    }
+
+    /// Write a statement followed by a blank line
+    pub fn statement(&mut self, sql: &str) -> Result<(), String> {
+        self.out.write_str(sql)?;
+        self.out.write_str("\n\n")
+    }
+
+    /// Write a `--` comment line announcing the next section
+    pub fn section(&mut self, title: &str) -> Result<(), String> {
+        self.out.write_str(&format!("--\n-- {}\n--\n\n", title))
+    }
+
+    /// Number of tables whose rows will be written, for progress events
+    pub fn set_tables_total(&mut self, total: usize) {
+        self.tables_total = total;
+    }
+
+    pub fn begin_table(&mut self, name: &str) {
+        self.table = Some(name.to_string());
+        self.emit_progress();
+    }
+
+    pub fn end_table(&mut self) {
+        self.tables_done += 1;
+        self.table = None;
+    }
+
+    /// Write COPY data as received from the server; every row is one line
+    pub fn copy_data(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.out.write_bytes(chunk)?;
+        self.rows += chunk.iter().filter(|b| **b == b'\n').count() as u64;
+        self.tick();
+        Ok(())
+    }
+
+    /// Start the INSERT statements of a table; `prefix` ends with `VALUES`
+    pub fn begin_inserts(&mut self, prefix: String) {
+        self.insert = Some((prefix, 0));
+    }
+
+    /// Add a parenthesized row of literals to the current INSERT statement
+    pub fn insert_row(&mut self, values: &str) -> Result<(), String> {
+        let (prefix, in_batch) = self
+            .insert
+            .as_mut()
+            .ok_or_else(|| "INSERT rows written outside a table".to_string())?;
+        let mut text = if *in_batch == 0 { format!("{}\n", prefix) } else { ",\n".to_string() };
+        text.push_str("  ");
+        text.push_str(values);
+        *in_batch += 1;
+        if *in_batch == self.batch_size {
+            text.push_str(";\n");
+            *in_batch = 0;
+        }
+        self.out.write_str(&text)?;
+        self.rows += 1;
+        self.tick();
+        Ok(())
+    }
+
+    pub fn end_inserts(&mut self) -> Result<(), String> {
+        if let Some((_, in_batch)) = self.insert.take() {
+            if in_batch > 0 {
+                self.out.write_str(";\n")?;
+            }
+            self.out.write_str("\n")?;
+        }
+        Ok(())
+    }
+
+    /// Whether the task was cancelled; the backends stop writing as soon as it is
+    pub fn is_cancelled(&mut self) -> bool {
+        if self.cancel.is_cancelled() {
+            self.cancelled = true;
+        }
+        self.cancelled
+    }
+
+    pub fn cancel_flag(&self) -> &CancelFlag {
+        self.cancel
+    }
+
+    fn tick(&mut self) {
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            self.emit_progress();
+        }
+    }
+
+    fn emit_progress(&mut self) {
+        self.last_progress = Instant::now();
+        let _ = self.app.emit(
+            "dump-progress",
+            DumpProgress {
+                task_id: self.task_id.to_string(),
+                table: self.table.clone(),
+                tables_done: self.tables_done,
+                tables_total: self.tables_total,
+                rows: self.rows,
+                bytes: self.out.bytes_written(),
+            },
+        );
+    }
+}
+
+/// Raised when the snapshot connection has to be reopened, which would
+/// silently mix two snapshots in one dump
+const SNAPSHOT_LOST: &str = "The connection to the database was lost during the dump";
+
+/// Fails the second time it is called
+fn first_connection(connected: &AtomicBool) -> Result<(), sqlx::Error> {
+    if connected.swap(true, Ordering::SeqCst) {
+        return Err(sqlx::Error::Protocol(SNAPSHOT_LOST.to_string()));
+    }
+    Ok(())
+}
+
+/// A pool with a single connection that runs every query inside one
+/// read-only REPEATABLE READ transaction (a deferred read transaction on
+/// SQLite). The connection never expires, so the snapshot lasts as long as
+/// the pool.
+///
+/// Postgres sessions get an empty search_path so that the catalog functions
+/// qualify every name they print; MySQL sessions switch to `database` and UTC.
+async fn snapshot_pool(pool: &SqlPool, database: Option<&str>) -> Result<SqlPool, String> {
+    let connected = Arc::new(AtomicBool::new(false));
+    Ok(match pool {
+        SqlPool::Postgres(pool) => SqlPool::Postgres(
+            PgPoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .after_connect(move |conn, _| {
+                    let connected = connected.clone();
+                    Box::pin(async move {
+                        first_connection(&connected)?;
+                        conn.execute("SET search_path = ''").await?;
+                        conn.execute("BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY").await?;
+                        Ok(())
+                    })
+                })
+                .connect_with((*pool.connect_options()).clone())
+                .await
+                .map_err(|e| e.to_string())?,
+        ),
+        SqlPool::MySql(pool) => {
+            let database = database.map(|db| format!("USE {}", Dialect::MySql.quote_ident(db)));
+            SqlPool::MySql(
+                MySqlPoolOptions::new()
+                    .max_connections(1)
+                    .idle_timeout(None)
+                    .max_lifetime(None)
+                    .after_connect(move |conn, _| {
+                        let connected = connected.clone();
+                        let database = database.clone();
+                        Box::pin(async move {
+                            first_connection(&connected)?;
+                            if let Some(database) = &database {
+                                conn.execute(database.as_str()).await?;
+                            }
+                            conn.execute("SET time_zone = '+00:00'").await?;
+                            conn.execute("SET SESSION TRANSACTION ISOLATION LEVEL REPEATABLE READ").await?;
+                            conn.execute("START TRANSACTION WITH CONSISTENT SNAPSHOT, READ ONLY").await?;
+                            Ok(())
+                        })
+                    })
+                    .connect_with((*pool.connect_options()).clone())
+                    .await
+                    .map_err(|e| e.to_string())?,
+            )
+        }
+        SqlPool::Sqlite(pool) => {
+            // Attachments are made per connection, not in the connect options
+            let attached: Vec<(String, String)> = sqlx::query_as(
+                "SELECT name, file FROM pragma_database_list WHERE name NOT IN ('main', 'temp') AND file <> '' ORDER BY seq",
+            )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+            let attachments: Vec<(String, String)> = attached
+                .into_iter()
+                .map(|(alias, path)| connect::attach_statement(&SqliteAttachment { alias, path, read_only: true }))
+                .collect();
+            SqlPool::Sqlite(
+                SqlitePoolOptions::new()
+                    .max_connections(1)
+                    .idle_timeout(None)
+                    .max_lifetime(None)
+                    .after_connect(move |conn, _| {
+                        let connected = connected.clone();
+                        let attachments = attachments.clone();
+                        Box::pin(async move {
+                            first_connection(&connected)?;
+                            for (statement, path) in &attachments {
+                                conn.execute(sqlx::query(statement).bind(path)).await?;
+                            }
+                            conn.execute("BEGIN").await?;
+                            // A deferred transaction takes its snapshot at the first read
+                            conn.execute("SELECT count(*) FROM sqlite_master").await?;
+                            Ok(())
+                        })
+                    })
+                    .connect_with((*pool.connect_options()).clone())
+                    .await
+                    .map_err(|e| e.to_string())?,
+            )
+        }
+    })
+}
+
+/// Write a dump of the database behind `pool` to `options.path`. A failed or
+/// cancelled dump removes the partial file.
+pub async fn dump_database(
+    app: &AppHandle,
+    pool: &SqlPool,
+    options: &DumpOptions,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<DumpSummary, String> {
+    let start = Instant::now();
+    let dialect = pool.dialect();
+    if options.data_format == Some(DumpDataFormat::Copy) && dialect != Dialect::Postgres {
+        return Err("COPY data is only supported for PostgreSQL dumps; use INSERT statements".to_string());
+    }
+
+    let path = Path::new(&options.path);
+    let snapshot = snapshot_pool(pool, options.schema.as_deref()).await?;
+    let mut out = DumpWriter::create(path, app, task_id, cancel, options.batch_size)?;
+    let header = format!(
+        "{}\n-- Dialect: {}\n-- Dumped at: {}\n\n",
+        DUMP_HEADER,
+        dialect_name(dialect),
+        sqlx::types::chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    let mut result = out.write(&header);
+    if result.is_ok() {
+        result = match &snapshot {
+            SqlPool::Postgres(pool) => postgres::dump(&mut out, pool, options).await,
+            SqlPool::MySql(pool) => mysql::dump(&mut out, pool, options).await,
+            SqlPool::Sqlite(pool) => sqlite::dump(&mut out, pool, options).await,
+        };
+    }
+    // Ends the snapshot transaction
+    match snapshot {
+        SqlPool::Postgres(pool) => pool.close().await,
+        SqlPool::MySql(pool) => pool.close().await,
+        SqlPool::Sqlite(pool) => pool.close().await,
+    }
+    let result = result.and_then(|_| out.out.flush());
+    let cancelled = out.is_cancelled();
+    if let Err(e) = result {
+        drop(out);
+        let _ = std::fs::remove_file(path);
+        return Err(e);
+    }
+
+    out.emit_progress();
+    let summary = DumpSummary {
+        path: options.path.clone(),
+        tables: out.tables_done,
+        rows: out.rows,
+        bytes: out.out.bytes_written(),
+        cancelled,
+        execution_time_ms: start.elapsed().as_millis(),
+    };
+    if cancelled {
+        drop(out);
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(summary)
+}
+
+/// Replay a dump written by `dump_database`. Dumps of another engine are
+/// refused; files without a dump header are run as plain scripts.
+pub async fn restore_dump(
+    app: &AppHandle,
+    pool: &SqlPool,
+    request: &SqlFileRequest,
+    database: Option<&str>,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<SqlFileSummary, String> {
+    let file = std::fs::File::open(&request.path).map_err(|e| e.to_string())?;
+    let mut lines = BufReader::new(file).lines();
+    let mut is_dump = false;
+    if let Some(Ok(first)) = lines.next() {
+        if first.trim_start_matches('\u{feff}').trim_end() == DUMP_HEADER {
+            is_dump = true;
+            let source = lines
+                .take(5)
+                .map_while(Result::ok)
+                .find_map(|line| line.strip_prefix("-- Dialect:").map(|d| d.trim().to_string()));
+            let target = dialect_name(pool.dialect());
+            if let Some(source) = source.filter(|source| source != target) {
+                return Err(format!(
+                    "This dump was written from a {} database and cannot be restored on {}",
+                    source, target
+                ));
+            }
+        }
+    }
+    // A failed statement aborts a Postgres transaction and every statement after
+    // it would fail too, so continuing past errors runs the dump without its
+    // BEGIN/COMMIT wrapper
+    let skip_transaction = is_dump && pool.dialect() == Dialect::Postgres && request.on_error == OnError::Continue;
+    script::execute_file(app, pool, request, database, task_id, cancel, skip_transaction).await
+}
@@ -0,0 +1,255 @@
+use sqlx::{MySql, Pool};
+
+use crate::db::ddl;
+use crate::db::dialect::Dialect;
+use crate::db::dump::{dependency_order, DumpWriter, TableFilter};
+use crate::db::export::sql::sql_literal;
+use crate::db::introspection;
+use crate::db::stream::{self, Cell, RowSink, StreamColumn};
+use crate::db::types::{DumpContent, DumpOptions, ObjectType};
+
+const MY: Dialect = Dialect::MySql;
+
+/// Checks are off while loading, as with mysqldump, so tables can be created
+/// and filled in any order; the footer restores the session's values
+const SESSION_SETTINGS: &str = "SET NAMES utf8mb4;
+SET @OLD_FOREIGN_KEY_CHECKS = @@FOREIGN_KEY_CHECKS, FOREIGN_KEY_CHECKS = 0;
+SET @OLD_UNIQUE_CHECKS = @@UNIQUE_CHECKS, UNIQUE_CHECKS = 0;
+SET @OLD_SQL_MODE = @@SQL_MODE, SQL_MODE = 'NO_AUTO_VALUE_ON_ZERO';
+SET @OLD_TIME_ZONE = @@TIME_ZONE, TIME_ZONE = '+00:00';
+
+";
+
+const SESSION_RESET: &str = "SET TIME_ZONE = @OLD_TIME_ZONE;
+SET SQL_MODE = @OLD_SQL_MODE;
+SET UNIQUE_CHECKS = @OLD_UNIQUE_CHECKS;
+SET FOREIGN_KEY_CHECKS = @OLD_FOREIGN_KEY_CHECKS;
+";
+
+/// Writes streamed rows as INSERT values
+struct InsertSink<'w, 'a> {
+    out: &'w mut DumpWriter<'a>,
+}
+
+impl RowSink for InsertSink<'_, '_> {
+    fn begin_result(&mut self, _columns: &[StreamColumn]) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: Vec<Cell>) -> Result<(), String> {
+        let values: Vec<String> = row.iter().map(|cell| sql_literal(MY, cell)).collect();
+        self.out.insert_row(&format!("({})", values.join(", ")))
+    }
+}
+
+/// Remove the `DEFINER=user@host` clause, so the restoring account becomes
+/// the definer instead of needing the privilege to name another one
+pub(crate) fn strip_definer(sql: &str) -> String {
+    let Some(start) = sql.find(" DEFINER=") else {
+        return sql.to_string();
+    };
+    let rest = &sql[start + " DEFINER=".len()..];
+    let mut quote: Option<char> = None;
+    let mut end = rest.len();
+    for (i, c) in rest.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '`' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => {
+                end = i;
+                break;
+            }
+            None => {}
+        }
+    }
+    format!("{}{}", &sql[..start], &rest[end..])
+}
+
+/// Wrap a routine or trigger body in a DELIMITER block
+fn delimited(sql: &str) -> String {
+    format!("DELIMITER ;;\n{};;\nDELIMITER ;", sql.trim_end().trim_end_matches(';'))
+}
+
+/// The `AUTO_INCREMENT=n` table option of a SHOW CREATE TABLE statement
+fn auto_increment(create: &str) -> Option<u64> {
+    let options = &create[create.rfind(')')?..];
+    let value = &options[options.find("AUTO_INCREMENT=")? + "AUTO_INCREMENT=".len()..];
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Dump one database. Object names are written unqualified, so the dump can
+/// be restored into a database of another name.
+pub async fn dump(out: &mut DumpWriter<'_>, pool: &Pool<MySql>, options: &DumpOptions) -> Result<(), String> {
+    let filter = TableFilter::new(options);
+    let database = match &options.schema {
+        Some(schema) => schema.clone(),
+        None => sqlx::query_scalar::<_, Option<String>>("SELECT DATABASE()")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "No database is selected; choose the database to dump".to_string())?,
+    };
+    let with_schema = options.content != DumpContent::Data;
+    let with_data = options.content != DumpContent::Schema;
+
+    let relations = introspection::mysql::list_tables(pool, Some(&database)).await?;
+    let included = |name: &String| filter.matches(Some(&database), name);
+    let tables: Vec<String> = relations
+        .iter()
+        .filter(|r| r.table_type == "TABLE")
+        .map(|r| r.name.clone())
+        .filter(included)
+        .collect();
+    let views: Vec<String> = relations
+        .iter()
+        .filter(|r| r.table_type == "VIEW")
+        .map(|r| r.name.clone())
+        .filter(included)
+        .collect();
+
+    // Parents first, so the data also loads with foreign key checks on
+    let references: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT TABLE_NAME, REFERENCED_TABLE_NAME
+        FROM information_schema.KEY_COLUMN_USAGE
+        WHERE TABLE_SCHEMA = ?
+          AND REFERENCED_TABLE_SCHEMA = ?
+          AND REFERENCED_TABLE_NAME IS NOT NULL
+        "#,
+    )
+    .bind(&database)
+    .bind(&database)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let tables = dependency_order(tables, &references);
+    out.set_tables_total(if with_data { tables.len() } else { 0 });
+
+    out.write(SESSION_SETTINGS)?;
+
+    if with_schema && options.drop_objects {
+        out.section("Drop existing objects")?;
+        for view in views.iter().rev() {
+            out.write(&format!("DROP VIEW IF EXISTS {};\n", MY.quote_ident(view)))?;
+        }
+        for table in tables.iter().rev() {
+            out.write(&format!("DROP TABLE IF EXISTS {};\n", MY.quote_ident(table)))?;
+        }
+        out.write("\n")?;
+    }
+
+    // SHOW CREATE TABLE carries the AUTO_INCREMENT counter
+    let mut counters = Vec::new();
+    if with_schema {
+        out.section("Tables")?;
+    }
+    for table in &tables {
+        if out.is_cancelled() {
+            return Ok(());
+        }
+        let create = ddl::mysql::generate_ddl(pool, ObjectType::Table, Some(&database), table).await?;
+        if with_schema {
+            out.statement(&create)?;
+        } else if let Some(value) = auto_increment(&create) {
+            counters.push(format!("ALTER TABLE {} AUTO_INCREMENT = {};", MY.quote_ident(table), value));
+        }
+    }
+
+    if with_data {
+        for table in &tables {
+            if out.is_cancelled() {
+                return Ok(());
+            }
+            out.section(&format!("Data for {}", table))?;
+            out.begin_table(table);
+            write_rows(out, pool, &database, table).await?;
+            if out.is_cancelled() {
+                return Ok(());
+            }
+            out.end_table();
+        }
+        if !counters.is_empty() {
+            out.section("AUTO_INCREMENT counters")?;
+            for statement in counters {
+                out.statement(&statement)?;
+            }
+        }
+    }
+
+    if with_schema {
+        let routines = introspection::mysql::list_routines(pool, Some(&database)).await?;
+        if !routines.is_empty() {
+            out.section("Routines")?;
+        }
+        for routine in &routines {
+            let object_type = if routine.kind == "PROCEDURE" { ObjectType::Procedure } else { ObjectType::Function };
+            if options.drop_objects {
+                out.statement(&format!("DROP {} IF EXISTS {};", routine.kind, MY.quote_ident(&routine.name)))?;
+            }
+            match ddl::mysql::generate_ddl(pool, object_type, Some(&database), &routine.name).await {
+                Ok(create) => out.statement(&delimited(&strip_definer(&create)))?,
+                // Usually a missing privilege on the routine body; the rest of the dump is still usable
+                Err(e) => out.write(&format!("-- Skipped {} {}: {}\n\n", routine.kind, routine.name, e))?,
+            }
+        }
+
+        // A view depends on another when its definition names it
+        let mut definitions = Vec::new();
+        for view in &views {
+            let create = ddl::mysql::generate_ddl(pool, ObjectType::View, Some(&database), view).await?;
+            definitions.push((view.clone(), strip_definer(&create)));
+        }
+        let edges: Vec<(String, String)> = definitions
+            .iter()
+            .flat_map(|(view, create)| {
+                views
+                    .iter()
+                    .filter(move |other| *other != view && create.contains(&MY.quote_ident(other)))
+                    .map(move |other| (view.clone(), other.clone()))
+            })
+            .collect();
+        if !views.is_empty() {
+            out.section("Views")?;
+        }
+        for view in dependency_order(views.clone(), &edges) {
+            if let Some((_, create)) = definitions.iter().find(|(name, _)| *name == view) {
+                out.statement(create)?;
+            }
+        }
+
+        let triggers = introspection::mysql::list_triggers(pool, Some(&database), None).await?;
+        let triggers: Vec<_> = triggers.iter().filter(|t| tables.contains(&t.table)).collect();
+        if !triggers.is_empty() {
+            out.section("Triggers")?;
+        }
+        for trigger in triggers {
+            let create = ddl::mysql::generate_ddl(pool, ObjectType::Trigger, Some(&database), &trigger.name).await?;
+            out.statement(&delimited(&strip_definer(&create)))?;
+        }
+    }
+
+    out.write(SESSION_RESET)
+}
+
+/// Write the table's rows as INSERT statements; generated columns are computed on restore
+async fn write_rows(out: &mut DumpWriter<'_>, pool: &Pool<MySql>, database: &str, table: &str) -> Result<(), String> {
+    let columns: Vec<String> = introspection::mysql::list_columns(pool, Some(database), table)
+        .await?
+        .iter()
+        .filter(|c| c.generated.is_none())
+        .map(|c| MY.quote_ident(&c.name))
+        .collect();
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let query = format!("SELECT {} FROM {}", columns.join(", "), MY.qualified(Some(database), table));
+    out.begin_inserts(format!("INSERT INTO {} ({}) VALUES", MY.quote_ident(table), columns.join(", ")));
+    let cancel = out.cancel_flag().clone();
+    let stats = stream::mysql::stream_query(pool, &query, None, &mut InsertSink { out: &mut *out }, &cancel).await?;
+    if stats.cancelled {
+        return Ok(());
+    }
+    out.end_inserts()
+}
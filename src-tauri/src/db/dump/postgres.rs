@@ -0,0 +1,717 @@
+use std::collections::HashSet;
+use futures::TryStreamExt;
+use sqlx::{Pool, Postgres, Row};
+
+use crate::db::ddl;
+use crate::db::dialect::Dialect;
+use crate::db::dump::{dependency_order, DumpWriter, TableFilter};
+use crate::db::introspection;
+use crate::db::types::{DumpContent, DumpDataFormat, DumpOptions, ObjectType, SequenceInfo};
+
+const PG: Dialect = Dialect::Postgres;
+
+/// Settings the restore session needs; an empty search_path matches the
+/// fully qualified names the dump is written with
+const SESSION_SETTINGS: &str = "SET client_encoding = 'UTF8';
+SET standard_conforming_strings = on;
+SET check_function_bodies = false;
+SET client_min_messages = warning;
+SELECT pg_catalog.set_config('search_path', '', false);
+
+";
+
+/// A table or partition to dump
+struct DumpTable {
+    schema: String,
+    name: String,
+    /// Partitioned parents hold no rows of their own
+    partitioned: bool,
+    /// Partitions are created by their parent's DDL
+    is_partition: bool,
+    /// Inheritance parents and referenced tables, as `schema.name`
+    depends_on: Vec<String>,
+}
+
+impl DumpTable {
+    fn key(&self) -> String {
+        format!("{}.{}", self.schema, self.name)
+    }
+
+    fn qualified(&self) -> String {
+        PG.qualified(Some(&self.schema), &self.name)
+    }
+}
+
+/// Dump the schemas named in `options`, or every user schema
+pub async fn dump(out: &mut DumpWriter<'_>, pool: &Pool<Postgres>, options: &DumpOptions) -> Result<(), String> {
+    let filter = TableFilter::new(options);
+    let schemas = match &options.schema {
+        Some(schema) => vec![schema.clone()],
+        None => user_schemas(pool).await?,
+    };
+    let with_schema = options.content != DumpContent::Data;
+    let with_data = options.content != DumpContent::Schema;
+
+    let tables = list_tables(pool, &schemas, &filter).await?;
+    let data_tables: Vec<&DumpTable> = tables.iter().filter(|t| !t.partitioned).collect();
+    out.set_tables_total(if with_data { data_tables.len() } else { 0 });
+
+    out.write(SESSION_SETTINGS)?;
+    out.statement("BEGIN;")?;
+
+    let sequences = list_sequences(pool, &schemas).await?;
+    let table_keys: HashSet<String> = tables.iter().map(DumpTable::key).collect();
+    let mut views = Vec::new();
+
+    if with_schema {
+        views = list_views(pool, &schemas, &filter).await?;
+        let routines = list_routines(pool, &schemas).await?;
+        let types = list_types(pool, &schemas).await?;
+
+        if options.drop_objects {
+            out.section("Drop existing objects")?;
+            for view in views.iter().rev() {
+                let kind = if view.materialized { "MATERIALIZED VIEW" } else { "VIEW" };
+                out.write(&format!("DROP {} IF EXISTS {} CASCADE;\n", kind, view.qualified()))?;
+            }
+            for table in tables.iter().rev().filter(|t| !t.is_partition) {
+                out.write(&format!("DROP TABLE IF EXISTS {} CASCADE;\n", table.qualified()))?;
+            }
+            for sequence in sequences.iter().filter(|s| !s.is_identity) {
+                out.write(&format!("DROP SEQUENCE IF EXISTS {} CASCADE;\n", sequence.qualified()))?;
+            }
+            for routine in &routines {
+                out.write(&format!("DROP {} IF EXISTS {} CASCADE;\n", routine.kind, routine.signature))?;
+            }
+            for (name, kind, _) in types.iter().rev() {
+                out.write(&format!("DROP {} IF EXISTS {} CASCADE;\n", kind, name))?;
+            }
+            out.write("\n")?;
+        }
+
+        out.section("Schemas and extensions")?;
+        for schema in &schemas {
+            out.statement(&format!("CREATE SCHEMA IF NOT EXISTS {};", PG.quote_ident(schema)))?;
+        }
+        for extension in introspection::postgres::list_extensions(pool).await? {
+            if extension.name != "plpgsql" && schemas.contains(&extension.schema) {
+                out.statement(&format!(
+                    "CREATE EXTENSION IF NOT EXISTS {} WITH SCHEMA {};",
+                    PG.quote_ident(&extension.name),
+                    PG.quote_ident(&extension.schema)
+                ))?;
+            }
+        }
+
+        if !types.is_empty() {
+            out.section("Types")?;
+            for (_, _, create) in &types {
+                out.statement(create)?;
+            }
+        }
+
+        let standalone: Vec<&DumpSequence> = sequences.iter().filter(|s| !s.is_identity).collect();
+        if !standalone.is_empty() {
+            out.section("Sequences")?;
+            for sequence in standalone {
+                out.statement(&ddl::postgres::create_sequence_statement(&sequence.info))?;
+            }
+        }
+
+        // Routines taking or returning a table's row type need the table first
+        let (late, early): (Vec<DumpRoutine>, Vec<DumpRoutine>) =
+            routines.into_iter().partition(|r| r.uses_row_type);
+        let late_routines = late;
+        if !early.is_empty() {
+            out.section("Functions and procedures")?;
+            for routine in &early {
+                out.statement(&routine.definition)?;
+            }
+        }
+
+        out.section("Tables")?;
+        for table in tables.iter().filter(|t| !t.is_partition) {
+            if out.is_cancelled() {
+                return Ok(());
+            }
+            let statements =
+                ddl::postgres::table_statements(pool, Some(&table.schema), &table.name, false).await?;
+            for statement in statements {
+                out.statement(&statement)?;
+            }
+        }
+
+        if !late_routines.is_empty() {
+            out.section("Functions and procedures using table row types")?;
+            for routine in &late_routines {
+                out.statement(&routine.definition)?;
+            }
+        }
+    }
+
+    if with_data {
+        let copy = options.data_format != Some(DumpDataFormat::Insert);
+        for table in data_tables {
+            if out.is_cancelled() {
+                return Ok(());
+            }
+            out.section(&format!("Data for {}", table.key()))?;
+            out.begin_table(&table.key());
+            if copy {
+                copy_table(out, pool, table).await?;
+            } else {
+                insert_table(out, pool, table).await?;
+            }
+            if out.is_cancelled() {
+                return Ok(());
+            }
+            out.end_table();
+        }
+    }
+
+    if with_schema {
+        let owned: Vec<String> = sequences
+            .iter()
+            .filter(|s| !s.is_identity && s.owner.as_ref().is_some_and(|o| table_keys.contains(o)))
+            .filter_map(|s| ddl::postgres::owned_by_statement(&s.info))
+            .collect();
+        if !owned.is_empty() {
+            out.section("Sequence ownership")?;
+            for statement in owned {
+                out.statement(&statement)?;
+            }
+        }
+    }
+
+    if with_data {
+        let values: Vec<String> = sequences.iter().filter_map(|s| s.setval(&table_keys)).collect();
+        if !values.is_empty() {
+            out.section("Sequence values")?;
+            for statement in values {
+                out.statement(&statement)?;
+            }
+        }
+    }
+
+    if with_schema {
+        let mut foreign_keys = Vec::new();
+        for table in tables.iter().filter(|t| !t.is_partition) {
+            foreign_keys.extend(ddl::postgres::foreign_key_statements(pool, &table.schema, &table.name).await?);
+        }
+        if !foreign_keys.is_empty() {
+            out.section("Foreign keys")?;
+            for statement in foreign_keys {
+                out.statement(&statement)?;
+            }
+        }
+
+        if !views.is_empty() {
+            out.section("Views")?;
+            for view in &views {
+                if out.is_cancelled() {
+                    return Ok(());
+                }
+                let ddl =
+                    ddl::postgres::generate_ddl(pool, ObjectType::View, Some(&view.schema), &view.name).await?;
+                out.statement(&ddl)?;
+            }
+        }
+
+        let mut relations = table_keys;
+        relations.extend(views.iter().map(|v| format!("{}.{}", v.schema, v.name)));
+        let triggers = list_triggers(pool, &schemas, &relations).await?;
+        if !triggers.is_empty() {
+            out.section("Triggers")?;
+            for trigger in triggers {
+                out.statement(&trigger)?;
+            }
+        }
+    }
+
+    out.statement("COMMIT;")
+}
+
+/// Schemas other than the system ones and those created by extensions
+async fn user_schemas(pool: &Pool<Postgres>) -> Result<Vec<String>, String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT n.nspname::text
+        FROM pg_namespace n
+        WHERE n.nspname <> 'information_schema'
+          AND n.nspname NOT LIKE 'pg\_%'
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_namespace'::regclass AND d.objid = n.oid AND d.deptype = 'e'
+          )
+        ORDER BY n.nspname
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Tables and partitions matching the filter (partitions follow their root),
+/// with inheritance parents and referenced tables first
+async fn list_tables(
+    pool: &Pool<Postgres>,
+    schemas: &[String],
+    filter: &TableFilter<'_>,
+) -> Result<Vec<DumpTable>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            c.relname::text AS name,
+            c.relkind = 'p' AS partitioned,
+            c.relispartition AS is_partition,
+            rn.nspname::text AS root_schema,
+            r.relname::text AS root_name,
+            ARRAY(
+                SELECT pn.nspname || '.' || p.relname
+                FROM pg_inherits i
+                JOIN pg_class p ON p.oid = i.inhparent
+                JOIN pg_namespace pn ON pn.oid = p.relnamespace
+                WHERE i.inhrelid = c.oid
+                UNION
+                SELECT fn.nspname || '.' || f.relname
+                FROM pg_constraint k
+                JOIN pg_class f ON f.oid = k.confrelid
+                JOIN pg_namespace fn ON fn.oid = f.relnamespace
+                WHERE k.conrelid = c.oid AND k.contype = 'f'
+            ) AS depends_on
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        LEFT JOIN pg_class r ON r.oid = pg_partition_root(c.oid)
+        LEFT JOIN pg_namespace rn ON rn.oid = r.relnamespace
+        WHERE n.nspname = ANY($1)
+          AND c.relkind IN ('r', 'p')
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid AND d.deptype = 'e'
+          )
+        ORDER BY n.nspname, c.relname
+        "#,
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut tables = Vec::new();
+    for row in &rows {
+        let schema: String = row.try_get("schema").map_err(|e| e.to_string())?;
+        let name: String = row.try_get("name").map_err(|e| e.to_string())?;
+        let root_schema: Option<String> = row.try_get("root_schema").map_err(|e| e.to_string())?;
+        let root_name: Option<String> = row.try_get("root_name").map_err(|e| e.to_string())?;
+        let included = match (&root_schema, &root_name) {
+            (Some(root_schema), Some(root_name)) => filter.matches(Some(root_schema), root_name),
+            _ => filter.matches(Some(&schema), &name),
+        };
+        if included {
+            tables.push(DumpTable {
+                schema,
+                name,
+                partitioned: row.try_get("partitioned").map_err(|e| e.to_string())?,
+                is_partition: row.try_get("is_partition").map_err(|e| e.to_string())?,
+                depends_on: row.try_get("depends_on").map_err(|e| e.to_string())?,
+            });
+        }
+    }
+
+    let edges: Vec<(String, String)> = tables
+        .iter()
+        .flat_map(|t| t.depends_on.iter().map(move |d| (t.key(), d.clone())))
+        .collect();
+    let order = dependency_order(tables.iter().map(DumpTable::key).collect(), &edges);
+    tables.sort_by_key(|t| order.iter().position(|key| *key == t.key()));
+    Ok(tables)
+}
+
+struct DumpView {
+    schema: String,
+    name: String,
+    materialized: bool,
+}
+
+impl DumpView {
+    fn qualified(&self) -> String {
+        PG.qualified(Some(&self.schema), &self.name)
+    }
+}
+
+/// Views and materialized views matching the filter, each after the views it reads
+async fn list_views(
+    pool: &Pool<Postgres>,
+    schemas: &[String],
+    filter: &TableFilter<'_>,
+) -> Result<Vec<DumpView>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            c.relname::text AS name,
+            c.relkind = 'm' AS materialized,
+            ARRAY(
+                SELECT DISTINCT rn.nspname || '.' || r.relname
+                FROM pg_rewrite w
+                JOIN pg_depend d ON d.classid = 'pg_rewrite'::regclass AND d.objid = w.oid
+                JOIN pg_class r ON r.oid = d.refobjid AND d.refclassid = 'pg_class'::regclass
+                JOIN pg_namespace rn ON rn.oid = r.relnamespace
+                WHERE w.ev_class = c.oid AND r.oid <> c.oid AND r.relkind IN ('v', 'm')
+            ) AS depends_on
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = ANY($1)
+          AND c.relkind IN ('v', 'm')
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid AND d.deptype = 'e'
+          )
+        ORDER BY n.nspname, c.relname
+        "#,
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut views = Vec::new();
+    let mut edges = Vec::new();
+    for row in &rows {
+        let schema: String = row.try_get("schema").map_err(|e| e.to_string())?;
+        let name: String = row.try_get("name").map_err(|e| e.to_string())?;
+        if !filter.matches(Some(&schema), &name) {
+            continue;
+        }
+        let depends_on: Vec<String> = row.try_get("depends_on").map_err(|e| e.to_string())?;
+        let key = format!("{}.{}", schema, name);
+        edges.extend(depends_on.into_iter().map(|d| (key.clone(), d)));
+        views.push(DumpView {
+            schema,
+            name,
+            materialized: row.try_get("materialized").map_err(|e| e.to_string())?,
+        });
+    }
+
+    let order = dependency_order(views.iter().map(|v| format!("{}.{}", v.schema, v.name)).collect(), &edges);
+    views.sort_by_key(|v| order.iter().position(|key| *key == format!("{}.{}", v.schema, v.name)));
+    Ok(views)
+}
+
+struct DumpRoutine {
+    /// `FUNCTION` or `PROCEDURE`
+    kind: String,
+    /// Qualified name with identity arguments, for DROP
+    signature: String,
+    definition: String,
+    uses_row_type: bool,
+}
+
+/// Functions and procedures; aggregates have no `pg_get_functiondef` and are skipped
+async fn list_routines(pool: &Pool<Postgres>, schemas: &[String]) -> Result<Vec<DumpRoutine>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            CASE WHEN p.prokind = 'p' THEN 'PROCEDURE' ELSE 'FUNCTION' END AS kind,
+            format('%I.%I(%s)', n.nspname, p.proname, pg_get_function_identity_arguments(p.oid)) AS signature,
+            pg_get_functiondef(p.oid) AS definition,
+            EXISTS (
+                SELECT 1 FROM pg_type t
+                JOIN pg_class r ON r.oid = t.typrelid
+                WHERE r.relkind IN ('r', 'p', 'v', 'm', 'f')
+                  AND (t.oid = p.prorettype
+                       OR t.oid = ANY(p.proargtypes::oid[])
+                       OR t.oid = ANY(COALESCE(p.proallargtypes, '{}'::oid[])))
+            ) AS uses_row_type
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = ANY($1)
+          AND p.prokind IN ('f', 'p', 'w')
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+          )
+        ORDER BY n.nspname, p.proname, pg_get_function_identity_arguments(p.oid)
+        "#,
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let definition: String = row.try_get("definition").map_err(|e| e.to_string())?;
+            Ok(DumpRoutine {
+                kind: row.try_get("kind").map_err(|e| e.to_string())?,
+                signature: row.try_get("signature").map_err(|e| e.to_string())?,
+                definition: format!("{};", definition.trim_end()),
+                uses_row_type: row.try_get("uses_row_type").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// Enums, domains and composite types as `(qualified name, kind, CREATE statement)`,
+/// in an order where each can use the previous ones
+async fn list_types(pool: &Pool<Postgres>, schemas: &[String]) -> Result<Vec<(String, &'static str, String)>, String> {
+    let mut types = Vec::new();
+    for schema in schemas {
+        for e in introspection::postgres::list_enums(pool, Some(schema)).await? {
+            let name = PG.qualified(e.schema.as_deref(), &e.name);
+            let labels: Vec<String> = e.labels.iter().map(|l| PG.quote_literal(l)).collect();
+            let create = format!("CREATE TYPE {} AS ENUM ({});", name, labels.join(", "));
+            types.push((name, "TYPE", create));
+        }
+    }
+    for schema in schemas {
+        for d in introspection::postgres::list_domains(pool, Some(schema)).await? {
+            let name = PG.qualified(d.schema.as_deref(), &d.name);
+            let mut create = format!("CREATE DOMAIN {} AS {}", name, d.base_type);
+            if let Some(default) = &d.default_value {
+                create.push_str(&format!(" DEFAULT {}", default));
+            }
+            if d.not_null {
+                create.push_str(" NOT NULL");
+            }
+            for check in &d.checks {
+                create.push_str(&format!(" {}", check));
+            }
+            create.push(';');
+            types.push((name, "DOMAIN", create));
+        }
+    }
+
+    let composites: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT
+            format('%I.%I', n.nspname, t.typname),
+            format('CREATE TYPE %I.%I AS (%s);', n.nspname, t.typname, (
+                SELECT string_agg(format('%I %s', a.attname, format_type(a.atttypid, a.atttypmod)), ', '
+                                  ORDER BY a.attnum)
+                FROM pg_attribute a
+                WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+            ))
+        FROM pg_type t
+        JOIN pg_class c ON c.oid = t.typrelid
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE c.relkind = 'c'
+          AND n.nspname = ANY($1)
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_type'::regclass AND d.objid = t.oid AND d.deptype = 'e'
+          )
+        ORDER BY n.nspname, t.typname
+        "#,
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    types.extend(composites.into_iter().map(|(name, create)| (name, "TYPE", create)));
+    Ok(types)
+}
+
+struct DumpSequence {
+    info: SequenceInfo,
+    /// Backs an identity column, so the table creates it
+    is_identity: bool,
+    /// Owning table as `schema.name`
+    owner: Option<String>,
+    owner_column: Option<String>,
+}
+
+impl DumpSequence {
+    fn qualified(&self) -> String {
+        PG.qualified(self.info.schema.as_deref(), &self.info.name)
+    }
+
+    /// `setval` restoring the current value. Identity sequences are looked up
+    /// through their column, as the restored sequence may get another name.
+    fn setval(&self, tables: &HashSet<String>) -> Option<String> {
+        let value = self.info.current_value?;
+        if let Some(owner) = &self.owner {
+            if !tables.contains(owner) {
+                return None;
+            }
+        }
+        let sequence = match (self.is_identity, &self.owner, &self.owner_column) {
+            (true, Some(_), Some(column)) => format!(
+                "pg_catalog.pg_get_serial_sequence({}, {})",
+                PG.quote_literal(&self.owner_table()?),
+                PG.quote_literal(column)
+            ),
+            _ => PG.quote_literal(&self.qualified()),
+        };
+        Some(format!("SELECT pg_catalog.setval({}, {}, true);", sequence, value))
+    }
+
+    fn owner_table(&self) -> Option<String> {
+        let owner = self.owner.as_ref()?;
+        let (schema, table) = owner.split_once('.')?;
+        Some(PG.qualified(Some(schema), table))
+    }
+}
+
+async fn list_sequences(pool: &Pool<Postgres>, schemas: &[String]) -> Result<Vec<DumpSequence>, String> {
+    let owners = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            c.relname::text AS name,
+            COALESCE(d.deptype = 'i', false) AS is_identity,
+            tn.nspname || '.' || t.relname AS owner,
+            a.attname::text AS owner_column
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        LEFT JOIN pg_depend d
+          ON d.classid = 'pg_class'::regclass AND d.objid = c.oid
+         AND d.refclassid = 'pg_class'::regclass AND d.deptype IN ('a', 'i')
+        LEFT JOIN pg_class t ON t.oid = d.refobjid
+        LEFT JOIN pg_namespace tn ON tn.oid = t.relnamespace
+        LEFT JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+        WHERE c.relkind = 'S'
+          AND n.nspname = ANY($1)
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_depend e
+              WHERE e.classid = 'pg_class'::regclass AND e.objid = c.oid AND e.deptype = 'e'
+          )
+        "#,
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut sequences = Vec::new();
+    for schema in schemas {
+        for info in introspection::postgres::list_sequences(pool, Some(schema)).await? {
+            let owner = owners.iter().find(|row| {
+                row.try_get::<String, _>("schema").is_ok_and(|s| s == *schema)
+                    && row.try_get::<String, _>("name").is_ok_and(|name| name == info.name)
+            });
+            let Some(owner) = owner else {
+                continue;
+            };
+            sequences.push(DumpSequence {
+                is_identity: owner.try_get("is_identity").map_err(|e| e.to_string())?,
+                owner: owner.try_get("owner").map_err(|e| e.to_string())?,
+                owner_column: owner.try_get("owner_column").map_err(|e| e.to_string())?,
+                info,
+            });
+        }
+    }
+    Ok(sequences)
+}
+
+/// CREATE TRIGGER statements for triggers on the dumped tables and views.
+/// Triggers cloned onto partitions come with the parent's.
+async fn list_triggers(
+    pool: &Pool<Postgres>,
+    schemas: &[String],
+    relations: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT n.nspname || '.' || c.relname, pg_get_triggerdef(t.oid, true) || ';'
+        FROM pg_trigger t
+        JOIN pg_class c ON c.oid = t.tgrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = ANY($1)
+          AND NOT t.tgisinternal
+          AND t.tgparentid = 0
+        ORDER BY n.nspname, c.relname, t.tgname
+        "#,
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .filter(|(relation, _)| relations.contains(relation))
+        .map(|(_, definition)| definition)
+        .collect())
+}
+
+/// Columns whose values are dumped (generated columns are computed on restore)
+async fn data_columns(pool: &Pool<Postgres>, table: &DumpTable) -> Result<(Vec<String>, bool), String> {
+    let columns = introspection::postgres::list_columns(pool, Some(&table.schema), &table.name).await?;
+    let identity = columns.iter().any(|c| c.identity.is_some());
+    let names = columns
+        .iter()
+        .filter(|c| c.generated.is_none())
+        .map(|c| PG.quote_ident(&c.name))
+        .collect();
+    Ok((names, identity))
+}
+
+/// Write the table's rows as a `COPY ... FROM stdin` block
+async fn copy_table(out: &mut DumpWriter<'_>, pool: &Pool<Postgres>, table: &DumpTable) -> Result<(), String> {
+    let (columns, _) = data_columns(pool, table).await?;
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let columns = columns.join(", ");
+    out.write(&format!("COPY {} ({}) FROM stdin;\n", table.qualified(), columns))?;
+
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let mut stream = conn
+        .copy_out_raw(&format!("COPY {} ({}) TO STDOUT", table.qualified(), columns))
+        .await
+        .map_err(|e| e.to_string())?;
+    let copied: Result<(), String> = loop {
+        if out.is_cancelled() {
+            break Ok(());
+        }
+        match stream.try_next().await {
+            Ok(Some(chunk)) => {
+                if let Err(e) = out.copy_data(&chunk) {
+                    break Err(e);
+                }
+            }
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e.to_string()),
+        }
+    };
+    drop(stream);
+    if out.is_cancelled() || copied.is_err() {
+        // The server keeps sending until the connection is closed
+        let _ = conn.close().await;
+        return copied;
+    }
+    out.write("\\.\n\n")
+}
+
+/// Write the table's rows as INSERT statements, with literals rendered by the
+/// server so every type keeps its exact text form
+async fn insert_table(out: &mut DumpWriter<'_>, pool: &Pool<Postgres>, table: &DumpTable) -> Result<(), String> {
+    let (columns, identity) = data_columns(pool, table).await?;
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let values: Vec<String> = columns.iter().map(|c| format!("pg_catalog.quote_nullable({})", c)).collect();
+    let query = format!(
+        "SELECT '(' || {} || ')' FROM ONLY {}",
+        values.join(" || ', ' || "),
+        table.qualified()
+    );
+    out.begin_inserts(format!(
+        "INSERT INTO {} ({}){} VALUES",
+        table.qualified(),
+        columns.join(", "),
+        if identity { " OVERRIDING SYSTEM VALUE" } else { "" }
+    ));
+
+    let mut rows = sqlx::query_scalar::<_, String>(&query).fetch(pool);
+    while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+        if out.is_cancelled() {
+            return Ok(());
+        }
+        out.insert_row(&row)?;
+    }
+    drop(rows);
+    out.end_inserts()
+}
@@ -0,0 +1,182 @@
+use futures::TryStreamExt;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Pool, Row, Sqlite, TypeInfo, ValueRef};
+
+use crate::db::dialect::Dialect;
+use crate::db::dump::{DumpWriter, TableFilter};
+use crate::db::stream::encode_hex;
+use crate::db::types::{DumpContent, DumpOptions};
+
+const LITE: Dialect = Dialect::Sqlite;
+
+/// A schema object as stored in sqlite_master
+struct MasterEntry {
+    kind: String,
+    name: String,
+    table: String,
+    sql: String,
+}
+
+/// Literal for a stored value, by storage class, so values come back exactly
+/// as they were whatever the declared column type
+fn literal(row: &SqliteRow, index: usize) -> Result<String, String> {
+    let value = row.try_get_raw(index).map_err(|e| e.to_string())?;
+    if value.is_null() {
+        return Ok("NULL".to_string());
+    }
+    let storage = value.type_info().name().to_string();
+    Ok(match storage.as_str() {
+        "INTEGER" => row.try_get_unchecked::<i64, _>(index).map_err(|e| e.to_string())?.to_string(),
+        "REAL" => {
+            let v: f64 = row.try_get_unchecked(index).map_err(|e| e.to_string())?;
+            match v {
+                // Debug keeps a decimal point or exponent, so the value stays REAL
+                v if v.is_finite() => format!("{:?}", v),
+                v if v > 0.0 => "9e999".to_string(),
+                v if v < 0.0 => "-9e999".to_string(),
+                _ => "NULL".to_string(),
+            }
+        }
+        "BLOB" => format!("X'{}'", encode_hex(&row.try_get_unchecked::<Vec<u8>, _>(index).map_err(|e| e.to_string())?)),
+        _ => LITE.quote_literal(&row.try_get_unchecked::<String, _>(index).map_err(|e| e.to_string())?),
+    })
+}
+
+/// Dump the `main` database, or the attached schema named in `options`.
+/// Statements are written unqualified, in the order the objects were created.
+pub async fn dump(out: &mut DumpWriter<'_>, pool: &Pool<Sqlite>, options: &DumpOptions) -> Result<(), String> {
+    let filter = TableFilter::new(options);
+    let schema = options.schema.as_deref().unwrap_or("main");
+    let with_schema = options.content != DumpContent::Data;
+    let with_data = options.content != DumpContent::Schema;
+
+    let rows: Vec<(String, String, String, String)> = sqlx::query_as(&format!(
+        "SELECT type, name, tbl_name, sql FROM {} WHERE sql IS NOT NULL ORDER BY rowid",
+        LITE.qualified(Some(schema), "sqlite_master")
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let entries: Vec<MasterEntry> = rows
+        .into_iter()
+        .map(|(kind, name, table, sql)| MasterEntry { kind, name, table, sql })
+        .collect();
+
+    // Virtual tables maintain their shadow tables themselves
+    let virtual_tables: Vec<&str> = entries
+        .iter()
+        .filter(|e| e.kind == "table" && e.sql.to_ascii_uppercase().starts_with("CREATE VIRTUAL TABLE"))
+        .map(|e| e.name.as_str())
+        .collect();
+    let is_shadow = |name: &str| virtual_tables.iter().any(|v| name.starts_with(&format!("{}_", v)));
+    let included: Vec<&str> = entries
+        .iter()
+        .filter(|e| e.kind == "table" || e.kind == "view")
+        .filter(|e| !e.name.starts_with("sqlite_") && !is_shadow(&e.name))
+        .filter(|e| filter.matches(Some(schema), &e.name))
+        .map(|e| e.name.as_str())
+        .collect();
+    let tables: Vec<&MasterEntry> = entries
+        .iter()
+        .filter(|e| e.kind == "table" && included.contains(&e.name.as_str()))
+        .collect();
+    out.set_tables_total(if with_data { tables.len() } else { 0 });
+
+    out.write("PRAGMA foreign_keys = OFF;\n")?;
+    out.statement("BEGIN TRANSACTION;")?;
+
+    if with_schema {
+        if options.drop_objects {
+            out.section("Drop existing objects")?;
+            for entry in entries.iter().rev().filter(|e| included.contains(&e.name.as_str())) {
+                let kind = if entry.kind == "view" { "VIEW" } else { "TABLE" };
+                out.write(&format!("DROP {} IF EXISTS {};\n", kind, LITE.quote_ident(&entry.name)))?;
+            }
+            out.write("\n")?;
+        }
+        out.section("Tables")?;
+        for table in &tables {
+            out.statement(&format!("{};", table.sql))?;
+        }
+    }
+
+    if with_data {
+        for table in &tables {
+            if out.is_cancelled() {
+                return Ok(());
+            }
+            out.section(&format!("Data for {}", table.name))?;
+            out.begin_table(&table.name);
+            write_rows(out, pool, schema, &table.name).await?;
+            if out.is_cancelled() {
+                return Ok(());
+            }
+            out.end_table();
+        }
+
+        // AUTOINCREMENT counters
+        if entries.iter().any(|e| e.name == "sqlite_sequence") {
+            let counters: Vec<(String, i64)> = sqlx::query_as(&format!(
+                "SELECT name, seq FROM {} ORDER BY name",
+                LITE.qualified(Some(schema), "sqlite_sequence")
+            ))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+            let counters: Vec<_> = counters.iter().filter(|(name, _)| included.contains(&name.as_str())).collect();
+            if !counters.is_empty() {
+                out.section("AUTOINCREMENT counters")?;
+            }
+            for (name, seq) in counters {
+                out.statement(&format!("DELETE FROM sqlite_sequence WHERE name = {};", LITE.quote_literal(name)))?;
+                out.statement(&format!(
+                    "INSERT INTO sqlite_sequence (name, seq) VALUES ({}, {});",
+                    LITE.quote_literal(name),
+                    seq
+                ))?;
+            }
+        }
+    }
+
+    if with_schema {
+        let rest: Vec<&MasterEntry> = entries
+            .iter()
+            .filter(|e| e.kind != "table" && included.contains(&e.table.as_str()))
+            .collect();
+        if !rest.is_empty() {
+            out.section("Indexes, views and triggers")?;
+        }
+        for entry in rest {
+            out.statement(&format!("{};", entry.sql))?;
+        }
+    }
+
+    out.statement("COMMIT;")
+}
+
+/// Write the table's rows as INSERT statements; generated and hidden columns are left out
+async fn write_rows(out: &mut DumpWriter<'_>, pool: &Pool<Sqlite>, schema: &str, table: &str) -> Result<(), String> {
+    let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_xinfo(?1, ?2) WHERE hidden = 0 ORDER BY cid")
+        .bind(table)
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let columns: Vec<String> = columns.iter().map(|c| LITE.quote_ident(c)).collect();
+    let query = format!("SELECT {} FROM {}", columns.join(", "), LITE.qualified(Some(schema), table));
+    out.begin_inserts(format!("INSERT INTO {} ({}) VALUES", LITE.quote_ident(table), columns.join(", ")));
+
+    let mut rows = sqlx::query(&query).fetch(pool);
+    while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+        if out.is_cancelled() {
+            return Ok(());
+        }
+        let values = (0..columns.len()).map(|i| literal(&row, i)).collect::<Result<Vec<_>, _>>()?;
+        out.insert_row(&format!("({})", values.join(", ")))?;
+    }
+    drop(rows);
+    out.end_inserts()
+}
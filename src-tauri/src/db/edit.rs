@@ -0,0 +1,377 @@
+// Row edits
+//
+// `apply_row_changes` turns edits made in the result grid into parameterized
+// DELETE, UPDATE and INSERT statements and runs them in one transaction.
+// Rows are found by their primary or unique key, and every other original
+// value the grid sends has to match as well, so a row that someone else
+// changed since it was read is not overwritten: its statement matches no row
+// and the whole transaction is rolled back.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use serde_json::{Map, Value};
+use sqlx::query::Query;
+use sqlx::{Database, Encode, Executor, IntoArguments, Pool, Type};
+
+use crate::db::dialect::Dialect;
+use crate::db::export::sql::sql_literal;
+use crate::db::import::coerce::{coerce, TargetKind};
+use crate::db::import::reader::json_cell;
+use crate::db::introspection;
+use crate::db::stream::Cell;
+use crate::db::types::{ColumnInfo, RowChangeSummary, RowChanges, SqlPool};
+
+/// Postgres types without an equality operator; their original values are not compared
+const PG_UNCOMPARABLE: &[&str] = &["xml", "point", "line", "lseg", "box", "path", "polygon", "circle"];
+
+/// The type a Postgres parameter is cast to: the column type without its
+/// modifiers, so that a too long value fails on assignment instead of being
+/// cut short by the cast (`'abc'::varchar(2)` is `'ab'`)
+pub fn pg_cast_type(data_type: &str) -> String {
+    let mut base = String::new();
+    let mut depth = 0;
+    for c in data_type.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if depth == 0 => base.push(c),
+            _ => {}
+        }
+    }
+    let base = base.split_whitespace().collect::<Vec<_>>().join(" ");
+    let (element, array) = match base.find('[') {
+        Some(i) => (&base[..i], &base[i..]),
+        None => (base.as_str(), ""),
+    };
+    // Without a length these two mean char(1) and bit(1)
+    let element = match element {
+        "character" => "bpchar",
+        "bit" => "bit varying",
+        other => other,
+    };
+    format!("{}{}", element, array)
+}
+
+/// One statement of the change set
+struct Statement {
+    /// E.g. `Update 3`, for error messages
+    label: String,
+    sql: String,
+    params: Vec<Cell>,
+    /// Updates and deletes must affect exactly one row
+    single_row: bool,
+}
+
+/// Collects the parameters of a statement and writes their placeholders
+pub struct Params {
+    dialect: Dialect,
+    pub cells: Vec<Cell>,
+    /// Write values as literals instead, for statements run without binding
+    inline: bool,
+}
+
+impl Params {
+    pub fn new(dialect: Dialect) -> Params {
+        Params { dialect, cells: Vec::new(), inline: false }
+    }
+
+    /// Params that are written into the statement as SQL literals
+    pub fn inline(dialect: Dialect) -> Params {
+        Params { dialect, cells: Vec::new(), inline: true }
+    }
+
+    fn param(&mut self, cell: Cell) -> String {
+        if self.inline {
+            return sql_literal(self.dialect, &cell);
+        }
+        self.cells.push(cell);
+        match self.dialect {
+            Dialect::Postgres => format!("${}", self.cells.len()),
+            Dialect::MySql | Dialect::Sqlite => "?".to_string(),
+        }
+    }
+
+    /// Placeholder for a value of `column`
+    pub fn push(&mut self, column: &ColumnInfo, cell: Cell) -> String {
+        let param = self.param(cell);
+        match self.dialect {
+            Dialect::Postgres => format!("{}::{}", param, pg_cast_type(&column.data_type)),
+            Dialect::MySql | Dialect::Sqlite => param,
+        }
+    }
+
+    /// Placeholder for a text value that is not stored in a column, such as a LIKE pattern
+    pub fn push_text(&mut self, text: String) -> String {
+        let param = self.param(Cell::Text(text));
+        match self.dialect {
+            Dialect::Postgres => format!("{}::text", param),
+            Dialect::MySql | Dialect::Sqlite => param,
+        }
+    }
+
+    /// `column <operator> value` for a non-null value, or None when the
+    /// column type cannot be compared
+    pub fn compare(&mut self, column: &ColumnInfo, operator: &str, cell: Cell) -> Option<String> {
+        let name = self.dialect.quote_ident(&column.name);
+        let base = pg_cast_type(&column.data_type).to_ascii_lowercase();
+        match self.dialect {
+            Dialect::Postgres if PG_UNCOMPARABLE.contains(&base.as_str()) => None,
+            Dialect::Postgres if base == "json" => {
+                let param = self.param(cell);
+                Some(format!("{}::jsonb {} {}::jsonb", name, operator, param))
+            }
+            Dialect::MySql if base == "json" => {
+                let param = self.param(cell);
+                Some(format!("{} {} CAST({} AS JSON)", name, operator, param))
+            }
+            _ => {
+                let placeholder = self.push(column, cell);
+                Some(format!("{} {} {}", name, operator, placeholder))
+            }
+        }
+    }
+
+    /// `column` equals the value, or None when the column type cannot be compared
+    fn condition(&mut self, column: &ColumnInfo, cell: Cell) -> Option<String> {
+        if cell == Cell::Null {
+            return Some(format!("{} IS NULL", self.dialect.quote_ident(&column.name)));
+        }
+        self.compare(column, "=", cell)
+    }
+}
+
+/// Convert a value from the grid for `column`
+pub fn value_cell(column: &ColumnInfo, value: &Value) -> Result<Cell, String> {
+    coerce(TargetKind::from_type(&column.data_type), json_cell(value.clone()))
+        .map_err(|e| format!("{}: {}", column.name, e))
+}
+
+/// FLOAT, REAL and DOUBLE columns (SQLite's REAL affinity rule covers the
+/// spellings of all three dialects)
+fn is_approximate(data_type: &str) -> bool {
+    let t = data_type.to_ascii_lowercase();
+    t.contains("real") || t.contains("floa") || t.contains("doub")
+}
+
+/// Builds the statements of a change set against the table's columns
+struct Planner<'a> {
+    dialect: Dialect,
+    table: String,
+    columns: HashMap<&'a str, &'a ColumnInfo>,
+    /// None for a keyless table
+    key: Option<Vec<String>>,
+}
+
+impl Planner<'_> {
+    fn column(&self, name: &str) -> Result<&ColumnInfo, String> {
+        self.columns
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Column '{}' does not exist in {}", name, self.table))
+    }
+
+    /// WHERE clause matching a row by its key and the rest of its original values
+    fn row_filter(&self, label: &str, original: &Map<String, Value>, params: &mut Params) -> Result<String, String> {
+        let mut conditions = Vec::new();
+        if let Some(key) = &self.key {
+            for name in key {
+                let value = original
+                    .get(name)
+                    .ok_or_else(|| format!("{} lacks the value of key column '{}'", label, name))?;
+                let column = self.column(name)?;
+                let condition = params
+                    .condition(column, value_cell(column, value)?)
+                    .ok_or_else(|| format!("Key column '{}' cannot be compared", name))?;
+                conditions.push(condition);
+            }
+        }
+        for (name, value) in original {
+            if self.key.as_ref().is_some_and(|key| key.contains(name)) {
+                continue;
+            }
+            let column = self.column(name)?;
+            let cell = value_cell(column, value)?;
+            // A float read back through JSON need not equal the stored value
+            // bit for bit, so approximate columns only take part when NULL
+            if cell != Cell::Null && is_approximate(&column.data_type) {
+                continue;
+            }
+            if let Some(condition) = params.condition(column, cell) {
+                conditions.push(condition);
+            }
+        }
+        if conditions.is_empty() {
+            return Err(format!("{} has no original values to find the row by", label));
+        }
+        Ok(conditions.join(" AND "))
+    }
+
+    fn delete(&self, index: usize, original: &Map<String, Value>) -> Result<Statement, String> {
+        let label = format!("Delete {}", index + 1);
+        let mut params = Params::new(self.dialect);
+        let filter = self.row_filter(&label, original, &mut params)?;
+        Ok(Statement {
+            sql: format!("DELETE FROM {} WHERE {}", self.table, filter),
+            params: params.cells,
+            single_row: true,
+            label,
+        })
+    }
+
+    fn update(&self, index: usize, original: &Map<String, Value>, values: &Map<String, Value>) -> Result<Statement, String> {
+        let label = format!("Update {}", index + 1);
+        let mut params = Params::new(self.dialect);
+        let mut assignments = Vec::new();
+        for (name, value) in values {
+            let column = self.column(name)?;
+            if column.generated.is_some() {
+                return Err(format!("Column '{}' is generated and cannot be set", name));
+            }
+            let placeholder = params.push(column, value_cell(column, value)?);
+            assignments.push(format!("{} = {}", self.dialect.quote_ident(name), placeholder));
+        }
+        let filter = self.row_filter(&label, original, &mut params)?;
+        Ok(Statement {
+            sql: format!("UPDATE {} SET {} WHERE {}", self.table, assignments.join(", "), filter),
+            params: params.cells,
+            single_row: true,
+            label,
+        })
+    }
+
+    fn insert(&self, index: usize, values: &Map<String, Value>) -> Result<Statement, String> {
+        let mut params = Params::new(self.dialect);
+        let mut names = Vec::new();
+        let mut placeholders = Vec::new();
+        for (name, value) in values {
+            let column = self.column(name)?;
+            if column.generated.is_some() {
+                return Err(format!("Column '{}' is generated and cannot be set", name));
+            }
+            names.push(self.dialect.quote_ident(name));
+            placeholders.push(params.push(column, value_cell(column, value)?));
+        }
+        let sql = if names.is_empty() {
+            match self.dialect {
+                Dialect::MySql => format!("INSERT INTO {} () VALUES ()", self.table),
+                Dialect::Postgres | Dialect::Sqlite => format!("INSERT INTO {} DEFAULT VALUES", self.table),
+            }
+        } else {
+            format!("INSERT INTO {} ({}) VALUES ({})", self.table, names.join(", "), placeholders.join(", "))
+        };
+        Ok(Statement { label: format!("Insert {}", index + 1), sql, params: params.cells, single_row: false })
+    }
+}
+
+/// Bind a cell with the Rust type matching its variant
+pub fn bind<'q, DB: Database>(
+    query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    cell: &Cell,
+) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+where
+    Option<String>: Encode<'q, DB> + Type<DB>,
+    bool: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+    Vec<u8>: Encode<'q, DB> + Type<DB>,
+{
+    match cell {
+        Cell::Null => query.bind(None::<String>),
+        Cell::Bool(v) => query.bind(*v),
+        Cell::Int(v) => query.bind(*v),
+        Cell::Float(v) => query.bind(*v),
+        Cell::Decimal(v) | Cell::Text(v) => query.bind(v.clone()),
+        Cell::Bytes(v) => query.bind(v.clone()),
+        Cell::Json(v) => query.bind(v.to_string()),
+    }
+}
+
+/// Run the statements in one transaction, rolled back unless every update
+/// and delete affected exactly one row
+async fn run<DB: Database>(
+    pool: &Pool<DB>,
+    statements: &[Statement],
+    rows_affected: fn(&DB::QueryResult) -> u64,
+) -> Result<(), String>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> Option<String>: Encode<'q, DB> + Type<DB>,
+    for<'q> bool: Encode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> f64: Encode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> Vec<u8>: Encode<'q, DB> + Type<DB>,
+{
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for statement in statements {
+        let query = statement.params.iter().fold(sqlx::query(&statement.sql), bind);
+        let result = query
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("{}: {}", statement.label, e))?;
+        let affected = rows_affected(&result);
+        if statement.single_row && affected == 0 {
+            return Err(format!(
+                "{} matched no row; it was changed or deleted since it was read. Nothing was saved.",
+                statement.label
+            ));
+        }
+        if statement.single_row && affected > 1 {
+            return Err(format!("{} matched {} rows. Nothing was saved.", statement.label, affected));
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Apply the deletes, then the updates, then the inserts of `changes` in one transaction
+pub async fn apply_row_changes(pool: &SqlPool, changes: &RowChanges) -> Result<RowChangeSummary, String> {
+    let start = Instant::now();
+    let dialect = pool.dialect();
+    let schema = changes.schema.as_deref();
+    let columns = introspection::columns(pool, schema, &changes.table).await?;
+    if columns.is_empty() {
+        return Err(format!("Table '{}' was not found", changes.table));
+    }
+    let key = introspection::row_key(pool, schema, &changes.table, &columns).await?;
+    if key.is_none() && !changes.allow_keyless && !(changes.updates.is_empty() && changes.deletes.is_empty()) {
+        return Err(format!(
+            "{} has no primary or unique key, so edited rows cannot be identified reliably",
+            changes.table
+        ));
+    }
+
+    let planner = Planner {
+        dialect,
+        table: dialect.qualified(schema, &changes.table),
+        columns: columns.iter().map(|c| (c.name.as_str(), c)).collect(),
+        key,
+    };
+    let mut statements = Vec::new();
+    for (i, original) in changes.deletes.iter().enumerate() {
+        statements.push(planner.delete(i, original)?);
+    }
+    for (i, update) in changes.updates.iter().enumerate() {
+        if !update.values.is_empty() {
+            statements.push(planner.update(i, &update.original, &update.values)?);
+        }
+    }
+    for (i, values) in changes.inserts.iter().enumerate() {
+        statements.push(planner.insert(i, values)?);
+    }
+
+    match pool {
+        SqlPool::Postgres(pool) => run(pool, &statements, |r| r.rows_affected()).await?,
+        SqlPool::MySql(pool) => run(pool, &statements, |r| r.rows_affected()).await?,
+        SqlPool::Sqlite(pool) => run(pool, &statements, |r| r.rows_affected()).await?,
+    }
+
+    Ok(RowChangeSummary {
+        updated: changes.updates.iter().filter(|u| !u.values.is_empty()).count() as u64,
+        inserted: changes.inserts.len() as u64,
+        deleted: changes.deletes.len() as u64,
+        key_columns: planner.key.unwrap_or_default(),
+        execution_time_ms: start.elapsed().as_millis(),
+    })
+}
@@ -0,0 +1,490 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, ArrowPrimitiveType, BinaryBuilder, BooleanBuilder, PrimitiveArray, PrimitiveBuilder, StringBuilder,
+};
+use arrow::datatypes::{
+    DataType, Date32Type, Decimal128Type, Field, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+    Int8Type, Schema, SchemaRef, Time64MicrosecondType, TimeUnit, TimestampMicrosecondType, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use sqlx::{Column, Executor, Pool, Postgres, TypeInfo};
+
+use crate::db::dialect::Dialect;
+use crate::db::export::{cell_text, FormatWriter, MULTIPLE_RESULTS};
+use crate::db::stream::{Cell, ColumnKind, StreamColumn};
+use crate::db::types::ParquetCompression;
+
+/// Rows buffered per record batch
+const BATCH_ROWS: usize = 8192;
+/// Keeps the Parquet writer from buffering a huge row group in memory
+const ROW_GROUP_ROWS: usize = 128 * 1024;
+const DECIMAL_PRECISION: u8 = 38;
+/// Scale of exact numerics whose declaration is unknown, as in the common
+/// `Decimal(38, 18)` default; such values are rounded to it
+const UNDECLARED_SCALE: i8 = 18;
+
+/// File writer that counts the bytes passing through it
+struct CountingWriter {
+    inner: BufWriter<File>,
+    bytes: Arc<AtomicU64>,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes.fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+enum Output {
+    Parquet(ArrowWriter<CountingWriter>),
+    Ipc(FileWriter<CountingWriter>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ColumnarFormat {
+    Parquet(ParquetCompression),
+    ArrowIpc,
+}
+
+/// Writes Apache Parquet or Arrow IPC files.
+///
+/// The Arrow schema is derived from the driver's column types. Exact numerics
+/// become `Decimal128(p, s)` with the declared precision and scale when they
+/// are known (see `postgres_decimals`), where a value with more digits than
+/// its column holds fails the export rather than being rounded, and otherwise
+/// `Decimal128(38, 18)`. SQLite stores decimals as REAL or INTEGER, so they
+/// stay Float64.
+pub struct ColumnarWriter {
+    format: ColumnarFormat,
+    source: Dialect,
+    file: Option<File>,
+    output: Option<Output>,
+    schema: Option<SchemaRef>,
+    columns: Vec<StreamColumn>,
+    /// Declared precision and scale of the result's exact numeric columns
+    decimals: Vec<Option<(u8, i8)>>,
+    started: bool,
+    /// Buffered cells of the current batch, one vector per column
+    buffer: Vec<Vec<Cell>>,
+    buffered_rows: usize,
+    bytes: Arc<AtomicU64>,
+}
+
+impl ColumnarWriter {
+    pub fn create(
+        path: &Path,
+        format: ColumnarFormat,
+        source: Dialect,
+        decimals: Vec<Option<(u8, i8)>>,
+    ) -> Result<ColumnarWriter, String> {
+        Ok(ColumnarWriter {
+            format,
+            source,
+            file: Some(File::create(path).map_err(|e| e.to_string())?),
+            output: None,
+            schema: None,
+            columns: Vec::new(),
+            decimals,
+            started: false,
+            buffer: Vec::new(),
+            buffered_rows: 0,
+            bytes: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    fn arrow_type(&self, column: &StreamColumn, declared: Option<(u8, i8)>) -> DataType {
+        let name = column.type_name.to_ascii_uppercase();
+        match column.kind {
+            ColumnKind::Bool => DataType::Boolean,
+            ColumnKind::Int => match (self.source, name.as_str()) {
+                (Dialect::Postgres, "INT2") => DataType::Int16,
+                (Dialect::Postgres, "INT4") => DataType::Int32,
+                (Dialect::MySql, "TINYINT") => DataType::Int8,
+                (Dialect::MySql, "TINYINT UNSIGNED") => DataType::UInt8,
+                (Dialect::MySql, "SMALLINT" | "YEAR") => DataType::Int16,
+                (Dialect::MySql, "SMALLINT UNSIGNED") => DataType::UInt16,
+                (Dialect::MySql, "MEDIUMINT" | "INT") => DataType::Int32,
+                (Dialect::MySql, "MEDIUMINT UNSIGNED" | "INT UNSIGNED") => DataType::UInt32,
+                (Dialect::MySql, "BIGINT UNSIGNED") => DataType::UInt64,
+                _ => DataType::Int64,
+            },
+            ColumnKind::Float => match name.as_str() {
+                "FLOAT4" | "FLOAT" => DataType::Float32,
+                _ => DataType::Float64,
+            },
+            ColumnKind::Decimal if self.source == Dialect::Sqlite => DataType::Float64,
+            ColumnKind::Decimal => {
+                let (precision, scale) = declared.unwrap_or((DECIMAL_PRECISION, UNDECLARED_SCALE));
+                DataType::Decimal128(precision, scale)
+            }
+            ColumnKind::Bytes => DataType::Binary,
+            ColumnKind::Date => DataType::Date32,
+            // MySQL TIME is a duration that may exceed 24 hours
+            ColumnKind::Time if self.source != Dialect::MySql => DataType::Time64(TimeUnit::Microsecond),
+            ColumnKind::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+            ColumnKind::TimestampTz => DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into())),
+            _ => DataType::Utf8,
+        }
+    }
+
+    /// Build the schema and open the output on the first batch
+    fn open(&mut self) -> Result<(), String> {
+        let fields: Vec<Field> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let declared = self.decimals.get(i).copied().flatten();
+                Field::new(&column.name, self.arrow_type(column, declared), true)
+            })
+            .collect();
+        let schema: SchemaRef = Arc::new(Schema::new(fields));
+
+        let file = self.file.take().ok_or_else(|| "Export file already closed".to_string())?;
+        let writer = CountingWriter {
+            inner: BufWriter::with_capacity(256 * 1024, file),
+            bytes: self.bytes.clone(),
+        };
+        self.output = Some(match self.format {
+            ColumnarFormat::Parquet(compression) => {
+                let props = WriterProperties::builder()
+                    .set_compression(parquet_compression(compression))
+                    .set_max_row_group_size(ROW_GROUP_ROWS)
+                    .build();
+                Output::Parquet(ArrowWriter::try_new(writer, schema.clone(), Some(props)).map_err(|e| e.to_string())?)
+            }
+            ColumnarFormat::ArrowIpc => {
+                Output::Ipc(FileWriter::try_new(writer, &schema).map_err(|e| e.to_string())?)
+            }
+        });
+        self.schema = Some(schema);
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> Result<(), String> {
+        if self.output.is_none() {
+            self.open()?;
+        }
+        let schema = self.schema.clone().ok_or_else(|| "Export schema missing".to_string())?;
+        let arrays = schema
+            .fields()
+            .iter()
+            .zip(&self.buffer)
+            .enumerate()
+            .map(|(i, (field, cells))| build_array(field, cells, self.decimals.get(i).is_some_and(Option::is_some)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let batch = RecordBatch::try_new(schema, arrays).map_err(|e| e.to_string())?;
+        match self.output.as_mut() {
+            Some(Output::Parquet(writer)) => writer.write(&batch).map_err(|e| e.to_string())?,
+            Some(Output::Ipc(writer)) => writer.write(&batch).map_err(|e| e.to_string())?,
+            None => {}
+        }
+        self.buffer.iter_mut().for_each(Vec::clear);
+        self.buffered_rows = 0;
+        Ok(())
+    }
+}
+
+impl FormatWriter for ColumnarWriter {
+    fn begin(&mut self, columns: &[StreamColumn]) -> Result<(), String> {
+        if self.started {
+            return Err(MULTIPLE_RESULTS.to_string());
+        }
+        self.started = true;
+        self.columns = columns.to_vec();
+        self.buffer = vec![Vec::with_capacity(BATCH_ROWS); columns.len()];
+        Ok(())
+    }
+
+    fn row(&mut self, row: &[Cell]) -> Result<(), String> {
+        for (column, cell) in self.buffer.iter_mut().zip(row) {
+            column.push(cell.clone());
+        }
+        self.buffered_rows += 1;
+        if self.buffered_rows >= BATCH_ROWS {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        // An empty result still produces a file with the schema
+        if self.buffered_rows > 0 || self.output.is_none() {
+            self.flush_batch()?;
+        }
+        match self.output.take() {
+            Some(Output::Parquet(writer)) => {
+                writer.close().map_err(|e| e.to_string())?;
+            }
+            Some(Output::Ipc(mut writer)) => {
+                writer.finish().map_err(|e| e.to_string())?;
+                writer.get_mut().flush().map_err(|e| e.to_string())?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Declared precision and scale of each NUMERIC column of a query's result,
+/// for the columns that come straight from a table. The driver does not
+/// report type modifiers, so they are read from the catalog; anything that
+/// cannot be described (several statements, expressions) is left unknown.
+pub async fn postgres_decimals(pool: &Pool<Postgres>, query: &str) -> Vec<Option<(u8, i8)>> {
+    let Ok(describe) = pool.describe(query).await else { return Vec::new() };
+    let mut decimals = Vec::with_capacity(describe.columns.len());
+    for column in &describe.columns {
+        let source = match (column.relation_id(), column.relation_attribute_no()) {
+            (Some(table), Some(attnum)) if column.type_info().name() == "NUMERIC" => Some((table, attnum)),
+            _ => None,
+        };
+        let typmod = match source {
+            Some((table, attnum)) => sqlx::query_scalar::<_, i32>(
+                "SELECT atttypmod FROM pg_catalog.pg_attribute WHERE attrelid = $1 AND attnum = $2",
+            )
+            .bind(table)
+            .bind(attnum)
+            .fetch_optional(pool)
+            .await
+            .ok()
+            .flatten(),
+            None => None,
+        };
+        // numeric(p, s) is stored as ((p << 16) | s) + 4; -1 means unconstrained
+        decimals.push(typmod.filter(|t| *t >= 4).and_then(|t| {
+            let precision = ((t - 4) >> 16) & 0xffff;
+            let scale = (t - 4) & 0xffff;
+            // Precision beyond Decimal128 keeps the scale and checks each value
+            (scale <= precision.min(DECIMAL_PRECISION as i32))
+                .then(|| (precision.min(DECIMAL_PRECISION as i32) as u8, scale as i8))
+        }));
+    }
+    decimals
+}
+
+fn parquet_compression(compression: ParquetCompression) -> Compression {
+    match compression {
+        ParquetCompression::None => Compression::UNCOMPRESSED,
+        ParquetCompression::Snappy => Compression::SNAPPY,
+        ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+        ParquetCompression::Lz4 => Compression::LZ4_RAW,
+        ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        ParquetCompression::Brotli => Compression::BROTLI(BrotliLevel::default()),
+    }
+}
+
+fn conversion_error(field: &Field, cell: &Cell) -> String {
+    format!(
+        "Cannot store value {} of column {} as {}; cast it in the query",
+        cell_text(cell),
+        field.name(),
+        field.data_type()
+    )
+}
+
+/// Parse a decimal string into an unscaled integer. None when the value has
+/// more digits than `precision`, or more fractional digits than `scale`
+/// unless `round`, since storing it would lose some.
+fn parse_decimal(text: &str, precision: u8, scale: i8, round: bool) -> Option<i128> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int_part, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    let scale = scale as usize;
+    let mut value: i128 = 0;
+    for c in int_part.chars().chain(fraction.chars().chain(std::iter::repeat('0')).take(scale)) {
+        value = value.checked_mul(10)?.checked_add(c.to_digit(10)? as i128)?;
+    }
+    let mut rest = fraction.chars().skip(scale);
+    if round {
+        if rest.next().is_some_and(|c| c >= '5') {
+            value = value.checked_add(1)?;
+        }
+    } else if rest.any(|c| c != '0') {
+        return None;
+    }
+    if value >= 10i128.pow(precision as u32) {
+        return None;
+    }
+    Some(if negative { -value } else { value })
+}
+
+/// Values the engines use for "no date" are written as NULL
+fn is_null_date(text: &str) -> bool {
+    matches!(text, "infinity" | "-infinity") || text.starts_with("0000-00-00")
+}
+
+fn parse_timestamp(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))
+}
+
+fn int_value(cell: &Cell) -> Option<i128> {
+    match cell {
+        Cell::Int(v) => Some(*v as i128),
+        Cell::Bool(v) => Some(*v as i128),
+        Cell::Decimal(text) | Cell::Text(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+fn float_value(cell: &Cell) -> Option<f64> {
+    match cell {
+        Cell::Float(v) => Some(*v),
+        Cell::Int(v) => Some(*v as f64),
+        Cell::Decimal(text) | Cell::Text(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Build a primitive array; `convert` returns `Some(None)` for values stored as NULL
+fn primitive_array<T: ArrowPrimitiveType>(
+    field: &Field,
+    cells: &[Cell],
+    convert: impl Fn(&Cell) -> Option<Option<T::Native>>,
+) -> Result<PrimitiveArray<T>, String> {
+    let mut builder = PrimitiveBuilder::<T>::with_capacity(cells.len());
+    for cell in cells {
+        if *cell == Cell::Null {
+            builder.append_null();
+            continue;
+        }
+        match convert(cell) {
+            Some(value) => builder.append_option(value),
+            None => return Err(conversion_error(field, cell)),
+        }
+    }
+    Ok(builder.finish())
+}
+
+fn int_array<T: ArrowPrimitiveType>(field: &Field, cells: &[Cell]) -> Result<ArrayRef, String>
+where
+    T::Native: TryFrom<i128>,
+{
+    let array = primitive_array::<T>(field, cells, |cell| {
+        int_value(cell).and_then(|v| T::Native::try_from(v).ok()).map(Some)
+    })?;
+    Ok(Arc::new(array))
+}
+
+/// `declared` tells whether a Decimal128 field has the column's own scale
+fn build_array(field: &Field, cells: &[Cell], declared: bool) -> Result<ArrayRef, String> {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default();
+    let array: ArrayRef = match field.data_type() {
+        DataType::Boolean => {
+            let mut builder = BooleanBuilder::with_capacity(cells.len());
+            for cell in cells {
+                match cell {
+                    Cell::Null => builder.append_null(),
+                    Cell::Bool(v) => builder.append_value(*v),
+                    Cell::Int(v) => builder.append_value(*v != 0),
+                    Cell::Text(text) if matches!(text.as_str(), "t" | "true") => builder.append_value(true),
+                    Cell::Text(text) if matches!(text.as_str(), "f" | "false") => builder.append_value(false),
+                    _ => return Err(conversion_error(field, cell)),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Int8 => int_array::<Int8Type>(field, cells)?,
+        DataType::Int16 => int_array::<Int16Type>(field, cells)?,
+        DataType::Int32 => int_array::<Int32Type>(field, cells)?,
+        DataType::Int64 => int_array::<Int64Type>(field, cells)?,
+        DataType::UInt8 => int_array::<UInt8Type>(field, cells)?,
+        DataType::UInt16 => int_array::<UInt16Type>(field, cells)?,
+        DataType::UInt32 => int_array::<UInt32Type>(field, cells)?,
+        DataType::UInt64 => int_array::<UInt64Type>(field, cells)?,
+        DataType::Float32 => Arc::new(primitive_array::<Float32Type>(field, cells, |cell| {
+            float_value(cell).map(|v| Some(v as f32))
+        })?),
+        DataType::Float64 => Arc::new(primitive_array::<Float64Type>(field, cells, |cell| float_value(cell).map(Some))?),
+        DataType::Decimal128(precision, scale) => {
+            let array = primitive_array::<Decimal128Type>(field, cells, |cell| match cell {
+                Cell::Decimal(text) if text == "NaN" => Some(None),
+                Cell::Int(v) => parse_decimal(&v.to_string(), *precision, *scale, !declared).map(Some),
+                Cell::Float(v) => parse_decimal(&v.to_string(), *precision, *scale, !declared).map(Some),
+                Cell::Decimal(text) | Cell::Text(text) => parse_decimal(text, *precision, *scale, !declared).map(Some),
+                _ => None,
+            })?;
+            Arc::new(array.with_precision_and_scale(*precision, *scale).map_err(|e| e.to_string())?)
+        }
+        DataType::Date32 => Arc::new(primitive_array::<Date32Type>(field, cells, |cell| match cell {
+            Cell::Text(text) if is_null_date(text) => Some(None),
+            Cell::Text(text) => {
+                let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+                Some(Some((date - epoch).num_days() as i32))
+            }
+            _ => None,
+        })?),
+        DataType::Time64(_) => Arc::new(primitive_array::<Time64MicrosecondType>(field, cells, |cell| match cell {
+            Cell::Text(text) => {
+                let time = NaiveTime::parse_from_str(text, "%H:%M:%S%.f").ok()?;
+                let midnight = NaiveTime::from_hms_opt(0, 0, 0)?;
+                time.signed_duration_since(midnight).num_microseconds().map(Some)
+            }
+            _ => None,
+        })?),
+        DataType::Timestamp(_, timezone) => {
+            let array = primitive_array::<TimestampMicrosecondType>(field, cells, |cell| match cell {
+                Cell::Text(text) if is_null_date(text) => Some(None),
+                // Offsets like `+00` or `+05:30`, as printed by Postgres
+                Cell::Text(text) if timezone.is_some() => DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%#z")
+                    .ok()
+                    .map(|ts| Some(ts.timestamp_micros())),
+                Cell::Text(text) => parse_timestamp(text).map(|ts| Some(ts.and_utc().timestamp_micros())),
+                // SQLite applications often store Unix seconds
+                Cell::Int(seconds) => seconds.checked_mul(1_000_000).map(Some),
+                _ => None,
+            })?;
+            Arc::new(array.with_timezone_opt(timezone.clone()))
+        }
+        DataType::Binary => {
+            let mut builder = BinaryBuilder::with_capacity(cells.len(), 0);
+            for cell in cells {
+                match cell {
+                    Cell::Null => builder.append_null(),
+                    Cell::Bytes(bytes) => builder.append_value(bytes),
+                    other => builder.append_value(cell_text(other).as_bytes()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        _ => {
+            let mut builder = StringBuilder::with_capacity(cells.len(), 0);
+            for cell in cells {
+                match cell {
+                    Cell::Null => builder.append_null(),
+                    other => builder.append_value(cell_text(other)),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+    };
+    Ok(array)
+}
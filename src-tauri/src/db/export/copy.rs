@@ -0,0 +1,201 @@
+use std::path::Path;
+use std::time::Instant;
+use futures::TryStreamExt;
+use sqlx::{Column, Executor, Pool, Postgres, TypeInfo};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::dialect::Dialect;
+use crate::db::export::text::{TextEncoding, TextWriter};
+use crate::db::export::PROGRESS_INTERVAL;
+use crate::db::stream::ColumnKind;
+use crate::db::tasks::CancelFlag;
+use crate::db::types::{CsvOptions, CsvQuoting, ExportFormat, ExportOptions, ExportProgress, ExportSummary};
+
+/// `COPY (query) TO STDOUT` statement for a CSV export, when Postgres can
+/// produce exactly what was asked for: a single query, UTF-8 output, LF line
+/// endings and quoting Postgres supports.
+///
+/// The values have to read as they do from the streaming path (`cell_text`),
+/// so booleans and bytea are cast to `true`/`false` and `0x...` text. Floats
+/// and JSON have no such cast (Postgres switches floats to exponent notation
+/// and spaces JSON differently), so results with them are streamed instead.
+pub async fn copy_out_statement(pool: &Pool<Postgres>, query: &str, options: &ExportOptions) -> Option<String> {
+    if options.format != ExportFormat::Csv {
+        return None;
+    }
+    let CsvOptions { delimiter, quote, quoting, header, ref null_token, crlf, ref encoding } = options.csv;
+    if crlf || !delimiter.is_ascii() || !quote.is_ascii() || delimiter == '\n' || delimiter == '\r' {
+        return None;
+    }
+    if !matches!(TextEncoding::from_label(encoding).ok()?, TextEncoding::Utf8 { .. }) {
+        return None;
+    }
+    let force_quote = match quoting {
+        CsvQuoting::Necessary => "",
+        CsvQuoting::Always => ", FORCE_QUOTE *",
+        CsvQuoting::NonNumeric | CsvQuoting::Never => return None,
+    };
+
+    let query = query.trim().trim_end_matches(';').trim_end();
+    let keyword = query.split_whitespace().next()?.to_ascii_uppercase();
+    // A `;` left in the text may be a second statement; leave those to the streaming path
+    if query.contains(';') || !matches!(keyword.as_str(), "SELECT" | "WITH" | "VALUES" | "TABLE") {
+        return None;
+    }
+    let dialect = Dialect::Postgres;
+
+    let describe = pool.describe(query).await.ok()?;
+    let kinds: Vec<ColumnKind> = describe
+        .columns
+        .iter()
+        .map(|column| ColumnKind::from_type_name(dialect, column.type_info().name()))
+        .collect();
+    if kinds.iter().any(|kind| matches!(kind, ColumnKind::Float | ColumnKind::Json)) {
+        return None;
+    }
+    let query = if kinds.iter().any(|kind| matches!(kind, ColumnKind::Bool | ColumnKind::Bytes)) {
+        // Columns are renamed by position, since result names may repeat
+        let positions: Vec<String> = (1..=kinds.len()).map(|i| format!("c{}", i)).collect();
+        let values: Vec<String> = describe
+            .columns
+            .iter()
+            .zip(&kinds)
+            .zip(&positions)
+            .map(|((column, kind), position)| {
+                let value = match kind {
+                    ColumnKind::Bool => format!("q.{}::text", position),
+                    ColumnKind::Bytes => format!("'0x' || encode(q.{}, 'hex')", position),
+                    _ => format!("q.{}", position),
+                };
+                format!("{} AS {}", value, dialect.quote_ident(column.name()))
+            })
+            .collect();
+        format!("SELECT {} FROM ({}) AS q({})", values.join(", "), query, positions.join(", "))
+    } else {
+        query.to_string()
+    };
+
+    Some(format!(
+        "COPY ({}) TO STDOUT WITH (FORMAT csv, HEADER {}, DELIMITER {}, QUOTE {}, NULL {}{})",
+        query,
+        header,
+        dialect.quote_literal(&delimiter.to_string()),
+        dialect.quote_literal(&quote.to_string()),
+        dialect.quote_literal(null_token),
+        force_quote,
+    ))
+}
+
+/// Counts CSV records in a byte stream (line breaks outside quoted fields)
+struct RecordCounter {
+    quote: u8,
+    in_quotes: bool,
+    records: u64,
+}
+
+impl RecordCounter {
+    fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            if b == self.quote {
+                self.in_quotes = !self.in_quotes;
+            } else if b == b'\n' && !self.in_quotes {
+                self.records += 1;
+            }
+        }
+    }
+}
+
+/// Run `COPY ... TO STDOUT` into `path`.
+///
+/// Returns `Ok(None)` when Postgres rejects the statement before sending any
+/// data (e.g. the query cannot be wrapped in COPY), so the caller can fall back
+/// to streaming rows.
+pub async fn export_copy(
+    app: &AppHandle,
+    pool: &Pool<Postgres>,
+    statement: &str,
+    path: &Path,
+    options: &ExportOptions,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<Option<ExportSummary>, String> {
+    let start = Instant::now();
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let mut stream = match conn.copy_out_raw(statement).await {
+        Ok(stream) => stream,
+        Err(sqlx::Error::Database(_)) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let encoding = TextEncoding::from_label(&options.csv.encoding)?;
+    let mut out = TextWriter::create(path, encoding)?;
+    let mut counter = RecordCounter { quote: options.csv.quote as u8, in_quotes: false, records: 0 };
+    let mut last_progress = Instant::now();
+    let emit = |counter: &RecordCounter, out: &TextWriter| {
+        let header = u64::from(options.csv.header && counter.records > 0);
+        let _ = app.emit(
+            "export-progress",
+            ExportProgress {
+                task_id: task_id.to_string(),
+                rows: counter.records - header,
+                bytes: out.bytes_written(),
+            },
+        );
+    };
+
+    let mut received = false;
+    let mut cancelled = false;
+    let copied: Result<(), String> = loop {
+        if cancel.is_cancelled() {
+            cancelled = true;
+            break Ok(());
+        }
+        match stream.try_next().await {
+            Ok(Some(chunk)) => {
+                received = true;
+                counter.feed(&chunk);
+                if let Err(e) = out.write_bytes(&chunk) {
+                    break Err(e);
+                }
+            }
+            Ok(None) => break out.flush(),
+            // Nothing has been written yet: let the caller retry without COPY
+            Err(sqlx::Error::Database(_)) if !received => {
+                drop(stream);
+                drop(out);
+                let _ = std::fs::remove_file(path);
+                return Ok(None);
+            }
+            Err(e) => break Err(e.to_string()),
+        }
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            emit(&counter, &out);
+        }
+    };
+    drop(stream);
+    if cancelled || copied.is_err() {
+        // The server keeps sending until the connection is closed
+        let _ = conn.close().await;
+    }
+    if let Err(e) = copied {
+        drop(out);
+        let _ = std::fs::remove_file(path);
+        return Err(e);
+    }
+    emit(&counter, &out);
+
+    let header = u64::from(options.csv.header && counter.records > 0);
+    let summary = ExportSummary {
+        path: path.to_string_lossy().into_owned(),
+        rows: counter.records - header,
+        bytes: out.bytes_written(),
+        cancelled,
+        execution_time_ms: start.elapsed().as_millis(),
+    };
+    if cancelled {
+        drop(out);
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(Some(summary))
+}
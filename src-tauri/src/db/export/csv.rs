@@ -0,0 +1,110 @@
+use std::path::Path;
+use crate::db::export::text::{TextEncoding, TextWriter};
+use crate::db::export::{cell_text, FormatWriter, MULTIPLE_RESULTS};
+use crate::db::stream::{Cell, StreamColumn};
+use crate::db::types::{CsvOptions, CsvQuoting};
+
+pub struct CsvWriter {
+    out: TextWriter,
+    options: CsvOptions,
+    started: bool,
+    record: String,
+    fields: usize,
+}
+
+impl CsvWriter {
+    pub fn create(path: &Path, options: &CsvOptions) -> Result<CsvWriter, String> {
+        if options.delimiter == options.quote {
+            return Err("The CSV delimiter and quote character must differ".to_string());
+        }
+        let encoding = TextEncoding::from_label(&options.encoding)?;
+        Ok(CsvWriter {
+            out: TextWriter::create(path, encoding)?,
+            options: options.clone(),
+            started: false,
+            record: String::new(),
+            fields: 0,
+        })
+    }
+
+    fn push_delimiter(&mut self) {
+        if self.fields > 0 {
+            self.record.push(self.options.delimiter);
+        }
+        self.fields += 1;
+    }
+
+    /// Append one field to the current record, quoting it as configured.
+    /// `numeric` fields are left bare by `CsvQuoting::NonNumeric`.
+    fn push_field(&mut self, text: &str, numeric: bool) {
+        self.push_delimiter();
+        let CsvOptions { delimiter, quote, ref null_token, .. } = self.options;
+        let quoted = match self.options.quoting {
+            CsvQuoting::Always => true,
+            CsvQuoting::Never => false,
+            CsvQuoting::NonNumeric => !numeric,
+            // Quote values that would otherwise read back as NULL
+            CsvQuoting::Necessary => {
+                text.contains([delimiter, quote, '\r', '\n']) || text == null_token.as_str()
+            }
+        };
+        if quoted {
+            self.record.push(quote);
+            for c in text.chars() {
+                if c == quote {
+                    self.record.push(quote);
+                }
+                self.record.push(c);
+            }
+            self.record.push(quote);
+        } else {
+            self.record.push_str(text);
+        }
+    }
+
+    fn end_record(&mut self) -> Result<(), String> {
+        self.record.push_str(if self.options.crlf { "\r\n" } else { "\n" });
+        self.out.write_str(&self.record)?;
+        self.record.clear();
+        self.fields = 0;
+        Ok(())
+    }
+}
+
+impl FormatWriter for CsvWriter {
+    fn begin(&mut self, columns: &[StreamColumn]) -> Result<(), String> {
+        if self.started {
+            return Err(MULTIPLE_RESULTS.to_string());
+        }
+        self.started = true;
+        if self.options.header {
+            for column in columns {
+                self.push_field(&column.name, false);
+            }
+            self.end_record()?;
+        }
+        Ok(())
+    }
+
+    fn row(&mut self, row: &[Cell]) -> Result<(), String> {
+        for cell in row {
+            if *cell == Cell::Null {
+                // The NULL token itself is never quoted
+                self.push_delimiter();
+                self.record.push_str(&self.options.null_token);
+                continue;
+            }
+            let numeric = matches!(cell, Cell::Bool(_) | Cell::Int(_) | Cell::Float(_) | Cell::Decimal(_));
+            self.push_field(&cell_text(cell), numeric);
+        }
+        self.end_record()
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.out.flush()
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.out.bytes_written()
+    }
+}
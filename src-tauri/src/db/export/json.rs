@@ -0,0 +1,89 @@
+use std::path::Path;
+use crate::db::export::text::{TextEncoding, TextWriter};
+use crate::db::export::{is_plain_number, FormatWriter, MULTIPLE_RESULTS};
+use crate::db::stream::{self, Cell, StreamColumn};
+
+/// JSON value text of a cell; exact numerics are written as numbers when they are plain decimals
+pub fn json_value(cell: &Cell) -> String {
+    match cell {
+        Cell::Null => "null".to_string(),
+        Cell::Bool(v) => v.to_string(),
+        Cell::Int(v) => v.to_string(),
+        Cell::Float(v) => serde_json::Number::from_f64(*v)
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "null".to_string()),
+        Cell::Decimal(v) if is_plain_number(v) => v.clone(),
+        Cell::Decimal(v) | Cell::Text(v) => serde_json::Value::from(v.as_str()).to_string(),
+        Cell::Bytes(v) => format!("\"0x{}\"", stream::encode_hex(v)),
+        Cell::Json(v) => v.to_string(),
+    }
+}
+
+/// Writes rows as objects, either as one JSON array or one object per line (NDJSON)
+pub struct JsonWriter {
+    out: TextWriter,
+    lines: bool,
+    /// Column names, already encoded as JSON strings
+    keys: Vec<String>,
+    started: bool,
+    rows: u64,
+}
+
+impl JsonWriter {
+    pub fn create(path: &Path, lines: bool) -> Result<JsonWriter, String> {
+        Ok(JsonWriter {
+            out: TextWriter::create(path, TextEncoding::Utf8 { bom: false })?,
+            lines,
+            keys: Vec::new(),
+            started: false,
+            rows: 0,
+        })
+    }
+}
+
+impl FormatWriter for JsonWriter {
+    fn begin(&mut self, columns: &[StreamColumn]) -> Result<(), String> {
+        if self.started {
+            return Err(MULTIPLE_RESULTS.to_string());
+        }
+        self.started = true;
+        self.keys = columns
+            .iter()
+            .map(|c| serde_json::Value::from(c.name.as_str()).to_string())
+            .collect();
+        Ok(())
+    }
+
+    fn row(&mut self, row: &[Cell]) -> Result<(), String> {
+        let mut line = String::new();
+        if !self.lines {
+            line.push_str(if self.rows == 0 { "[\n  " } else { ",\n  " });
+        }
+        line.push('{');
+        for (i, (key, cell)) in self.keys.iter().zip(row).enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            line.push_str(key);
+            line.push(':');
+            line.push_str(&json_value(cell));
+        }
+        line.push('}');
+        if self.lines {
+            line.push('\n');
+        }
+        self.rows += 1;
+        self.out.write_str(&line)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if !self.lines {
+            self.out.write_str(if self.rows == 0 { "[]\n" } else { "\n]\n" })?;
+        }
+        self.out.flush()
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.out.bytes_written()
+    }
+}
@@ -0,0 +1,213 @@
+// Query export
+//
+// `export_query` streams rows (see `db::stream`) into a format writer that
+// writes straight to the destination file, so large results never have to be
+// held in memory or passed to the frontend. Parquet and Arrow buffer one
+// record batch at a time. Plain CSV exports from Postgres use
+// `COPY ... TO STDOUT` instead (see `copy`), falling back to streaming when
+// the server rejects it.
+
+pub mod text;
+pub mod csv;
+pub mod json;
+pub mod sql;
+pub mod columnar;
+pub mod xlsx;
+pub mod table;
+pub mod copy;
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::stream::{self, Cell, RowSink, StreamColumn};
+use crate::db::tasks::CancelFlag;
+use crate::db::types::{ExportFormat, ExportOptions, ExportProgress, ExportSummary, SqlPool, TableFormat};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Returned by the single-table formats; XLSX, Markdown and HTML write every result set
+pub(crate) const MULTIPLE_RESULTS: &str =
+    "The query returned more than one result set; export one statement at a time";
+
+/// Writes one export format to a file
+pub trait FormatWriter: Send {
+    /// Called once per result set, before its rows
+    fn begin(&mut self, columns: &[StreamColumn]) -> Result<(), String>;
+    fn row(&mut self, row: &[Cell]) -> Result<(), String>;
+    /// Write any trailer and flush
+    fn finish(&mut self) -> Result<(), String>;
+    fn bytes_written(&self) -> u64;
+}
+
+/// Plain-text rendering of a value, shared by the text formats
+pub fn cell_text(cell: &Cell) -> Cow<'_, str> {
+    match cell {
+        Cell::Null => Cow::Borrowed(""),
+        Cell::Bool(v) => Cow::Borrowed(if *v { "true" } else { "false" }),
+        Cell::Int(v) => Cow::Owned(v.to_string()),
+        Cell::Float(v) if v.is_nan() => Cow::Borrowed("NaN"),
+        Cell::Float(v) if v.is_infinite() => Cow::Borrowed(if *v > 0.0 { "Infinity" } else { "-Infinity" }),
+        Cell::Float(v) => Cow::Owned(v.to_string()),
+        Cell::Decimal(v) | Cell::Text(v) => Cow::Borrowed(v),
+        Cell::Bytes(v) => Cow::Owned(format!("0x{}", stream::encode_hex(v))),
+        Cell::Json(v) => Cow::Owned(v.to_string()),
+    }
+}
+
+/// Whether `text` is a plain decimal number (valid as a JSON number and as a SQL literal)
+pub fn is_plain_number(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let (mantissa, exponent) = match digits.find(['e', 'E']) {
+        Some(i) => (&digits[..i], Some(&digits[i + 1..])),
+        None => (digits, None),
+    };
+    let (int_part, frac_part) = match mantissa.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (mantissa, None),
+    };
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    all_digits(int_part)
+        && frac_part.map_or(true, all_digits)
+        && exponent.map_or(true, |e| all_digits(e.strip_prefix(['+', '-']).unwrap_or(e)))
+}
+
+/// Forwards streamed rows to the format writer and reports progress
+struct ExportSink<'a> {
+    writer: Box<dyn FormatWriter>,
+    app: &'a AppHandle,
+    task_id: &'a str,
+    rows: u64,
+    last_progress: Instant,
+}
+
+impl ExportSink<'_> {
+    fn emit_progress(&mut self) {
+        self.last_progress = Instant::now();
+        let _ = self.app.emit(
+            "export-progress",
+            ExportProgress {
+                task_id: self.task_id.to_string(),
+                rows: self.rows,
+                bytes: self.writer.bytes_written(),
+            },
+        );
+    }
+}
+
+impl RowSink for ExportSink<'_> {
+    fn begin_result(&mut self, columns: &[StreamColumn]) -> Result<(), String> {
+        self.writer.begin(columns)
+    }
+
+    fn write_row(&mut self, row: Vec<Cell>) -> Result<(), String> {
+        self.writer.row(&row)?;
+        self.rows += 1;
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            self.emit_progress();
+        }
+        Ok(())
+    }
+}
+
+/// Create the writer for `options.format` at `path`
+async fn create_writer(
+    path: &Path,
+    options: &ExportOptions,
+    pool: &SqlPool,
+    query: &str,
+) -> Result<Box<dyn FormatWriter>, String> {
+    let decimals = match (options.format, pool) {
+        (ExportFormat::Parquet | ExportFormat::Arrow, SqlPool::Postgres(pool)) => {
+            columnar::postgres_decimals(pool, query).await
+        }
+        _ => Vec::new(),
+    };
+    Ok(match options.format {
+        ExportFormat::Csv => Box::new(csv::CsvWriter::create(path, &options.csv)?),
+        ExportFormat::Json => Box::new(json::JsonWriter::create(path, false)?),
+        ExportFormat::Ndjson => Box::new(json::JsonWriter::create(path, true)?),
+        ExportFormat::Sql => Box::new(sql::InsertWriter::create(path, &options.sql, pool.dialect())?),
+        ExportFormat::Parquet => Box::new(columnar::ColumnarWriter::create(
+            path,
+            columnar::ColumnarFormat::Parquet(options.parquet.compression),
+            pool.dialect(),
+            decimals,
+        )?),
+        ExportFormat::Arrow => Box::new(columnar::ColumnarWriter::create(
+            path,
+            columnar::ColumnarFormat::ArrowIpc,
+            pool.dialect(),
+            decimals,
+        )?),
+        ExportFormat::Xlsx => Box::new(xlsx::XlsxWriter::create(path)?),
+        ExportFormat::Markdown => Box::new(table::TableWriter::create(path, TableFormat::Markdown)?),
+        ExportFormat::Html => Box::new(table::TableWriter::create(path, TableFormat::Html)?),
+    })
+}
+
+/// Run `query` and write its rows to `path`. A cancelled or failed export
+/// removes the partial file.
+#[allow(clippy::too_many_arguments)]
+pub async fn export_query(
+    app: &AppHandle,
+    pool: &SqlPool,
+    query: &str,
+    database: Option<&str>,
+    path: &str,
+    options: &ExportOptions,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<ExportSummary, String> {
+    let start = Instant::now();
+    let file = Path::new(path);
+    if let SqlPool::Postgres(pool) = pool {
+        if let Some(statement) = copy::copy_out_statement(pool, query, options).await {
+            if let Some(summary) = copy::export_copy(app, pool, &statement, file, options, task_id, cancel).await? {
+                return Ok(summary);
+            }
+        }
+    }
+    let mut sink = ExportSink {
+        writer: create_writer(file, options, pool, query).await?,
+        app,
+        task_id,
+        rows: 0,
+        last_progress: Instant::now(),
+    };
+
+    let streamed = match pool {
+        SqlPool::Postgres(pool) => stream::postgres::stream_query(pool, query, &mut sink, cancel).await,
+        SqlPool::MySql(pool) => stream::mysql::stream_query(pool, query, database, &mut sink, cancel).await,
+        SqlPool::Sqlite(pool) => stream::sqlite::stream_query(pool, query, &mut sink, cancel).await,
+    };
+    let finished = streamed.and_then(|stats| {
+        if !stats.cancelled {
+            sink.writer.finish()?;
+        }
+        Ok(stats)
+    });
+    let stats = match finished {
+        Ok(stats) => stats,
+        Err(e) => {
+            drop(sink);
+            let _ = std::fs::remove_file(file);
+            return Err(e);
+        }
+    };
+
+    sink.emit_progress();
+    let summary = ExportSummary {
+        path: path.to_string(),
+        rows: sink.rows,
+        bytes: sink.writer.bytes_written(),
+        cancelled: stats.cancelled,
+        execution_time_ms: start.elapsed().as_millis(),
+    };
+    if stats.cancelled {
+        drop(sink);
+        let _ = std::fs::remove_file(file);
+    }
+    Ok(summary)
+}
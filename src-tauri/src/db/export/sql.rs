@@ -0,0 +1,99 @@
+use std::path::Path;
+use crate::db::dialect::Dialect;
+use crate::db::export::text::{TextEncoding, TextWriter};
+use crate::db::export::{is_plain_number, FormatWriter, MULTIPLE_RESULTS};
+use crate::db::stream::{self, Cell, StreamColumn};
+use crate::db::types::SqlExportOptions;
+
+/// SQL literal for a value in the given dialect
+pub fn sql_literal(dialect: Dialect, cell: &Cell) -> String {
+    match cell {
+        Cell::Null => "NULL".to_string(),
+        Cell::Bool(v) => match dialect {
+            // Older SQLite versions have no TRUE/FALSE keywords
+            Dialect::Sqlite => if *v { "1" } else { "0" }.to_string(),
+            Dialect::Postgres | Dialect::MySql => if *v { "TRUE" } else { "FALSE" }.to_string(),
+        },
+        Cell::Int(v) => v.to_string(),
+        Cell::Float(v) if v.is_finite() => v.to_string(),
+        Cell::Float(v) => match dialect {
+            Dialect::Postgres if v.is_nan() => "'NaN'".to_string(),
+            Dialect::Postgres if *v > 0.0 => "'Infinity'".to_string(),
+            Dialect::Postgres => "'-Infinity'".to_string(),
+            Dialect::MySql | Dialect::Sqlite => "NULL".to_string(),
+        },
+        Cell::Decimal(v) if is_plain_number(v) => v.clone(),
+        Cell::Decimal(v) | Cell::Text(v) => dialect.quote_literal(v),
+        Cell::Bytes(v) => match dialect {
+            Dialect::Postgres => format!("'\\x{}'", stream::encode_hex(v)),
+            Dialect::MySql | Dialect::Sqlite => format!("X'{}'", stream::encode_hex(v)),
+        },
+        Cell::Json(v) => dialect.quote_literal(&v.to_string()),
+    }
+}
+
+/// Writes rows as multi-row INSERT statements of `batch_size` rows each
+pub struct InsertWriter {
+    out: TextWriter,
+    dialect: Dialect,
+    table: String,
+    batch_size: usize,
+    /// `INSERT INTO ... (...) VALUES` prefix of every statement
+    prefix: String,
+    started: bool,
+    in_batch: usize,
+}
+
+impl InsertWriter {
+    pub fn create(path: &Path, options: &SqlExportOptions, source: Dialect) -> Result<InsertWriter, String> {
+        if options.table.trim().is_empty() {
+            return Err("A target table name is required for SQL export".to_string());
+        }
+        let dialect = options.dialect.unwrap_or(source);
+        Ok(InsertWriter {
+            out: TextWriter::create(path, TextEncoding::Utf8 { bom: false })?,
+            dialect,
+            table: dialect.qualified(options.schema.as_deref(), options.table.trim()),
+            batch_size: options.batch_size.max(1),
+            prefix: String::new(),
+            started: false,
+            in_batch: 0,
+        })
+    }
+}
+
+impl FormatWriter for InsertWriter {
+    fn begin(&mut self, columns: &[StreamColumn]) -> Result<(), String> {
+        if self.started {
+            return Err(MULTIPLE_RESULTS.to_string());
+        }
+        self.started = true;
+        let names: Vec<String> = columns.iter().map(|c| self.dialect.quote_ident(&c.name)).collect();
+        self.prefix = format!("INSERT INTO {} ({}) VALUES\n", self.table, names.join(", "));
+        Ok(())
+    }
+
+    fn row(&mut self, row: &[Cell]) -> Result<(), String> {
+        let values: Vec<String> = row.iter().map(|cell| sql_literal(self.dialect, cell)).collect();
+        let mut text = if self.in_batch == 0 { self.prefix.clone() } else { ",\n".to_string() };
+        text.push_str(&format!("  ({})", values.join(", ")));
+        self.in_batch += 1;
+        if self.in_batch == self.batch_size {
+            text.push_str(";\n");
+            self.in_batch = 0;
+        }
+        self.out.write_str(&text)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if self.in_batch > 0 {
+            self.out.write_str(";\n")?;
+            self.in_batch = 0;
+        }
+        self.out.flush()
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.out.bytes_written()
+    }
+}
@@ -0,0 +1,228 @@
+use std::borrow::Cow;
+use std::path::Path;
+use crate::db::export::text::{TextEncoding, TextWriter};
+use crate::db::export::{cell_text, FormatWriter};
+use crate::db::stream::{Cell, StreamColumn};
+use crate::db::types::TableFormat;
+
+const NULL_TEXT: &str = "NULL";
+
+const HTML_HEAD: &str = "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n\
+table { border-collapse: collapse; margin-bottom: 1em; }\n\
+th, td { border: 1px solid #ccc; padding: 4px 8px; }\n\
+td.null { color: #999; }\n\
+</style>\n</head>\n<body>\n";
+const HTML_TAIL: &str = "</body>\n</html>\n";
+
+/// Renders result sets as Markdown (GitHub-flavoured) or HTML tables.
+/// Numeric columns are right-aligned.
+pub struct TableRenderer {
+    format: TableFormat,
+    numeric: Vec<bool>,
+    open: bool,
+    tables: usize,
+    out: String,
+}
+
+impl TableRenderer {
+    pub fn new(format: TableFormat) -> TableRenderer {
+        TableRenderer {
+            format,
+            numeric: Vec::new(),
+            open: false,
+            tables: 0,
+            out: String::new(),
+        }
+    }
+
+    /// Start a table, closing the previous one
+    pub fn begin(&mut self, names: &[&str], numeric: Vec<bool>) {
+        self.end();
+        self.numeric = numeric;
+        match self.format {
+            TableFormat::Markdown => {
+                if self.tables > 0 {
+                    self.out.push('\n');
+                }
+                self.out.push('|');
+                for name in names {
+                    self.out.push(' ');
+                    self.out.push_str(&escape_markdown(name));
+                    self.out.push_str(" |");
+                }
+                self.out.push_str("\n|");
+                for numeric in &self.numeric {
+                    self.out.push_str(if *numeric { " ---: |" } else { " --- |" });
+                }
+                self.out.push('\n');
+            }
+            TableFormat::Html => {
+                self.out.push_str("<table>\n<thead>\n<tr>");
+                for name in names {
+                    self.out.push_str("<th>");
+                    self.out.push_str(&escape_html(name));
+                    self.out.push_str("</th>");
+                }
+                self.out.push_str("</tr>\n</thead>\n<tbody>\n");
+            }
+        }
+        self.open = true;
+        self.tables += 1;
+    }
+
+    /// Append a row; `None` is a NULL value
+    pub fn row(&mut self, values: &[Option<Cow<'_, str>>]) {
+        match self.format {
+            TableFormat::Markdown => {
+                self.out.push('|');
+                for value in values {
+                    self.out.push(' ');
+                    self.out.push_str(&value.as_deref().map_or(Cow::Borrowed(NULL_TEXT), escape_markdown));
+                    self.out.push_str(" |");
+                }
+                self.out.push('\n');
+            }
+            TableFormat::Html => {
+                self.out.push_str("<tr>");
+                for (i, value) in values.iter().enumerate() {
+                    match value {
+                        None => self.out.push_str("<td class=\"null\">NULL</td>"),
+                        Some(text) => {
+                            let numeric = self.numeric.get(i).copied().unwrap_or(false);
+                            self.out.push_str(if numeric { "<td align=\"right\">" } else { "<td>" });
+                            self.out.push_str(&escape_html(text));
+                            self.out.push_str("</td>");
+                        }
+                    }
+                }
+                self.out.push_str("</tr>\n");
+            }
+        }
+    }
+
+    /// Close the current table, if any
+    pub fn end(&mut self) {
+        if self.open && self.format == TableFormat::Html {
+            self.out.push_str("</tbody>\n</table>\n");
+        }
+        self.open = false;
+    }
+
+    /// Take the text rendered so far
+    pub fn take(&mut self) -> String {
+        std::mem::take(&mut self.out)
+    }
+}
+
+/// Escape pipes and line breaks, which would otherwise break the table row
+fn escape_markdown(text: &str) -> Cow<'_, str> {
+    if !text.contains(['|', '\\', '\r', '\n']) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.replace('\\', "\\\\")
+            .replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace(['\r', '\n'], "<br>"),
+    )
+}
+
+fn escape_html(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\n']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 16);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("<br>"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Render rows already loaded in the frontend (e.g. for copying to the clipboard).
+/// HTML output is a bare `<table>` fragment.
+pub fn render_rows(format: TableFormat, columns: &[String], rows: &[Vec<serde_json::Value>]) -> String {
+    // A column is numeric when all of its non-NULL values are numbers
+    let numeric = (0..columns.len())
+        .map(|i| {
+            let mut values = rows.iter().filter_map(|row| row.get(i)).filter(|v| !v.is_null()).peekable();
+            values.peek().is_some() && values.all(|v| v.is_number())
+        })
+        .collect();
+    let names: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let mut renderer = TableRenderer::new(format);
+    renderer.begin(&names, numeric);
+    for row in rows {
+        let values: Vec<Option<Cow<str>>> = row
+            .iter()
+            .map(|value| match value {
+                serde_json::Value::Null => None,
+                serde_json::Value::String(text) => Some(Cow::Borrowed(text.as_str())),
+                other => Some(Cow::Owned(other.to_string())),
+            })
+            .collect();
+        renderer.row(&values);
+    }
+    renderer.end();
+    renderer.take()
+}
+
+/// Writes each result set of the export as a Markdown or HTML table
+pub struct TableWriter {
+    out: TextWriter,
+    renderer: TableRenderer,
+}
+
+impl TableWriter {
+    pub fn create(path: &Path, format: TableFormat) -> Result<TableWriter, String> {
+        let mut out = TextWriter::create(path, TextEncoding::Utf8 { bom: false })?;
+        if format == TableFormat::Html {
+            out.write_str(HTML_HEAD)?;
+        }
+        Ok(TableWriter {
+            out,
+            renderer: TableRenderer::new(format),
+        })
+    }
+
+    fn flush_rendered(&mut self) -> Result<(), String> {
+        let text = self.renderer.take();
+        self.out.write_str(&text)
+    }
+}
+
+impl FormatWriter for TableWriter {
+    fn begin(&mut self, columns: &[StreamColumn]) -> Result<(), String> {
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        self.renderer.begin(&names, columns.iter().map(|c| c.kind.is_numeric()).collect());
+        self.flush_rendered()
+    }
+
+    fn row(&mut self, row: &[Cell]) -> Result<(), String> {
+        let values: Vec<Option<Cow<str>>> = row
+            .iter()
+            .map(|cell| if *cell == Cell::Null { None } else { Some(cell_text(cell)) })
+            .collect();
+        self.renderer.row(&values);
+        self.flush_rendered()
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.renderer.end();
+        self.flush_rendered()?;
+        if self.renderer.format == TableFormat::Html {
+            self.out.write_str(HTML_TAIL)?;
+        }
+        self.out.flush()
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.out.bytes_written()
+    }
+}
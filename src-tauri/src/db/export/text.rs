@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use encoding_rs::Encoding;
+
+/// Character encoding of a text export
+#[derive(Debug, Clone, Copy)]
+pub enum TextEncoding {
+    Utf8 { bom: bool },
+    Utf16 { little_endian: bool },
+    /// Any other WHATWG encoding; unmappable characters become numeric character references
+    Legacy(&'static Encoding),
+}
+
+impl TextEncoding {
+    pub fn from_label(label: &str) -> Result<TextEncoding, String> {
+        let normalized = label.trim().to_ascii_lowercase().replace('_', "-");
+        match normalized.as_str() {
+            "" | "utf-8" | "utf8" => Ok(TextEncoding::Utf8 { bom: false }),
+            "utf-8-bom" | "utf8-bom" | "utf-8 bom" => Ok(TextEncoding::Utf8 { bom: true }),
+            "utf-16" | "utf-16le" => Ok(TextEncoding::Utf16 { little_endian: true }),
+            "utf-16be" => Ok(TextEncoding::Utf16 { little_endian: false }),
+            _ => Encoding::for_label(normalized.as_bytes())
+                .map(TextEncoding::Legacy)
+                .ok_or_else(|| format!("Unsupported encoding: {}", label)),
+        }
+    }
+
+    /// The encoding_rs encoding, for decoding files in this encoding
+    pub fn encoding(&self) -> &'static Encoding {
+        match self {
+            TextEncoding::Utf8 { .. } => encoding_rs::UTF_8,
+            TextEncoding::Utf16 { little_endian: true } => encoding_rs::UTF_16LE,
+            TextEncoding::Utf16 { little_endian: false } => encoding_rs::UTF_16BE,
+            TextEncoding::Legacy(encoding) => encoding,
+        }
+    }
+}
+
+/// Buffered file writer that encodes text and counts the bytes written
+pub struct TextWriter {
+    out: BufWriter<File>,
+    encoding: TextEncoding,
+    bytes: u64,
+}
+
+impl TextWriter {
+    pub fn create(path: &Path, encoding: TextEncoding) -> Result<TextWriter, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut writer = TextWriter {
+            out: BufWriter::with_capacity(64 * 1024, file),
+            encoding,
+            bytes: 0,
+        };
+        match encoding {
+            TextEncoding::Utf8 { bom: true } => writer.write_bytes(&[0xEF, 0xBB, 0xBF])?,
+            TextEncoding::Utf16 { little_endian: true } => writer.write_bytes(&[0xFF, 0xFE])?,
+            TextEncoding::Utf16 { little_endian: false } => writer.write_bytes(&[0xFE, 0xFF])?,
+            _ => {}
+        }
+        Ok(writer)
+    }
+
+    /// Write bytes that are already in the target encoding
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.out.write_all(bytes).map_err(|e| e.to_string())?;
+        self.bytes += bytes.len() as u64;
+        Ok(())
+    }
+
+    pub fn write_str(&mut self, text: &str) -> Result<(), String> {
+        match self.encoding {
+            TextEncoding::Utf8 { .. } => self.write_bytes(text.as_bytes()),
+            TextEncoding::Utf16 { little_endian } => {
+                let bytes: Vec<u8> = text
+                    .encode_utf16()
+                    .flat_map(|unit| if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() })
+                    .collect();
+                self.write_bytes(&bytes)
+            }
+            TextEncoding::Legacy(encoding) => {
+                let (bytes, _, _) = encoding.encode(text);
+                self.write_bytes(&bytes)
+            }
+        }
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.out.flush().map_err(|e| e.to_string())
+    }
+}
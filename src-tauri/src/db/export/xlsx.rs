@@ -0,0 +1,202 @@
+use std::path::{Path, PathBuf};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::db::export::{cell_text, is_plain_number, FormatWriter};
+use crate::db::stream::{Cell, ColumnKind, StreamColumn};
+
+/// Rows per worksheet, including the header
+const MAX_ROWS: u32 = 1_048_576;
+const MAX_COLUMNS: usize = 16_384;
+/// Longest string Excel stores in a cell
+const MAX_STRING_CHARS: usize = 32_767;
+/// Integers beyond this lose precision as Excel numbers and are written as text
+const MAX_EXACT_INT: i64 = 1 << 53;
+
+fn xlsx_error(e: XlsxError) -> String {
+    e.to_string()
+}
+
+/// Writes an Excel workbook with one worksheet per result set.
+///
+/// Worksheets use rust_xlsxwriter's constant-memory mode, so rows are flushed
+/// to a temporary file as they are written. A result larger than Excel's row
+/// limit continues on another worksheet.
+pub struct XlsxWriter {
+    path: PathBuf,
+    workbook: Workbook,
+    header_format: Format,
+    date_format: Format,
+    time_format: Format,
+    timestamp_format: Format,
+    columns: Vec<StreamColumn>,
+    /// Index of the current worksheet
+    sheet: Option<usize>,
+    /// Result sets started so far
+    results: usize,
+    /// Worksheets used by the current result set
+    parts: usize,
+    /// Next row of the current worksheet
+    next_row: u32,
+    bytes: u64,
+}
+
+impl XlsxWriter {
+    pub fn create(path: &Path) -> Result<XlsxWriter, String> {
+        // Fail early on an unwritable destination; the workbook is only written in `finish`
+        std::fs::File::create(path).map_err(|e| e.to_string())?;
+        Ok(XlsxWriter {
+            path: path.to_path_buf(),
+            workbook: Workbook::new(),
+            header_format: Format::new().set_bold(),
+            date_format: Format::new().set_num_format("yyyy-mm-dd"),
+            time_format: Format::new().set_num_format("hh:mm:ss"),
+            timestamp_format: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+            columns: Vec::new(),
+            sheet: None,
+            results: 0,
+            parts: 0,
+            next_row: 0,
+            bytes: 0,
+        })
+    }
+
+    /// Add a worksheet for the current result set with its header row
+    fn add_sheet(&mut self) -> Result<(), String> {
+        self.parts += 1;
+        let name = if self.parts == 1 {
+            format!("Result {}", self.results)
+        } else {
+            format!("Result {} ({})", self.results, self.parts)
+        };
+        let worksheet = self.workbook.add_worksheet_with_constant_memory();
+        worksheet.set_name(name).map_err(xlsx_error)?;
+        worksheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+        for (i, column) in self.columns.iter().enumerate() {
+            let col = i as u16;
+            // Column widths must be set before any row in constant-memory mode
+            worksheet.set_column_width(col, column_width(column)).map_err(xlsx_error)?;
+            worksheet
+                .write_string_with_format(0, col, &column.name, &self.header_format)
+                .map_err(xlsx_error)?;
+        }
+        self.sheet = Some(self.workbook.worksheets().len() - 1);
+        self.next_row = 1;
+        Ok(())
+    }
+}
+
+fn column_width(column: &StreamColumn) -> f64 {
+    let name = column.name.chars().count() as f64 + 2.0;
+    let content: f64 = match column.kind {
+        ColumnKind::Bool => 8.0,
+        ColumnKind::Date => 12.0,
+        ColumnKind::Time => 10.0,
+        ColumnKind::Timestamp | ColumnKind::TimestampTz => 20.0,
+        ColumnKind::Int | ColumnKind::Float | ColumnKind::Decimal => 12.0,
+        _ => 20.0,
+    };
+    name.max(content).min(60.0)
+}
+
+/// Date and time values Excel can represent; anything else is written as text
+fn excel_date(date: NaiveDate) -> Option<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(1900, 1, 1)?;
+    let last = NaiveDate::from_ymd_opt(9999, 12, 31)?;
+    (first..=last).contains(&date).then_some(date)
+}
+
+fn parse_datetime(kind: ColumnKind, text: &str) -> Option<NaiveDateTime> {
+    let datetime = match kind {
+        // Keep the wall-clock time the server printed
+        ColumnKind::TimestampTz => DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%#z")
+            .ok()
+            .map(|ts| ts.naive_local()),
+        _ => NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+            .ok(),
+    }?;
+    excel_date(datetime.date()).map(|_| datetime)
+}
+
+fn truncate_chars(text: &str) -> &str {
+    match text.char_indices().nth(MAX_STRING_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+impl FormatWriter for XlsxWriter {
+    fn begin(&mut self, columns: &[StreamColumn]) -> Result<(), String> {
+        if columns.len() > MAX_COLUMNS {
+            return Err(format!("Excel worksheets are limited to {} columns", MAX_COLUMNS));
+        }
+        self.columns = columns.to_vec();
+        self.results += 1;
+        self.parts = 0;
+        self.add_sheet()
+    }
+
+    fn row(&mut self, row: &[Cell]) -> Result<(), String> {
+        if self.next_row >= MAX_ROWS {
+            self.add_sheet()?;
+        }
+        let row_num = self.next_row;
+        let index = self.sheet.ok_or_else(|| "No result set started".to_string())?;
+        let worksheet = self.workbook.worksheet_from_index(index).map_err(xlsx_error)?;
+        for (i, (column, cell)) in self.columns.iter().zip(row).enumerate() {
+            let col = i as u16;
+            let written = match cell {
+                Cell::Null => continue,
+                Cell::Bool(v) => worksheet.write_boolean(row_num, col, *v),
+                Cell::Int(v) if v.unsigned_abs() <= MAX_EXACT_INT as u64 => {
+                    worksheet.write_number(row_num, col, *v as f64)
+                }
+                Cell::Float(v) if v.is_finite() => worksheet.write_number(row_num, col, *v),
+                // Up to 15 significant digits survive the conversion to an Excel number
+                Cell::Decimal(v) if is_plain_number(v) && v.bytes().filter(u8::is_ascii_digit).count() <= 15 => {
+                    match v.parse::<f64>() {
+                        Ok(number) => worksheet.write_number(row_num, col, number),
+                        Err(_) => worksheet.write_string(row_num, col, v),
+                    }
+                }
+                Cell::Text(text) => match column.kind {
+                    ColumnKind::Date => match NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(excel_date) {
+                        Some(date) => worksheet.write_datetime_with_format(row_num, col, date, &self.date_format),
+                        None => worksheet.write_string(row_num, col, text),
+                    },
+                    ColumnKind::Time => match NaiveTime::parse_from_str(text, "%H:%M:%S%.f") {
+                        Ok(time) => worksheet.write_datetime_with_format(row_num, col, time, &self.time_format),
+                        Err(_) => worksheet.write_string(row_num, col, text),
+                    },
+                    ColumnKind::Timestamp | ColumnKind::TimestampTz => match parse_datetime(column.kind, text) {
+                        Some(datetime) => {
+                            worksheet.write_datetime_with_format(row_num, col, datetime, &self.timestamp_format)
+                        }
+                        None => worksheet.write_string(row_num, col, text),
+                    },
+                    _ => worksheet.write_string(row_num, col, truncate_chars(text)),
+                },
+                other => worksheet.write_string(row_num, col, truncate_chars(&cell_text(other))),
+            };
+            written.map_err(xlsx_error)?;
+        }
+        self.next_row += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if self.sheet.is_none() {
+            // A script without result sets still produces a valid workbook
+            self.workbook.add_worksheet();
+        }
+        self.workbook.save(&self.path).map_err(xlsx_error)?;
+        self.bytes = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        Ok(())
+    }
+
+    /// The workbook is only written by `finish`
+    fn bytes_written(&self) -> u64 {
+        self.bytes
+    }
+}
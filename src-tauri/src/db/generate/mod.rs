@@ -0,0 +1,934 @@
+// Test data generation
+//
+// `generate_test_data` fills a table with made-up rows. Each column gets a
+// `Generator`, chosen from, in order: the request's override, its foreign key,
+// the values its enum type or a CHECK (... IN ...) constraint allows, what its
+// name suggests (email, first_name, price, created_at...) and finally its type.
+// Foreign key columns take values sampled from the parent table. Unique keys
+// are kept distinct among the generated rows, single integer ones by counting
+// on from the column's current maximum. Rows come from a seeded generator, so
+// the same seed fills the same rows, and are loaded in batches by the import
+// `Loader`. Dates fall between 2021 and 2025 so that they too do not depend on
+// the day the rows are generated.
+
+pub mod words;
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+use sqlx::Row;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::dialect::{identifier_offsets, Dialect};
+use crate::db::import::bulk::{Batch, Loader};
+use crate::db::import::coerce::{coerce, TargetKind};
+use crate::db::import::reader::json_cell;
+use crate::db::introspection;
+use crate::db::export::cell_text;
+use crate::db::stream::{self, encode_hex, Cell};
+use crate::db::tasks::CancelFlag;
+use crate::db::transfer::mapping::{self, Family};
+use crate::db::types::{
+    ColumnGenerator, ColumnInfo, GenerateDataRequest, GenerateDataSummary, GenerateProgress, GeneratedColumn,
+    LoadMethod, RejectedRow, SqlPool,
+};
+use words::*;
+
+/// Rejected rows returned in the summary; the rest are only counted
+const MAX_REJECTED: usize = 1000;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Distinct parent keys sampled per foreign key
+const REFERENCE_SAMPLE: usize = 10000;
+/// Attempts at a row whose unique values are all new
+const UNIQUE_ATTEMPTS: usize = 20;
+
+/// 2021-01-01 and 2026-01-01, in days since 1970-01-01
+const RECENT_DAYS: (i64, i64) = (18628, 20454);
+/// 1950-01-01 and 2006-01-01, for birth dates
+const BIRTH_DAYS: (i64, i64) = (-7305, 13149);
+
+#[derive(Debug, Clone)]
+enum Generator {
+    Values(Vec<Cell>),
+    /// Column `position` of a row sampled from the parent of foreign key `key`
+    Reference { key: usize, position: usize, label: String },
+    /// Counts up from the value
+    Sequence(i64),
+    Int { min: i64, max: i64 },
+    Decimal { min: f64, max: f64, scale: u32 },
+    Float { min: f64, max: f64 },
+    Bool,
+    /// Days since 1970-01-01
+    Date { min: i64, max: i64 },
+    /// Seconds since 1970-01-01 00:00:00
+    Timestamp { min: i64, max: i64, zone: bool },
+    Time,
+    /// Words of lorem ipsum
+    Sentence { min: usize, max: usize },
+    /// Random letters, e.g. for CHAR(n)
+    Letters(usize),
+    HexDigits(usize),
+    Uuid,
+    Json,
+    Bytes,
+    Interval,
+    Inet,
+    MacAddress,
+    /// A Postgres array of 1 to 3 elements
+    Array(Box<Generator>),
+    Email,
+    FirstName,
+    LastName,
+    FullName,
+    Username,
+    Phone,
+    Street,
+    City,
+    State,
+    PostalCode,
+    Country,
+    CountryCode,
+    Company,
+    Url,
+    Slug,
+    Color,
+    Currency,
+    Status,
+}
+
+impl Generator {
+    fn label(&self) -> String {
+        match self {
+            Generator::Values(values) => format!("one of {} values", values.len()),
+            Generator::Reference { label, .. } => format!("foreign key {}", label),
+            Generator::Sequence(start) => format!("sequence from {}", start),
+            Generator::Int { min, max } => format!("integer {}..{}", min, max),
+            Generator::Decimal { min, max, scale } => format!("decimal {}..{} ({} decimals)", min, max, scale),
+            Generator::Float { min, max } => format!("number {}..{}", min, max),
+            Generator::Date { .. } => "date".to_string(),
+            Generator::Timestamp { .. } => "timestamp".to_string(),
+            Generator::Sentence { .. } => "text".to_string(),
+            Generator::Letters(n) => format!("{} letters", n),
+            Generator::HexDigits(n) => format!("{} hex digits", n),
+            Generator::Array(element) => format!("array of {}", element.label()),
+            Generator::Inet => "ip address".to_string(),
+            Generator::MacAddress => "mac address".to_string(),
+            Generator::FirstName => "first name".to_string(),
+            Generator::LastName => "last name".to_string(),
+            Generator::FullName => "full name".to_string(),
+            Generator::PostalCode => "postal code".to_string(),
+            Generator::CountryCode => "country code".to_string(),
+            other => format!("{:?}", other).to_ascii_lowercase(),
+        }
+    }
+
+    fn value(&mut self, rng: &mut StdRng, references: &[Vec<Cell>]) -> Cell {
+        let pick = |rng: &mut StdRng, items: &[&str]| items.choose(rng).copied().unwrap_or_default().to_string();
+        match self {
+            Generator::Values(values) => values.choose(rng).cloned().unwrap_or(Cell::Null),
+            Generator::Reference { key, position, .. } => references[*key][*position].clone(),
+            Generator::Sequence(next) => {
+                *next += 1;
+                Cell::Int(*next - 1)
+            }
+            Generator::Int { min, max } => Cell::Int(rng.gen_range(*min..=*max)),
+            Generator::Decimal { min, max, scale } => {
+                Cell::Decimal(format!("{:.*}", *scale as usize, rng.gen_range(*min..=*max)))
+            }
+            Generator::Float { min, max } => Cell::Float((rng.gen_range(*min..=*max) * 1000.0).round() / 1000.0),
+            Generator::Bool => Cell::Bool(rng.gen()),
+            Generator::Date { min, max } => Cell::Text(date_text(rng.gen_range(*min..*max))),
+            Generator::Timestamp { min, max, zone } => {
+                let seconds = rng.gen_range(*min..*max);
+                let text = format!("{} {}", date_text(seconds.div_euclid(86400)), time_text(seconds.rem_euclid(86400)));
+                Cell::Text(if *zone { format!("{}+00", text) } else { text })
+            }
+            Generator::Time => Cell::Text(time_text(rng.gen_range(0..86400))),
+            Generator::Sentence { min, max } => {
+                let count = rng.gen_range(*min..=*max);
+                let mut text = (0..count).map(|_| pick(rng, LOREM)).collect::<Vec<_>>().join(" ");
+                if let Some(first) = text.get(..1) {
+                    text = first.to_ascii_uppercase() + &text[1..];
+                }
+                Cell::Text(text)
+            }
+            Generator::Letters(n) => Cell::Text((0..*n).map(|_| rng.gen_range(b'A'..=b'Z') as char).collect()),
+            Generator::HexDigits(n) => Cell::Text((0..*n).map(|_| format!("{:x}", rng.gen_range(0..16u8))).collect()),
+            Generator::Uuid => {
+                let mut bytes: [u8; 16] = rng.gen();
+                bytes[6] = (bytes[6] & 0x0f) | 0x40;
+                bytes[8] = (bytes[8] & 0x3f) | 0x80;
+                let hex = encode_hex(&bytes);
+                Cell::Text(format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..]))
+            }
+            Generator::Json => {
+                let tags: Vec<String> = (0..rng.gen_range(0..=3)).map(|_| pick(rng, TAGS)).collect();
+                Cell::Json(serde_json::json!({
+                    "id": rng.gen_range(1..100000),
+                    "name": pick(rng, FIRST_NAMES),
+                    "active": rng.gen::<bool>(),
+                    "score": (rng.gen_range(0.0..100.0f64) * 100.0).round() / 100.0,
+                    "tags": tags,
+                }))
+            }
+            Generator::Bytes => Cell::Bytes((0..16).map(|_| rng.gen()).collect()),
+            Generator::Interval => Cell::Text(format!("{} days {} hours", rng.gen_range(0..365), rng.gen_range(0..24))),
+            Generator::Inet => Cell::Text(format!(
+                "{}.{}.{}.{}",
+                rng.gen_range(1..224),
+                rng.gen_range(0..256),
+                rng.gen_range(0..256),
+                rng.gen_range(1..255)
+            )),
+            Generator::MacAddress => {
+                Cell::Text((0..6).map(|_| format!("{:02x}", rng.gen::<u8>())).collect::<Vec<_>>().join(":"))
+            }
+            Generator::Array(element) => {
+                let items: Vec<String> = (0..rng.gen_range(1..=3))
+                    .map(|_| match element.value(rng, references) {
+                        Cell::Text(text) => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+                        other => cell_text(&other).into_owned(),
+                    })
+                    .collect();
+                Cell::Text(format!("{{{}}}", items.join(",")))
+            }
+            Generator::Email => Cell::Text(
+                format!(
+                    "{}.{}{}@{}",
+                    pick(rng, FIRST_NAMES),
+                    pick(rng, LAST_NAMES),
+                    rng.gen_range(1..1000),
+                    pick(rng, DOMAINS)
+                )
+                .to_lowercase(),
+            ),
+            Generator::FirstName => Cell::Text(pick(rng, FIRST_NAMES)),
+            Generator::LastName => Cell::Text(pick(rng, LAST_NAMES)),
+            Generator::FullName => Cell::Text(format!("{} {}", pick(rng, FIRST_NAMES), pick(rng, LAST_NAMES))),
+            Generator::Username => Cell::Text(
+                format!("{}{}{}", pick(rng, FIRST_NAMES), pick(rng, LAST_NAMES).get(..1).unwrap_or_default(), rng.gen_range(1..10000))
+                    .to_lowercase(),
+            ),
+            Generator::Phone => Cell::Text(format!(
+                "+1-{}-{:03}-{:04}",
+                rng.gen_range(201..990),
+                rng.gen_range(100..1000),
+                rng.gen_range(0..10000)
+            )),
+            Generator::Street => Cell::Text(format!(
+                "{} {} {}",
+                rng.gen_range(1..2000),
+                pick(rng, STREETS),
+                pick(rng, STREET_SUFFIXES)
+            )),
+            Generator::City => Cell::Text(pick(rng, CITIES)),
+            Generator::State => Cell::Text(pick(rng, STATES)),
+            Generator::PostalCode => Cell::Text(format!("{:05}", rng.gen_range(1000..100000))),
+            Generator::Country => Cell::Text(COUNTRIES.choose(rng).map(|c| c.0).unwrap_or_default().to_string()),
+            Generator::CountryCode => Cell::Text(COUNTRIES.choose(rng).map(|c| c.1).unwrap_or_default().to_string()),
+            Generator::Company => Cell::Text(format!("{} {}", pick(rng, COMPANY_WORDS), pick(rng, COMPANY_SUFFIXES))),
+            Generator::Url => Cell::Text(format!(
+                "https://www.{}.com/{}",
+                pick(rng, COMPANY_WORDS).to_lowercase().replace(' ', ""),
+                pick(rng, LOREM)
+            )),
+            Generator::Slug => {
+                let count = rng.gen_range(2..=4);
+                Cell::Text((0..count).map(|_| pick(rng, LOREM)).collect::<Vec<_>>().join("-"))
+            }
+            Generator::Color => Cell::Text(format!("#{:06x}", rng.gen_range(0..0x1000000))),
+            Generator::Currency => Cell::Text(pick(rng, CURRENCIES)),
+            Generator::Status => Cell::Text(pick(rng, STATUSES)),
+        }
+    }
+}
+
+/// `YYYY-MM-DD` of a day counted from 1970-01-01
+fn date_text(days: i64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Days from 1970-01-01 of a `YYYY-MM-DD` date
+fn date_days(text: &str) -> Option<i64> {
+    let mut parts = text.get(..10)?.split('-');
+    let (year, month, day): (i64, i64, i64) =
+        (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Howard Hinnant's days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146097 + doe - 719468)
+}
+
+/// Seconds from 1970-01-01 of `YYYY-MM-DD[ HH:MM[:SS]]`
+fn timestamp_seconds(text: &str) -> Option<i64> {
+    let days = date_days(text)?;
+    let time = text.get(11..).unwrap_or_default();
+    let mut parts = time.split(':').map(|part| part.get(..2).and_then(|p| p.parse::<i64>().ok()));
+    let hours = parts.next().flatten().unwrap_or(0);
+    let minutes = parts.next().flatten().unwrap_or(0);
+    let seconds = parts.next().flatten().unwrap_or(0);
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+fn time_text(seconds: i64) -> String {
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// Quoted literals of a CHECK constraint restricting `column` to a list,
+/// e.g. `status IN ('a', 'b')` or Postgres' `status = ANY (ARRAY['a'::text, ...])`
+fn check_values(checks: &[String], column: &str) -> Option<Vec<String>> {
+    checks.iter().find_map(|check| {
+        let position = *identifier_offsets(check, column).first()?;
+        let rest = &check[position + column.len()..];
+        let rest = rest.trim_start_matches(['"', '`', ')', ' ']).trim_start_matches("::text").trim_start_matches([')', ' ']);
+        let upper = rest.to_ascii_uppercase();
+        if !(upper.starts_with("IN") || upper.starts_with("= ANY")) {
+            return None;
+        }
+        let list = &rest[..rest.find(')').unwrap_or(rest.len())];
+        let mut values = Vec::new();
+        let mut chars = list.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\'' {
+                continue;
+            }
+            let mut value = String::new();
+            while let Some(c) = chars.next() {
+                if c == '\'' {
+                    if chars.peek() == Some(&'\'') {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                value.push(c);
+            }
+            values.push(value);
+        }
+        (!values.is_empty()).then_some(values)
+    })
+}
+
+/// Bounds a CHECK constraint puts on a numeric column: `column >= 0`, `column < 100`...
+fn check_bounds(checks: &[String], column: &str) -> (Option<f64>, Option<f64>) {
+    let (mut min, mut max) = (None, None);
+    for check in checks {
+        for position in identifier_offsets(check, column) {
+            let rest = check[position + column.len()..].trim_start_matches(['"', '`', ')', ' ']);
+            let (operator, rest) = match rest.get(..2) {
+                Some(">=") | Some("<=") => rest.split_at(2),
+                _ if rest.starts_with('>') || rest.starts_with('<') => rest.split_at(1),
+                _ => continue,
+            };
+            let number: String = rest
+                .trim_start_matches([' ', '('])
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+                .collect();
+            let Ok(value) = number.parse::<f64>() else { continue };
+            match operator {
+                ">=" => min = Some(value),
+                ">" => min = Some(value + 1.0),
+                "<=" => max = Some(value),
+                _ => max = Some(value - 1.0),
+            }
+        }
+    }
+    (min, max)
+}
+
+/// What the column's name suggests, for its family of types
+fn by_name(name: &str, family: &Family) -> Option<Generator> {
+    let name = name.to_ascii_lowercase();
+    let has = |parts: &[&str]| parts.iter().any(|part| name.contains(part));
+    let is = |parts: &[&str]| {
+        parts.iter().any(|part| name == *part || name.ends_with(&format!("_{}", part)) || name.ends_with(part) && name.len() > part.len() && part.len() > 3)
+    };
+    match family {
+        Family::VarChar(_) | Family::Char(_) | Family::Text => Some(if has(&["email", "e_mail"]) {
+            Generator::Email
+        } else if has(&["first_name", "firstname", "given_name", "forename"]) {
+            Generator::FirstName
+        } else if has(&["last_name", "lastname", "surname", "family_name"]) {
+            Generator::LastName
+        } else if has(&["username", "user_name", "login", "handle", "nickname"]) {
+            Generator::Username
+        } else if has(&["company", "organization", "organisation", "employer"]) {
+            Generator::Company
+        } else if has(&["phone", "mobile", "fax"]) || is(&["tel"]) {
+            Generator::Phone
+        } else if has(&["country_code", "countrycode"]) {
+            Generator::CountryCode
+        } else if has(&["country"]) {
+            Generator::Country
+        } else if has(&["city", "town"]) {
+            Generator::City
+        } else if is(&["state", "province", "region"]) {
+            Generator::State
+        } else if has(&["zip", "postal", "postcode"]) {
+            Generator::PostalCode
+        } else if has(&["address", "street"]) {
+            Generator::Street
+        } else if has(&["url", "website", "homepage", "link"]) {
+            Generator::Url
+        } else if has(&["slug"]) {
+            Generator::Slug
+        } else if has(&["color", "colour"]) {
+            Generator::Color
+        } else if has(&["currency"]) {
+            Generator::Currency
+        } else if has(&["password", "hash", "token", "secret", "salt", "checksum"]) {
+            Generator::HexDigits(32)
+        } else if has(&["uuid", "guid"]) {
+            Generator::Uuid
+        } else if is(&["ip", "ip_address", "ipaddress"]) {
+            Generator::Inet
+        } else if is(&["status"]) {
+            Generator::Status
+        } else if is(&["title", "subject", "headline", "caption", "label"]) {
+            Generator::Sentence { min: 2, max: 6 }
+        } else if has(&["description", "body", "content", "note", "comment", "bio", "summary", "message", "text"]) {
+            Generator::Sentence { min: 8, max: 30 }
+        } else if is(&["name", "fullname", "full_name", "display_name", "author", "owner", "customer", "contact"]) {
+            Generator::FullName
+        } else {
+            return None;
+        }),
+        Family::SmallInt | Family::Int | Family::BigInt => {
+            if is(&["age"]) {
+                Some(Generator::Int { min: 18, max: 90 })
+            } else if has(&["quantity", "qty", "stock"]) {
+                Some(Generator::Int { min: 0, max: 500 })
+            } else if has(&["rating", "stars"]) {
+                Some(Generator::Int { min: 1, max: 5 })
+            } else if is(&["year"]) {
+                Some(Generator::Int { min: 1990, max: 2025 })
+            } else if has(&["count"]) {
+                Some(Generator::Int { min: 0, max: 1000 })
+            } else {
+                None
+            }
+        }
+        Family::Decimal(_) | Family::Real | Family::Double => {
+            if has(&["price", "amount", "cost", "total", "salary", "balance", "fee", "tax", "discount"]) {
+                Some(Generator::Decimal { min: 1.0, max: 1000.0, scale: 2 })
+            } else if has(&["latitude"]) || is(&["lat"]) {
+                Some(Generator::Decimal { min: -90.0, max: 90.0, scale: 6 })
+            } else if has(&["longitude"]) || is(&["lng", "lon", "long"]) {
+                Some(Generator::Decimal { min: -180.0, max: 180.0, scale: 6 })
+            } else if has(&["rating", "score"]) {
+                Some(Generator::Decimal { min: 1.0, max: 5.0, scale: 1 })
+            } else if has(&["percent", "ratio", "rate"]) {
+                Some(Generator::Decimal { min: 0.0, max: 100.0, scale: 2 })
+            } else {
+                None
+            }
+        }
+        Family::Date | Family::Timestamp(_) | Family::TimestampTz(_) if has(&["birth", "dob"]) => {
+            Some(Generator::Date { min: BIRTH_DAYS.0, max: BIRTH_DAYS.1 })
+        }
+        _ => None,
+    }
+}
+
+/// Generator for a column of `data_type` on `dialect`
+fn by_type(dialect: Dialect, data_type: &str) -> Generator {
+    let lower = data_type.trim().to_ascii_lowercase();
+    let base = lower.split(['(', ' ']).next().unwrap_or_default();
+    if let Some(element) = lower.strip_suffix("[]") {
+        return Generator::Array(Box::new(by_type(dialect, element)));
+    }
+    match (dialect, base) {
+        (Dialect::Postgres, "inet" | "cidr") => return Generator::Inet,
+        (Dialect::Postgres, "macaddr" | "macaddr8") => return Generator::MacAddress,
+        (Dialect::Postgres, "interval") => return Generator::Interval,
+        (Dialect::MySql, "enum" | "set") => {
+            let values = check_values(&[format!("x IN {}", &data_type[base.len()..])], "x").unwrap_or_default();
+            return Generator::Values(values.into_iter().map(Cell::Text).collect());
+        }
+        (Dialect::MySql, "tinyint") if !lower.starts_with("tinyint(1)") => return Generator::Int { min: 0, max: 100 },
+        (Dialect::MySql, "year") => return Generator::Int { min: 1990, max: 2025 },
+        _ => {}
+    }
+    let recent = (RECENT_DAYS.0 * 86400, RECENT_DAYS.1 * 86400);
+    match mapping::family(dialect, data_type) {
+        Family::Bool => Generator::Bool,
+        Family::SmallInt => Generator::Int { min: 0, max: 1000 },
+        Family::Int => Generator::Int { min: 1, max: 100000 },
+        Family::BigInt => Generator::Int { min: 1, max: 1000000 },
+        Family::Decimal(args) => {
+            let (precision, scale): (u32, u32) = match args.as_deref().map(|a| a.split_once(',').unwrap_or((a, "0"))) {
+                Some((p, s)) => (p.parse().unwrap_or(10), s.parse().unwrap_or(0)),
+                None => (10, 2),
+            };
+            let max = 10f64.powi(precision.saturating_sub(scale) as i32) - 1.0;
+            Generator::Decimal { min: 0.0, max: max.clamp(0.0, 10000.0), scale }
+        }
+        Family::Real | Family::Double => Generator::Float { min: 0.0, max: 1000.0 },
+        Family::VarChar(Some(n)) if n < 8 => Generator::Letters(n as usize),
+        Family::VarChar(_) => Generator::Sentence { min: 1, max: 5 },
+        Family::Char(n) => Generator::Letters(n.unwrap_or(1) as usize),
+        Family::Text => Generator::Sentence { min: 3, max: 12 },
+        Family::Bytes => Generator::Bytes,
+        Family::Date => Generator::Date { min: RECENT_DAYS.0, max: RECENT_DAYS.1 },
+        Family::Time => Generator::Time,
+        Family::Timestamp(_) => Generator::Timestamp { min: recent.0, max: recent.1, zone: false },
+        Family::TimestampTz(_) => Generator::Timestamp { min: recent.0, max: recent.1, zone: true },
+        Family::Json => Generator::Json,
+        Family::Uuid => Generator::Uuid,
+    }
+}
+
+/// Apply an override's range to a generator that has one
+fn with_range(generator: Generator, spec: &ColumnGenerator) -> Result<Generator, String> {
+    if spec.min.is_none() && spec.max.is_none() {
+        return Ok(generator);
+    }
+    let number = |value: &Option<Value>, default: f64| match value {
+        None => Ok(default),
+        Some(value) => value.as_f64().ok_or_else(|| format!("{} is not a number", value)),
+    };
+    let moment = |value: &Option<Value>, default: i64, parse: fn(&str) -> Option<i64>| match value {
+        None => Ok(default),
+        Some(value) => value
+            .as_str()
+            .and_then(parse)
+            .ok_or_else(|| format!("{} is not a date (YYYY-MM-DD[ HH:MM:SS])", value)),
+    };
+    let generator = match generator {
+        Generator::Int { min, max } => Generator::Int {
+            min: number(&spec.min, min as f64)? as i64,
+            max: number(&spec.max, max as f64)? as i64,
+        },
+        Generator::Decimal { min, max, scale } => {
+            Generator::Decimal { min: number(&spec.min, min)?, max: number(&spec.max, max)?, scale }
+        }
+        Generator::Float { min, max } => Generator::Float { min: number(&spec.min, min)?, max: number(&spec.max, max)? },
+        Generator::Date { min, max } => Generator::Date {
+            min: moment(&spec.min, min, date_days)?,
+            // The range includes its last day
+            max: moment(&spec.max, max - 1, date_days)? + 1,
+        },
+        Generator::Timestamp { min, max, zone } => Generator::Timestamp {
+            min: moment(&spec.min, min, timestamp_seconds)?,
+            max: moment(&spec.max, max - 1, timestamp_seconds)? + 1,
+            zone,
+        },
+        other => return Err(format!("A range does not apply to {} values", other.label())),
+    };
+    let empty = match &generator {
+        Generator::Int { min, max } => min > max,
+        Generator::Decimal { min, max, .. } | Generator::Float { min, max } => min > max,
+        Generator::Date { min, max } | Generator::Timestamp { min, max, .. } => min >= max,
+        _ => false,
+    };
+    if empty {
+        return Err("min is greater than max".to_string());
+    }
+    Ok(generator)
+}
+
+/// Keep a name-based generator within what the column's type can hold:
+/// narrow integers, and the precision and scale of a declared NUMERIC
+fn within_type(generator: Generator, dialect: Dialect, data_type: &str, family: &Family) -> Generator {
+    let lower = data_type.trim().to_ascii_lowercase();
+    let unsigned = lower.contains("unsigned");
+    let (generator, low, high) = match (dialect, lower.split(['(', ' ']).next().unwrap_or_default(), family) {
+        (Dialect::MySql, "tinyint", _) if unsigned => (generator, 0.0, 255.0),
+        (Dialect::MySql, "tinyint", _) => (generator, -128.0, 127.0),
+        (Dialect::MySql, "year", _) => (generator, 1901.0, 2155.0),
+        (_, _, Family::SmallInt) => (generator, -32768.0, 32767.0),
+        (_, _, Family::Decimal(Some(args))) => {
+            let Generator::Decimal { min, max, scale: digits } = generator else { return generator };
+            let (precision, scale) = args.split_once(',').unwrap_or((args, "0"));
+            let (precision, scale): (i32, u32) = (precision.parse().unwrap_or(38), scale.parse().unwrap_or(0));
+            let digits = digits.min(scale);
+            // The largest value that still fits once rounded to `digits`
+            let high = 10f64.powi(precision - scale as i32) - 10f64.powi(-(digits as i32));
+            (Generator::Decimal { min, max, scale: digits }, if unsigned { 0.0 } else { -high }, high)
+        }
+        _ => return generator,
+    };
+    let (min, max) = match &generator {
+        Generator::Int { min, max } => (*min as f64, *max as f64),
+        Generator::Decimal { min, max, .. } | Generator::Float { min, max } => (*min, *max),
+        _ => return generator,
+    };
+    // Nothing of the name's range fits, like a price in a NUMERIC(2,2) column
+    if min > high || max < low {
+        return by_type(dialect, data_type);
+    }
+    within_checks(generator, (Some(low), Some(high)))
+}
+
+/// Keep a generator within the bounds of the column's CHECK constraints
+fn within_checks(generator: Generator, (low, high): (Option<f64>, Option<f64>)) -> Generator {
+    match generator {
+        Generator::Int { min, max } => {
+            let min = low.map_or(min, |low| min.max(low.ceil() as i64));
+            let max = high.map_or(max, |high| max.min(high.floor() as i64)).max(min);
+            Generator::Int { min, max }
+        }
+        Generator::Decimal { min, max, scale } => {
+            let min = low.map_or(min, |low| min.max(low));
+            Generator::Decimal { min, max: high.map_or(max, |high| max.min(high)).max(min), scale }
+        }
+        Generator::Float { min, max } => {
+            let min = low.map_or(min, |low| min.max(low));
+            Generator::Float { min, max: high.map_or(max, |high| max.min(high)).max(min) }
+        }
+        other => other,
+    }
+}
+
+/// A generated column
+struct Column {
+    info: ColumnInfo,
+    generator: Generator,
+    null_ratio: f64,
+    kind: TargetKind,
+    /// Characters of a VARCHAR(n) / CHAR(n)
+    max_length: Option<usize>,
+}
+
+/// Labels of a Postgres table's enum columns
+async fn postgres_enums(pool: &sqlx::Pool<sqlx::Postgres>, table: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT a.attname::text AS name,
+               ARRAY(SELECT e.enumlabel::text FROM pg_enum e WHERE e.enumtypid = t.oid ORDER BY e.enumsortorder) AS labels
+        FROM pg_attribute a
+        JOIN pg_type t ON t.oid = a.atttypid
+        WHERE a.attrelid = $1::regclass AND a.attnum > 0 AND NOT a.attisdropped AND t.typtype = 'e'
+        "#,
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| Ok((row.try_get("name").map_err(|e| e.to_string())?, row.try_get("labels").map_err(|e| e.to_string())?)))
+        .collect()
+}
+
+/// Columns of the table and how each is filled
+struct Plan {
+    columns: Vec<Column>,
+    skipped: Vec<String>,
+    /// Parent keys sampled per foreign key
+    references: Vec<Vec<Vec<Cell>>>,
+    /// Unique keys, as positions in `columns`
+    unique: Vec<Vec<usize>>,
+}
+
+/// Introspect the table and pick every column's generator
+async fn plan(pool: &SqlPool, request: &GenerateDataRequest) -> Result<Plan, String> {
+    let dialect = pool.dialect();
+    let schema = request.schema.as_deref();
+    let table = request.table.as_str();
+    let infos = introspection::columns(pool, schema, table).await?;
+    if infos.is_empty() {
+        return Err(format!("Table {} not found", table));
+    }
+    if let Some(name) = request.columns.keys().find(|name| !infos.iter().any(|c| &c.name == *name)) {
+        return Err(format!("Column {} does not exist in table {}", name, table));
+    }
+    let checks: Vec<String> = introspection::constraints(pool, schema, table)
+        .await?
+        .into_iter()
+        .filter(|c| c.constraint_type == "CHECK")
+        .filter_map(|c| c.definition)
+        .collect();
+    let enums = match pool {
+        SqlPool::Postgres(pool) => postgres_enums(pool, &dialect.qualified(schema, table)).await?,
+        SqlPool::MySql(_) | SqlPool::Sqlite(_) => Vec::new(),
+    };
+    let overridden = |name: &String| request.columns.get(name).is_some_and(|spec| spec.skip || !spec.values.is_empty());
+
+    // Foreign key columns take their values from rows sampled from the parent,
+    // the first ones in key order so a seeded run samples the same rows again
+    let mut references = Vec::new();
+    let mut referenced: HashMap<String, Generator> = HashMap::new();
+    for fk in introspection::foreign_keys(pool, schema, table).await? {
+        if fk.columns.iter().any(|c| overridden(c) || referenced.contains_key(c)) {
+            continue;
+        }
+        let parent_columns: Vec<String> = fk.referenced_columns.iter().map(|c| dialect.quote_ident(c)).collect();
+        let sql = format!(
+            "SELECT DISTINCT {} FROM {} WHERE {} ORDER BY {} LIMIT {}",
+            parent_columns.join(", "),
+            dialect.qualified(fk.referenced_schema.as_deref(), &fk.referenced_table),
+            parent_columns.iter().map(|c| format!("{} IS NOT NULL", c)).collect::<Vec<_>>().join(" AND "),
+            parent_columns.join(", "),
+            REFERENCE_SAMPLE
+        );
+        let rows = stream::fetch_cells(pool, &sql).await?;
+        if rows.is_empty() {
+            let nullable = fk.columns.iter().all(|name| infos.iter().any(|c| &c.name == name && c.is_nullable));
+            if !nullable {
+                return Err(format!(
+                    "{} has no rows to take values of {} from",
+                    fk.referenced_table,
+                    fk.columns.join(", ")
+                ));
+            }
+            for column in &fk.columns {
+                referenced.insert(column.clone(), Generator::Values(vec![Cell::Null]));
+            }
+            continue;
+        }
+        for (position, column) in fk.columns.iter().enumerate() {
+            let label = format!("{}.{}", fk.referenced_table, fk.referenced_columns.get(position).cloned().unwrap_or_default());
+            referenced.insert(column.clone(), Generator::Reference { key: references.len(), position, label });
+        }
+        references.push(rows);
+    }
+
+    let mut columns = Vec::new();
+    let mut skipped = Vec::new();
+    let mut sequences = Vec::new();
+    for info in infos {
+        let spec = request.columns.get(&info.name).cloned().unwrap_or_default();
+        if spec.skip || info.is_auto_increment || info.identity.is_some() || info.generated.is_some() {
+            skipped.push(info.name);
+            continue;
+        }
+        let family = mapping::family(dialect, &info.data_type);
+        let generator = if !spec.values.is_empty() {
+            Generator::Values(spec.values.iter().cloned().map(json_cell).collect())
+        } else if let Some(generator) = referenced.remove(&info.name) {
+            generator
+        } else if let Some((_, labels)) = enums.iter().find(|(name, _)| name == &info.name) {
+            Generator::Values(labels.iter().cloned().map(Cell::Text).collect())
+        } else if let Some(values) = check_values(&checks, &info.name) {
+            Generator::Values(values.into_iter().map(Cell::Text).collect())
+        } else {
+            let generator = match by_name(&info.name, &family) {
+                Some(generator) => within_type(generator, dialect, &info.data_type, &family),
+                None => by_type(dialect, &info.data_type),
+            };
+            let generator = within_checks(generator, check_bounds(&checks, &info.name));
+            if spec.min.is_none() && spec.max.is_none() && matches!(generator, Generator::Int { .. }) {
+                sequences.push(columns.len());
+            }
+            with_range(generator, &spec).map_err(|e| format!("{}: {}", info.name, e))?
+        };
+        columns.push(Column {
+            kind: TargetKind::from_type(&info.data_type),
+            max_length: match family {
+                Family::VarChar(Some(n)) | Family::Char(Some(n)) => Some(n as usize),
+                _ => None,
+            },
+            null_ratio: spec.null_ratio.unwrap_or(request.null_ratio).clamp(0.0, 1.0),
+            generator,
+            info,
+        });
+    }
+
+    // Keys on columns left to the database are unique already
+    let position = |name: &String| columns.iter().position(|c| &c.info.name == name);
+    let unique: Vec<Vec<usize>> = introspection::unique_keys(pool, schema, table)
+        .await?
+        .iter()
+        .filter_map(|key| key.iter().map(position).collect::<Option<Vec<_>>>())
+        .collect();
+    // Single integer keys count on from their largest value
+    for index in sequences {
+        if !unique.iter().any(|key| key == &[index]) {
+            continue;
+        }
+        let column = dialect.quote_ident(&columns[index].info.name);
+        let sql = format!("SELECT MAX({}) FROM {}", column, dialect.qualified(schema, table));
+        let max = match stream::fetch_cells(pool, &sql).await?.first().and_then(|row| row.first()) {
+            Some(Cell::Int(max)) => *max,
+            Some(Cell::Decimal(max) | Cell::Text(max)) => max.parse().unwrap_or(0),
+            _ => 0,
+        };
+        columns[index].generator = Generator::Sequence(max.saturating_add(1));
+    }
+
+    Ok(Plan { columns, skipped, references, unique })
+}
+
+/// One row of values, coerced to the columns' types
+fn row(columns: &mut [Column], rng: &mut StdRng, references: &[Vec<Vec<Cell>>]) -> Result<Vec<Cell>, String> {
+    let picks: Vec<Vec<Cell>> = references.iter().map(|rows| rows.choose(rng).cloned().unwrap_or_default()).collect();
+    columns
+        .iter_mut()
+        .map(|column| {
+            if column.info.is_nullable && column.null_ratio > 0.0 && rng.gen_bool(column.null_ratio) {
+                return Ok(Cell::Null);
+            }
+            let cell = match column.generator.value(rng, &picks) {
+                Cell::Text(text) => Cell::Text(truncate(text, column.max_length)),
+                cell => cell,
+            };
+            coerce(column.kind, cell).map_err(|e| format!("{}: {}", column.info.name, e))
+        })
+        .collect()
+}
+
+fn truncate(text: String, max_length: Option<usize>) -> String {
+    match max_length {
+        Some(max) if text.chars().count() > max => text.chars().take(max).collect(),
+        _ => text,
+    }
+}
+
+/// Fill `request.table` with `request.row_count` generated rows
+pub async fn generate_test_data(
+    app: &AppHandle,
+    pool: &SqlPool,
+    request: &GenerateDataRequest,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<GenerateDataSummary, String> {
+    let start = Instant::now();
+    let seed = request.seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let Plan { mut columns, skipped, references, unique } = plan(pool, request).await?;
+    if columns.is_empty() {
+        return Err(format!("{} has no columns to fill; the database sets all of them", request.table));
+    }
+    let names: Vec<String> = columns.iter().map(|c| c.info.name.clone()).collect();
+    let mut summary = GenerateDataSummary {
+        rows_inserted: 0,
+        rows_rejected: 0,
+        rejected: Vec::new(),
+        seed,
+        columns: columns
+            .iter()
+            .map(|c| GeneratedColumn { name: c.info.name.clone(), generator: c.generator.label() })
+            .collect(),
+        skipped_columns: skipped,
+        method: LoadMethod::Insert,
+        cancelled: false,
+        execution_time_ms: 0,
+    };
+    let emit = |summary: &GenerateDataSummary| {
+        let _ = app.emit(
+            "generate-progress",
+            GenerateProgress {
+                task_id: task_id.to_string(),
+                rows_inserted: summary.rows_inserted,
+                rows_rejected: summary.rows_rejected,
+                total_rows: request.row_count,
+            },
+        );
+    };
+    let mut loader = Loader::new(pool, request.schema.as_deref(), &request.table, &names).await?;
+    summary.method = loader.method();
+    let batch_size = request.batch_size.max(1);
+    let mut batch: Batch = Vec::with_capacity(batch_size);
+    let mut seen: Vec<HashSet<Vec<String>>> = vec![HashSet::new(); unique.len()];
+    let mut last_progress = Instant::now();
+    emit(&summary);
+
+    for line in 1..=request.row_count {
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        let mut cells = match row(&mut columns, &mut rng, &references) {
+            Ok(cells) => cells,
+            Err(reason) => {
+                reject(&mut summary, line, reason);
+                continue;
+            }
+        };
+        // Draw again while a unique key repeats; text keys finally get the row number appended
+        for attempt in 1..=UNIQUE_ATTEMPTS {
+            let keys: Vec<Option<Vec<String>>> = unique
+                .iter()
+                .map(|key| {
+                    let values: Vec<&Cell> = key.iter().map(|&p| &cells[p]).collect();
+                    (!values.contains(&&Cell::Null)).then(|| values.iter().map(|cell| format!("{:?}", cell)).collect())
+                })
+                .collect();
+            let repeated: Vec<usize> = keys
+                .iter()
+                .enumerate()
+                .filter(|(i, key)| key.as_ref().is_some_and(|key| seen[*i].contains(key)))
+                .map(|(i, _)| i)
+                .collect();
+            if repeated.is_empty() || attempt == UNIQUE_ATTEMPTS {
+                if attempt == UNIQUE_ATTEMPTS {
+                    for &position in repeated.iter().flat_map(|&i| &unique[i]) {
+                        if let Cell::Text(text) = &cells[position] {
+                            let suffix = format!("-{}", line);
+                            let room = columns[position].max_length.map(|max| max.saturating_sub(suffix.len()));
+                            cells[position] = Cell::Text(truncate(text.clone(), room) + &suffix);
+                        }
+                    }
+                }
+                for (i, key) in unique.iter().enumerate() {
+                    let values: Vec<String> = key.iter().map(|&p| format!("{:?}", cells[p])).collect();
+                    if keys[i].is_some() {
+                        seen[i].insert(values);
+                    }
+                }
+                break;
+            }
+            match row(&mut columns, &mut rng, &references) {
+                Ok(next) => cells = next,
+                Err(_) => break,
+            }
+        }
+        batch.push((line, cells));
+
+        if batch.len() >= batch_size {
+            flush(&mut loader, &mut batch, &mut summary).await?;
+        }
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            emit(&summary);
+        }
+    }
+    flush(&mut loader, &mut batch, &mut summary).await?;
+    loader.finish().await?;
+
+    summary.execution_time_ms = start.elapsed().as_millis();
+    emit(&summary);
+    Ok(summary)
+}
+
+fn reject(summary: &mut GenerateDataSummary, line: u64, reason: String) {
+    summary.rows_rejected += 1;
+    if summary.rejected.len() < MAX_REJECTED {
+        summary.rejected.push(RejectedRow { line, reason });
+    }
+}
+
+async fn flush(loader: &mut Loader, batch: &mut Batch, summary: &mut GenerateDataSummary) -> Result<(), String> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    let rejected =
+        loader.load(batch).await.map_err(|e| loader.describe_error(e, summary.rows_inserted, "generation"))?;
+    summary.rows_inserted += (batch.len() - rejected.len()) as u64;
+    for row in rejected {
+        reject(summary, row.line, row.reason);
+    }
+    batch.clear();
+    Ok(())
+}
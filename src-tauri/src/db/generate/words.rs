@@ -0,0 +1,69 @@
+// Word lists the generators draw from
+
+pub const FIRST_NAMES: &[&str] = &[
+    "James", "Mary", "John", "Patricia", "Robert", "Jennifer", "Michael", "Linda", "William", "Elizabeth", "David",
+    "Barbara", "Richard", "Susan", "Joseph", "Jessica", "Thomas", "Sarah", "Charles", "Karen", "Daniel", "Nancy",
+    "Matthew", "Lisa", "Anthony", "Margaret", "Mark", "Sandra", "Paul", "Ashley", "Steven", "Emily", "Andrew",
+    "Donna", "Joshua", "Michelle", "Kevin", "Carol", "Brian", "Amanda", "Wei", "Yuki", "Aisha", "Mateo", "Sofia",
+    "Lucas", "Amara", "Noah", "Priya", "Omar", "Elena", "Hiroshi", "Fatima", "Liam", "Chloe", "Arjun", "Ingrid",
+    "Diego", "Zara", "Mohammed",
+];
+
+pub const LAST_NAMES: &[&str] = &[
+    "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Rodriguez", "Martinez",
+    "Hernandez", "Lopez", "Gonzalez", "Wilson", "Anderson", "Thomas", "Taylor", "Moore", "Jackson", "Martin", "Lee",
+    "Perez", "Thompson", "White", "Harris", "Sanchez", "Clark", "Ramirez", "Lewis", "Robinson", "Walker", "Young",
+    "Allen", "King", "Wright", "Scott", "Torres", "Nguyen", "Hill", "Flores", "Chen", "Kim", "Patel", "Müller",
+    "Rossi", "Silva", "Tanaka", "Kowalski", "Novak", "Ivanov", "Dubois", "Larsen", "Okafor", "Haddad",
+];
+
+pub const DOMAINS: &[&str] = &["example.com", "example.org", "example.net", "mail.test", "inbox.test"];
+
+pub const CITIES: &[&str] = &[
+    "New York", "London", "Paris", "Berlin", "Tokyo", "Toronto", "Sydney", "Madrid", "Rome", "Amsterdam", "Chicago",
+    "San Francisco", "Seattle", "Austin", "Boston", "Dublin", "Lisbon", "Vienna", "Prague", "Stockholm", "Oslo",
+    "Copenhagen", "Singapore", "Seoul", "Mumbai", "São Paulo", "Mexico City", "Cape Town", "Nairobi", "Melbourne",
+];
+
+/// Country names with their ISO 3166-1 alpha-2 codes
+pub const COUNTRIES: &[(&str, &str)] = &[
+    ("United States", "US"), ("United Kingdom", "GB"), ("France", "FR"), ("Germany", "DE"), ("Japan", "JP"),
+    ("Canada", "CA"), ("Australia", "AU"), ("Spain", "ES"), ("Italy", "IT"), ("Netherlands", "NL"),
+    ("Ireland", "IE"), ("Portugal", "PT"), ("Austria", "AT"), ("Czechia", "CZ"), ("Sweden", "SE"),
+    ("Norway", "NO"), ("Denmark", "DK"), ("Singapore", "SG"), ("South Korea", "KR"), ("India", "IN"),
+    ("Brazil", "BR"), ("Mexico", "MX"), ("South Africa", "ZA"), ("Kenya", "KE"), ("New Zealand", "NZ"),
+];
+
+pub const STATES: &[&str] = &[
+    "California", "Texas", "New York", "Florida", "Illinois", "Washington", "Oregon", "Colorado", "Ontario",
+    "Quebec", "Bavaria", "Catalonia", "Queensland", "Victoria", "Lombardy",
+];
+
+pub const STREETS: &[&str] = &[
+    "Main", "Oak", "Maple", "Cedar", "Elm", "Pine", "Lake", "Hill", "Park", "Washington", "Church", "Mill", "River",
+    "Sunset", "Highland", "Station", "Market", "Bridge", "Meadow", "Forest",
+];
+
+pub const STREET_SUFFIXES: &[&str] = &["Street", "Avenue", "Road", "Lane", "Boulevard", "Drive", "Court", "Way"];
+
+pub const COMPANY_WORDS: &[&str] = &[
+    "Acme", "Globex", "Initech", "Umbrella", "Stark", "Wayne", "Cyberdyne", "Soylent", "Hooli", "Vandelay",
+    "Northwind", "Contoso", "Fabrikam", "Tailspin", "Blue Yonder", "Litware", "Proseware", "Adatum",
+];
+
+pub const COMPANY_SUFFIXES: &[&str] = &["Inc.", "LLC", "Ltd.", "Group", "Labs", "Systems", "Partners", "GmbH"];
+
+pub const CURRENCIES: &[&str] = &["USD", "EUR", "GBP", "JPY", "CAD", "AUD", "CHF", "SEK", "NZD", "SGD"];
+
+pub const STATUSES: &[&str] = &["active", "inactive", "pending", "archived", "suspended"];
+
+pub const TAGS: &[&str] = &["new", "featured", "sale", "popular", "limited", "eco", "premium", "basic"];
+
+pub const LOREM: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do", "eiusmod", "tempor",
+    "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim", "ad", "minim", "veniam", "quis",
+    "nostrud", "exercitation", "ullamco", "laboris", "nisi", "aliquip", "ex", "ea", "commodo", "consequat", "duis",
+    "aute", "irure", "in", "reprehenderit", "voluptate", "velit", "esse", "cillum", "fugiat", "nulla", "pariatur",
+    "excepteur", "sint", "occaecat", "cupidatat", "non", "proident", "sunt", "culpa", "qui", "officia", "deserunt",
+    "mollit", "anim", "id", "est", "laborum",
+];
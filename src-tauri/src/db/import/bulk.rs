@@ -0,0 +1,338 @@
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use mysql_async::prelude::Queryable;
+use mysql_async::{Conn, OptsBuilder, SslOpts};
+use sqlx::mysql::MySqlSslMode;
+use sqlx::postgres::PgPoolCopyExt;
+use sqlx::{ConnectOptions, MySql, Pool, Postgres, Sqlite, Transaction};
+
+use crate::db::dialect::Dialect;
+use crate::db::export::cell_text;
+use crate::db::export::sql::sql_literal;
+use crate::db::import::insert_rows;
+use crate::db::stream::{encode_hex, Cell};
+use crate::db::types::{LoadMethod, RejectedRow, SqlPool};
+
+/// Coerced rows waiting to be loaded, with the file line each starts on
+pub type Batch = Vec<(u64, Vec<Cell>)>;
+
+/// Loads batches of rows into the target table with the fastest method the
+/// connection supports
+pub enum Loader {
+    /// `COPY ... FROM STDIN` per batch; a batch the server refuses is retried
+    /// with INSERTs to find the offending rows
+    Copy { pool: Pool<Postgres>, statement: String, prefix: String },
+    /// `LOAD DATA LOCAL INFILE` per batch over a dedicated mysql_async
+    /// connection, since sqlx cannot answer the server's file request. A batch
+    /// that errors or loads with warnings is rolled back and retried with INSERTs,
+    /// so this is only used on tables whose engine supports transactions
+    LoadData { conn: Conn, pool: Pool<MySql>, statement: String, prefix: String },
+    Insert { pool: SqlPool, dialect: Dialect, prefix: String },
+    /// The whole import runs in one transaction with a prepared INSERT
+    Prepared { tx: Transaction<'static, Sqlite>, statement: String },
+}
+
+impl Loader {
+    pub async fn new(pool: &SqlPool, schema: Option<&str>, table: &str, columns: &[String]) -> Result<Loader, String> {
+        let dialect = pool.dialect();
+        let names: Vec<String> = columns.iter().map(|c| dialect.quote_ident(c)).collect();
+        let qualified = dialect.qualified(schema, table);
+        let prefix = format!("INSERT INTO {} ({}) VALUES\n", qualified, names.join(", "));
+        Ok(match pool {
+            SqlPool::Postgres(pool) => Loader::Copy {
+                pool: pool.clone(),
+                statement: format!("COPY {} ({}) FROM STDIN", qualified, names.join(", ")),
+                prefix,
+            },
+            SqlPool::MySql(mysql) => match load_data_connection(mysql, schema, table).await {
+                Some(conn) => Loader::LoadData {
+                    conn,
+                    pool: mysql.clone(),
+                    statement: format!(
+                        "LOAD DATA LOCAL INFILE 'rows' INTO TABLE {} CHARACTER SET utf8mb4 ({})",
+                        qualified,
+                        names.join(", ")
+                    ),
+                    prefix,
+                },
+                // local_infile is off on the server, the table cannot roll back a
+                // failed batch, or the side connection failed
+                None => Loader::Insert { pool: pool.clone(), dialect, prefix },
+            },
+            SqlPool::Sqlite(pool) => Loader::Prepared {
+                tx: pool.begin().await.map_err(|e| e.to_string())?,
+                statement: format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    qualified,
+                    names.join(", "),
+                    vec!["?"; names.len()].join(", ")
+                ),
+            },
+        })
+    }
+
+    pub fn method(&self) -> LoadMethod {
+        match self {
+            Loader::Copy { .. } => LoadMethod::Copy,
+            Loader::LoadData { .. } => LoadMethod::LoadData,
+            Loader::Insert { .. } => LoadMethod::Insert,
+            Loader::Prepared { .. } => LoadMethod::Prepared,
+        }
+    }
+
+    /// Explain a failed `load` in terms of what was kept: the SQLite path rolls
+    /// the whole `what` back, the others keep the `loaded` rows already written
+    pub fn describe_error(&self, error: String, loaded: u64, what: &str) -> String {
+        match self.method() {
+            LoadMethod::Prepared => format!("{} (the {} was rolled back)", error, what),
+            LoadMethod::Copy | LoadMethod::LoadData | LoadMethod::Insert => {
+                format!("{} ({} rows were written before the error)", error, loaded)
+            }
+        }
+    }
+
+    /// Load one batch and return the rows the database refused
+    pub async fn load(&mut self, rows: &Batch) -> Result<Vec<RejectedRow>, String> {
+        match self {
+            Loader::Copy { pool, statement, prefix } => {
+                let mut data = String::new();
+                for (_, cells) in rows {
+                    copy_row(cells, &mut data);
+                }
+                match copy_in(pool, statement, data).await {
+                    Ok(()) => Ok(Vec::new()),
+                    Err(sqlx::Error::Database(_)) => {
+                        let rejected = insert_rows(pool, prefix, &literal_rows(Dialect::Postgres, rows)).await?;
+                        if rejected.is_empty() {
+                            // Every row inserts fine, so COPY itself is the problem
+                            *self = Loader::Insert {
+                                pool: SqlPool::Postgres(pool.clone()),
+                                dialect: Dialect::Postgres,
+                                prefix: std::mem::take(prefix),
+                            };
+                        }
+                        Ok(rejected)
+                    }
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Loader::LoadData { conn, pool, statement, prefix } => {
+                let mut data = Vec::new();
+                for (_, cells) in rows {
+                    load_data_row(cells, &mut data);
+                }
+                match load_data(conn, statement, data, rows.len()).await {
+                    Ok(true) => Ok(Vec::new()),
+                    Ok(false) | Err(mysql_async::Error::Server(_)) => {
+                        let rejected = insert_rows(pool, prefix, &literal_rows(Dialect::MySql, rows)).await?;
+                        if rejected.is_empty() {
+                            // Every row inserts cleanly, so LOAD DATA itself is the problem
+                            *self = Loader::Insert {
+                                pool: SqlPool::MySql(pool.clone()),
+                                dialect: Dialect::MySql,
+                                prefix: std::mem::take(prefix),
+                            };
+                        }
+                        Ok(rejected)
+                    }
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Loader::Insert { pool, dialect, prefix } => {
+                let rows = literal_rows(*dialect, rows);
+                match pool {
+                    SqlPool::Postgres(pool) => insert_rows(pool, prefix, &rows).await,
+                    SqlPool::MySql(pool) => insert_rows(pool, prefix, &rows).await,
+                    SqlPool::Sqlite(pool) => insert_rows(pool, prefix, &rows).await,
+                }
+            }
+            Loader::Prepared { tx, statement } => {
+                let mut rejected = Vec::new();
+                for (line, cells) in rows {
+                    let mut query = sqlx::query(statement.as_str());
+                    for cell in cells {
+                        query = match cell {
+                            Cell::Null => query.bind(None::<String>),
+                            Cell::Bool(v) => query.bind(*v),
+                            Cell::Int(v) => query.bind(*v),
+                            Cell::Float(v) => query.bind(*v),
+                            Cell::Decimal(v) | Cell::Text(v) => query.bind(v.clone()),
+                            Cell::Bytes(v) => query.bind(v.clone()),
+                            Cell::Json(v) => query.bind(v.to_string()),
+                        };
+                    }
+                    // SQLite undoes only the failed statement, so the transaction carries on
+                    match query.execute(&mut **tx).await {
+                        Ok(_) => {}
+                        Err(sqlx::Error::Database(e)) => {
+                            rejected.push(RejectedRow { line: *line, reason: e.message().to_string() })
+                        }
+                        Err(e) => return Err(e.to_string()),
+                    }
+                }
+                Ok(rejected)
+            }
+        }
+    }
+
+    /// Commit the rows loaded so far (only SQLite holds an open transaction)
+    pub async fn finish(self) -> Result<(), String> {
+        match self {
+            Loader::Prepared { tx, .. } => tx.commit().await.map_err(|e| e.to_string()),
+            Loader::LoadData { conn, .. } => conn.disconnect().await.map_err(|e| e.to_string()),
+            Loader::Copy { .. } | Loader::Insert { .. } => Ok(()),
+        }
+    }
+}
+
+async fn copy_in(pool: &Pool<Postgres>, statement: &str, data: String) -> Result<(), sqlx::Error> {
+    let mut copy = pool.copy_in_raw(statement).await?;
+    if let Err(e) = copy.send(data.into_bytes()).await {
+        let _ = copy.abort(e.to_string()).await;
+        return Err(e);
+    }
+    copy.finish().await.map(|_| ())
+}
+
+/// Open a mysql_async connection with the pool's settings, or None when the
+/// server does not allow `LOAD DATA LOCAL INFILE` or the table's engine is not
+/// transactional (a failed batch on MyISAM keeps the rows it already loaded, so
+/// retrying it with INSERTs would duplicate them)
+async fn load_data_connection(pool: &Pool<MySql>, schema: Option<&str>, table: &str) -> Option<Conn> {
+    let options = pool.connect_options();
+    let mut url = options.to_url_lossy();
+    let ca = url.query_pairs().find(|(key, _)| key == "ssl-ca").map(|(_, value)| value.into_owned());
+    // sqlx's own parameters are unknown to mysql_async
+    url.set_query(None);
+    let base = OptsBuilder::from_opts(mysql_async::Opts::from_url(url.as_str()).ok()?)
+        .socket(options.get_socket().map(|path| path.to_string_lossy().into_owned()))
+        .init(vec!["SET NAMES utf8mb4", "SET time_zone = '+00:00'"]);
+
+    let ssl = match options.get_ssl_mode() {
+        MySqlSslMode::Disabled => None,
+        MySqlSslMode::Preferred | MySqlSslMode::Required => {
+            Some(SslOpts::default().with_danger_accept_invalid_certs(true))
+        }
+        mode @ (MySqlSslMode::VerifyCa | MySqlSslMode::VerifyIdentity) => Some(
+            SslOpts::default()
+                .with_root_certs(ca.map(|path| std::path::PathBuf::from(path).into()).into_iter().collect())
+                .with_danger_skip_domain_validation(matches!(mode, MySqlSslMode::VerifyCa)),
+        ),
+    };
+    let mut conn = match Conn::new(base.clone().ssl_opts(ssl)).await {
+        Ok(conn) => conn,
+        Err(_) if matches!(options.get_ssl_mode(), MySqlSslMode::Preferred) => {
+            Conn::new(base.ssl_opts(None::<SslOpts>)).await.ok()?
+        }
+        Err(_) => return None,
+    };
+
+    let enabled: Option<i64> = conn.query_first("SELECT @@local_infile").await.ok()?;
+    let transactional: Option<String> = conn
+        .exec_first(
+            "SELECT e.TRANSACTIONS FROM information_schema.TABLES t \
+             JOIN information_schema.ENGINES e ON e.ENGINE = t.ENGINE \
+             WHERE t.TABLE_SCHEMA = COALESCE(?, DATABASE()) AND t.TABLE_NAME = ?",
+            (schema, table),
+        )
+        .await
+        .ok()?;
+    if enabled == Some(1) && transactional.as_deref() == Some("YES") {
+        Some(conn)
+    } else {
+        let _ = conn.disconnect().await;
+        None
+    }
+}
+
+/// Run one `LOAD DATA` in its own transaction, committing only when every row
+/// went in without a warning (MySQL truncates or coerces bad values instead of
+/// failing the statement)
+async fn load_data(conn: &mut Conn, statement: &str, data: Vec<u8>, rows: usize) -> Result<bool, mysql_async::Error> {
+    conn.query_drop("START TRANSACTION").await?;
+    conn.set_infile_handler(async move { Ok(stream::iter(std::iter::once(Ok(Bytes::from(data)))).boxed()) });
+    if let Err(e) = conn.query_drop(statement).await {
+        let _ = conn.query_drop("ROLLBACK").await;
+        return Err(e);
+    }
+    if conn.affected_rows() == rows as u64 && conn.get_warnings() == 0 {
+        conn.query_drop("COMMIT").await?;
+        Ok(true)
+    } else {
+        conn.query_drop("ROLLBACK").await?;
+        Ok(false)
+    }
+}
+
+/// Append one row in `LOAD DATA`'s default format: tab separated, `\N` for
+/// NULL and backslash escapes
+fn load_data_row(cells: &[Cell], out: &mut Vec<u8>) {
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            out.push(b'\t');
+        }
+        let text;
+        let bytes: &[u8] = match cell {
+            Cell::Null => {
+                out.extend_from_slice(b"\\N");
+                continue;
+            }
+            Cell::Bool(v) => if *v { b"1" } else { b"0" },
+            Cell::Bytes(v) => v,
+            other => {
+                text = cell_text(other);
+                text.as_bytes()
+            }
+        };
+        for &b in bytes {
+            match b {
+                b'\\' => out.extend_from_slice(b"\\\\"),
+                b'\t' => out.extend_from_slice(b"\\t"),
+                b'\n' => out.extend_from_slice(b"\\n"),
+                b'\r' => out.extend_from_slice(b"\\r"),
+                0 => out.extend_from_slice(b"\\0"),
+                b => out.push(b),
+            }
+        }
+    }
+    out.push(b'\n');
+}
+
+/// Append one row in COPY text format
+fn copy_row(cells: &[Cell], out: &mut String) {
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            out.push('\t');
+        }
+        match cell {
+            Cell::Null => out.push_str("\\N"),
+            Cell::Bool(v) => out.push(if *v { 't' } else { 'f' }),
+            // bytea hex input, with its backslash escaped for COPY
+            Cell::Bytes(v) => {
+                out.push_str("\\\\x");
+                out.push_str(&encode_hex(v));
+            }
+            other => {
+                for c in cell_text(other).chars() {
+                    match c {
+                        '\\' => out.push_str("\\\\"),
+                        '\n' => out.push_str("\\n"),
+                        '\r' => out.push_str("\\r"),
+                        '\t' => out.push_str("\\t"),
+                        c => out.push(c),
+                    }
+                }
+            }
+        }
+    }
+    out.push('\n');
+}
+
+fn literal_rows(dialect: Dialect, rows: &Batch) -> Vec<(u64, String)> {
+    rows.iter()
+        .map(|(line, cells)| {
+            let values: Vec<String> = cells.iter().map(|cell| sql_literal(dialect, cell)).collect();
+            (*line, format!("({})", values.join(", ")))
+        })
+        .collect()
+}
@@ -0,0 +1,112 @@
+use crate::db::export::{cell_text, is_plain_number};
+use crate::db::stream::Cell;
+
+/// What an imported value is converted to, from the target column's declared type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Bool,
+    Int,
+    Number,
+    Json,
+    Bytes,
+    /// Passed as text; the database converts or rejects it
+    Other,
+}
+
+impl TargetKind {
+    /// Classify a catalog type such as `integer`, `int(11) unsigned`, `numeric(10,2)` or `jsonb`
+    pub fn from_type(data_type: &str) -> TargetKind {
+        let t = data_type.trim().to_ascii_lowercase();
+        let base = t.split(['(', ' ']).next().unwrap_or("");
+        if base.starts_with("bool") || t.starts_with("tinyint(1)") {
+            TargetKind::Bool
+        } else if matches!(
+            base,
+            "int" | "integer" | "smallint" | "bigint" | "tinyint" | "mediumint" | "int2" | "int4" | "int8"
+                | "serial" | "smallserial" | "bigserial"
+        ) {
+            TargetKind::Int
+        } else if matches!(
+            base,
+            "numeric" | "decimal" | "dec" | "fixed" | "real" | "double" | "float" | "float4" | "float8"
+        ) {
+            TargetKind::Number
+        } else if base.starts_with("json") {
+            TargetKind::Json
+        } else if base == "bytea" || base.ends_with("blob") || base.ends_with("binary") {
+            TargetKind::Bytes
+        } else {
+            TargetKind::Other
+        }
+    }
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "f" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let hex = text.strip_prefix("\\x").or_else(|| text.strip_prefix("0x"))?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Convert a source value for a column of `kind`; the error describes the bad value
+pub fn coerce(kind: TargetKind, cell: Cell) -> Result<Cell, String> {
+    let invalid = |what: &str, text: &str| format!("'{}' is not a valid {}", text, what);
+    match (kind, cell) {
+        (_, Cell::Null) => Ok(Cell::Null),
+        (TargetKind::Bool, Cell::Bool(v)) => Ok(Cell::Bool(v)),
+        (TargetKind::Bool, Cell::Int(v)) if v == 0 || v == 1 => Ok(Cell::Bool(v == 1)),
+        (TargetKind::Bool, Cell::Text(text)) => {
+            parse_bool(&text).map(Cell::Bool).ok_or_else(|| invalid("boolean", &text))
+        }
+        (TargetKind::Int, Cell::Int(v)) => Ok(Cell::Int(v)),
+        (TargetKind::Int, Cell::Bool(v)) => Ok(Cell::Int(v as i64)),
+        (TargetKind::Int, Cell::Text(text) | Cell::Decimal(text)) => {
+            let trimmed = text.trim();
+            match trimmed.parse::<i64>() {
+                Ok(v) => Ok(Cell::Int(v)),
+                // Beyond i64 (e.g. BIGINT UNSIGNED); the database checks the range
+                Err(_) if is_plain_number(trimmed) && !trimmed.contains(['.', 'e', 'E']) => {
+                    Ok(Cell::Decimal(trimmed.to_string()))
+                }
+                Err(_) => Err(invalid("integer", &text)),
+            }
+        }
+        (TargetKind::Number, Cell::Int(v)) => Ok(Cell::Int(v)),
+        (TargetKind::Number, Cell::Float(v)) => Ok(Cell::Float(v)),
+        (TargetKind::Number, Cell::Text(text) | Cell::Decimal(text)) => {
+            let trimmed = text.trim();
+            if is_plain_number(trimmed) {
+                Ok(Cell::Decimal(trimmed.to_string()))
+            } else {
+                Err(invalid("number", &text))
+            }
+        }
+        (TargetKind::Json, Cell::Text(text)) => serde_json::from_str(&text)
+            .map(Cell::Json)
+            .map_err(|_| invalid("JSON value", &text)),
+        (TargetKind::Json, Cell::Bool(v)) => Ok(Cell::Json(v.into())),
+        (TargetKind::Json, Cell::Int(v)) => Ok(Cell::Json(v.into())),
+        (TargetKind::Json, Cell::Decimal(text)) => serde_json::from_str(&text)
+            .map(Cell::Json)
+            .map_err(|_| invalid("JSON value", &text)),
+        (TargetKind::Bytes, Cell::Text(text)) => {
+            Ok(Cell::Bytes(decode_hex(&text).unwrap_or_else(|| text.into_bytes())))
+        }
+        (_, cell @ Cell::Bytes(_)) | (TargetKind::Json, cell @ Cell::Json(_)) => Ok(cell),
+        // Quoted text converts to dates, UUIDs, enums... on every engine
+        (TargetKind::Other, cell) => Ok(Cell::Text(cell_text(&cell).into_owned())),
+        (_, cell) => Err(format!("'{}' cannot be stored in this column", cell_text(&cell))),
+    }
+}
@@ -0,0 +1,175 @@
+use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use crate::db::dialect::Dialect;
+use crate::db::export::is_plain_number;
+use crate::db::stream::Cell;
+use crate::db::types::InferredType;
+
+fn is_bool_literal(text: &str) -> bool {
+    matches!(
+        text.to_ascii_lowercase().as_str(),
+        "true" | "false" | "t" | "f" | "yes" | "no" | "y" | "n"
+    )
+}
+
+/// Numbers with a leading zero (zip codes, phone numbers...) are kept as text
+fn has_leading_zero(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.")
+}
+
+pub fn is_date(text: &str) -> bool {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok()
+}
+
+pub fn is_timestamp(text: &str) -> bool {
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"]
+        .iter()
+        .any(|format| NaiveDateTime::parse_from_str(text, format).is_ok())
+        || ["%Y-%m-%d %H:%M:%S%.f%#z", "%Y-%m-%dT%H:%M:%S%.f%#z"]
+            .iter()
+            .any(|format| DateTime::parse_from_str(text, format).is_ok())
+        || DateTime::parse_from_rfc3339(text).is_ok()
+}
+
+/// Whether a header candidate looks like a data value rather than a name
+pub fn looks_typed(text: &str) -> bool {
+    let text = text.trim();
+    is_plain_number(text) || is_bool_literal(text) || is_date(text) || is_timestamp(text)
+}
+
+/// Running type inference for one source column
+#[derive(Debug, Clone)]
+pub struct ColumnStats {
+    values: usize,
+    pub nulls: usize,
+    boolean: bool,
+    integer: bool,
+    decimal: bool,
+    date: bool,
+    timestamp: bool,
+    json: bool,
+    integer_digits: usize,
+    scale: usize,
+}
+
+impl Default for ColumnStats {
+    fn default() -> Self {
+        ColumnStats {
+            values: 0,
+            nulls: 0,
+            boolean: true,
+            integer: true,
+            decimal: true,
+            date: true,
+            timestamp: true,
+            json: true,
+            integer_digits: 1,
+            scale: 0,
+        }
+    }
+}
+
+impl ColumnStats {
+    fn observe_number(&mut self, text: &str) {
+        let digits = text.strip_prefix('-').unwrap_or(text);
+        let (int_part, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        self.integer_digits = self.integer_digits.max(int_part.len());
+        self.scale = self.scale.max(fraction.len());
+    }
+
+    pub fn observe(&mut self, cell: &Cell) {
+        if *cell == Cell::Null {
+            self.nulls += 1;
+            return;
+        }
+        self.values += 1;
+        let (boolean, integer, decimal, date, timestamp, json) = match cell {
+            Cell::Bool(_) => (true, false, false, false, false, false),
+            Cell::Int(v) => {
+                self.observe_number(&v.to_string());
+                (false, true, true, false, false, false)
+            }
+            Cell::Decimal(text) => {
+                let plain = is_plain_number(text) && !text.contains(['e', 'E']);
+                if plain {
+                    self.observe_number(text);
+                }
+                (false, false, plain, false, false, false)
+            }
+            Cell::Json(_) => (false, false, false, false, false, true),
+            Cell::Text(text) => {
+                let text = text.trim();
+                let number = is_plain_number(text) && !text.contains(['e', 'E']) && !has_leading_zero(text);
+                if number {
+                    self.observe_number(text);
+                }
+                let date = is_date(text);
+                let json = (text.starts_with('{') || text.starts_with('['))
+                    && serde_json::from_str::<serde_json::Value>(text).is_ok();
+                (
+                    is_bool_literal(text),
+                    number && text.parse::<i64>().is_ok(),
+                    number,
+                    date,
+                    date || is_timestamp(text),
+                    json,
+                )
+            }
+            _ => (false, false, false, false, false, false),
+        };
+        self.boolean &= boolean;
+        self.integer &= integer;
+        self.decimal &= decimal;
+        self.date &= date;
+        self.timestamp &= timestamp;
+        self.json &= json;
+    }
+
+    pub fn inferred_type(&self) -> InferredType {
+        if self.values == 0 {
+            InferredType::Text
+        } else if self.integer {
+            InferredType::Integer
+        } else if self.decimal {
+            InferredType::Decimal
+        } else if self.boolean {
+            InferredType::Boolean
+        } else if self.date {
+            InferredType::Date
+        } else if self.timestamp {
+            InferredType::Timestamp
+        } else if self.json {
+            InferredType::Json
+        } else {
+            InferredType::Text
+        }
+    }
+
+    /// Column type for a new table holding these values
+    pub fn sql_type(&self, dialect: Dialect) -> String {
+        let sql_type = match (self.inferred_type(), dialect) {
+            (InferredType::Boolean, Dialect::Postgres) => "boolean",
+            (InferredType::Boolean, _) => "BOOLEAN",
+            (InferredType::Integer, Dialect::Postgres) => "bigint",
+            (InferredType::Integer, Dialect::MySql) => "BIGINT",
+            (InferredType::Integer, Dialect::Sqlite) => "INTEGER",
+            (InferredType::Decimal, Dialect::Postgres) => "numeric",
+            (InferredType::Decimal, Dialect::MySql) => {
+                let scale = self.scale.min(30);
+                let precision = (self.integer_digits + scale).clamp(1, 65);
+                return format!("DECIMAL({},{})", precision, scale.min(precision));
+            }
+            (InferredType::Decimal, Dialect::Sqlite) => "NUMERIC",
+            (InferredType::Date, Dialect::Postgres) => "date",
+            (InferredType::Date, _) => "DATE",
+            (InferredType::Timestamp, Dialect::Postgres) => "timestamp",
+            (InferredType::Timestamp, _) => "DATETIME",
+            (InferredType::Json, Dialect::Postgres) => "jsonb",
+            (InferredType::Json, Dialect::MySql) => "JSON",
+            (InferredType::Text, Dialect::Postgres) => "text",
+            (InferredType::Json | InferredType::Text, _) => "TEXT",
+        };
+        sql_type.to_string()
+    }
+}
@@ -0,0 +1,415 @@
+// File import
+//
+// `preview_import` sniffs a CSV dialect (or reads JSON / NDJSON) and infers
+// column types from the first rows; `import_file` streams the file through a
+// column mapping into a table. Rows are loaded in batches (see `bulk`): with
+// COPY on Postgres, one multi-row INSERT transaction per batch on MySQL, and a
+// prepared INSERT in a single transaction on SQLite. When a batch fails it is
+// retried row by row behind savepoints so only the offending rows are rejected.
+
+pub mod reader;
+pub mod sniff;
+pub mod infer;
+pub mod coerce;
+pub mod bulk;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use sqlx::{Database, Executor, Pool};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::alter;
+use crate::db::dialect::Dialect;
+use crate::db::export::json::json_value;
+use crate::db::import::bulk::{Batch, Loader};
+use crate::db::import::coerce::{coerce, TargetKind};
+use crate::db::import::infer::ColumnStats;
+use crate::db::import::reader::{json_cell, RowReader};
+use crate::db::introspection;
+use crate::db::stream::Cell;
+use crate::db::tasks::CancelFlag;
+use crate::db::types::{
+    ColumnDef, ColumnMapping, CsvDialect, ImportColumnPreview, ImportFormat, ImportPreview,
+    ImportProgress, ImportRequest, ImportSummary, RejectedRow, RowStatus, SqlPool, TableChangeRequest,
+    TableDesign,
+};
+
+/// Rows sampled for type inference
+const SAMPLE_ROWS: usize = 1000;
+/// Rejected rows returned in the summary; the rest are only counted
+const MAX_REJECTED: usize = 1000;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Detect the file layout and infer column types from the first rows.
+/// `format` and `csv` override detection.
+pub fn preview(
+    path: &str,
+    format: Option<ImportFormat>,
+    csv: Option<CsvDialect>,
+    dialect: Dialect,
+    limit: usize,
+) -> Result<ImportPreview, String> {
+    let file = Path::new(path);
+    let file_size = std::fs::metadata(file).map_err(|e| e.to_string())?.len();
+    let format = match format {
+        Some(format) => format,
+        None => sniff::detect_format(file)?,
+    };
+    let csv = match (format, csv) {
+        (ImportFormat::Csv, Some(csv)) => Some(csv),
+        (ImportFormat::Csv, None) => Some(sniff::sniff_csv(file)?),
+        _ => None,
+    };
+
+    let mut reader = RowReader::open(file, format, &csv.clone().unwrap_or_default())?;
+    let mut stats = vec![ColumnStats::default(); reader.columns().len()];
+    let mut rows = Vec::new();
+    let mut sampled = 0;
+    while sampled < SAMPLE_ROWS {
+        let Some(row) = reader.next_row()? else { break };
+        if row.error.is_some() {
+            continue;
+        }
+        for (stats, cell) in stats.iter_mut().zip(&row.values) {
+            stats.observe(cell);
+        }
+        if rows.len() < limit {
+            rows.push(row.values.iter().map(preview_value).collect());
+        }
+        sampled += 1;
+    }
+
+    let columns = reader
+        .columns()
+        .iter()
+        .zip(&stats)
+        .map(|(name, stats)| ImportColumnPreview {
+            name: name.clone(),
+            inferred_type: stats.inferred_type(),
+            sql_type: stats.sql_type(dialect),
+            nullable: stats.nulls > 0,
+        })
+        .collect();
+    Ok(ImportPreview { format, csv, columns, rows, file_size })
+}
+
+pub fn preview_value(cell: &Cell) -> serde_json::Value {
+    match cell {
+        Cell::Json(value) => value.clone(),
+        Cell::Text(text) => serde_json::Value::String(text.clone()),
+        other => serde_json::from_str(&json_value(other)).unwrap_or(serde_json::Value::Null),
+    }
+}
+
+/// Where a target column's value comes from
+enum MappedValue {
+    Source(usize),
+    Constant(Cell),
+}
+
+struct Target {
+    name: String,
+    kind: TargetKind,
+    value: MappedValue,
+}
+
+/// Create the target table from the mapping, inferring missing column types
+async fn create_table(pool: &SqlPool, request: &ImportRequest, source_columns: usize) -> Result<Vec<String>, String> {
+    let dialect = pool.dialect();
+    let mut reader = RowReader::open(Path::new(&request.path), request.format, &request.csv)?;
+    let mut stats = vec![ColumnStats::default(); source_columns];
+    for _ in 0..SAMPLE_ROWS {
+        let Some(row) = reader.next_row()? else { break };
+        if row.error.is_none() {
+            for (stats, cell) in stats.iter_mut().zip(&row.values) {
+                stats.observe(cell);
+            }
+        }
+    }
+
+    let mut columns = Vec::new();
+    for mapping in &request.columns {
+        let (name, data_type) = match mapping {
+            ColumnMapping::Column { source, target, data_type } => (
+                target,
+                data_type.clone().unwrap_or_else(|| stats[*source].sql_type(dialect)),
+            ),
+            ColumnMapping::Constant { target, value, data_type } => {
+                let mut constant = ColumnStats::default();
+                constant.observe(&json_cell(value.clone()));
+                (target, data_type.clone().unwrap_or_else(|| constant.sql_type(dialect)))
+            }
+            ColumnMapping::Skip { .. } => continue,
+        };
+        columns.push(ColumnDef {
+            id: name.clone(),
+            status: RowStatus::Added,
+            original_name: None,
+            name: name.clone(),
+            data_type,
+            length: String::new(),
+            is_primary_key: false,
+            is_not_null: false,
+            is_auto_increment: false,
+            default_value: String::new(),
+            comment: String::new(),
+        });
+    }
+    let types = columns.iter().map(|c| c.data_type.clone()).collect();
+    let change = TableChangeRequest {
+        schema: request.schema.clone(),
+        table: request.table.clone(),
+        original: None,
+        design: TableDesign { columns, indexes: Vec::new(), foreign_keys: Vec::new() },
+    };
+    let plan = alter::plan(dialect, &change, &alter::PlanContext::default())?;
+    match pool {
+        SqlPool::Postgres(pool) => alter::apply_postgres(pool, &plan).await?,
+        SqlPool::MySql(pool) => alter::apply_mysql(pool, &plan).await?,
+        SqlPool::Sqlite(pool) => alter::apply_sqlite(pool, &plan).await?,
+    }
+    Ok(types)
+}
+
+/// Resolve the mapping against the source columns and the target table
+async fn resolve_targets(
+    pool: &SqlPool,
+    request: &ImportRequest,
+    source_columns: usize,
+) -> Result<(Vec<Target>, bool), String> {
+    let mut seen = Vec::new();
+    for mapping in &request.columns {
+        let (source, target) = match mapping {
+            ColumnMapping::Column { source, target, .. } => (Some(*source), Some(target)),
+            ColumnMapping::Constant { target, .. } => (None, Some(target)),
+            ColumnMapping::Skip { source } => (Some(*source), None),
+        };
+        if let Some(source) = source.filter(|&s| s >= source_columns) {
+            return Err(format!("Source column {} does not exist in the file", source + 1));
+        }
+        if let Some(target) = target {
+            if seen.contains(&target) {
+                return Err(format!("Column {} is mapped more than once", target));
+            }
+            seen.push(target);
+        }
+    }
+    if seen.is_empty() {
+        return Err("Map at least one column to import".to_string());
+    }
+
+    let (types, created): (HashMap<String, String>, bool) = if request.create_table {
+        let types = create_table(pool, request, source_columns).await?;
+        (seen.iter().map(|s| s.to_string()).zip(types).collect(), true)
+    } else {
+        let columns = introspection::columns(pool, request.schema.as_deref(), &request.table).await?;
+        if columns.is_empty() {
+            return Err(format!("Table {} not found", request.table));
+        }
+        (columns.into_iter().map(|c| (c.name, c.data_type)).collect(), false)
+    };
+
+    let mut targets = Vec::new();
+    for mapping in &request.columns {
+        let (target, value) = match mapping {
+            ColumnMapping::Column { source, target, .. } => (target, MappedValue::Source(*source)),
+            ColumnMapping::Constant { target, value, .. } => (target, MappedValue::Constant(json_cell(value.clone()))),
+            ColumnMapping::Skip { .. } => continue,
+        };
+        let data_type = types
+            .get(target)
+            .ok_or_else(|| format!("Column {} does not exist in table {}", target, request.table))?;
+        targets.push(Target {
+            name: target.clone(),
+            kind: TargetKind::from_type(data_type),
+            value,
+        });
+    }
+    Ok((targets, created))
+}
+
+/// Insert one batch in a transaction. If the batch fails, it is retried row by
+/// row in a new transaction with a savepoint around each row, and the rows the
+/// database refuses are returned. Errors other than database errors (lost
+/// connection...) abort the import.
+pub(crate) async fn insert_rows<DB: Database>(
+    pool: &Pool<DB>,
+    prefix: &str,
+    rows: &[(u64, String)],
+) -> Result<Vec<RejectedRow>, String>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let values: Vec<&str> = rows.iter().map(|(_, values)| values.as_str()).collect();
+    let statement = format!("{}{}", prefix, values.join(",\n"));
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    match sqlx::raw_sql(&statement).execute(&mut *tx).await {
+        Ok(_) => {
+            tx.commit().await.map_err(|e| e.to_string())?;
+            return Ok(Vec::new());
+        }
+        Err(sqlx::Error::Database(_)) => tx.rollback().await.map_err(|e| e.to_string())?,
+        Err(e) => return Err(e.to_string()),
+    }
+
+    let mut rejected = Vec::new();
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for (line, values) in rows {
+        sqlx::raw_sql("SAVEPOINT nebula_import_row").execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let statement = format!("{}{}", prefix, values);
+        match sqlx::raw_sql(&statement).execute(&mut *tx).await {
+            Ok(_) => {
+                sqlx::raw_sql("RELEASE SAVEPOINT nebula_import_row")
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            Err(sqlx::Error::Database(e)) => {
+                sqlx::raw_sql("ROLLBACK TO SAVEPOINT nebula_import_row")
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                rejected.push(RejectedRow { line: *line, reason: e.message().to_string() });
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(rejected)
+}
+
+/// Counters of a running import
+struct ImportState<'a> {
+    app: &'a AppHandle,
+    task_id: &'a str,
+    total_bytes: u64,
+    summary: ImportSummary,
+    last_progress: Instant,
+}
+
+impl ImportState<'_> {
+    fn reject(&mut self, line: u64, reason: String) {
+        self.summary.rows_rejected += 1;
+        if self.summary.rejected.len() < MAX_REJECTED {
+            self.summary.rejected.push(RejectedRow { line, reason });
+        }
+    }
+
+    fn emit_progress(&mut self, bytes_read: u64) {
+        self.last_progress = Instant::now();
+        let _ = self.app.emit(
+            "import-progress",
+            ImportProgress {
+                task_id: self.task_id.to_string(),
+                rows_read: self.summary.rows_read,
+                rows_imported: self.summary.rows_imported,
+                rows_rejected: self.summary.rows_rejected,
+                bytes_read,
+                total_bytes: self.total_bytes,
+            },
+        );
+    }
+
+    async fn flush(&mut self, loader: &mut Loader, batch: &mut Batch) -> Result<(), String> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let rejected =
+            loader.load(batch).await.map_err(|e| loader.describe_error(e, self.summary.rows_imported, "import"))?;
+        self.summary.rows_imported += (batch.len() - rejected.len()) as u64;
+        for row in rejected {
+            self.reject(row.line, row.reason);
+        }
+        batch.clear();
+        Ok(())
+    }
+}
+
+/// Import the rows of `request.path` into the target table
+pub async fn import_file(
+    app: &AppHandle,
+    pool: &SqlPool,
+    request: &ImportRequest,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<ImportSummary, String> {
+    let start = Instant::now();
+    if request.table.trim().is_empty() {
+        return Err("A target table is required".to_string());
+    }
+    let path = Path::new(&request.path);
+    let total_bytes = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let mut reader = RowReader::open(path, request.format, &request.csv)?;
+    let (targets, table_created) = resolve_targets(pool, request, reader.columns().len()).await?;
+
+    let names: Vec<String> = targets.iter().map(|t| t.name.clone()).collect();
+    let mut loader = Loader::new(pool, request.schema.as_deref(), &request.table, &names).await?;
+    let batch_size = request.batch_size.max(1);
+    let mut batch: Batch = Vec::with_capacity(batch_size);
+    let mut state = ImportState {
+        app,
+        task_id,
+        total_bytes,
+        summary: ImportSummary {
+            rows_read: 0,
+            rows_imported: 0,
+            rows_rejected: 0,
+            rejected: Vec::new(),
+            table_created,
+            method: loader.method(),
+            cancelled: false,
+            execution_time_ms: 0,
+        },
+        last_progress: Instant::now(),
+    };
+
+    loop {
+        if cancel.is_cancelled() {
+            state.summary.cancelled = true;
+            break;
+        }
+        let Some(row) = reader.next_row()? else { break };
+        state.summary.rows_read += 1;
+        if let Some(error) = row.error {
+            state.reject(row.line, error);
+            continue;
+        }
+
+        let mut values = Vec::with_capacity(targets.len());
+        let mut error = None;
+        for target in &targets {
+            let cell = match &target.value {
+                MappedValue::Source(i) => row.values.get(*i).cloned().unwrap_or(Cell::Null),
+                MappedValue::Constant(cell) => cell.clone(),
+            };
+            match coerce(target.kind, cell) {
+                Ok(cell) => values.push(cell),
+                Err(e) => {
+                    error = Some(format!("{}: {}", target.name, e));
+                    break;
+                }
+            }
+        }
+        match error {
+            Some(reason) => state.reject(row.line, reason),
+            None => batch.push((row.line, values)),
+        }
+
+        if batch.len() >= batch_size {
+            state.flush(&mut loader, &mut batch).await?;
+        }
+        if state.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            state.emit_progress(reader.bytes_read());
+        }
+    }
+    if !state.summary.cancelled {
+        state.flush(&mut loader, &mut batch).await?;
+    }
+    state.summary.method = loader.method();
+    loader.finish().await?;
+
+    state.emit_progress(reader.bytes_read());
+    state.summary.execution_time_ms = start.elapsed().as_millis();
+    Ok(state.summary)
+}
@@ -0,0 +1,435 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use encoding_rs::{Decoder, Encoding};
+
+use crate::db::export::text::TextEncoding;
+use crate::db::stream::Cell;
+use crate::db::types::{CsvDialect, ImportFormat};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Records read ahead to name the columns of a JSON file
+const JSON_SAMPLE_ROWS: usize = 1000;
+
+/// Reads a file as UTF-8 text, chunk by chunk, whatever its encoding
+pub struct DecodingReader {
+    file: File,
+    decoder: Decoder,
+    chunk: Vec<u8>,
+    bytes_read: u64,
+    eof: bool,
+}
+
+impl DecodingReader {
+    pub fn open(path: &Path, encoding: &'static Encoding) -> Result<DecodingReader, String> {
+        Ok(DecodingReader {
+            file: File::open(path).map_err(|e| e.to_string())?,
+            // A byte order mark overrides `encoding` and is not part of the text
+            decoder: encoding.new_decoder_with_bom_removal(),
+            chunk: vec![0; CHUNK_SIZE],
+            bytes_read: 0,
+            eof: false,
+        })
+    }
+
+    /// Append the next chunk of text to `out`; returns false once the file is exhausted
+    pub fn read_into(&mut self, out: &mut String) -> Result<bool, String> {
+        if self.eof {
+            return Ok(false);
+        }
+        let n = self.file.read(&mut self.chunk).map_err(|e| e.to_string())?;
+        let last = n == 0;
+        out.reserve(self.decoder.max_utf8_buffer_length(n).unwrap_or(n * 3 + 16));
+        // Malformed sequences become U+FFFD rather than failing the import
+        let _ = self.decoder.decode_to_string(&self.chunk[..n], out, last);
+        self.bytes_read += n as u64;
+        self.eof = last;
+        Ok(!last)
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+}
+
+/// One record of a CSV file; `None` fields are NULL
+#[derive(Debug)]
+pub struct CsvRecord {
+    /// Line where the record starts (1-based)
+    pub line: u64,
+    pub fields: Vec<Option<String>>,
+}
+
+enum Parsed {
+    Record { fields: Vec<Option<String>>, consumed: usize, newlines: u64, blank: bool },
+    /// The buffer ends inside the record
+    Incomplete,
+    End,
+}
+
+/// Split the first record off `text`. Quoted fields may span lines; a doubled
+/// quote inside a quoted field is a literal quote.
+fn parse_record(text: &str, dialect: &CsvDialect, eof: bool) -> Parsed {
+    if text.is_empty() {
+        return if eof { Parsed::End } else { Parsed::Incomplete };
+    }
+    let CsvDialect { delimiter, quote, ref null_token, .. } = *dialect;
+    let finish = |field: &mut String, quoted: bool| {
+        let field = std::mem::take(field);
+        if !quoted && field == *null_token {
+            None
+        } else {
+            Some(field)
+        }
+    };
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut at_start = true;
+    let mut newlines = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if in_quotes {
+            if c == quote {
+                match chars.peek() {
+                    Some(&(_, next)) if next == quote => {
+                        field.push(quote);
+                        chars.next();
+                    }
+                    None if !eof => return Parsed::Incomplete,
+                    _ => in_quotes = false,
+                }
+            } else {
+                if c == '\n' {
+                    newlines += 1;
+                }
+                field.push(c);
+            }
+        } else if c == quote && at_start {
+            in_quotes = true;
+            quoted = true;
+            at_start = false;
+        } else if c == delimiter {
+            fields.push(finish(&mut field, quoted));
+            quoted = false;
+            at_start = true;
+        } else if c == '\r' || c == '\n' {
+            let mut consumed = i + 1;
+            if c == '\r' {
+                match chars.peek() {
+                    Some(&(_, '\n')) => consumed += 1,
+                    None if !eof => return Parsed::Incomplete,
+                    _ => {}
+                }
+            }
+            let blank = fields.is_empty() && field.is_empty() && !quoted;
+            fields.push(finish(&mut field, quoted));
+            return Parsed::Record { fields, consumed, newlines: newlines + 1, blank };
+        } else {
+            at_start = false;
+            field.push(c);
+        }
+    }
+    if !eof {
+        return Parsed::Incomplete;
+    }
+    // Last record without a line break (an unterminated quote runs to the end of the file)
+    fields.push(finish(&mut field, quoted));
+    Parsed::Record { fields, consumed: text.len(), newlines, blank: false }
+}
+
+/// Streams the records of a CSV file
+pub struct CsvReader {
+    source: DecodingReader,
+    dialect: CsvDialect,
+    buf: String,
+    pos: usize,
+    line: u64,
+}
+
+impl CsvReader {
+    pub fn open(path: &Path, dialect: &CsvDialect) -> Result<CsvReader, String> {
+        if dialect.delimiter == dialect.quote {
+            return Err("The CSV delimiter and quote character must differ".to_string());
+        }
+        let encoding = TextEncoding::from_label(&dialect.encoding)?;
+        Ok(CsvReader {
+            source: DecodingReader::open(path, encoding.encoding())?,
+            dialect: dialect.clone(),
+            buf: String::new(),
+            pos: 0,
+            line: 1,
+        })
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<CsvRecord>, String> {
+        loop {
+            match parse_record(&self.buf[self.pos..], &self.dialect, self.source.is_eof()) {
+                Parsed::Record { fields, consumed, newlines, blank } => {
+                    let line = self.line;
+                    self.line += newlines;
+                    self.pos += consumed;
+                    if !blank {
+                        return Ok(Some(CsvRecord { line, fields }));
+                    }
+                }
+                Parsed::Incomplete => {
+                    self.buf.drain(..self.pos);
+                    self.pos = 0;
+                    self.source.read_into(&mut self.buf)?;
+                }
+                Parsed::End => return Ok(None),
+            }
+        }
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.source.bytes_read()
+    }
+}
+
+/// A JSON record with its line, or why it could not be parsed
+type JsonRecord = (u64, Result<serde_json::Value, String>);
+
+/// Reads a JSON array of rows, or NDJSON line by line
+struct JsonReader {
+    lines: bool,
+    source: DecodingReader,
+    buf: String,
+    pos: usize,
+    line: u64,
+    /// Elements of a JSON array, which is parsed in one go
+    elements: VecDeque<serde_json::Value>,
+    element: u64,
+}
+
+impl JsonReader {
+    fn open(path: &Path, lines: bool) -> Result<JsonReader, String> {
+        let mut reader = JsonReader {
+            lines,
+            source: DecodingReader::open(path, encoding_rs::UTF_8)?,
+            buf: String::new(),
+            pos: 0,
+            line: 0,
+            elements: VecDeque::new(),
+            element: 0,
+        };
+        if !lines {
+            while reader.source.read_into(&mut reader.buf)? {}
+            let value: serde_json::Value =
+                serde_json::from_str(&reader.buf).map_err(|e| format!("Invalid JSON: {}", e))?;
+            reader.buf = String::new();
+            match value {
+                serde_json::Value::Array(elements) => reader.elements = elements.into(),
+                _ => return Err("The JSON file must contain an array of rows".to_string()),
+            }
+        }
+        Ok(reader)
+    }
+
+    /// Next record with its line (NDJSON) or position in the array
+    fn next_value(&mut self) -> Result<Option<JsonRecord>, String> {
+        if !self.lines {
+            self.element += 1;
+            return Ok(self.elements.pop_front().map(|value| (self.element, Ok(value))));
+        }
+        loop {
+            let Some(end) = self.buf[self.pos..].find('\n').map(|i| self.pos + i) else {
+                if self.source.read_into(&mut self.buf)? {
+                    continue;
+                }
+                if self.pos >= self.buf.len() {
+                    return Ok(None);
+                }
+                // Last line without a line break
+                self.buf.push('\n');
+                continue;
+            };
+            self.line += 1;
+            let text = self.buf[self.pos..end].trim();
+            let value = (!text.is_empty())
+                .then(|| serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e)));
+            self.pos = end + 1;
+            if self.pos > CHUNK_SIZE {
+                self.buf.drain(..self.pos);
+                self.pos = 0;
+            }
+            if let Some(value) = value {
+                return Ok(Some((self.line, value)));
+            }
+        }
+    }
+}
+
+/// A source row, with one value per source column
+#[derive(Debug)]
+pub struct SourceRow {
+    pub line: u64,
+    pub values: Vec<Cell>,
+    /// Why the row cannot be imported (malformed record)
+    pub error: Option<String>,
+}
+
+/// Value of a JSON row field
+pub fn json_cell(value: serde_json::Value) -> Cell {
+    match value {
+        serde_json::Value::Null => Cell::Null,
+        serde_json::Value::Bool(v) => Cell::Bool(v),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(v) => Cell::Int(v),
+            None => Cell::Decimal(n.to_string()),
+        },
+        serde_json::Value::String(text) => Cell::Text(text),
+        other => Cell::Json(other),
+    }
+}
+
+enum Source {
+    Csv(CsvReader),
+    Json(JsonReader),
+}
+
+/// Reads the rows of a CSV or JSON file as cells, with named columns
+pub struct RowReader {
+    source: Source,
+    columns: Vec<String>,
+    /// Rows read ahead while naming the columns
+    pending: VecDeque<SourceRow>,
+}
+
+impl RowReader {
+    pub fn open(path: &Path, format: ImportFormat, dialect: &CsvDialect) -> Result<RowReader, String> {
+        match format {
+            ImportFormat::Csv => {
+                let mut csv = CsvReader::open(path, dialect)?;
+                let mut pending = VecDeque::new();
+                let columns = match csv.next_record()? {
+                    Some(record) if dialect.header => column_names(record.fields.into_iter().map(Option::unwrap_or_default)),
+                    Some(record) => {
+                        let names = generated_names(record.fields.len());
+                        pending.push_back(csv_row(record, names.len()));
+                        names
+                    }
+                    None => Vec::new(),
+                };
+                Ok(RowReader { source: Source::Csv(csv), columns, pending })
+            }
+            ImportFormat::Json | ImportFormat::Ndjson => {
+                let mut json = JsonReader::open(path, format == ImportFormat::Ndjson)?;
+                let mut sample = Vec::new();
+                while sample.len() < JSON_SAMPLE_ROWS {
+                    match json.next_value()? {
+                        Some(value) => sample.push(value),
+                        None => break,
+                    }
+                }
+                let columns = json_columns(sample.iter().filter_map(|(_, value)| value.as_ref().ok()));
+                let pending = sample
+                    .into_iter()
+                    .map(|(line, value)| json_row(line, value, &columns))
+                    .collect();
+                Ok(RowReader { source: Source::Json(json), columns, pending })
+            }
+        }
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn next_row(&mut self) -> Result<Option<SourceRow>, String> {
+        if let Some(row) = self.pending.pop_front() {
+            return Ok(Some(row));
+        }
+        match &mut self.source {
+            Source::Csv(csv) => Ok(csv.next_record()?.map(|record| csv_row(record, self.columns.len()))),
+            Source::Json(json) => Ok(json.next_value()?.map(|(line, value)| json_row(line, value, &self.columns))),
+        }
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        match &self.source {
+            Source::Csv(csv) => csv.bytes_read(),
+            Source::Json(json) => json.source.bytes_read(),
+        }
+    }
+}
+
+fn generated_names(count: usize) -> Vec<String> {
+    (1..=count).map(|i| format!("column_{}", i)).collect()
+}
+
+/// Header names, with blanks and duplicates renamed
+fn column_names(names: impl Iterator<Item = String>) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for (i, name) in names.enumerate() {
+        let name = name.trim();
+        let base = if name.is_empty() { format!("column_{}", i + 1) } else { name.to_string() };
+        let mut unique = base.clone();
+        let mut suffix = 2;
+        while columns.contains(&unique) {
+            unique = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        columns.push(unique);
+    }
+    columns
+}
+
+fn csv_row(record: CsvRecord, columns: usize) -> SourceRow {
+    let error = (record.fields.len() != columns)
+        .then(|| format!("Expected {} fields, found {}", columns, record.fields.len()));
+    SourceRow {
+        line: record.line,
+        values: record.fields.into_iter().map(|f| f.map_or(Cell::Null, Cell::Text)).collect(),
+        error,
+    }
+}
+
+/// Object keys in order of first appearance; arrays are named by position
+fn json_columns<'a>(values: impl Iterator<Item = &'a serde_json::Value>) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut width = 0;
+    for value in values {
+        match value {
+            serde_json::Value::Object(object) => {
+                for key in object.keys() {
+                    if !keys.contains(key) {
+                        keys.push(key.clone());
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => width = width.max(items.len()),
+            _ => {}
+        }
+    }
+    if keys.is_empty() {
+        generated_names(width)
+    } else {
+        keys
+    }
+}
+
+fn json_row(line: u64, value: Result<serde_json::Value, String>, columns: &[String]) -> SourceRow {
+    let (values, error) = match value {
+        Ok(serde_json::Value::Object(mut object)) => (
+            columns.iter().map(|name| object.remove(name).map_or(Cell::Null, json_cell)).collect(),
+            None,
+        ),
+        Ok(serde_json::Value::Array(items)) => {
+            let mut values: Vec<Cell> = items.into_iter().map(json_cell).collect();
+            values.resize(columns.len(), Cell::Null);
+            (values, None)
+        }
+        Ok(_) => (Vec::new(), Some("Expected a JSON object or array".to_string())),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    SourceRow { line, values, error }
+}
@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::db::import::infer::looks_typed;
+use crate::db::import::reader::CsvReader;
+use crate::db::types::{CsvDialect, ImportFormat};
+
+/// Bytes examined when sniffing a file
+const SAMPLE_BYTES: usize = 64 * 1024;
+/// Records compared when choosing a delimiter
+const SAMPLE_RECORDS: usize = 50;
+
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+fn read_sample(path: &Path) -> Result<Vec<u8>, String> {
+    let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+    File::open(path)
+        .map_err(|e| e.to_string())?
+        .take(SAMPLE_BYTES as u64)
+        .read_to_end(&mut sample)
+        .map_err(|e| e.to_string())?;
+    Ok(sample)
+}
+
+/// Guess the format from the extension, then from the first character
+pub fn detect_format(path: &Path) -> Result<ImportFormat, String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match extension.as_str() {
+        "json" => return Ok(ImportFormat::Json),
+        "ndjson" | "jsonl" => return Ok(ImportFormat::Ndjson),
+        "csv" | "tsv" | "txt" => return Ok(ImportFormat::Csv),
+        _ => {}
+    }
+    let sample = read_sample(path)?;
+    let text = String::from_utf8_lossy(&sample);
+    Ok(match text.trim_start_matches('\u{feff}').trim_start().chars().next() {
+        Some('[') => ImportFormat::Json,
+        Some('{') => ImportFormat::Ndjson,
+        _ => ImportFormat::Csv,
+    })
+}
+
+/// Encoding label from the byte order mark, or UTF-8 when the sample is valid
+/// UTF-8, or Windows-1252 as the usual legacy fallback
+fn detect_encoding(sample: &[u8]) -> &'static str {
+    if sample.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return "utf-8";
+    }
+    if sample.starts_with(&[0xFF, 0xFE]) {
+        return "utf-16le";
+    }
+    if sample.starts_with(&[0xFE, 0xFF]) {
+        return "utf-16be";
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => "utf-8",
+        // The sample may end in the middle of a character
+        Err(e) if e.error_len().is_none() => "utf-8",
+        Err(_) => "windows-1252",
+    }
+}
+
+/// Field counts of the first records of `path` read with `dialect`
+fn field_counts(path: &Path, dialect: &CsvDialect) -> Result<Vec<usize>, String> {
+    let mut reader = CsvReader::open(path, dialect)?;
+    let mut counts = Vec::new();
+    while counts.len() < SAMPLE_RECORDS && reader.bytes_read() <= SAMPLE_BYTES as u64 {
+        match reader.next_record()? {
+            Some(record) => counts.push(record.fields.len()),
+            None => break,
+        }
+    }
+    Ok(counts)
+}
+
+/// Detect the encoding, delimiter, quote character and header of a CSV file.
+///
+/// The delimiter is the candidate that splits the sampled records into the
+/// same number of fields most consistently (and into the most fields on a tie).
+pub fn sniff_csv(path: &Path) -> Result<CsvDialect, String> {
+    let sample = read_sample(path)?;
+    let mut dialect = CsvDialect {
+        encoding: detect_encoding(&sample).to_string(),
+        ..CsvDialect::default()
+    };
+    let text = String::from_utf8_lossy(&sample);
+    if !text.contains('"') && text.contains('\'') {
+        dialect.quote = '\'';
+    }
+    if path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("tsv")) {
+        dialect.delimiter = '\t';
+    } else {
+        let mut best: Option<(usize, usize, char)> = None;
+        for delimiter in DELIMITERS {
+            if !text.contains(delimiter) {
+                continue;
+            }
+            let candidate = CsvDialect { delimiter, header: false, ..dialect.clone() };
+            let counts = field_counts(path, &candidate)?;
+            let Some(&first) = counts.first() else { continue };
+            let consistent = counts.iter().filter(|&&c| c == first).count();
+            if first > 1 && best.map_or(true, |(records, fields, _)| (consistent, first) > (records, fields)) {
+                best = Some((consistent, first, delimiter));
+            }
+        }
+        if let Some((_, _, delimiter)) = best {
+            dialect.delimiter = delimiter;
+        }
+    }
+    dialect.header = detect_header(path, &dialect)?;
+    Ok(dialect)
+}
+
+/// The first record is a header unless one of its values looks like data
+/// (a number, date or boolean) or it repeats a value
+fn detect_header(path: &Path, dialect: &CsvDialect) -> Result<bool, String> {
+    let mut reader = CsvReader::open(path, &CsvDialect { header: false, ..dialect.clone() })?;
+    let Some(first) = reader.next_record()? else {
+        return Ok(true);
+    };
+    let names: Vec<&str> = first.fields.iter().map(|f| f.as_deref().unwrap_or("")).collect();
+    let mut unique = names.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    Ok(unique.len() == names.len() && names.iter().all(|name| !name.trim().is_empty() && !looks_typed(name)))
+}
@@ -0,0 +1,115 @@
+// Catalog introspection
+//
+// Each backend reads its own system catalogs and maps the result onto the
+// engine-neutral structs in `db::types`, so the frontend can render indexes,
+// constraints and foreign keys the same way for every connection type. The
+// functions here dispatch on the pool for code that works on any engine.
+
+pub mod postgres;
+pub mod mysql;
+pub mod sqlite;
+
+use crate::db::types::{ColumnInfo, ConstraintInfo, ForeignKeyInfo, IndexInfo, SqlPool};
+
+/// Columns of a table or view on any engine
+pub async fn columns(pool: &SqlPool, schema: Option<&str>, table: &str) -> Result<Vec<ColumnInfo>, String> {
+    match pool {
+        SqlPool::Postgres(pool) => postgres::list_columns(pool, schema, table).await,
+        SqlPool::MySql(pool) => mysql::list_columns(pool, schema, table).await,
+        SqlPool::Sqlite(pool) => sqlite::list_columns(pool, schema, table).await,
+    }
+}
+
+/// Constraints of a table on any engine
+pub async fn constraints(pool: &SqlPool, schema: Option<&str>, table: &str) -> Result<Vec<ConstraintInfo>, String> {
+    match pool {
+        SqlPool::Postgres(pool) => postgres::list_constraints(pool, schema, table).await,
+        SqlPool::MySql(pool) => mysql::list_constraints(pool, schema, table).await,
+        SqlPool::Sqlite(pool) => sqlite::list_constraints(pool, schema, table).await,
+    }
+}
+
+async fn indexes(pool: &SqlPool, schema: Option<&str>, table: &str) -> Result<Vec<IndexInfo>, String> {
+    match pool {
+        SqlPool::Postgres(pool) => postgres::list_indexes(pool, schema, table).await,
+        SqlPool::MySql(pool) => mysql::list_indexes(pool, schema, table).await,
+        SqlPool::Sqlite(pool) => sqlite::list_indexes(pool, schema, table).await,
+    }
+}
+
+/// Foreign keys declared on a table
+pub async fn foreign_keys(pool: &SqlPool, schema: Option<&str>, table: &str) -> Result<Vec<ForeignKeyInfo>, String> {
+    match pool {
+        SqlPool::Postgres(pool) => postgres::list_foreign_keys(pool, schema, table).await,
+        SqlPool::MySql(pool) => mysql::list_foreign_keys(pool, schema, table).await,
+        SqlPool::Sqlite(pool) => sqlite::list_foreign_keys(pool, schema, table).await,
+    }
+}
+
+/// Foreign keys of other tables (or the table itself) that reference a table
+pub async fn referencing_foreign_keys(
+    pool: &SqlPool,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    match pool {
+        SqlPool::Postgres(pool) => postgres::list_referencing_foreign_keys(pool, schema, table).await,
+        SqlPool::MySql(pool) => mysql::list_referencing_foreign_keys(pool, schema, table).await,
+        SqlPool::Sqlite(pool) => sqlite::list_referencing_foreign_keys(pool, schema, table).await,
+    }
+}
+
+/// Columns of the primary key, if the table has one
+pub async fn primary_key(pool: &SqlPool, schema: Option<&str>, table: &str) -> Result<Option<Vec<String>>, String> {
+    Ok(constraints(pool, schema, table)
+        .await?
+        .into_iter()
+        .find(|c| c.constraint_type == "PRIMARY KEY")
+        .map(|c| c.columns))
+}
+
+/// Columns that identify a row: the primary key, or else the first unique
+/// constraint or full unique index whose columns are all NOT NULL. None for
+/// tables without such a key.
+pub async fn row_key(
+    pool: &SqlPool,
+    schema: Option<&str>,
+    table: &str,
+    columns: &[ColumnInfo],
+) -> Result<Option<Vec<String>>, String> {
+    let constraints = constraints(pool, schema, table).await?;
+    if let Some(pk) = constraints.iter().find(|c| c.constraint_type == "PRIMARY KEY") {
+        return Ok(Some(pk.columns.clone()));
+    }
+    let not_null = |name: &String| columns.iter().any(|c| &c.name == name && !c.is_nullable);
+    let unique = constraints
+        .into_iter()
+        .filter(|c| c.constraint_type == "UNIQUE")
+        .map(|c| c.columns)
+        .chain(
+            indexes(pool, schema, table)
+                .await?
+                .into_iter()
+                .filter(|i| i.is_unique && i.predicate.is_none())
+                .map(|i| i.columns),
+        )
+        .find(|key| !key.is_empty() && key.iter().all(not_null));
+    Ok(unique)
+}
+
+/// Column sets whose values must be unique: the primary key, unique
+/// constraints and unique indexes without a predicate
+pub async fn unique_keys(pool: &SqlPool, schema: Option<&str>, table: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut keys: Vec<Vec<String>> = constraints(pool, schema, table)
+        .await?
+        .into_iter()
+        .filter(|c| c.constraint_type == "PRIMARY KEY" || c.constraint_type == "UNIQUE")
+        .map(|c| c.columns)
+        .collect();
+    for index in indexes(pool, schema, table).await? {
+        if index.is_unique && index.predicate.is_none() && !index.columns.is_empty() && !keys.contains(&index.columns) {
+            keys.push(index.columns);
+        }
+    }
+    Ok(keys)
+}
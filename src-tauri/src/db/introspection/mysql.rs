@@ -0,0 +1,552 @@
+use sqlx::{Pool, MySql, Row};
+use sqlx::mysql::MySqlRow;
+use std::collections::HashMap;
+use crate::db::types::{
+    TableInfo, ColumnInfo, IndexInfo, ConstraintInfo, ForeignKeyInfo, ViewInfo, RoutineInfo, TriggerInfo, SequenceInfo,
+};
+
+/// Read a nullable text column from an information_schema row.
+///
+/// Depending on server version and collation, catalog columns come back either
+/// as VARCHAR or as binary strings, so fall back to decoding raw bytes.
+pub(crate) fn text(row: &MySqlRow, column: &str) -> Result<Option<String>, String> {
+    if let Ok(v) = row.try_get::<Option<String>, _>(column) {
+        return Ok(v);
+    }
+    row.try_get::<Option<Vec<u8>>, _>(column)
+        .map(|v| v.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+        .map_err(|e| e.to_string())
+}
+
+/// Like `text`, but for columns that are never NULL
+pub(crate) fn text_required(row: &MySqlRow, column: &str) -> Result<String, String> {
+    Ok(text(row, column)?.unwrap_or_default())
+}
+
+/// List databases, leaving out the server's system databases
+pub async fn list_schemas(pool: &Pool<MySql>) -> Result<Vec<String>, String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT SCHEMA_NAME
+        FROM information_schema.SCHEMATA
+        WHERE SCHEMA_NAME NOT IN ('information_schema', 'mysql', 'performance_schema', 'sys')
+        ORDER BY SCHEMA_NAME
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// List tables and views of a database
+pub async fn list_tables(pool: &Pool<MySql>, schema: Option<&str>) -> Result<Vec<TableInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            TABLE_SCHEMA AS table_schema,
+            TABLE_NAME AS name,
+            TABLE_TYPE AS table_type,
+            TABLE_COMMENT AS comment,
+            CAST(TABLE_ROWS AS SIGNED) AS estimated_rows
+        FROM information_schema.TABLES
+        WHERE TABLE_SCHEMA = COALESCE(?, DATABASE())
+        ORDER BY TABLE_NAME
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let table_type = text_required(row, "table_type")?;
+            let is_view = table_type.ends_with("VIEW");
+            Ok(TableInfo {
+                schema: text(row, "table_schema")?,
+                name: text_required(row, "name")?,
+                table_type: if is_view { "VIEW".to_string() } else { "TABLE".to_string() },
+                // Views always carry the comment "VIEW"
+                comment: text(row, "comment")?.filter(|c| !c.is_empty() && !is_view),
+                estimated_rows: row.try_get("estimated_rows").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List columns of a table or view in ordinal order
+pub async fn list_columns(
+    pool: &Pool<MySql>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ColumnInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            COLUMN_NAME AS name,
+            CAST(ORDINAL_POSITION AS SIGNED) AS ordinal,
+            COLUMN_TYPE AS data_type,
+            IS_NULLABLE AS is_nullable,
+            COLUMN_DEFAULT AS default_value,
+            EXTRA AS extra,
+            GENERATION_EXPRESSION AS generated,
+            COLUMN_COMMENT AS comment
+        FROM information_schema.COLUMNS
+        WHERE TABLE_SCHEMA = COALESCE(?, DATABASE())
+          AND TABLE_NAME = ?
+        ORDER BY ORDINAL_POSITION
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let extra = text(row, "extra")?.unwrap_or_default().to_lowercase();
+            Ok(ColumnInfo {
+                name: text_required(row, "name")?,
+                ordinal: row.try_get("ordinal").map_err(|e| e.to_string())?,
+                data_type: text_required(row, "data_type")?,
+                is_nullable: text_required(row, "is_nullable")? == "YES",
+                default_value: text(row, "default_value")?,
+                is_auto_increment: extra.contains("auto_increment"),
+                identity: None,
+                generated: text(row, "generated")?.filter(|g| !g.is_empty()),
+                comment: text(row, "comment")?.filter(|c| !c.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// List indexes of a table; `schema` is the database name and defaults to `DATABASE()`
+pub async fn list_indexes(
+    pool: &Pool<MySql>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<IndexInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            INDEX_NAME AS name,
+            CAST(NON_UNIQUE AS SIGNED) AS non_unique,
+            COLUMN_NAME AS column_name,
+            CAST(SUB_PART AS SIGNED) AS sub_part,
+            INDEX_TYPE AS index_type
+        FROM information_schema.STATISTICS
+        WHERE TABLE_SCHEMA = COALESCE(?, DATABASE())
+          AND TABLE_NAME = ?
+        ORDER BY INDEX_NAME = 'PRIMARY' DESC, INDEX_NAME, SEQ_IN_INDEX
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut indexes: Vec<IndexInfo> = Vec::new();
+    for row in &rows {
+        let name = text_required(row, "name")?;
+        let non_unique: i64 = row.try_get("non_unique").map_err(|e| e.to_string())?;
+        let sub_part: Option<i64> = row.try_get("sub_part").map_err(|e| e.to_string())?;
+        // Functional key parts (8.0.13+) have no column name
+        let column = match (text(row, "column_name")?, sub_part) {
+            (Some(col), Some(len)) => format!("{}({})", col, len),
+            (Some(col), None) => col,
+            (None, _) => "<expression>".to_string(),
+        };
+
+        match indexes.last_mut() {
+            Some(index) if index.name == name => index.columns.push(column),
+            _ => indexes.push(IndexInfo {
+                is_primary: name == "PRIMARY",
+                name,
+                table: table.to_string(),
+                columns: vec![column],
+                is_unique: non_unique == 0,
+                index_type: text_required(row, "index_type")?,
+                predicate: None,
+                definition: None,
+            }),
+        }
+    }
+
+    Ok(indexes)
+}
+
+/// List primary key, unique and check constraints of a table
+pub async fn list_constraints(
+    pool: &Pool<MySql>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ConstraintInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            tc.CONSTRAINT_NAME AS name,
+            tc.CONSTRAINT_TYPE AS constraint_type,
+            kcu.COLUMN_NAME AS column_name
+        FROM information_schema.TABLE_CONSTRAINTS tc
+        LEFT JOIN information_schema.KEY_COLUMN_USAGE kcu
+          ON kcu.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA
+         AND kcu.TABLE_NAME = tc.TABLE_NAME
+         AND kcu.CONSTRAINT_NAME = tc.CONSTRAINT_NAME
+        WHERE tc.TABLE_SCHEMA = COALESCE(?, DATABASE())
+          AND tc.TABLE_NAME = ?
+          AND tc.CONSTRAINT_TYPE IN ('PRIMARY KEY', 'UNIQUE', 'CHECK')
+        ORDER BY FIELD(tc.CONSTRAINT_TYPE, 'PRIMARY KEY', 'UNIQUE', 'CHECK'),
+                 tc.CONSTRAINT_NAME, kcu.ORDINAL_POSITION
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut constraints: Vec<ConstraintInfo> = Vec::new();
+    for row in &rows {
+        let name = text_required(row, "name")?;
+        let column = text(row, "column_name")?;
+        match constraints.last_mut() {
+            Some(constraint) if constraint.name == name => constraint.columns.extend(column),
+            _ => constraints.push(ConstraintInfo {
+                name,
+                table: table.to_string(),
+                constraint_type: text_required(row, "constraint_type")?,
+                columns: column.into_iter().collect(),
+                definition: None,
+            }),
+        }
+    }
+
+    // CHECK_CONSTRAINTS only exists from MySQL 8.0.16 / MariaDB 10.2, so a
+    // failure here just means there are no check bodies to report.
+    if constraints.iter().any(|c| c.constraint_type == "CHECK") {
+        let checks = sqlx::query(
+            r#"
+            SELECT cc.CONSTRAINT_NAME AS name, cc.CHECK_CLAUSE AS check_clause
+            FROM information_schema.CHECK_CONSTRAINTS cc
+            JOIN information_schema.TABLE_CONSTRAINTS tc
+              ON tc.CONSTRAINT_SCHEMA = cc.CONSTRAINT_SCHEMA
+             AND tc.CONSTRAINT_NAME = cc.CONSTRAINT_NAME
+            WHERE tc.TABLE_SCHEMA = COALESCE(?, DATABASE())
+              AND tc.TABLE_NAME = ?
+              AND tc.CONSTRAINT_TYPE = 'CHECK'
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default();
+
+        for row in &checks {
+            let name = text_required(row, "name")?;
+            let clause = text(row, "check_clause")?;
+            if let Some(constraint) = constraints
+                .iter_mut()
+                .find(|c| c.constraint_type == "CHECK" && c.name == name)
+            {
+                constraint.definition = clause;
+            }
+        }
+    }
+
+    Ok(constraints)
+}
+
+/// Foreign key columns with their tables; completed with a WHERE clause
+const FOREIGN_KEY_QUERY: &str = r#"
+        SELECT
+            kcu.CONSTRAINT_NAME AS name,
+            kcu.TABLE_SCHEMA AS table_schema,
+            kcu.TABLE_NAME AS table_name,
+            kcu.COLUMN_NAME AS column_name,
+            kcu.REFERENCED_TABLE_SCHEMA AS referenced_schema,
+            kcu.REFERENCED_TABLE_NAME AS referenced_table,
+            kcu.REFERENCED_COLUMN_NAME AS referenced_column,
+            rc.UPDATE_RULE AS on_update,
+            rc.DELETE_RULE AS on_delete
+        FROM information_schema.KEY_COLUMN_USAGE kcu
+        JOIN information_schema.REFERENTIAL_CONSTRAINTS rc
+          ON rc.CONSTRAINT_SCHEMA = kcu.CONSTRAINT_SCHEMA
+         AND rc.TABLE_NAME = kcu.TABLE_NAME
+         AND rc.CONSTRAINT_NAME = kcu.CONSTRAINT_NAME
+        WHERE kcu.REFERENCED_TABLE_NAME IS NOT NULL
+"#;
+
+async fn query_foreign_keys(
+    pool: &Pool<MySql>,
+    condition: &str,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    let rows = sqlx::query(&format!("{} AND {}", FOREIGN_KEY_QUERY, condition))
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut foreign_keys: Vec<ForeignKeyInfo> = Vec::new();
+    for row in &rows {
+        let name = text_required(row, "name")?;
+        let schema = text(row, "table_schema")?;
+        let table = text_required(row, "table_name")?;
+        let column = text_required(row, "column_name")?;
+        let referenced_column = text_required(row, "referenced_column")?;
+        match foreign_keys.last_mut() {
+            Some(fk) if fk.name == name && fk.table == table && fk.schema == schema => {
+                fk.columns.push(column);
+                fk.referenced_columns.push(referenced_column);
+            }
+            _ => foreign_keys.push(ForeignKeyInfo {
+                name,
+                schema,
+                table,
+                columns: vec![column],
+                referenced_schema: text(row, "referenced_schema")?,
+                referenced_table: text_required(row, "referenced_table")?,
+                referenced_columns: vec![referenced_column],
+                on_update: text_required(row, "on_update")?,
+                on_delete: text_required(row, "on_delete")?,
+            }),
+        }
+    }
+
+    Ok(foreign_keys)
+}
+
+/// List foreign keys declared on a table, including their ON UPDATE / ON DELETE actions
+pub async fn list_foreign_keys(
+    pool: &Pool<MySql>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    query_foreign_keys(
+        pool,
+        "kcu.TABLE_SCHEMA = COALESCE(?, DATABASE()) AND kcu.TABLE_NAME = ? \
+         ORDER BY kcu.CONSTRAINT_NAME, kcu.ORDINAL_POSITION",
+        schema,
+        table,
+    )
+    .await
+}
+
+/// List foreign keys of any table that reference a table
+pub async fn list_referencing_foreign_keys(
+    pool: &Pool<MySql>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    query_foreign_keys(
+        pool,
+        "kcu.REFERENCED_TABLE_SCHEMA = COALESCE(?, DATABASE()) AND kcu.REFERENCED_TABLE_NAME = ? \
+         ORDER BY kcu.TABLE_SCHEMA, kcu.TABLE_NAME, kcu.CONSTRAINT_NAME, kcu.ORDINAL_POSITION",
+        schema,
+        table,
+    )
+    .await
+}
+
+/// List views of a database
+pub async fn list_views(pool: &Pool<MySql>, schema: Option<&str>) -> Result<Vec<ViewInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT TABLE_SCHEMA AS table_schema, TABLE_NAME AS name, VIEW_DEFINITION AS definition
+        FROM information_schema.VIEWS
+        WHERE TABLE_SCHEMA = COALESCE(?, DATABASE())
+        ORDER BY TABLE_NAME
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(ViewInfo {
+                schema: text(row, "table_schema")?,
+                name: text_required(row, "name")?,
+                is_materialized: false,
+                definition: text(row, "definition")?,
+            })
+        })
+        .collect()
+}
+
+/// List stored functions and procedures of a database
+pub async fn list_routines(pool: &Pool<MySql>, schema: Option<&str>) -> Result<Vec<RoutineInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            ROUTINE_SCHEMA AS routine_schema,
+            ROUTINE_NAME AS name,
+            SPECIFIC_NAME AS specific_name,
+            ROUTINE_TYPE AS kind,
+            DTD_IDENTIFIER AS return_type,
+            ROUTINE_BODY AS language,
+            ROUTINE_DEFINITION AS body
+        FROM information_schema.ROUTINES
+        WHERE ROUTINE_SCHEMA = COALESCE(?, DATABASE())
+        ORDER BY ROUTINE_NAME
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Function return values show up in PARAMETERS with ORDINAL_POSITION = 0
+    let params = sqlx::query(
+        r#"
+        SELECT
+            SPECIFIC_NAME AS specific_name,
+            PARAMETER_MODE AS mode,
+            PARAMETER_NAME AS name,
+            DTD_IDENTIFIER AS data_type
+        FROM information_schema.PARAMETERS
+        WHERE SPECIFIC_SCHEMA = COALESCE(?, DATABASE())
+          AND ORDINAL_POSITION > 0
+        ORDER BY SPECIFIC_NAME, ORDINAL_POSITION
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut arguments: HashMap<String, Vec<String>> = HashMap::new();
+    for row in &params {
+        let mut arg = Vec::new();
+        if let Some(mode) = text(row, "mode")? {
+            arg.push(mode);
+        }
+        if let Some(name) = text(row, "name")? {
+            arg.push(name);
+        }
+        arg.push(text_required(row, "data_type")?);
+        arguments
+            .entry(text_required(row, "specific_name")?)
+            .or_default()
+            .push(arg.join(" "));
+    }
+
+    rows.iter()
+        .map(|row| {
+            let kind = text_required(row, "kind")?;
+            let specific_name = text_required(row, "specific_name")?;
+            Ok(RoutineInfo {
+                schema: text(row, "routine_schema")?,
+                name: text_required(row, "name")?,
+                arguments: arguments.get(&specific_name).map(|a| a.join(", ")).unwrap_or_default(),
+                return_type: if kind == "FUNCTION" { text(row, "return_type")? } else { None },
+                kind,
+                language: text(row, "language")?,
+                body: text(row, "body")?,
+            })
+        })
+        .collect()
+}
+
+/// List triggers of a database, optionally restricted to one table
+pub async fn list_triggers(
+    pool: &Pool<MySql>,
+    schema: Option<&str>,
+    table: Option<&str>,
+) -> Result<Vec<TriggerInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            TRIGGER_SCHEMA AS trigger_schema,
+            TRIGGER_NAME AS name,
+            EVENT_OBJECT_TABLE AS table_name,
+            ACTION_TIMING AS timing,
+            EVENT_MANIPULATION AS event,
+            ACTION_ORIENTATION AS orientation,
+            ACTION_STATEMENT AS body
+        FROM information_schema.TRIGGERS
+        WHERE TRIGGER_SCHEMA = COALESCE(?, DATABASE())
+          AND (? IS NULL OR EVENT_OBJECT_TABLE = ?)
+        ORDER BY EVENT_OBJECT_TABLE, ACTION_ORDER, TRIGGER_NAME
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(TriggerInfo {
+                schema: text(row, "trigger_schema")?,
+                name: text_required(row, "name")?,
+                table: text_required(row, "table_name")?,
+                timing: text_required(row, "timing")?,
+                events: vec![text_required(row, "event")?],
+                orientation: text_required(row, "orientation")?,
+                definition: None,
+                body: text(row, "body")?,
+            })
+        })
+        .collect()
+}
+
+/// List AUTO_INCREMENT counters of a database, reported as one sequence per table
+pub async fn list_sequences(pool: &Pool<MySql>, schema: Option<&str>) -> Result<Vec<SequenceInfo>, String> {
+    let increment: i64 = sqlx::query_scalar("SELECT CAST(@@auto_increment_increment AS SIGNED)")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            t.TABLE_SCHEMA AS table_schema,
+            t.TABLE_NAME AS table_name,
+            c.COLUMN_NAME AS column_name,
+            c.COLUMN_TYPE AS data_type,
+            CAST(t.AUTO_INCREMENT AS SIGNED) AS next_value
+        FROM information_schema.TABLES t
+        JOIN information_schema.COLUMNS c
+          ON c.TABLE_SCHEMA = t.TABLE_SCHEMA
+         AND c.TABLE_NAME = t.TABLE_NAME
+         AND c.EXTRA LIKE '%auto_increment%'
+        WHERE t.TABLE_SCHEMA = COALESCE(?, DATABASE())
+          AND t.AUTO_INCREMENT IS NOT NULL
+        ORDER BY t.TABLE_NAME
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let table = text_required(row, "table_name")?;
+            let column = text_required(row, "column_name")?;
+            let next_value: Option<i64> = row.try_get("next_value").map_err(|e| e.to_string())?;
+            Ok(SequenceInfo {
+                schema: text(row, "table_schema")?,
+                owned_by: Some(format!("{}.{}", table, column)),
+                name: table,
+                data_type: text(row, "data_type")?,
+                // AUTO_INCREMENT holds the next value; it stays at 1 until a row is inserted
+                current_value: next_value.filter(|v| *v > 1).map(|v| v - increment),
+                increment,
+                start_value: Some(1),
+                min_value: None,
+                max_value: None,
+                cycle: false,
+            })
+        })
+        .collect()
+}
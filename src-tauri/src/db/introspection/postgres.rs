@@ -0,0 +1,636 @@
+use sqlx::{Pool, Postgres, Row};
+use crate::db::types::{
+    TableInfo, ColumnInfo, IndexInfo, ConstraintInfo, ForeignKeyInfo, ViewInfo, RoutineInfo, TriggerInfo,
+    SequenceInfo, EnumTypeInfo, DomainInfo, ExtensionInfo,
+};
+
+/// Map a `pg_constraint.confupdtype` / `confdeltype` code to its SQL keyword
+fn referential_action(code: &str) -> String {
+    match code {
+        "r" => "RESTRICT",
+        "c" => "CASCADE",
+        "n" => "SET NULL",
+        "d" => "SET DEFAULT",
+        _ => "NO ACTION",
+    }
+    .to_string()
+}
+
+/// List schemas, leaving out the system catalogs and TOAST schemas
+pub async fn list_schemas(pool: &Pool<Postgres>) -> Result<Vec<String>, String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT nspname::text
+        FROM pg_namespace
+        WHERE nspname <> 'information_schema' AND nspname NOT LIKE 'pg\_%'
+        ORDER BY nspname
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// List tables, views, materialized views and foreign tables of a schema
+pub async fn list_tables(pool: &Pool<Postgres>, schema: Option<&str>) -> Result<Vec<TableInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            c.relname::text AS name,
+            CASE c.relkind
+                WHEN 'p' THEN 'PARTITIONED TABLE'
+                WHEN 'v' THEN 'VIEW'
+                WHEN 'm' THEN 'MATERIALIZED VIEW'
+                WHEN 'f' THEN 'FOREIGN TABLE'
+                ELSE 'TABLE'
+            END AS table_type,
+            obj_description(c.oid, 'pg_class') AS comment,
+            -- reltuples is -1 for tables that were never analyzed
+            CASE WHEN c.relkind IN ('r', 'm') AND c.reltuples >= 0 THEN c.reltuples::bigint END AS estimated_rows
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+        ORDER BY c.relname
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(TableInfo {
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                table_type: row.try_get("table_type").map_err(|e| e.to_string())?,
+                comment: row.try_get("comment").map_err(|e| e.to_string())?,
+                estimated_rows: row.try_get("estimated_rows").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List columns of a table, view or materialized view in ordinal order
+pub async fn list_columns(
+    pool: &Pool<Postgres>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ColumnInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            a.attname::text AS name,
+            a.attnum::bigint AS ordinal,
+            format_type(a.atttypid, a.atttypmod) AS data_type,
+            NOT a.attnotnull AS is_nullable,
+            pg_get_expr(d.adbin, d.adrelid) AS default_expr,
+            a.attidentity::text AS identity,
+            a.attgenerated::text AS generated,
+            col_description(a.attrelid, a.attnum) AS comment
+        FROM pg_attribute a
+        JOIN pg_class c ON c.oid = a.attrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND c.relname = $2
+          AND a.attnum > 0
+          AND NOT a.attisdropped
+        ORDER BY a.attnum
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let default_expr: Option<String> = row.try_get("default_expr").map_err(|e| e.to_string())?;
+            let identity: String = row.try_get("identity").map_err(|e| e.to_string())?;
+            let generated: String = row.try_get("generated").map_err(|e| e.to_string())?;
+            let identity = match identity.as_str() {
+                "a" => Some("ALWAYS".to_string()),
+                "d" => Some("BY DEFAULT".to_string()),
+                _ => None,
+            };
+            // For generated columns pg_attrdef holds the generation expression
+            let (default_value, generated) = if generated.is_empty() {
+                (default_expr, None)
+            } else {
+                (None, default_expr)
+            };
+            let is_serial = default_value.as_deref().is_some_and(|d| d.starts_with("nextval("));
+            Ok(ColumnInfo {
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                ordinal: row.try_get("ordinal").map_err(|e| e.to_string())?,
+                data_type: row.try_get("data_type").map_err(|e| e.to_string())?,
+                is_nullable: row.try_get("is_nullable").map_err(|e| e.to_string())?,
+                is_auto_increment: identity.is_some() || is_serial,
+                default_value,
+                identity,
+                generated,
+                comment: row.try_get("comment").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List indexes of a table; `schema` defaults to the connection's current schema
+pub async fn list_indexes(
+    pool: &Pool<Postgres>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<IndexInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            i.relname::text AS name,
+            ix.indisunique AS is_unique,
+            ix.indisprimary AS is_primary,
+            am.amname::text AS index_type,
+            pg_get_expr(ix.indpred, ix.indrelid) AS predicate,
+            pg_get_indexdef(ix.indexrelid) AS definition,
+            ARRAY(
+                SELECT pg_get_indexdef(ix.indexrelid, k, true)
+                FROM generate_series(1, ix.indnkeyatts) AS k
+                ORDER BY k
+            ) AS columns
+        FROM pg_index ix
+        JOIN pg_class t ON t.oid = ix.indrelid
+        JOIN pg_class i ON i.oid = ix.indexrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        JOIN pg_am am ON am.oid = i.relam
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND t.relname = $2
+        ORDER BY ix.indisprimary DESC, i.relname
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(IndexInfo {
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                table: table.to_string(),
+                columns: row.try_get("columns").map_err(|e| e.to_string())?,
+                is_unique: row.try_get("is_unique").map_err(|e| e.to_string())?,
+                is_primary: row.try_get("is_primary").map_err(|e| e.to_string())?,
+                index_type: row.try_get("index_type").map_err(|e| e.to_string())?,
+                predicate: row.try_get("predicate").map_err(|e| e.to_string())?,
+                definition: row.try_get("definition").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List primary key, unique, check and exclusion constraints of a table
+pub async fn list_constraints(
+    pool: &Pool<Postgres>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ConstraintInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            c.conname::text AS name,
+            c.contype::text AS contype,
+            pg_get_constraintdef(c.oid, true) AS definition,
+            ARRAY(
+                SELECT a.attname::text
+                FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, ord)
+                JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                ORDER BY k.ord
+            ) AS columns
+        FROM pg_constraint c
+        JOIN pg_class t ON t.oid = c.conrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND t.relname = $2
+          AND c.contype IN ('p', 'u', 'c', 'x')
+        ORDER BY array_position(ARRAY['p', 'u', 'c', 'x'], c.contype::text), c.conname
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let contype: String = row.try_get("contype").map_err(|e| e.to_string())?;
+            let constraint_type = match contype.as_str() {
+                "p" => "PRIMARY KEY",
+                "u" => "UNIQUE",
+                "c" => "CHECK",
+                _ => "EXCLUDE",
+            };
+            Ok(ConstraintInfo {
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                table: table.to_string(),
+                constraint_type: constraint_type.to_string(),
+                columns: row.try_get("columns").map_err(|e| e.to_string())?,
+                definition: row.try_get("definition").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// Foreign keys with their referencing and referenced tables; completed with a WHERE clause
+const FOREIGN_KEY_QUERY: &str = r#"
+        SELECT
+            c.conname::text AS name,
+            n.nspname::text AS schema,
+            t.relname::text AS table_name,
+            rn.nspname::text AS referenced_schema,
+            rt.relname::text AS referenced_table,
+            c.confupdtype::text AS on_update,
+            c.confdeltype::text AS on_delete,
+            ARRAY(
+                SELECT a.attname::text
+                FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, ord)
+                JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                ORDER BY k.ord
+            ) AS columns,
+            ARRAY(
+                SELECT a.attname::text
+                FROM unnest(c.confkey) WITH ORDINALITY AS k(attnum, ord)
+                JOIN pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.attnum
+                ORDER BY k.ord
+            ) AS referenced_columns
+        FROM pg_constraint c
+        JOIN pg_class t ON t.oid = c.conrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        JOIN pg_class rt ON rt.oid = c.confrelid
+        JOIN pg_namespace rn ON rn.oid = rt.relnamespace
+        WHERE c.contype = 'f'
+"#;
+
+async fn query_foreign_keys(
+    pool: &Pool<Postgres>,
+    condition: &str,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    let rows = sqlx::query(&format!("{} AND {}", FOREIGN_KEY_QUERY, condition))
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let on_update: String = row.try_get("on_update").map_err(|e| e.to_string())?;
+            let on_delete: String = row.try_get("on_delete").map_err(|e| e.to_string())?;
+            Ok(ForeignKeyInfo {
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                table: row.try_get("table_name").map_err(|e| e.to_string())?,
+                columns: row.try_get("columns").map_err(|e| e.to_string())?,
+                referenced_schema: row.try_get("referenced_schema").map_err(|e| e.to_string())?,
+                referenced_table: row.try_get("referenced_table").map_err(|e| e.to_string())?,
+                referenced_columns: row.try_get("referenced_columns").map_err(|e| e.to_string())?,
+                on_update: referential_action(&on_update),
+                on_delete: referential_action(&on_delete),
+            })
+        })
+        .collect()
+}
+
+/// List foreign keys declared on a table, including their ON UPDATE / ON DELETE actions
+pub async fn list_foreign_keys(
+    pool: &Pool<Postgres>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    query_foreign_keys(
+        pool,
+        "n.nspname = COALESCE($1, current_schema()::text) AND t.relname = $2 ORDER BY c.conname",
+        schema,
+        table,
+    )
+    .await
+}
+
+/// List foreign keys of any table that reference a table
+pub async fn list_referencing_foreign_keys(
+    pool: &Pool<Postgres>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    query_foreign_keys(
+        pool,
+        "rn.nspname = COALESCE($1, current_schema()::text) AND rt.relname = $2 ORDER BY n.nspname, t.relname, c.conname",
+        schema,
+        table,
+    )
+    .await
+}
+
+/// List views and materialized views of a schema
+pub async fn list_views(pool: &Pool<Postgres>, schema: Option<&str>) -> Result<Vec<ViewInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            c.relname::text AS name,
+            c.relkind = 'm' AS is_materialized,
+            pg_get_viewdef(c.oid, true) AS definition
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.relkind IN ('v', 'm')
+          AND n.nspname = COALESCE($1, current_schema()::text)
+        ORDER BY c.relname
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(ViewInfo {
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                is_materialized: row.try_get("is_materialized").map_err(|e| e.to_string())?,
+                definition: row.try_get("definition").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List functions, procedures and aggregates of a schema, skipping extension members
+pub async fn list_routines(pool: &Pool<Postgres>, schema: Option<&str>) -> Result<Vec<RoutineInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            p.proname::text AS name,
+            CASE p.prokind
+                WHEN 'p' THEN 'PROCEDURE'
+                WHEN 'a' THEN 'AGGREGATE'
+                WHEN 'w' THEN 'WINDOW'
+                ELSE 'FUNCTION'
+            END AS kind,
+            pg_get_function_arguments(p.oid) AS arguments,
+            CASE WHEN p.prokind = 'p' THEN NULL ELSE pg_get_function_result(p.oid) END AS return_type,
+            l.lanname::text AS language,
+            CASE WHEN p.prokind IN ('f', 'p') THEN p.prosrc END AS body
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        JOIN pg_language l ON l.oid = p.prolang
+        WHERE n.nspname = COALESCE($1, current_schema()::text)
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_depend d
+              WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+          )
+        ORDER BY p.proname, pg_get_function_identity_arguments(p.oid)
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(RoutineInfo {
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                kind: row.try_get("kind").map_err(|e| e.to_string())?,
+                arguments: row.try_get("arguments").map_err(|e| e.to_string())?,
+                return_type: row.try_get("return_type").map_err(|e| e.to_string())?,
+                language: row.try_get("language").map_err(|e| e.to_string())?,
+                body: row.try_get("body").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List user-defined triggers of a schema, optionally restricted to one table
+pub async fn list_triggers(
+    pool: &Pool<Postgres>,
+    schema: Option<&str>,
+    table: Option<&str>,
+) -> Result<Vec<TriggerInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            t.tgname::text AS name,
+            c.relname::text AS table_name,
+            CASE
+                WHEN t.tgtype::int & 2 <> 0 THEN 'BEFORE'
+                WHEN t.tgtype::int & 64 <> 0 THEN 'INSTEAD OF'
+                ELSE 'AFTER'
+            END AS timing,
+            array_remove(ARRAY[
+                CASE WHEN t.tgtype::int & 4 <> 0 THEN 'INSERT' END,
+                CASE WHEN t.tgtype::int & 16 <> 0 THEN 'UPDATE' END,
+                CASE WHEN t.tgtype::int & 8 <> 0 THEN 'DELETE' END,
+                CASE WHEN t.tgtype::int & 32 <> 0 THEN 'TRUNCATE' END
+            ], NULL) AS events,
+            CASE WHEN t.tgtype::int & 1 <> 0 THEN 'ROW' ELSE 'STATEMENT' END AS orientation,
+            pg_get_triggerdef(t.oid, true) AS definition,
+            p.prosrc AS body
+        FROM pg_trigger t
+        JOIN pg_class c ON c.oid = t.tgrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_proc p ON p.oid = t.tgfoid
+        WHERE NOT t.tgisinternal
+          AND n.nspname = COALESCE($1, current_schema()::text)
+          AND ($2::text IS NULL OR c.relname = $2)
+        ORDER BY c.relname, t.tgname
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(TriggerInfo {
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                table: row.try_get("table_name").map_err(|e| e.to_string())?,
+                timing: row.try_get("timing").map_err(|e| e.to_string())?,
+                events: row.try_get("events").map_err(|e| e.to_string())?,
+                orientation: row.try_get("orientation").map_err(|e| e.to_string())?,
+                definition: row.try_get("definition").map_err(|e| e.to_string())?,
+                body: row.try_get("body").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List sequences of a schema with their current value and owning column
+pub async fn list_sequences(pool: &Pool<Postgres>, schema: Option<&str>) -> Result<Vec<SequenceInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            s.schemaname::text AS schema,
+            s.sequencename::text AS name,
+            format_type(s.data_type, NULL) AS data_type,
+            s.last_value,
+            s.increment_by,
+            s.start_value,
+            s.min_value,
+            s.max_value,
+            s.cycle,
+            (
+                SELECT format('%s.%s', quote_ident(dc.relname), quote_ident(a.attname))
+                FROM pg_depend d
+                JOIN pg_class dc ON dc.oid = d.refobjid
+                JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+                WHERE d.classid = 'pg_class'::regclass
+                  AND d.objid = format('%I.%I', s.schemaname, s.sequencename)::regclass
+                  AND d.deptype IN ('a', 'i')
+                LIMIT 1
+            ) AS owned_by
+        FROM pg_sequences s
+        WHERE s.schemaname = COALESCE($1, current_schema()::text)
+        ORDER BY s.sequencename
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(SequenceInfo {
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                data_type: row.try_get("data_type").map_err(|e| e.to_string())?,
+                current_value: row.try_get("last_value").map_err(|e| e.to_string())?,
+                increment: row.try_get("increment_by").map_err(|e| e.to_string())?,
+                start_value: row.try_get("start_value").map_err(|e| e.to_string())?,
+                min_value: row.try_get("min_value").map_err(|e| e.to_string())?,
+                max_value: row.try_get("max_value").map_err(|e| e.to_string())?,
+                cycle: row.try_get("cycle").map_err(|e| e.to_string())?,
+                owned_by: row.try_get("owned_by").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List enum types of a schema with their labels in sort order
+pub async fn list_enums(pool: &Pool<Postgres>, schema: Option<&str>) -> Result<Vec<EnumTypeInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            t.typname::text AS name,
+            ARRAY(
+                SELECT e.enumlabel::text FROM pg_enum e
+                WHERE e.enumtypid = t.oid
+                ORDER BY e.enumsortorder
+            ) AS labels
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE t.typtype = 'e'
+          AND n.nspname = COALESCE($1, current_schema()::text)
+        ORDER BY t.typname
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(EnumTypeInfo {
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                labels: row.try_get("labels").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List domains of a schema with their base type and CHECK constraints
+pub async fn list_domains(pool: &Pool<Postgres>, schema: Option<&str>) -> Result<Vec<DomainInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname::text AS schema,
+            t.typname::text AS name,
+            format_type(t.typbasetype, t.typtypmod) AS base_type,
+            t.typnotnull AS not_null,
+            t.typdefault AS default_value,
+            ARRAY(
+                SELECT pg_get_constraintdef(c.oid, true) FROM pg_constraint c
+                WHERE c.contypid = t.oid
+                ORDER BY c.conname
+            ) AS checks
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE t.typtype = 'd'
+          AND n.nspname = COALESCE($1, current_schema()::text)
+        ORDER BY t.typname
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(DomainInfo {
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                base_type: row.try_get("base_type").map_err(|e| e.to_string())?,
+                not_null: row.try_get("not_null").map_err(|e| e.to_string())?,
+                default_value: row.try_get("default_value").map_err(|e| e.to_string())?,
+                checks: row.try_get("checks").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// List extensions installed in the current database
+pub async fn list_extensions(pool: &Pool<Postgres>) -> Result<Vec<ExtensionInfo>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            e.extname::text AS name,
+            e.extversion AS version,
+            n.nspname::text AS schema,
+            d.description
+        FROM pg_extension e
+        JOIN pg_namespace n ON n.oid = e.extnamespace
+        LEFT JOIN pg_description d
+          ON d.objoid = e.oid AND d.classoid = 'pg_extension'::regclass
+        ORDER BY e.extname
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(ExtensionInfo {
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                version: row.try_get("version").map_err(|e| e.to_string())?,
+                schema: row.try_get("schema").map_err(|e| e.to_string())?,
+                description: row.try_get("description").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
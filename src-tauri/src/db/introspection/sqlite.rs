@@ -0,0 +1,714 @@
+use sqlx::{Pool, Sqlite, Row};
+use crate::db::dialect::Dialect;
+use crate::db::types::{TableInfo, ColumnInfo, IndexInfo, ConstraintInfo, ForeignKeyInfo, ViewInfo, TriggerInfo, SequenceInfo};
+
+/// Lexical token of a CREATE statement, just detailed enough to find clauses
+/// that the PRAGMA interface does not expose (CHECK bodies, partial index predicates).
+#[derive(Debug)]
+enum TokenKind {
+    Word(String),
+    Open,
+    Close,
+    Comma,
+    Other,
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(sql: &str) -> Vec<Token> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let kind = match c {
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(bytes.len());
+                continue;
+            }
+            b'\'' | b'"' | b'`' | b'[' => {
+                let close = if c == b'[' { b']' } else { c };
+                i += 1;
+                let mut text = Vec::new();
+                while i < bytes.len() {
+                    if bytes[i] == close {
+                        // Doubled quote characters are escapes, except inside [...]
+                        if close != b']' && bytes.get(i + 1) == Some(&close) {
+                            text.push(close);
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    text.push(bytes[i]);
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                if c == b'\'' {
+                    TokenKind::Other
+                } else {
+                    TokenKind::Word(String::from_utf8_lossy(&text).into_owned())
+                }
+            }
+            b'(' => {
+                i += 1;
+                TokenKind::Open
+            }
+            b')' => {
+                i += 1;
+                TokenKind::Close
+            }
+            b',' => {
+                i += 1;
+                TokenKind::Comma
+            }
+            c if c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80 => {
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'$' || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                TokenKind::Word(sql[start..i].to_string())
+            }
+            _ => {
+                i += 1;
+                TokenKind::Other
+            }
+        };
+        tokens.push(Token { kind, start, end: i });
+    }
+
+    tokens
+}
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(&token.kind, TokenKind::Word(w) if w.eq_ignore_ascii_case(word))
+}
+
+/// Extract CHECK constraints from a CREATE TABLE statement as (name, column, body)
+fn parse_check_constraints(sql: &str) -> Vec<(Option<String>, Option<String>, String)> {
+    const CONSTRAINT_KEYWORDS: [&str; 5] = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"];
+
+    let tokens = tokenize(sql);
+    let mut checks = Vec::new();
+    let mut depth = 0usize;
+    let mut definition_start = false;
+    let mut current_column: Option<String> = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.kind {
+            TokenKind::Open => {
+                depth += 1;
+                if depth == 1 {
+                    definition_start = true;
+                    current_column = None;
+                }
+                continue;
+            }
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            TokenKind::Comma if depth == 1 => {
+                definition_start = true;
+                current_column = None;
+                continue;
+            }
+            TokenKind::Word(word) => {
+                if depth == 1 && definition_start
+                    && !CONSTRAINT_KEYWORDS.iter().any(|k| word.eq_ignore_ascii_case(k))
+                {
+                    current_column = Some(word.clone());
+                }
+
+                if word.eq_ignore_ascii_case("CHECK")
+                    && matches!(tokens.get(i + 1).map(|t| &t.kind), Some(TokenKind::Open))
+                {
+                    let open = &tokens[i + 1];
+                    if let Some(close) = matching_close(&tokens, i + 1).map(|j| &tokens[j]) {
+                        let name = match &tokens[i.saturating_sub(1)].kind {
+                            TokenKind::Word(name) if i >= 2 && is_word(&tokens[i - 2], "CONSTRAINT") => {
+                                Some(name.clone())
+                            }
+                            _ => None,
+                        };
+                        let body = sql[open.end..close.start].trim().to_string();
+                        checks.push((name, current_column.clone(), body));
+                    }
+                }
+            }
+            _ => {}
+        }
+        definition_start = false;
+    }
+
+    checks
+}
+
+/// Extract generated column expressions from a CREATE TABLE statement as (column, expression)
+fn parse_generated_columns(sql: &str) -> Vec<(String, String)> {
+    let tokens = tokenize(sql);
+    let mut generated = Vec::new();
+    let mut depth = 0usize;
+    let mut definition_start = false;
+    let mut current_column: Option<String> = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.kind {
+            TokenKind::Open => {
+                depth += 1;
+                if depth == 1 {
+                    definition_start = true;
+                    current_column = None;
+                }
+                continue;
+            }
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            TokenKind::Comma if depth == 1 => {
+                definition_start = true;
+                current_column = None;
+                continue;
+            }
+            TokenKind::Word(word) if depth == 1 => {
+                if definition_start {
+                    current_column = Some(word.clone());
+                }
+                // Both `GENERATED ALWAYS AS (...)` and the short `AS (...)` form end in AS
+                if word.eq_ignore_ascii_case("AS")
+                    && matches!(tokens.get(i + 1).map(|t| &t.kind), Some(TokenKind::Open))
+                {
+                    if let (Some(column), Some(close)) = (&current_column, matching_close(&tokens, i + 1)) {
+                        let expr = sql[tokens[i + 1].end..tokens[close].start].trim().to_string();
+                        generated.push((column.clone(), expr));
+                    }
+                }
+            }
+            _ => {}
+        }
+        definition_start = false;
+    }
+
+    generated
+}
+
+/// Index of the token closing the parenthesis opened at `open`
+fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut level = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenKind::Open => level += 1,
+            TokenKind::Close => {
+                level -= 1;
+                if level == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Extract the WHERE clause of a partial CREATE INDEX statement
+fn parse_index_predicate(sql: &str) -> Option<String> {
+    let tokens = tokenize(sql);
+    let mut depth = 0usize;
+    for token in &tokens {
+        match token.kind {
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            _ if depth == 0 && is_word(token, "WHERE") => {
+                return Some(sql[token.end..].trim().trim_end_matches(';').trim().to_string());
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Extract the SELECT part of a CREATE VIEW statement
+fn parse_view_select(sql: &str) -> Option<String> {
+    tokenize(sql)
+        .iter()
+        .find(|t| is_word(t, "AS"))
+        .map(|t| sql[t.end..].trim().trim_end_matches(';').trim().to_string())
+}
+
+/// Extract (timing, events, body) from a CREATE TRIGGER statement
+fn parse_trigger(sql: &str) -> (String, Vec<String>, Option<String>) {
+    let tokens = tokenize(sql);
+    // BEFORE is the default when no timing keyword is given
+    let mut timing = "BEFORE".to_string();
+    let mut events = Vec::new();
+
+    for token in &tokens {
+        if is_word(token, "BEGIN") {
+            break;
+        }
+        if is_word(token, "BEFORE") || is_word(token, "AFTER") {
+            timing = sql[token.start..token.end].to_uppercase();
+        } else if is_word(token, "INSTEAD") {
+            timing = "INSTEAD OF".to_string();
+        } else if (is_word(token, "INSERT") || is_word(token, "UPDATE") || is_word(token, "DELETE")) && events.is_empty() {
+            events.push(sql[token.start..token.end].to_uppercase());
+        }
+    }
+
+    let begin = tokens.iter().find(|t| is_word(t, "BEGIN"));
+    let end = tokens.iter().rev().find(|t| is_word(t, "END"));
+    let body = match (begin, end) {
+        (Some(begin), Some(end)) if begin.end <= end.start => Some(sql[begin.end..end.start].trim().to_string()),
+        _ => None,
+    };
+
+    (timing, events, body)
+}
+
+async fn object_sql(pool: &Pool<Sqlite>, schema: &str, object_type: &str, name: &str) -> Result<Option<String>, String> {
+    let query = format!(
+        "SELECT sql FROM {}.sqlite_master WHERE type = ?1 AND name = ?2",
+        Dialect::Sqlite.quote_ident(schema)
+    );
+    let row = sqlx::query(&query)
+        .bind(object_type)
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    match row {
+        Some(row) => row.try_get::<Option<String>, _>("sql").map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+async fn index_columns(pool: &Pool<Sqlite>, schema: &str, index: &str) -> Result<Vec<String>, String> {
+    let rows = sqlx::query("SELECT name FROM pragma_index_xinfo(?1, ?2) WHERE key = 1 ORDER BY seqno")
+        .bind(index)
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| {
+            row.try_get::<Option<String>, _>("name")
+                .map(|name| name.unwrap_or_else(|| "<expression>".to_string()))
+                .map_err(|e| e.to_string())
+        })
+        .collect()
+}
+
+async fn primary_key_columns(pool: &Pool<Sqlite>, schema: &str, table: &str) -> Result<Vec<String>, String> {
+    let rows = sqlx::query("SELECT name FROM pragma_table_info(?1, ?2) WHERE pk > 0 ORDER BY pk")
+        .bind(table)
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| row.try_get::<String, _>("name").map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// List `main` and the aliases of attached databases, in attach order
+pub async fn list_schemas(pool: &Pool<Sqlite>) -> Result<Vec<String>, String> {
+    sqlx::query_scalar("SELECT name FROM pragma_database_list WHERE name <> 'temp' ORDER BY seq")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List tables and views of a schema, leaving out SQLite's internal tables
+pub async fn list_tables(pool: &Pool<Sqlite>, schema: Option<&str>) -> Result<Vec<TableInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    let query = format!(
+        "SELECT name, type FROM {}.sqlite_master
+         WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+         ORDER BY name",
+        Dialect::Sqlite.quote_ident(schema)
+    );
+    let rows = sqlx::query(&query)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let kind: String = row.try_get("type").map_err(|e| e.to_string())?;
+            Ok(TableInfo {
+                schema: Some(schema.to_string()),
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                table_type: kind.to_uppercase(),
+                comment: None,
+                estimated_rows: None,
+            })
+        })
+        .collect()
+}
+
+/// List columns of a table or view in ordinal order.
+///
+/// Generated columns are included; their expression is recovered from the
+/// CREATE TABLE text since PRAGMA only flags them as hidden.
+pub async fn list_columns(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ColumnInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    let rows = sqlx::query(
+        "SELECT cid, name, type, \"notnull\" AS not_null, dflt_value, pk, hidden
+         FROM pragma_table_xinfo(?1, ?2)
+         WHERE hidden <> 1
+         ORDER BY cid",
+    )
+    .bind(table)
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let sql = object_sql(pool, schema, "table", table).await?.unwrap_or_default();
+    let generated_exprs = parse_generated_columns(&sql);
+    // A lone INTEGER PRIMARY KEY column is an alias for the rowid
+    let pk_count = rows
+        .iter()
+        .filter(|row| row.try_get::<i64, _>("pk").unwrap_or(0) > 0)
+        .count();
+
+    rows.iter()
+        .map(|row| {
+            let name: String = row.try_get("name").map_err(|e| e.to_string())?;
+            let data_type: String = row.try_get("type").map_err(|e| e.to_string())?;
+            let not_null: i64 = row.try_get("not_null").map_err(|e| e.to_string())?;
+            let pk: i64 = row.try_get("pk").map_err(|e| e.to_string())?;
+            let hidden: i64 = row.try_get("hidden").map_err(|e| e.to_string())?;
+            let cid: i64 = row.try_get("cid").map_err(|e| e.to_string())?;
+            let generated = if hidden >= 2 {
+                generated_exprs
+                    .iter()
+                    .find(|(column, _)| column.eq_ignore_ascii_case(&name))
+                    .map(|(_, expr)| expr.clone())
+            } else {
+                None
+            };
+            let is_rowid_alias = pk > 0 && pk_count == 1 && data_type.eq_ignore_ascii_case("INTEGER");
+            Ok(ColumnInfo {
+                is_auto_increment: is_rowid_alias,
+                name,
+                ordinal: cid + 1,
+                data_type,
+                is_nullable: not_null == 0 && !is_rowid_alias,
+                default_value: row.try_get("dflt_value").map_err(|e| e.to_string())?,
+                identity: None,
+                generated,
+                comment: None,
+            })
+        })
+        .collect()
+}
+
+/// List indexes of a table; `schema` is `main` or the alias of an attached database
+pub async fn list_indexes(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<IndexInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    let rows = sqlx::query(r#"SELECT name, "unique" AS is_unique, origin, partial FROM pragma_index_list(?1, ?2)"#)
+        .bind(table)
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut indexes = Vec::new();
+    for row in &rows {
+        let name: String = row.try_get("name").map_err(|e| e.to_string())?;
+        let is_unique: i64 = row.try_get("is_unique").map_err(|e| e.to_string())?;
+        let origin: String = row.try_get("origin").map_err(|e| e.to_string())?;
+        let partial: i64 = row.try_get("partial").map_err(|e| e.to_string())?;
+        // Auto-indexes backing PRIMARY KEY / UNIQUE constraints have no SQL text
+        let definition = object_sql(pool, schema, "index", &name).await?;
+        let predicate = if partial != 0 {
+            definition.as_deref().and_then(parse_index_predicate)
+        } else {
+            None
+        };
+
+        indexes.push(IndexInfo {
+            columns: index_columns(pool, schema, &name).await?,
+            name,
+            table: table.to_string(),
+            is_unique: is_unique != 0,
+            is_primary: origin == "pk",
+            index_type: "btree".to_string(),
+            predicate,
+            definition,
+        });
+    }
+
+    indexes.sort_by(|a, b| b.is_primary.cmp(&a.is_primary).then_with(|| a.name.cmp(&b.name)));
+    Ok(indexes)
+}
+
+/// List primary key, unique and check constraints of a table.
+///
+/// SQLite does not keep constraint names in its catalog; unnamed constraints are
+/// reported under the name of their backing index, or `PRIMARY` for rowid keys.
+pub async fn list_constraints(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ConstraintInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    let mut constraints = Vec::new();
+
+    let pk_columns = primary_key_columns(pool, schema, table).await?;
+    if !pk_columns.is_empty() {
+        constraints.push(ConstraintInfo {
+            name: "PRIMARY".to_string(),
+            table: table.to_string(),
+            constraint_type: "PRIMARY KEY".to_string(),
+            columns: pk_columns,
+            definition: None,
+        });
+    }
+
+    let unique_rows = sqlx::query("SELECT name FROM pragma_index_list(?1, ?2) WHERE origin = 'u' ORDER BY name")
+        .bind(table)
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    for row in &unique_rows {
+        let name: String = row.try_get("name").map_err(|e| e.to_string())?;
+        constraints.push(ConstraintInfo {
+            columns: index_columns(pool, schema, &name).await?,
+            name,
+            table: table.to_string(),
+            constraint_type: "UNIQUE".to_string(),
+            definition: None,
+        });
+    }
+
+    if let Some(sql) = object_sql(pool, schema, "table", table).await? {
+        for (i, (name, column, body)) in parse_check_constraints(&sql).into_iter().enumerate() {
+            constraints.push(ConstraintInfo {
+                name: name.unwrap_or_else(|| format!("{}_check_{}", table, i + 1)),
+                table: table.to_string(),
+                constraint_type: "CHECK".to_string(),
+                columns: column.into_iter().collect(),
+                definition: Some(body),
+            });
+        }
+    }
+
+    Ok(constraints)
+}
+
+/// List foreign keys declared on a table, including their ON UPDATE / ON DELETE actions
+pub async fn list_foreign_keys(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    let rows = sqlx::query(
+        r#"SELECT id, "table" AS referenced_table, "from" AS column_name, "to" AS referenced_column,
+                  on_update, on_delete
+           FROM pragma_foreign_key_list(?1, ?2)
+           ORDER BY id, seq"#,
+    )
+    .bind(table)
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut foreign_keys: Vec<(i64, ForeignKeyInfo)> = Vec::new();
+    for row in &rows {
+        let id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+        let column: String = row.try_get("column_name").map_err(|e| e.to_string())?;
+        // NULL means the FK targets the parent's primary key implicitly
+        let referenced_column: Option<String> = row.try_get("referenced_column").map_err(|e| e.to_string())?;
+        match foreign_keys.last_mut() {
+            Some((last_id, fk)) if *last_id == id => {
+                fk.columns.push(column);
+                fk.referenced_columns.extend(referenced_column);
+            }
+            _ => foreign_keys.push((
+                id,
+                ForeignKeyInfo {
+                    name: format!("fk_{}_{}", table, id),
+                    schema: Some(schema.to_string()),
+                    table: table.to_string(),
+                    columns: vec![column],
+                    referenced_schema: Some(schema.to_string()),
+                    referenced_table: row.try_get("referenced_table").map_err(|e| e.to_string())?,
+                    referenced_columns: referenced_column.into_iter().collect(),
+                    on_update: row.try_get("on_update").map_err(|e| e.to_string())?,
+                    on_delete: row.try_get("on_delete").map_err(|e| e.to_string())?,
+                },
+            )),
+        }
+    }
+
+    let mut result = Vec::with_capacity(foreign_keys.len());
+    for (_, mut fk) in foreign_keys {
+        if fk.referenced_columns.is_empty() {
+            fk.referenced_columns = primary_key_columns(pool, schema, &fk.referenced_table).await?;
+        }
+        result.push(fk);
+    }
+    Ok(result)
+}
+
+/// List foreign keys of any table that reference a table. SQLite foreign
+/// keys only reach tables of their own schema.
+pub async fn list_referencing_foreign_keys(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    table: &str,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    let query = format!(
+        r#"SELECT DISTINCT m.name FROM {}.sqlite_master m, pragma_foreign_key_list(m.name, ?1) f
+           WHERE m.type = 'table' AND f."table" = ?2 COLLATE NOCASE
+           ORDER BY m.name"#,
+        Dialect::Sqlite.quote_ident(schema.unwrap_or("main"))
+    );
+    let tables: Vec<String> = sqlx::query_scalar(&query)
+        .bind(schema.unwrap_or("main"))
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for referencing in tables {
+        let foreign_keys = list_foreign_keys(pool, schema, &referencing).await?;
+        result.extend(foreign_keys.into_iter().filter(|fk| fk.referenced_table.eq_ignore_ascii_case(table)));
+    }
+    Ok(result)
+}
+
+/// List views of a schema
+pub async fn list_views(pool: &Pool<Sqlite>, schema: Option<&str>) -> Result<Vec<ViewInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    let query = format!(
+        "SELECT name, sql FROM {}.sqlite_master WHERE type = 'view' ORDER BY name",
+        Dialect::Sqlite.quote_ident(schema)
+    );
+    let rows = sqlx::query(&query)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let sql: Option<String> = row.try_get("sql").map_err(|e| e.to_string())?;
+            Ok(ViewInfo {
+                schema: Some(schema.to_string()),
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                is_materialized: false,
+                definition: sql.as_deref().and_then(parse_view_select),
+            })
+        })
+        .collect()
+}
+
+/// List triggers of a schema, optionally restricted to one table
+pub async fn list_triggers(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    table: Option<&str>,
+) -> Result<Vec<TriggerInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    let query = format!(
+        "SELECT name, tbl_name, sql FROM {}.sqlite_master
+         WHERE type = 'trigger' AND (?1 IS NULL OR tbl_name = ?1)
+         ORDER BY tbl_name, name",
+        Dialect::Sqlite.quote_ident(schema)
+    );
+    let rows = sqlx::query(&query)
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let sql: Option<String> = row.try_get("sql").map_err(|e| e.to_string())?;
+            let (timing, events, body) = sql.as_deref().map(parse_trigger).unwrap_or_default();
+            Ok(TriggerInfo {
+                schema: Some(schema.to_string()),
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                table: row.try_get("tbl_name").map_err(|e| e.to_string())?,
+                timing,
+                events,
+                // SQLite only supports FOR EACH ROW triggers
+                orientation: "ROW".to_string(),
+                definition: sql,
+                body,
+            })
+        })
+        .collect()
+}
+
+/// List AUTOINCREMENT counters from `sqlite_sequence`, one per table
+pub async fn list_sequences(pool: &Pool<Sqlite>, schema: Option<&str>) -> Result<Vec<SequenceInfo>, String> {
+    let schema = schema.unwrap_or("main");
+    // sqlite_sequence only exists once a table with AUTOINCREMENT has been created
+    if object_sql(pool, schema, "table", "sqlite_sequence").await?.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let query = format!(
+        "SELECT name, seq FROM {}.sqlite_sequence ORDER BY name",
+        Dialect::Sqlite.quote_ident(schema)
+    );
+    let rows = sqlx::query(&query)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut sequences = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let table: String = row.try_get("name").map_err(|e| e.to_string())?;
+        let seq: Option<i64> = row.try_get("seq").map_err(|e| e.to_string())?;
+        let owned_by = primary_key_columns(pool, schema, &table)
+            .await?
+            .first()
+            .map(|column| format!("{}.{}", table, column));
+        sequences.push(SequenceInfo {
+            schema: Some(schema.to_string()),
+            name: table,
+            data_type: Some("INTEGER".to_string()),
+            current_value: seq.filter(|v| *v > 0),
+            increment: 1,
+            start_value: Some(1),
+            min_value: None,
+            max_value: Some(i64::MAX),
+            cycle: false,
+            owned_by,
+        });
+    }
+    Ok(sequences)
+}
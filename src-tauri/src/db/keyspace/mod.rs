@@ -0,0 +1,148 @@
+// Redis key browsing
+//
+// `redis_scan_keys` pages through a keyspace with SCAN, never KEYS, so that a
+// large database is not blocked while it is listed. SCAN may return few or no
+// keys per call when a pattern matches sparsely, so a page takes as many
+// rounds as it needs to fill up, within a bound. The type, TTL and memory use
+// of the page's keys are then read in pipelines, one round trip each. With a
+// separator, keys are shown as a tree: the keys directly under the browsed
+// prefix, and the namespaces one level further down.
+
+pub mod values;
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+use redis::aio::MultiplexedConnection;
+use redis::Value;
+
+use crate::db::types::{RedisKeyInfo, RedisNamespace, RedisScanRequest, RedisScanResult};
+
+/// SCAN calls per page at most, so that a sparse pattern cannot hold a page
+/// back for the whole keyspace; the cursor lets the next page go on
+const MAX_SCAN_ROUNDS: usize = 100;
+
+pub async fn connection(client: &redis::Client) -> Result<MultiplexedConnection, String> {
+    client.get_multiplexed_async_connection().await.map_err(|e| e.to_string())
+}
+
+/// Key names as text; keys that are not UTF-8 get replacement characters
+fn key_text(key: &[u8]) -> String {
+    String::from_utf8_lossy(key).into_owned()
+}
+
+/// Escape the glob characters of SCAN's MATCH in literal text
+fn glob_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Type, TTL and memory use of each key. Keys that expired or were deleted
+/// since the scan are left out.
+pub async fn key_details(con: &mut MultiplexedConnection, keys: &[String]) -> Result<Vec<RedisKeyInfo>, String> {
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut pipe = redis::pipe();
+    for key in keys {
+        pipe.cmd("TYPE").arg(key).cmd("PTTL").arg(key);
+    }
+    let replies: Vec<Value> = pipe.query_async(con).await.map_err(|e| e.to_string())?;
+
+    // MEMORY USAGE is often disabled on managed servers; an error reply
+    // fails the whole pipeline, so it gets its own
+    let mut pipe = redis::pipe();
+    for key in keys {
+        pipe.cmd("MEMORY").arg("USAGE").arg(key);
+    }
+    let memory: Vec<Option<u64>> = pipe.query_async(con).await.unwrap_or_else(|_| vec![None; keys.len()]);
+
+    let mut details = Vec::with_capacity(keys.len());
+    for ((key, pair), memory_bytes) in keys.iter().zip(replies.chunks(2)).zip(memory) {
+        let key_type: String = redis::from_redis_value(&pair[0]).map_err(|e| e.to_string())?;
+        let ttl: i64 = redis::from_redis_value(&pair[1]).map_err(|e| e.to_string())?;
+        // -2: the key no longer exists
+        if key_type == "none" || ttl == -2 {
+            continue;
+        }
+        details.push(RedisKeyInfo {
+            key: key.clone(),
+            key_type,
+            ttl_ms: (ttl >= 0).then_some(ttl),
+            memory_bytes,
+        });
+    }
+    Ok(details)
+}
+
+/// A page of the keyspace
+pub async fn scan_keys(client: &redis::Client, request: &RedisScanRequest) -> Result<RedisScanResult, String> {
+    let start = Instant::now();
+    let mut con = connection(client).await?;
+    let mut cursor: u64 = request
+        .cursor
+        .parse()
+        .map_err(|_| format!("Invalid cursor '{}'", request.cursor))?;
+    let prefix = request.prefix.clone().unwrap_or_default();
+    let pattern = match &request.pattern {
+        Some(pattern) if !pattern.is_empty() => pattern.clone(),
+        _ => format!("{}*", glob_escape(&prefix)),
+    };
+
+    let mut found = Vec::new();
+    for _ in 0..MAX_SCAN_ROUNDS {
+        let mut scan = redis::cmd("SCAN");
+        scan.arg(cursor).arg("MATCH").arg(&pattern).arg("COUNT").arg(request.count.max(1));
+        if let Some(key_type) = &request.key_type {
+            scan.arg("TYPE").arg(key_type);
+        }
+        let (next, keys): (u64, Vec<Vec<u8>>) = scan.query_async(&mut con).await.map_err(|e| e.to_string())?;
+        found.extend(keys.iter().map(|key| key_text(key)));
+        cursor = next;
+        if cursor == 0 || found.len() >= request.page_size {
+            break;
+        }
+    }
+    // SCAN may return a key twice over a scan; within a page at least, list it once
+    found.sort();
+    found.dedup();
+
+    let mut leaves = Vec::new();
+    let mut namespaces: BTreeMap<String, u64> = BTreeMap::new();
+    match request.separator.as_deref().filter(|s| !s.is_empty()) {
+        Some(separator) => {
+            for key in found {
+                // A custom pattern may match keys outside the prefix
+                let Some(rest) = key.strip_prefix(prefix.as_str()) else { continue };
+                match rest.split_once(separator) {
+                    Some((name, _)) => *namespaces.entry(name.to_string()).or_default() += 1,
+                    None => leaves.push(key),
+                }
+            }
+        }
+        None => leaves = found,
+    }
+
+    let total_keys: u64 = redis::cmd("DBSIZE").query_async(&mut con).await.map_err(|e| e.to_string())?;
+    let separator = request.separator.clone().unwrap_or_default();
+    Ok(RedisScanResult {
+        cursor: cursor.to_string(),
+        complete: cursor == 0,
+        keys: key_details(&mut con, &leaves).await?,
+        namespaces: namespaces
+            .into_iter()
+            .map(|(name, key_count)| RedisNamespace {
+                prefix: format!("{}{}{}", prefix, name, separator),
+                name,
+                key_count,
+            })
+            .collect(),
+        total_keys,
+        execution_time_ms: start.elapsed().as_millis(),
+    })
+}
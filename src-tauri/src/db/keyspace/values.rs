@@ -0,0 +1,416 @@
+// Redis values by type
+//
+// `get_value` reads a page of a value with the command suited to its type:
+// GETRANGE for strings, HSCAN / SSCAN for hashes and sets, LRANGE windows for
+// lists, ZRANGE by rank or score for sorted sets and XRANGE for streams. Each
+// page comes with the cursor the next one starts from, whatever it stands for
+// (a SCAN cursor, an index or an entry id). Score ranges and exclusive
+// stream ids take Redis 6.2.
+//
+// `edit_value` changes one field, item, member or entry. Replacing something
+// is done in one MULTI / EXEC so that a rename never loses the old one alone.
+// List items are addressed by index, which other clients shift by pushing and
+// popping, so they are only changed by a script that first checks the item
+// still holds the value the user saw.
+
+use redis::aio::MultiplexedConnection;
+use redis::Script;
+
+use crate::db::keyspace::{connection, key_details};
+use crate::db::stream::encode_hex;
+use crate::db::types::{
+    RedisContent, RedisEdit, RedisEditResult, RedisField, RedisScoredMember, RedisStreamEntry, RedisValue,
+    RedisValueRequest, ValueFormat,
+};
+
+/// Bytes of a string value read at most
+const MAX_STRING_BYTES: usize = 1024 * 1024;
+/// HSCAN / SSCAN calls per page at most, as for keys
+const MAX_SCAN_ROUNDS: usize = 100;
+
+/// Replace the item at ARGV[1] with ARGV[3] if it is still ARGV[2]
+const SET_LIST_ITEM: &str = r#"
+if redis.call('LINDEX', KEYS[1], ARGV[1]) ~= ARGV[2] then return 0 end
+redis.call('LSET', KEYS[1], ARGV[1], ARGV[3])
+return 1
+"#;
+
+/// Remove the item at ARGV[1] if it is still ARGV[2]. LREM removes by value,
+/// so the item is first overwritten with the unique marker ARGV[3].
+const REMOVE_LIST_ITEM: &str = r#"
+if redis.call('LINDEX', KEYS[1], ARGV[1]) ~= ARGV[2] then return 0 end
+redis.call('LSET', KEYS[1], ARGV[1], ARGV[3])
+redis.call('LREM', KEYS[1], 1, ARGV[3])
+return 1
+"#;
+
+/// Set field ARGV[1] to ARGV[2] if it does not exist yet, removing the field
+/// ARGV[3] it was renamed from, if any
+const ADD_HASH_FIELD: &str = r#"
+if redis.call('HEXISTS', KEYS[1], ARGV[1]) == 1 then return 0 end
+if ARGV[3] then redis.call('HDEL', KEYS[1], ARGV[3]) end
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+return 1
+"#;
+
+fn text(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+/// Pairs of a flat reply, such as HSCAN's field, value, field, value...
+fn fields(flat: Vec<Vec<u8>>) -> Vec<RedisField> {
+    let mut flat = flat.into_iter();
+    let mut fields = Vec::new();
+    while let (Some(field), Some(value)) = (flat.next(), flat.next()) {
+        fields.push(RedisField { field: text(field), value: text(value) });
+    }
+    fields
+}
+
+/// A string value as text, JSON or, when it is not text, hex
+fn string_content(bytes: Vec<u8>, truncated: bool) -> RedisContent {
+    let format = match std::str::from_utf8(&bytes) {
+        // A cut value may end in the middle of a character
+        Err(e) if truncated && e.error_len().is_none() && e.valid_up_to() + 4 > bytes.len() => ValueFormat::Text,
+        Err(_) => ValueFormat::Binary,
+        Ok(text) if text.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')) => ValueFormat::Binary,
+        Ok(text) => {
+            let trimmed = text.trim_start();
+            let json = (trimmed.starts_with('{') || trimmed.starts_with('['))
+                && serde_json::from_str::<serde_json::Value>(text).is_ok();
+            if json { ValueFormat::Json } else { ValueFormat::Text }
+        }
+    };
+    let value = match format {
+        ValueFormat::Binary => encode_hex(&bytes),
+        ValueFormat::Text | ValueFormat::Json => text(bytes),
+    };
+    RedisContent::String { value, format, truncated }
+}
+
+fn parse_cursor(cursor: &Option<String>) -> Result<u64, String> {
+    match cursor {
+        None => Ok(0),
+        Some(cursor) => cursor.parse().map_err(|_| format!("Invalid cursor '{}'", cursor)),
+    }
+}
+
+/// Next page of HSCAN / SSCAN, going on while a sparse pattern returns too little
+async fn scan_value(
+    con: &mut MultiplexedConnection,
+    command: &str,
+    request: &RedisValueRequest,
+    per_item: usize,
+) -> Result<(Vec<Vec<u8>>, Option<String>), String> {
+    let mut cursor = parse_cursor(&request.cursor)?;
+    let mut found = Vec::new();
+    for _ in 0..MAX_SCAN_ROUNDS {
+        let mut scan = redis::cmd(command);
+        scan.arg(&request.key).arg(cursor);
+        if let Some(pattern) = request.pattern.as_deref().filter(|p| !p.is_empty()) {
+            scan.arg("MATCH").arg(pattern);
+        }
+        scan.arg("COUNT").arg(request.count.max(1));
+        let (next, items): (u64, Vec<Vec<u8>>) = scan.query_async(con).await.map_err(|e| e.to_string())?;
+        found.extend(items);
+        cursor = next;
+        if cursor == 0 || found.len() >= request.count * per_item {
+            break;
+        }
+    }
+    Ok((found, (cursor != 0).then(|| cursor.to_string())))
+}
+
+/// A page of the value at `request.key`
+pub async fn get_value(client: &redis::Client, request: &RedisValueRequest) -> Result<RedisValue, String> {
+    let mut con = connection(client).await?;
+    let key = &request.key;
+    let info = key_details(&mut con, std::slice::from_ref(key))
+        .await?
+        .pop()
+        .ok_or_else(|| format!("Key '{}' does not exist", key))?;
+    let count = request.count.max(1);
+
+    let (length, content, cursor): (u64, RedisContent, Option<String>) = match info.key_type.as_str() {
+        "string" => {
+            let length: u64 = redis::cmd("STRLEN").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            let bytes: Vec<u8> = redis::cmd("GETRANGE")
+                .arg(key)
+                .arg(0)
+                .arg(MAX_STRING_BYTES - 1)
+                .query_async(&mut con)
+                .await
+                .map_err(|e| e.to_string())?;
+            (length, string_content(bytes, length as usize > MAX_STRING_BYTES), None)
+        }
+        "hash" => {
+            let length: u64 = redis::cmd("HLEN").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            let (flat, cursor) = scan_value(&mut con, "HSCAN", request, 2).await?;
+            (length, RedisContent::Hash { fields: fields(flat) }, cursor)
+        }
+        "set" => {
+            let length: u64 = redis::cmd("SCARD").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            let (members, cursor) = scan_value(&mut con, "SSCAN", request, 1).await?;
+            (length, RedisContent::Set { members: members.into_iter().map(text).collect() }, cursor)
+        }
+        "list" => {
+            let length: u64 = redis::cmd("LLEN").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            let start = parse_cursor(&request.cursor)?;
+            let items: Vec<Vec<u8>> = redis::cmd("LRANGE")
+                .arg(key)
+                .arg(start)
+                .arg(start + count as u64 - 1)
+                .query_async(&mut con)
+                .await
+                .map_err(|e| e.to_string())?;
+            let end = start + items.len() as u64;
+            let items = items.into_iter().map(text).collect();
+            (length, RedisContent::List { start, items }, (end < length).then(|| end.to_string()))
+        }
+        "zset" => {
+            let length: u64 = redis::cmd("ZCARD").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            let offset = parse_cursor(&request.cursor)?;
+            let mut range = redis::cmd("ZRANGE");
+            range.arg(key);
+            if request.min_score.is_some() || request.max_score.is_some() {
+                let min = request.min_score.as_deref().unwrap_or("-inf");
+                let max = request.max_score.as_deref().unwrap_or("+inf");
+                // REV takes the bounds the other way round
+                if request.reverse {
+                    range.arg(max).arg(min).arg("BYSCORE").arg("REV");
+                } else {
+                    range.arg(min).arg(max).arg("BYSCORE");
+                }
+                range.arg("LIMIT").arg(offset).arg(count);
+            } else {
+                range.arg(offset).arg(offset + count as u64 - 1);
+                if request.reverse {
+                    range.arg("REV");
+                }
+            }
+            range.arg("WITHSCORES");
+            let flat: Vec<Vec<u8>> = range.query_async(&mut con).await.map_err(|e| e.to_string())?;
+            let members: Vec<RedisScoredMember> = fields(flat)
+                .into_iter()
+                .map(|f| RedisScoredMember {
+                    score: f.value.parse().unwrap_or(f64::NAN),
+                    member: f.field,
+                })
+                .collect();
+            let next = offset + members.len() as u64;
+            let cursor = (members.len() == count && next < length).then(|| next.to_string());
+            (length, RedisContent::ZSet { members }, cursor)
+        }
+        "stream" => {
+            let length: u64 = redis::cmd("XLEN").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            // The cursor is the last id read; "(" makes the bound exclusive
+            let after = request.cursor.as_ref().map(|id| format!("({}", id));
+            let mut range = if request.reverse { redis::cmd("XREVRANGE") } else { redis::cmd("XRANGE") };
+            range.arg(key);
+            if request.reverse {
+                range.arg(after.as_deref().unwrap_or("+")).arg("-");
+            } else {
+                range.arg(after.as_deref().unwrap_or("-")).arg("+");
+            }
+            range.arg("COUNT").arg(count);
+            // Read entry by entry: a Vec of pairs would take the reply as a flat list of them
+            let raw: Vec<redis::Value> = range.query_async(&mut con).await.map_err(|e| e.to_string())?;
+            let entries = raw
+                .iter()
+                .map(|entry| {
+                    let (id, flat): (String, Vec<Vec<u8>>) = redis::from_redis_value(entry).map_err(|e| e.to_string())?;
+                    Ok(RedisStreamEntry { id, fields: fields(flat) })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let cursor = entries.last().filter(|_| entries.len() == count).map(|entry| entry.id.clone());
+            (length, RedisContent::Stream { entries }, cursor)
+        }
+        other => return Err(format!("Values of type '{}' cannot be shown", other)),
+    };
+
+    Ok(RedisValue {
+        key: info.key,
+        key_type: info.key_type,
+        ttl_ms: info.ttl_ms,
+        length,
+        content,
+        cursor,
+    })
+}
+
+/// Run a pipeline as one transaction and return its replies
+async fn atomic<T: redis::FromRedisValue>(con: &mut MultiplexedConnection, pipe: &mut redis::Pipeline) -> Result<T, String> {
+    pipe.atomic().query_async(con).await.map_err(|e| e.to_string())
+}
+
+/// Change one part of the value at `key`
+pub async fn edit_value(client: &redis::Client, key: &str, edit: &RedisEdit) -> Result<RedisEditResult, String> {
+    let mut con = connection(client).await?;
+    let con = &mut con;
+    let done = |changed: u64| Ok(RedisEditResult { changed, entry_id: None });
+
+    match edit {
+        RedisEdit::SetString { value, keep_ttl } => {
+            let mut set = redis::cmd("SET");
+            set.arg(key).arg(value);
+            if *keep_ttl {
+                set.arg("KEEPTTL");
+            }
+            let _: () = set.query_async(con).await.map_err(|e| e.to_string())?;
+            done(1)
+        }
+        RedisEdit::SetHashField { field, value, previous_field, only_new } => {
+            let renamed = previous_field.as_ref().filter(|previous| *previous != field);
+            if !*only_new && renamed.is_none() {
+                let () = redis::cmd("HSET").arg(key).arg(field).arg(value).query_async(con).await.map_err(|e| e.to_string())?;
+                return done(1);
+            }
+            // The existence check runs in the script, so no other client can add the field in between
+            let script = Script::new(ADD_HASH_FIELD);
+            let mut invocation = script.key(key);
+            invocation.arg(field).arg(value);
+            if let Some(previous) = renamed {
+                invocation.arg(previous);
+            }
+            let added: u64 = invocation.invoke_async(con).await.map_err(|e| e.to_string())?;
+            if added == 0 {
+                return Err(format!("Field '{}' already exists", field));
+            }
+            done(1)
+        }
+        RedisEdit::RemoveHashFields { fields } => {
+            if fields.is_empty() {
+                return done(0);
+            }
+            done(redis::cmd("HDEL").arg(key).arg(fields).query_async(con).await.map_err(|e| e.to_string())?)
+        }
+        RedisEdit::PushListItems { items, head } => {
+            if items.is_empty() {
+                return done(0);
+            }
+            let command = if *head { "LPUSH" } else { "RPUSH" };
+            let _: u64 = redis::cmd(command).arg(key).arg(items).query_async(con).await.map_err(|e| e.to_string())?;
+            done(items.len() as u64)
+        }
+        RedisEdit::SetListItem { index, current, value } => {
+            let changed: u64 = Script::new(SET_LIST_ITEM)
+                .key(key)
+                .arg(index)
+                .arg(current)
+                .arg(value)
+                .invoke_async(con)
+                .await
+                .map_err(|e| e.to_string())?;
+            if changed == 0 {
+                return Err("The list changed since it was read; reload it and try again".to_string());
+            }
+            done(changed)
+        }
+        RedisEdit::RemoveListItem { index, current } => {
+            let marker = format!("\u{0}removed-{:016x}", rand::random::<u64>());
+            let changed: u64 = Script::new(REMOVE_LIST_ITEM)
+                .key(key)
+                .arg(index)
+                .arg(current)
+                .arg(marker)
+                .invoke_async(con)
+                .await
+                .map_err(|e| e.to_string())?;
+            if changed == 0 {
+                return Err("The list changed since it was read; reload it and try again".to_string());
+            }
+            done(changed)
+        }
+        RedisEdit::SetSetMember { member, previous } => {
+            let mut pipe = redis::pipe();
+            if let Some(previous) = previous.as_ref().filter(|previous| *previous != member) {
+                pipe.cmd("SREM").arg(key).arg(previous).ignore();
+            }
+            pipe.cmd("SADD").arg(key).arg(member);
+            let (added,): (u64,) = atomic(con, &mut pipe).await?;
+            done(added)
+        }
+        RedisEdit::RemoveSetMembers { members } => {
+            if members.is_empty() {
+                return done(0);
+            }
+            done(redis::cmd("SREM").arg(key).arg(members).query_async(con).await.map_err(|e| e.to_string())?)
+        }
+        RedisEdit::SetZSetMember { member, score, previous } => {
+            if score.is_nan() {
+                return Err("The score must be a number".to_string());
+            }
+            let mut pipe = redis::pipe();
+            if let Some(previous) = previous.as_ref().filter(|previous| *previous != member) {
+                pipe.cmd("ZREM").arg(key).arg(previous).ignore();
+            }
+            pipe.cmd("ZADD").arg(key).arg("CH").arg(score).arg(member);
+            let (changed,): (u64,) = atomic(con, &mut pipe).await?;
+            done(changed)
+        }
+        RedisEdit::RemoveZSetMembers { members } => {
+            if members.is_empty() {
+                return done(0);
+            }
+            done(redis::cmd("ZREM").arg(key).arg(members).query_async(con).await.map_err(|e| e.to_string())?)
+        }
+        RedisEdit::AddStreamEntry { id, fields } => {
+            if fields.is_empty() {
+                return Err("A stream entry needs at least one field".to_string());
+            }
+            let mut add = redis::cmd("XADD");
+            add.arg(key).arg(id.as_deref().filter(|id| !id.is_empty()).unwrap_or("*"));
+            for field in fields {
+                add.arg(&field.field).arg(&field.value);
+            }
+            let entry_id: String = add.query_async(con).await.map_err(|e| e.to_string())?;
+            Ok(RedisEditResult { changed: 1, entry_id: Some(entry_id) })
+        }
+        RedisEdit::RemoveStreamEntries { ids } => {
+            if ids.is_empty() {
+                return done(0);
+            }
+            done(redis::cmd("XDEL").arg(key).arg(ids).query_async(con).await.map_err(|e| e.to_string())?)
+        }
+    }
+}
+
+/// Set the key's time to live, or remove its expiry with None; false when the key does not exist
+pub async fn set_ttl(client: &redis::Client, key: &str, ttl_ms: Option<i64>) -> Result<bool, String> {
+    let mut con = connection(client).await?;
+    let updated: i64 = match ttl_ms {
+        Some(ms) if ms <= 0 => return Err("The time to live must be positive".to_string()),
+        Some(ms) => redis::cmd("PEXPIRE").arg(key).arg(ms).query_async(&mut con).await,
+        None => {
+            let exists: i64 = redis::cmd("EXISTS").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            // PERSIST also answers 0 for a key that had no expiry
+            let _: i64 = redis::cmd("PERSIST").arg(key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+            Ok(exists)
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(updated == 1)
+}
+
+/// Rename a key; unless `overwrite`, fail when `new_key` exists
+pub async fn rename_key(client: &redis::Client, key: &str, new_key: &str, overwrite: bool) -> Result<(), String> {
+    let mut con = connection(client).await?;
+    if overwrite {
+        let _: () = redis::cmd("RENAME").arg(key).arg(new_key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    let renamed: bool = redis::cmd("RENAMENX").arg(key).arg(new_key).query_async(&mut con).await.map_err(|e| e.to_string())?;
+    if !renamed {
+        return Err(format!("Key '{}' already exists", new_key));
+    }
+    Ok(())
+}
+
+/// Delete keys, reclaiming their memory in the background (UNLINK); returns how many existed
+pub async fn delete_keys(client: &redis::Client, keys: &[String]) -> Result<u64, String> {
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let mut con = connection(client).await?;
+    redis::cmd("UNLINK").arg(keys).query_async(&mut con).await.map_err(|e| e.to_string())
+}
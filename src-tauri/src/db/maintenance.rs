@@ -0,0 +1,432 @@
+// SQLite maintenance
+//
+// Backups through `VACUUM INTO`, which writes a consistent, compacted copy of
+// a live database without blocking writers for longer than a read
+// transaction; integrity and foreign key checks with their messages parsed;
+// page usage per table and index from the dbstat virtual table (compiled into
+// the bundled SQLite); ANALYZE / VACUUM with the size before and after; and
+// changing, adding or removing SQLCipher encryption of a database file.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Pool, Row, Sqlite};
+
+use crate::db::connect;
+use crate::db::dialect::Dialect;
+use crate::db::types::{
+    BackupSummary, ForeignKeyViolation, IntegrityIssue, IntegrityReport, OptimizeSummary, SqliteCipher, SqliteOptions,
+    StorageReport, StorageUsage,
+};
+
+const LITE: Dialect = Dialect::Sqlite;
+
+/// Copy the database (or attached `schema`) to `path` with `VACUUM INTO`.
+/// With `cipher` the copy is written by `sqlcipher_export` and encrypted,
+/// which is also the only way to copy an encrypted database.
+pub async fn backup(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    path: &str,
+    overwrite: bool,
+    cipher: Option<&SqliteCipher>,
+) -> Result<BackupSummary, String> {
+    let start = Instant::now();
+    let target = Path::new(path);
+    if target.exists() && !overwrite {
+        return Err(format!("'{}' already exists", path));
+    }
+    // Overwriting a file the connection has open would corrupt it
+    let resolved = resolve_path(target);
+    let open_files: Vec<String> = sqlx::query_scalar("SELECT file FROM pragma_database_list")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if open_files.iter().any(|file| !file.is_empty() && resolve_path(Path::new(file)) == resolved) {
+        return Err(format!("'{}' is a database open on this connection", path));
+    }
+
+    // Written next to the target and renamed over it only once complete, so
+    // a failed backup leaves an existing file untouched
+    let file_name = target.file_name().ok_or_else(|| format!("'{}' is not a file path", path))?;
+    let temp = target.with_file_name(format!(".{}.partial", file_name.to_string_lossy()));
+    let temp_path = temp.to_string_lossy().into_owned();
+    let _ = std::fs::remove_file(&temp);
+
+    let schema = schema.unwrap_or("main");
+    let written = if cipher.is_some() {
+        export_copy(pool, schema, &temp_path, cipher).await
+    } else {
+        // VACUUM INTO from an encrypted database writes pages no key opens
+        let encrypted = sqlx::query(&format!("PRAGMA {}.cipher_provider", LITE.quote_ident(schema)))
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .is_some();
+        if encrypted {
+            return Err("The database is encrypted; give the key to encrypt the backup with".to_string());
+        }
+        sqlx::query(&format!("VACUUM {} INTO ?", LITE.quote_ident(schema)))
+            .bind(&temp_path)
+            .execute(pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    };
+    if let Err(e) = written.and_then(|()| std::fs::rename(&temp, target).map_err(|e| e.to_string())) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    Ok(BackupSummary {
+        path: path.to_string(),
+        bytes: std::fs::metadata(target).map_err(|e| e.to_string())?.len(),
+        execution_time_ms: start.elapsed().as_millis(),
+    })
+}
+
+/// Absolute form of a path that may not exist yet, with symlinks resolved
+/// where possible
+fn resolve_path(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            parent.canonicalize().unwrap_or_else(|_| parent.to_path_buf()).join(name)
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// The name following `marker` in `message`, without trailing punctuation
+fn name_after(message: &str, marker: &str) -> Option<String> {
+    let rest = &message[message.find(marker)? + marker.len()..];
+    let name = rest.split_whitespace().next()?.trim_end_matches([',', ':', '.']);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// The number following the first `page ` in `message`
+fn page_number(message: &str) -> Option<i64> {
+    let lower = message.to_ascii_lowercase();
+    let rest = &lower[lower.find("page ")? + "page ".len()..];
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Pick the object and page out of an integrity_check message, e.g.
+/// `row 5 missing from index idx_name`, `NULL value in t.col` or
+/// `Tree 4 page 4 cell 0: invalid page number 9`
+fn integrity_issue(message: &str) -> IntegrityIssue {
+    // Attached databases prefix their first message with the schema name
+    let message = match message.strip_prefix("*** in database ") {
+        Some(rest) => rest.split_once("***").map_or(rest, |(_, m)| m).trim_start(),
+        None => message,
+    };
+    let object = name_after(message, " index ")
+        .or_else(|| name_after(message, "NULL value in "))
+        .or_else(|| name_after(message, "CHECK constraint failed in "))
+        .or_else(|| name_after(message, " value in "));
+    IntegrityIssue {
+        message: message.to_string(),
+        object,
+        page: page_number(message),
+    }
+}
+
+/// Run `PRAGMA integrity_check` (or the faster `quick_check`, which skips
+/// index contents) and parse its messages
+pub async fn integrity_check(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    quick: bool,
+    max_errors: u32,
+) -> Result<IntegrityReport, String> {
+    let pragma = if quick { "quick_check" } else { "integrity_check" };
+    let max_errors = max_errors.max(1);
+    let messages: Vec<String> = sqlx::query_scalar(&format!(
+        "PRAGMA {}.{}({})",
+        LITE.quote_ident(schema.unwrap_or("main")),
+        pragma,
+        max_errors
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let ok = messages.len() == 1 && messages[0] == "ok";
+    let issues: Vec<IntegrityIssue> = if ok {
+        Vec::new()
+    } else {
+        messages.iter().map(|m| integrity_issue(m)).collect()
+    };
+    Ok(IntegrityReport {
+        ok,
+        truncated: issues.len() >= max_errors as usize,
+        issues,
+    })
+}
+
+/// Run `PRAGMA foreign_key_check` on one table or the whole schema, naming
+/// the columns of each violated foreign key
+pub async fn foreign_key_check(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    table: Option<&str>,
+) -> Result<Vec<ForeignKeyViolation>, String> {
+    let schema = schema.unwrap_or("main");
+    let mut pragma = format!("PRAGMA {}.foreign_key_check", LITE.quote_ident(schema));
+    if let Some(table) = table {
+        pragma.push_str(&format!("({})", LITE.quote_ident(table)));
+    }
+    let rows = sqlx::query(&pragma).fetch_all(pool).await.map_err(|e| e.to_string())?;
+
+    // Columns of each (table, foreign key id)
+    let mut keys: HashMap<(String, i64), (Vec<String>, Vec<String>)> = HashMap::new();
+    let mut violations = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let table: String = row.try_get("table").map_err(|e| e.to_string())?;
+        let fkid: i64 = row.try_get("fkid").map_err(|e| e.to_string())?;
+        let (columns, parent_columns) = match keys.get(&(table.clone(), fkid)) {
+            Some(key) => key.clone(),
+            None => {
+                let pairs: Vec<(String, Option<String>)> = sqlx::query_as(
+                    r#"SELECT "from", "to" FROM pragma_foreign_key_list(?1, ?2) WHERE id = ?3 ORDER BY seq"#,
+                )
+                .bind(&table)
+                .bind(schema)
+                .bind(fkid)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+                let columns: Vec<String> = pairs.iter().map(|(from, _)| from.clone()).collect();
+                let parent_columns: Vec<String> = pairs.iter().filter_map(|(_, to)| to.clone()).collect();
+                keys.insert((table.clone(), fkid), (columns.clone(), parent_columns.clone()));
+                (columns, parent_columns)
+            }
+        };
+        violations.push(ForeignKeyViolation {
+            rowid: row.try_get("rowid").map_err(|e| e.to_string())?,
+            parent: row.try_get("parent").map_err(|e| e.to_string())?,
+            table,
+            columns,
+            parent_columns,
+        });
+    }
+    Ok(violations)
+}
+
+/// `(page_size, page_count, freelist_count)` of a schema
+async fn page_counts(pool: &Pool<Sqlite>, schema: &str) -> Result<(i64, i64, i64), String> {
+    let schema = LITE.quote_ident(schema);
+    let mut values = Vec::with_capacity(3);
+    for pragma in ["page_size", "page_count", "freelist_count"] {
+        let row = sqlx::query(&format!("PRAGMA {}.{}", schema, pragma))
+            .fetch_one(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        // SQLCipher answers page_size on an encrypted database as cipher_page_size, in text
+        values.push(row.try_get_unchecked::<i64, _>(0).map_err(|e| e.to_string())?);
+    }
+    Ok((values[0], values[1], values[2]))
+}
+
+/// Pages and bytes used by every table and index of a schema
+pub async fn storage_usage(pool: &Pool<Sqlite>, schema: Option<&str>) -> Result<StorageReport, String> {
+    let schema = schema.unwrap_or("main");
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            s.name,
+            COALESCE(m.type, 'schema') AS object_type,
+            CASE WHEN m.type = 'index' THEN m.tbl_name END AS table_name,
+            count(*) AS pages,
+            sum(s.pgsize) AS size_bytes,
+            sum(s.payload) AS payload_bytes,
+            sum(s.unused) AS unused_bytes
+        FROM dbstat(?1) s
+        LEFT JOIN {}.sqlite_master m ON m.name = s.name
+        GROUP BY s.name
+        ORDER BY size_bytes DESC, s.name
+        "#,
+        LITE.quote_ident(schema)
+    ))
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(e) if e.message().contains("dbstat") => {
+            "This SQLite build does not include the dbstat virtual table".to_string()
+        }
+        e => e.to_string(),
+    })?;
+
+    let objects = rows
+        .iter()
+        .map(|row| {
+            Ok(StorageUsage {
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                object_type: row.try_get("object_type").map_err(|e| e.to_string())?,
+                table: row.try_get("table_name").map_err(|e| e.to_string())?,
+                pages: row.try_get("pages").map_err(|e| e.to_string())?,
+                size_bytes: row.try_get("size_bytes").map_err(|e| e.to_string())?,
+                payload_bytes: row.try_get("payload_bytes").map_err(|e| e.to_string())?,
+                unused_bytes: row.try_get("unused_bytes").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let (page_size, page_count, freelist_pages) = page_counts(pool, schema).await?;
+    Ok(StorageReport {
+        page_size,
+        page_count,
+        freelist_pages,
+        size_bytes: page_size * page_count,
+        objects,
+    })
+}
+
+/// Run ANALYZE and / or VACUUM and report the database size before and after
+pub async fn optimize(
+    pool: &Pool<Sqlite>,
+    schema: Option<&str>,
+    analyze: bool,
+    vacuum: bool,
+) -> Result<OptimizeSummary, String> {
+    let start = Instant::now();
+    let schema = schema.unwrap_or("main");
+    let quoted = LITE.quote_ident(schema);
+    let (page_size, page_count, _) = page_counts(pool, schema).await?;
+    let size_before = page_size * page_count;
+
+    if analyze {
+        sqlx::query(&format!("ANALYZE {}", quoted))
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    if vacuum {
+        sqlx::query(&format!("VACUUM {}", quoted))
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        // In WAL mode the rewritten pages only reach the file at a checkpoint
+        sqlx::query(&format!("PRAGMA {}.wal_checkpoint(TRUNCATE)", quoted))
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let (page_size, page_count, _) = page_counts(pool, schema).await?;
+    Ok(OptimizeSummary {
+        size_before,
+        size_after: page_size * page_count,
+        execution_time_ms: start.elapsed().as_millis(),
+    })
+}
+
+/// Copy `schema` into a new file with `sqlcipher_export`, encrypted with
+/// `cipher` or plain
+async fn export_copy(pool: &Pool<Sqlite>, schema: &str, target: &str, cipher: Option<&SqliteCipher>) -> Result<(), String> {
+    let settings = cipher.map(connect::cipher_settings).transpose()?.unwrap_or_default();
+    // ATTACH only creates files when the connection itself was opened with `mode=rwc`
+    std::fs::File::create(target).map_err(|e| e.to_string())?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let user_version: i64 = sqlx::query_scalar(&format!("PRAGMA {}.user_version", LITE.quote_ident(schema)))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query("ATTACH DATABASE ?1 AS nebula_export KEY ?2")
+        .bind(target)
+        .bind(cipher.map_or("", |c| c.key.as_str()))
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    let result = async {
+        for (pragma, value) in &settings {
+            sqlx::query(&format!("PRAGMA nebula_export.{} = {}", pragma, value))
+                .execute(&mut *conn)
+                .await?;
+        }
+        sqlx::query("SELECT sqlcipher_export('nebula_export', ?1)")
+            .bind(schema)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(&format!("PRAGMA nebula_export.user_version = {}", user_version))
+            .execute(&mut *conn)
+            .await
+    }
+    .await
+    .map_err(|e| e.to_string());
+    let detached = sqlx::query("DETACH DATABASE nebula_export")
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string());
+    result.and(detached).map(|_| ())
+}
+
+/// Change the SQLCipher key of the file at `path`, encrypt a plain file
+/// (`cipher` None), or remove the encryption (`new_key` None). Key changes
+/// go through `PRAGMA rekey`; the other two write a converted copy with
+/// `sqlcipher_export` that then replaces the file.
+pub async fn rekey(path: &str, cipher: Option<&SqliteCipher>, new_key: Option<&str>) -> Result<(), String> {
+    let new_key = new_key.filter(|key| !key.is_empty());
+    if cipher.is_none() && new_key.is_none() {
+        return Err("The database is not encrypted and no new key was given".to_string());
+    }
+    if !Path::new(path).is_file() {
+        return Err(format!("'{}' does not exist", path));
+    }
+
+    let options = SqliteOptions { cipher: cipher.cloned(), ..Default::default() };
+    let connect = connect::apply_options(SqliteConnectOptions::new().filename(path), &options)?;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(connect)
+        .await
+        .map_err(connect::cipher_error)?;
+
+    let copy = format!("{}.rekey", path);
+    let result = async {
+        connect::check_cipher(&pool).await?;
+        match (cipher, new_key) {
+            (Some(_), Some(key)) => {
+                sqlx::query(&format!("PRAGMA rekey = {}", LITE.quote_literal(key)))
+                    .execute(&pool)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(false)
+            }
+            (_, key) => {
+                if Path::new(&copy).exists() {
+                    std::fs::remove_file(&copy).map_err(|e| e.to_string())?;
+                }
+                let cipher = key.map(|key| SqliteCipher {
+                    key: key.to_string(),
+                    kdf_iter: None,
+                    page_size: None,
+                    compatibility: None,
+                });
+                export_copy(&pool, "main", &copy, cipher.as_ref()).await?;
+                Ok(true)
+            }
+        }
+    }
+    .await;
+    // Closing the last connection also checkpoints and removes a WAL file
+    pool.close().await;
+
+    match result {
+        Ok(true) => std::fs::rename(&copy, path).map_err(|e| {
+            let _ = std::fs::remove_file(&copy);
+            e.to_string()
+        }),
+        Ok(false) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&copy);
+            Err(e)
+        }
+    }
+}
@@ -1,24 +1,82 @@
 // Database module - modular structure for database operations
 //
 // This module is organized as follows:
-// - types.rs: Common types (AppState, QueryResult)
+// - types.rs: Common types (AppState, QueryResult, catalog metadata)
+// - connect.rs: Pool setup for connect options (SQLite pragmas, ATTACH, extensions,
+//   SQLCipher keys)
+// - dialect.rs: Per-engine identifier and literal quoting
 // - postgres.rs: PostgreSQL query execution and type conversion
 // - mysql.rs: MySQL query execution and type conversion
 // - sqlite.rs: SQLite query execution and type conversion
+// - introspection/: Catalog queries (indexes, constraints, foreign keys, views,
+//   routines, triggers, sequences, Postgres types and extensions) per engine
+// - ddl/: CREATE statement generation for existing objects
+// - alter.rs: ALTER TABLE planning for the table designer
+// - schema_cache.rs: Per-connection catalog snapshot cache
+// - tasks.rs: Cancellation of long-running commands
+// - stream/: Row-by-row streaming of result sets as typed cells
+// - export/: File export writers (CSV, JSON, NDJSON, SQL, Parquet, Arrow IPC,
+//   XLSX, Markdown, HTML)
+// - import/: CSV / JSON / NDJSON import with dialect sniffing and type inference
+// - script/: Streaming execution of .sql files, split statement by statement
+// - dump/: Consistent SQL dumps of a database and their restore
+// - maintenance.rs: SQLite backup, integrity checks, page usage, VACUUM and re-keying
+// - edit.rs: Keyed UPDATE / INSERT / DELETE of rows edited in the result grid
+// - browse.rs: Filtered, sorted and paged table views
+// - references.rs: Navigation along foreign keys from a row
+// - transfer/: Table copies between connections, with column type mapping across engines
+// - schema_diff.rs: Schema comparison and migration scripts
+// - data_diff.rs: Row-by-row table comparison with chunk checksums and sync scripts
+// - generate/: Seeded fake rows for tables, aware of column names and constraints
+// - search.rs: Pattern search across the columns of many tables
+// - keyspace/: Redis key browsing with SCAN and type-aware value editing
 //
 // All Tauri commands are defined here in mod.rs to ensure proper macro expansion.
 // Database-specific query execution logic is delegated to respective modules.
 
 pub mod types;
+pub mod connect;
+pub mod dialect;
 pub mod postgres;
 pub mod mysql;
 pub mod sqlite;
+pub mod introspection;
+pub mod ddl;
+pub mod alter;
+pub mod schema_cache;
+pub mod tasks;
+pub mod stream;
+pub mod export;
+pub mod import;
+pub mod script;
+pub mod dump;
+pub mod maintenance;
+pub mod edit;
+pub mod browse;
+pub mod references;
+pub mod transfer;
+pub mod schema_diff;
+pub mod data_diff;
+pub mod generate;
+pub mod search;
+pub mod keyspace;
 
-use sqlx::{postgres::PgPoolOptions, mysql::MySqlPoolOptions, sqlite::SqlitePoolOptions};
-use tauri::State;
+use sqlx::{postgres::PgPoolOptions, mysql::MySqlPoolOptions};
+use tauri::{AppHandle, State};
 
 // Re-export types for convenience
-pub use types::{AppState, QueryResult};
+pub use types::{
+    AppState, QueryResult, SqlPool, TableInfo, ColumnInfo, IndexInfo, ConstraintInfo, ForeignKeyInfo, ViewInfo,
+    RoutineInfo, TriggerInfo, SequenceInfo, EnumTypeInfo, DomainInfo, ExtensionInfo, ObjectType,
+    TableChangeRequest, TableChangePlan, SchemaSnapshot, ExportOptions, ExportSummary, TableFormat,
+    ImportFormat, CsvDialect, ImportPreview, ImportRequest, ImportSummary, SqlFileRequest, SqlFileSummary,
+    DumpOptions, DumpSummary, SqliteOptions, SqliteCipher, BackupSummary, IntegrityReport, ForeignKeyViolation, StorageReport, OptimizeSummary,
+    RowChanges, RowChangeSummary, BrowseRequest, BrowseResult,
+    ReferenceRequest, ReferencedRows, ReferencingTable, TransferRequest, TransferMode, TransferSummary,
+    SchemaDiff, DataDiffRequest, DataDiff, GenerateDataRequest, GenerateDataSummary,
+    SearchRequest, SearchSummary, RedisScanRequest, RedisScanResult, RedisValueRequest, RedisValue, RedisEdit,
+    RedisEditResult,
+};
 
 // ============================================================================
 // Connection Commands
@@ -36,6 +94,7 @@ pub async fn connect_postgres(
         .await
         .map_err(|e| e.to_string())?;
 
+    state.schema_cache.invalidate(&connection_id)?;
     let mut pools = state.pg_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
     pools.insert(connection_id, pool);
 
@@ -54,24 +113,25 @@ pub async fn connect_mysql(
         .await
         .map_err(|e| e.to_string())?;
 
+    state.schema_cache.invalidate(&connection_id)?;
     let mut pools = state.mysql_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
     pools.insert(connection_id, pool);
 
     Ok("Connected successfully".to_string())
 }
 
+/// Open a SQLite database; `options` attaches further databases, sets
+/// pragmas and loads extensions on every pooled connection
 #[tauri::command]
 pub async fn connect_sqlite(
     state: State<'_, AppState>,
     connection_id: String,
     connection_string: String,
+    options: Option<SqliteOptions>,
 ) -> Result<String, String> {
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(&connection_string)
-        .await
-        .map_err(|e| e.to_string())?;
+    let pool = connect::sqlite_pool(&connection_string, &options.unwrap_or_default()).await?;
 
+    state.schema_cache.invalidate(&connection_id)?;
     let mut pools = state.sqlite_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
     pools.insert(connection_id, pool);
 
@@ -107,37 +167,54 @@ pub async fn execute_query(
     connection_id: String,
     query: String,
     database: Option<String>,
+) -> Result<QueryResult, String> {
+    let result = route_query(&state, &connection_id, &query, database).await;
+    // DDL may have changed the schema, whether or not the script completed.
+    // Invalidating only once it has run keeps a snapshot loaded in the meantime
+    // from being cached as current.
+    if schema_cache::is_schema_change(&query) {
+        state.schema_cache.invalidate(&connection_id)?;
+    }
+    result
+}
+
+/// Run a query on whichever pool holds `connection_id`
+async fn route_query(
+    state: &AppState,
+    connection_id: &str,
+    query: &str,
+    database: Option<String>,
 ) -> Result<QueryResult, String> {
     // Try Postgres first
     let pg_pool = {
         let pools = state.pg_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
-        pools.get(&connection_id).cloned()
+        pools.get(connection_id).cloned()
     };
 
     if let Some(pool) = pg_pool {
         // Postgres handles database selection at connection time, but we might want to support switching if possible
         // For now, we ignore the database parameter for Postgres as it requires a new connection
-        return postgres::execute_postgres_query(&pool, &query).await;
+        return postgres::execute_postgres_query(&pool, query).await;
     }
 
     // Try MySQL
     let mysql_pool = {
         let pools = state.mysql_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
-        pools.get(&connection_id).cloned()
+        pools.get(connection_id).cloned()
     };
 
     if let Some(pool) = mysql_pool {
-        return mysql::execute_mysql_query(&pool, &query, database).await;
+        return mysql::execute_mysql_query(&pool, query, database).await;
     }
 
     // Try SQLite
     let sqlite_pool = {
         let pools = state.sqlite_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
-        pools.get(&connection_id).cloned()
+        pools.get(connection_id).cloned()
     };
 
     if let Some(pool) = sqlite_pool {
-        return sqlite::execute_sqlite_query(&pool, &query).await;
+        return sqlite::execute_sqlite_query(&pool, query).await;
     }
 
     Err("Connection not found".to_string())
@@ -169,3 +246,806 @@ pub async fn execute_redis_command(
 
     Ok(result)
 }
+
+// ============================================================================
+// Introspection Commands
+// ============================================================================
+//
+// `schema` is the Postgres schema, the MySQL database, or the SQLite schema
+// (`main` or an attached alias). When omitted, the connection's current
+// schema / database is used.
+
+/// List schemas (Postgres), databases (MySQL), or `main` and the attached
+/// databases (SQLite)
+#[tauri::command]
+pub async fn list_schemas(state: State<'_, AppState>, connection_id: String) -> Result<Vec<String>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_schemas(&pool).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_schemas(&pool).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_schemas(&pool).await,
+    }
+}
+
+#[tauri::command]
+pub async fn list_tables(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<Vec<TableInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_tables(&pool, schema.as_deref()).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_tables(&pool, schema.as_deref()).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_tables(&pool, schema.as_deref()).await,
+    }
+}
+
+#[tauri::command]
+pub async fn list_columns(
+    state: State<'_, AppState>,
+    connection_id: String,
+    table: String,
+    schema: Option<String>,
+) -> Result<Vec<ColumnInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_columns(&pool, schema.as_deref(), &table).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_columns(&pool, schema.as_deref(), &table).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_columns(&pool, schema.as_deref(), &table).await,
+    }
+}
+
+#[tauri::command]
+pub async fn list_indexes(
+    state: State<'_, AppState>,
+    connection_id: String,
+    table: String,
+    schema: Option<String>,
+) -> Result<Vec<IndexInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_indexes(&pool, schema.as_deref(), &table).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_indexes(&pool, schema.as_deref(), &table).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_indexes(&pool, schema.as_deref(), &table).await,
+    }
+}
+
+#[tauri::command]
+pub async fn list_constraints(
+    state: State<'_, AppState>,
+    connection_id: String,
+    table: String,
+    schema: Option<String>,
+) -> Result<Vec<ConstraintInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_constraints(&pool, schema.as_deref(), &table).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_constraints(&pool, schema.as_deref(), &table).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_constraints(&pool, schema.as_deref(), &table).await,
+    }
+}
+
+#[tauri::command]
+pub async fn list_foreign_keys(
+    state: State<'_, AppState>,
+    connection_id: String,
+    table: String,
+    schema: Option<String>,
+) -> Result<Vec<ForeignKeyInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_foreign_keys(&pool, schema.as_deref(), &table).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_foreign_keys(&pool, schema.as_deref(), &table).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_foreign_keys(&pool, schema.as_deref(), &table).await,
+    }
+}
+
+#[tauri::command]
+pub async fn list_views(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<Vec<ViewInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_views(&pool, schema.as_deref()).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_views(&pool, schema.as_deref()).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_views(&pool, schema.as_deref()).await,
+    }
+}
+
+/// SQLite has no stored routines, so this is always empty there
+#[tauri::command]
+pub async fn list_routines(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<Vec<RoutineInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_routines(&pool, schema.as_deref()).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_routines(&pool, schema.as_deref()).await,
+        SqlPool::Sqlite(_) => Ok(Vec::new()),
+    }
+}
+
+#[tauri::command]
+pub async fn list_triggers(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+    table: Option<String>,
+) -> Result<Vec<TriggerInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_triggers(&pool, schema.as_deref(), table.as_deref()).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_triggers(&pool, schema.as_deref(), table.as_deref()).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_triggers(&pool, schema.as_deref(), table.as_deref()).await,
+    }
+}
+
+/// Postgres sequences; AUTO_INCREMENT / AUTOINCREMENT counters on MySQL and SQLite
+#[tauri::command]
+pub async fn list_sequences(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<Vec<SequenceInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_sequences(&pool, schema.as_deref()).await,
+        SqlPool::MySql(pool) => introspection::mysql::list_sequences(&pool, schema.as_deref()).await,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_sequences(&pool, schema.as_deref()).await,
+    }
+}
+
+// Enums, domains and extensions are Postgres-only objects; other engines
+// report an empty list so the sidebar can query every connection the same way.
+
+#[tauri::command]
+pub async fn list_enums(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<Vec<EnumTypeInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_enums(&pool, schema.as_deref()).await,
+        _ => Ok(Vec::new()),
+    }
+}
+
+#[tauri::command]
+pub async fn list_domains(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<Vec<DomainInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_domains(&pool, schema.as_deref()).await,
+        _ => Ok(Vec::new()),
+    }
+}
+
+#[tauri::command]
+pub async fn list_extensions(
+    state: State<'_, AppState>,
+    connection_id: String,
+) -> Result<Vec<ExtensionInfo>, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => introspection::postgres::list_extensions(&pool).await,
+        _ => Ok(Vec::new()),
+    }
+}
+
+// ============================================================================
+// DDL Commands
+// ============================================================================
+
+/// Script an existing object as CREATE statement(s), e.g. for "Copy DDL"
+#[tauri::command]
+pub async fn generate_ddl(
+    state: State<'_, AppState>,
+    connection_id: String,
+    object_type: ObjectType,
+    name: String,
+    schema: Option<String>,
+) -> Result<String, String> {
+    match state.sql_pool(&connection_id)? {
+        SqlPool::Postgres(pool) => ddl::postgres::generate_ddl(&pool, object_type, schema.as_deref(), &name).await,
+        SqlPool::MySql(pool) => ddl::mysql::generate_ddl(&pool, object_type, schema.as_deref(), &name).await,
+        SqlPool::Sqlite(pool) => ddl::sqlite::generate_ddl(&pool, object_type, schema.as_deref(), &name).await,
+    }
+}
+
+/// Turn the table designer's original and edited state into ALTER statements,
+/// optionally applying them
+#[tauri::command]
+pub async fn plan_table_changes(
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: TableChangeRequest,
+    apply: bool,
+) -> Result<TableChangePlan, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let schema = request.schema.as_deref();
+    let mut context = alter::PlanContext::default();
+
+    if request.original.is_some() {
+        match &pool {
+            SqlPool::Postgres(pool) => {
+                context.primary_key_name = introspection::postgres::list_constraints(pool, schema, &request.table)
+                    .await?
+                    .into_iter()
+                    .find(|c| c.constraint_type == "PRIMARY KEY")
+                    .map(|c| c.name);
+            }
+            SqlPool::Sqlite(pool) => {
+                context.triggers = introspection::sqlite::list_triggers(pool, schema, Some(&request.table))
+                    .await?
+                    .into_iter()
+                    .filter_map(|t| t.definition)
+                    .collect();
+                context.constraints = introspection::sqlite::list_constraints(pool, schema, &request.table)
+                    .await?
+                    .into_iter()
+                    .filter(|c| c.constraint_type == "UNIQUE" || c.constraint_type == "CHECK")
+                    .collect();
+                context.generated_columns = introspection::sqlite::list_columns(pool, schema, &request.table)
+                    .await?
+                    .into_iter()
+                    .filter(|c| c.generated.is_some())
+                    .map(|c| c.name)
+                    .collect();
+                context.foreign_keys = sqlx::query_scalar::<_, i64>("PRAGMA foreign_keys")
+                    .fetch_one(pool)
+                    .await
+                    .map_err(|e| e.to_string())?
+                    != 0;
+            }
+            SqlPool::MySql(_) => {}
+        }
+    }
+
+    let mut plan = alter::plan(pool.dialect(), &request, &context)?;
+    if apply {
+        match &pool {
+            SqlPool::Postgres(pool) => alter::apply_postgres(pool, &plan).await?,
+            SqlPool::MySql(pool) => alter::apply_mysql(pool, &plan).await?,
+            SqlPool::Sqlite(pool) => alter::apply_sqlite(pool, &plan).await?,
+        }
+        plan.applied = true;
+        state.schema_cache.invalidate(&connection_id)?;
+    }
+    Ok(plan)
+}
+
+// ============================================================================
+// Schema Cache Commands
+// ============================================================================
+
+/// Tables, columns, foreign keys and routine names of a schema, served from the
+/// cache when possible. `refresh` forces a reload from the catalog.
+#[tauri::command]
+pub async fn get_schema_snapshot(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+    refresh: Option<bool>,
+) -> Result<SchemaSnapshot, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    if !refresh.unwrap_or(false) {
+        if let Some(snapshot) = state.schema_cache.get(&connection_id, schema.as_deref())? {
+            return Ok(snapshot);
+        }
+    }
+
+    let generation = state.schema_cache.generation(&connection_id)?;
+    let snapshot = schema_cache::load_snapshot(&pool, schema.as_deref()).await?;
+    state.schema_cache.insert(&connection_id, generation, snapshot.clone())?;
+    Ok(snapshot)
+}
+
+/// Drop all cached metadata of a connection, e.g. after DDL run outside the app
+#[tauri::command]
+pub async fn invalidate_schema_cache(state: State<'_, AppState>, connection_id: String) -> Result<(), String> {
+    state.schema_cache.invalidate(&connection_id)
+}
+
+// ============================================================================
+// Export Commands
+// ============================================================================
+
+/// Stream the rows of `query` into a file at `path`.
+///
+/// Progress is reported through `export-progress` events tagged with `task_id`,
+/// which can also be passed to `cancel_task`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_query(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    query: String,
+    path: String,
+    options: ExportOptions,
+    task_id: String,
+    database: Option<String>,
+) -> Result<ExportSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = export::export_query(&app, &pool, &query, database.as_deref(), &path, &options, &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    result
+}
+
+/// Render rows already shown in the grid as a Markdown or HTML table for pasting
+#[tauri::command]
+pub async fn render_table(
+    columns: Vec<String>,
+    rows: Vec<Vec<serde_json::Value>>,
+    format: TableFormat,
+) -> Result<String, String> {
+    Ok(export::table::render_rows(format, &columns, &rows))
+}
+
+// ============================================================================
+// Import Commands
+// ============================================================================
+
+/// Detect the layout of a CSV / JSON file and preview its first rows with
+/// inferred column types. `format` and `csv` override detection.
+#[tauri::command]
+pub async fn preview_import(
+    state: State<'_, AppState>,
+    connection_id: String,
+    path: String,
+    format: Option<ImportFormat>,
+    csv: Option<CsvDialect>,
+    limit: Option<usize>,
+) -> Result<ImportPreview, String> {
+    let dialect = state.sql_pool(&connection_id)?.dialect();
+    import::preview(&path, format, csv, dialect, limit.unwrap_or(100))
+}
+
+/// Import a CSV / JSON file into a table through a column mapping.
+///
+/// Progress is reported through `import-progress` events tagged with `task_id`.
+/// Rows the database refuses are reported in the summary instead of aborting.
+#[tauri::command]
+pub async fn import_file(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: ImportRequest,
+    task_id: String,
+) -> Result<ImportSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = import::import_file(&app, &pool, &request, &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    if request.create_table {
+        state.schema_cache.invalidate(&connection_id)?;
+    }
+    result
+}
+
+// ============================================================================
+// Script Commands
+// ============================================================================
+
+/// Run the statements of a .sql file one after another on a single connection,
+/// without loading the file into memory.
+///
+/// Progress is reported through `sql-file-progress` events tagged with `task_id`.
+/// With `onError: "continue"`, failed statements are logged in the summary and
+/// the script carries on.
+#[tauri::command]
+pub async fn execute_sql_file(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: SqlFileRequest,
+    task_id: String,
+    database: Option<String>,
+) -> Result<SqlFileSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = script::execute_file(&app, &pool, &request, database.as_deref(), &task_id, &cancel, false).await;
+    state.tasks.finish(&task_id);
+    // Scripts usually create or alter objects, and may have stopped half-way
+    state.schema_cache.invalidate(&connection_id)?;
+    result
+}
+
+// ============================================================================
+// Dump Commands
+// ============================================================================
+
+/// Write a SQL dump of a database (every user schema on Postgres unless
+/// `options.schema` names one) from a single snapshot transaction.
+///
+/// Progress is reported through `dump-progress` events tagged with `task_id`.
+#[tauri::command]
+pub async fn dump_database(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    options: DumpOptions,
+    task_id: String,
+) -> Result<DumpSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = dump::dump_database(&app, &pool, &options, &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    result
+}
+
+/// Replay a dump written by `dump_database` on a single connection.
+///
+/// Progress is reported through `sql-file-progress` events tagged with `task_id`,
+/// as for `execute_sql_file`.
+#[tauri::command]
+pub async fn restore_dump(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: SqlFileRequest,
+    task_id: String,
+    database: Option<String>,
+) -> Result<SqlFileSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = dump::restore_dump(&app, &pool, &request, database.as_deref(), &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    state.schema_cache.invalidate(&connection_id)?;
+    result
+}
+
+// ============================================================================
+// SQLite Maintenance Commands
+// ============================================================================
+
+/// Copy a SQLite database (or an attached `schema`) to `path` while it stays
+/// in use, with `VACUUM INTO`. `cipher` encrypts the copy; it is required to
+/// back up a SQLCipher database.
+#[tauri::command]
+pub async fn backup_sqlite(
+    state: State<'_, AppState>,
+    connection_id: String,
+    path: String,
+    schema: Option<String>,
+    overwrite: Option<bool>,
+    cipher: Option<SqliteCipher>,
+) -> Result<BackupSummary, String> {
+    let pool = state.sqlite_pool(&connection_id)?;
+    maintenance::backup(&pool, schema.as_deref(), &path, overwrite.unwrap_or(false), cipher.as_ref()).await
+}
+
+/// Run `PRAGMA integrity_check`, or `quick_check` when `quick` is set,
+/// stopping after `max_errors` issues (100 by default)
+#[tauri::command]
+pub async fn sqlite_integrity_check(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+    quick: Option<bool>,
+    max_errors: Option<u32>,
+) -> Result<IntegrityReport, String> {
+    let pool = state.sqlite_pool(&connection_id)?;
+    maintenance::integrity_check(&pool, schema.as_deref(), quick.unwrap_or(false), max_errors.unwrap_or(100)).await
+}
+
+/// List rows whose foreign keys have no parent row, in one table or all of them
+#[tauri::command]
+pub async fn sqlite_foreign_key_check(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+    table: Option<String>,
+) -> Result<Vec<ForeignKeyViolation>, String> {
+    let pool = state.sqlite_pool(&connection_id)?;
+    maintenance::foreign_key_check(&pool, schema.as_deref(), table.as_deref()).await
+}
+
+/// Pages and bytes used by each table and index
+#[tauri::command]
+pub async fn sqlite_storage_usage(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<StorageReport, String> {
+    let pool = state.sqlite_pool(&connection_id)?;
+    maintenance::storage_usage(&pool, schema.as_deref()).await
+}
+
+/// Refresh planner statistics and / or rebuild the file, reporting its size
+/// before and after. Both steps run unless turned off.
+#[tauri::command]
+pub async fn optimize_sqlite(
+    state: State<'_, AppState>,
+    connection_id: String,
+    schema: Option<String>,
+    analyze: Option<bool>,
+    vacuum: Option<bool>,
+) -> Result<OptimizeSummary, String> {
+    let pool = state.sqlite_pool(&connection_id)?;
+    maintenance::optimize(&pool, schema.as_deref(), analyze.unwrap_or(true), vacuum.unwrap_or(true)).await
+}
+
+/// Change the SQLCipher key of a database file, encrypt a plain file (no
+/// `cipher`), or remove its encryption (no `new_key`). The file must not be
+/// open in any connection.
+#[tauri::command]
+pub async fn rekey_sqlite(
+    state: State<'_, AppState>,
+    path: String,
+    cipher: Option<SqliteCipher>,
+    new_key: Option<String>,
+) -> Result<(), String> {
+    let file = std::fs::canonicalize(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let in_use = {
+        let pools = state.sqlite_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        pools
+            .values()
+            .any(|pool| std::fs::canonicalize(pool.connect_options().get_filename()).is_ok_and(|open| open == file))
+    };
+    if in_use {
+        return Err("Disconnect from the database before changing its key".to_string());
+    }
+    maintenance::rekey(&path, cipher.as_ref(), new_key.as_deref()).await
+}
+
+// ============================================================================
+// Row Edit Commands
+// ============================================================================
+
+/// Save the edits, inserts and deletes made in the result grid in one
+/// transaction. Rows are matched by key and original values; if any of them
+/// changed in the meantime nothing is saved.
+#[tauri::command]
+pub async fn apply_row_changes(
+    state: State<'_, AppState>,
+    connection_id: String,
+    changes: RowChanges,
+) -> Result<RowChangeSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    edit::apply_row_changes(&pool, &changes).await
+}
+
+// ============================================================================
+// Table Browsing Commands
+// ============================================================================
+
+/// A page of a table's rows, filtered and sorted on the server
+#[tauri::command]
+pub async fn browse_table(
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: BrowseRequest,
+) -> Result<BrowseResult, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    browse::browse_table(&pool, &request).await
+}
+
+// ============================================================================
+// Foreign Key Navigation Commands
+// ============================================================================
+
+/// The row(s) a foreign key column of `request.row` points to
+#[tauri::command]
+pub async fn follow_reference(
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: ReferenceRequest,
+) -> Result<ReferencedRows, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    references::follow_reference(&pool, &request).await
+}
+
+/// Foreign keys referencing `table`, each with the number of rows that
+/// reference `row`. The row must include the referenced key columns.
+#[tauri::command]
+pub async fn referencing_rows(
+    state: State<'_, AppState>,
+    connection_id: String,
+    table: String,
+    row: serde_json::Map<String, serde_json::Value>,
+    schema: Option<String>,
+) -> Result<Vec<ReferencingTable>, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    references::referencing_rows(&pool, schema.as_deref(), &table, &row).await
+}
+
+// ============================================================================
+// Table Transfer Commands
+// ============================================================================
+
+/// Copy a table's rows into a table on another (or the same) connection,
+/// possibly of another engine, creating the target table if asked to.
+///
+/// Progress is reported through `transfer-progress` events tagged with `task_id`.
+#[tauri::command]
+pub async fn transfer_table(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    source_connection_id: String,
+    target_connection_id: String,
+    request: TransferRequest,
+    task_id: String,
+) -> Result<TransferSummary, String> {
+    let source = state.sql_pool(&source_connection_id)?;
+    let target = state.sql_pool(&target_connection_id)?;
+    let same_connection = source_connection_id == target_connection_id;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = transfer::transfer_table(&app, &source, &target, same_connection, &request, &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    if request.create_table || request.mode == TransferMode::Replace {
+        state.schema_cache.invalidate(&target_connection_id)?;
+    }
+    result
+}
+
+// ============================================================================
+// Schema Diff Commands
+// ============================================================================
+
+/// Compare two schemas of the same engine, possibly on different connections,
+/// and script the statements that turn the target schema into the source one.
+/// Schemas default to each connection's current schema / database.
+#[tauri::command]
+pub async fn diff_schemas(
+    state: State<'_, AppState>,
+    source_connection_id: String,
+    target_connection_id: String,
+    source_schema: Option<String>,
+    target_schema: Option<String>,
+) -> Result<SchemaDiff, String> {
+    let source = state.sql_pool(&source_connection_id)?;
+    let target = state.sql_pool(&target_connection_id)?;
+    schema_diff::diff_schemas(&source, &target, source_schema.as_deref(), target_schema.as_deref()).await
+}
+
+// ============================================================================
+// Data Diff Commands
+// ============================================================================
+
+/// Compare the rows of two tables, on the same or different connections,
+/// matched by key, and script the changes making the right table match the left.
+///
+/// Progress is reported through `data-diff-progress` events tagged with `task_id`.
+#[tauri::command]
+pub async fn diff_table_data(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    left_connection_id: String,
+    right_connection_id: String,
+    request: DataDiffRequest,
+    task_id: String,
+) -> Result<DataDiff, String> {
+    let left = state.sql_pool(&left_connection_id)?;
+    let right = state.sql_pool(&right_connection_id)?;
+    let same_connection = left_connection_id == right_connection_id;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = data_diff::diff_table_data(&app, &left, &right, same_connection, &request, &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    result
+}
+
+// ============================================================================
+// Test Data Commands
+// ============================================================================
+
+/// Fill a table with generated rows. Passing the returned seed back generates
+/// the same values again.
+///
+/// Progress is reported through `generate-progress` events tagged with `task_id`.
+#[tauri::command]
+pub async fn generate_test_data(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: GenerateDataRequest,
+    task_id: String,
+) -> Result<GenerateDataSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = generate::generate_test_data(&app, &pool, &request, &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    result
+}
+
+// ============================================================================
+// Search Commands
+// ============================================================================
+
+/// Look for a value or pattern in the text and numeric columns of a schema's
+/// tables, or of `request.tables`.
+///
+/// Matches arrive as `search-results` events, one per table, and progress as
+/// `search-progress` events, both tagged with `task_id`.
+#[tauri::command]
+pub async fn search_database(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: SearchRequest,
+    task_id: String,
+) -> Result<SearchSummary, String> {
+    let pool = state.sql_pool(&connection_id)?;
+    let cancel = state.tasks.start(&task_id)?;
+    let result = search::search_database(&app, &pool, &request, &task_id, &cancel).await;
+    state.tasks.finish(&task_id);
+    result
+}
+
+// ============================================================================
+// Redis Key Commands
+// ============================================================================
+
+/// A page of a Redis keyspace, with each key's type, TTL and memory use.
+/// Pass the returned cursor back for the next page.
+#[tauri::command]
+pub async fn redis_scan_keys(
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: RedisScanRequest,
+) -> Result<RedisScanResult, String> {
+    let client = state.redis_client(&connection_id)?;
+    keyspace::scan_keys(&client, &request).await
+}
+
+/// A page of a Redis value, read according to its type
+#[tauri::command]
+pub async fn redis_get_value(
+    state: State<'_, AppState>,
+    connection_id: String,
+    request: RedisValueRequest,
+) -> Result<RedisValue, String> {
+    let client = state.redis_client(&connection_id)?;
+    keyspace::values::get_value(&client, &request).await
+}
+
+/// Add, change or remove one field, item, member or entry of a Redis value
+#[tauri::command]
+pub async fn redis_edit_value(
+    state: State<'_, AppState>,
+    connection_id: String,
+    key: String,
+    edit: RedisEdit,
+) -> Result<RedisEditResult, String> {
+    let client = state.redis_client(&connection_id)?;
+    keyspace::values::edit_value(&client, &key, &edit).await
+}
+
+/// Expire a key after `ttl_ms`, or make it persistent when None
+#[tauri::command]
+pub async fn redis_set_ttl(
+    state: State<'_, AppState>,
+    connection_id: String,
+    key: String,
+    ttl_ms: Option<i64>,
+) -> Result<bool, String> {
+    let client = state.redis_client(&connection_id)?;
+    keyspace::values::set_ttl(&client, &key, ttl_ms).await
+}
+
+/// Rename a Redis key, refusing to replace an existing one unless `overwrite`
+#[tauri::command]
+pub async fn redis_rename_key(
+    state: State<'_, AppState>,
+    connection_id: String,
+    key: String,
+    new_key: String,
+    overwrite: bool,
+) -> Result<(), String> {
+    let client = state.redis_client(&connection_id)?;
+    keyspace::values::rename_key(&client, &key, &new_key, overwrite).await
+}
+
+/// Delete Redis keys; returns how many of them existed
+#[tauri::command]
+pub async fn redis_delete_keys(
+    state: State<'_, AppState>,
+    connection_id: String,
+    keys: Vec<String>,
+) -> Result<u64, String> {
+    let client = state.redis_client(&connection_id)?;
+    keyspace::values::delete_keys(&client, &keys).await
+}
+
+/// Ask a running export/import task to stop; returns false if it already finished
+#[tauri::command]
+pub async fn cancel_task(state: State<'_, AppState>, task_id: String) -> Result<bool, String> {
+    state.tasks.cancel(&task_id)
+}
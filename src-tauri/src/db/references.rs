@@ -0,0 +1,96 @@
+// Foreign-key navigation
+//
+// From a row, `follow_reference` opens the row a foreign key column points to
+// and `referencing_rows` counts the rows of other tables pointing at it. Both
+// turn the foreign key into a filter on the other table, which the frontend
+// can reuse to open that table in a browse view.
+
+use serde_json::{Map, Value};
+
+use crate::db::browse;
+use crate::db::introspection;
+use crate::db::types::{
+    BrowseRequest, CountMode, FilterJoin, FilterNode, FilterOperator, Paging, ReferenceRequest, ReferencedRows,
+    ReferencingTable, SqlPool,
+};
+
+/// Filter matching `columns` of the other table to the row's `values`, or
+/// None when one of them is NULL (and so references nothing)
+fn key_filter(columns: &[String], row_columns: &[String], row: &Map<String, Value>) -> Result<Option<FilterNode>, String> {
+    let mut children = Vec::with_capacity(columns.len());
+    for (column, row_column) in columns.iter().zip(row_columns) {
+        let value = row
+            .get(row_column)
+            .ok_or_else(|| format!("The row lacks a value for '{}'", row_column))?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        children.push(FilterNode::Condition {
+            column: column.clone(),
+            operator: FilterOperator::Eq,
+            value: value.clone(),
+        });
+    }
+    Ok(Some(FilterNode::Group { join: FilterJoin::And, children }))
+}
+
+/// Rows referenced by the foreign key of `request.column`
+pub async fn follow_reference(pool: &SqlPool, request: &ReferenceRequest) -> Result<ReferencedRows, String> {
+    let schema = request.schema.as_deref();
+    let foreign_key = introspection::foreign_keys(pool, schema, &request.table)
+        .await?
+        .into_iter()
+        .filter(|fk| fk.columns.contains(&request.column))
+        .find(|fk| request.constraint.as_ref().map_or(true, |name| &fk.name == name))
+        .ok_or_else(|| format!("Column '{}' is not part of a foreign key", request.column))?;
+
+    let filter = key_filter(&foreign_key.referenced_columns, &foreign_key.columns, &request.row)?
+        .ok_or_else(|| format!("{} is NULL, so it references no row", request.column))?;
+    let page = browse::browse_table(
+        pool,
+        &BrowseRequest {
+            schema: foreign_key.referenced_schema.clone(),
+            table: foreign_key.referenced_table.clone(),
+            filter: Some(filter.clone()),
+            sort: Vec::new(),
+            page_size: request.page_size,
+            paging: Paging::default(),
+            count: CountMode::Exact,
+        },
+    )
+    .await?;
+
+    Ok(ReferencedRows {
+        constraint: foreign_key.name,
+        schema: foreign_key.referenced_schema,
+        table: foreign_key.referenced_table,
+        filter,
+        page,
+    })
+}
+
+/// Tables with rows that reference `row` of `table`, with how many per foreign key
+pub async fn referencing_rows(
+    pool: &SqlPool,
+    schema: Option<&str>,
+    table: &str,
+    row: &Map<String, Value>,
+) -> Result<Vec<ReferencingTable>, String> {
+    let mut result = Vec::new();
+    for foreign_key in introspection::referencing_foreign_keys(pool, schema, table).await? {
+        let filter = key_filter(&foreign_key.columns, &foreign_key.referenced_columns, row)?;
+        let row_count = match &filter {
+            Some(filter) => browse::count_rows(pool, foreign_key.schema.as_deref(), &foreign_key.table, filter).await?,
+            None => 0,
+        };
+        result.push(ReferencingTable {
+            constraint: foreign_key.name,
+            schema: foreign_key.schema,
+            table: foreign_key.table,
+            columns: foreign_key.columns,
+            filter,
+            row_count,
+        });
+    }
+    Ok(result)
+}
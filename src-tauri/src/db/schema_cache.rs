@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use futures::stream::{self, StreamExt, TryStreamExt};
+
+use crate::db::introspection;
+use crate::db::types::{SchemaSnapshot, SqlPool, TableSnapshot};
+
+/// Statement keywords after which cached metadata may be stale
+const SCHEMA_CHANGING_KEYWORDS: [&str; 8] = ["CREATE", "ALTER", "DROP", "RENAME", "COMMENT", "ATTACH", "DETACH", "DO"];
+/// Tables looked up at once while loading a snapshot; below the pool size of
+/// 5, so the lookups neither time out waiting for a connection nor starve queries
+const SNAPSHOT_CONCURRENCY: usize = 4;
+
+#[derive(Default)]
+struct CacheEntries {
+    /// Snapshots keyed by (connection id, schema); the default schema uses ""
+    snapshots: HashMap<(String, String), SchemaSnapshot>,
+    /// Bumped on every invalidation so loads that raced with DDL are discarded
+    generations: HashMap<String, u64>,
+}
+
+/// Per-connection cache of catalog metadata, filled lazily by `get_schema_snapshot`
+#[derive(Default)]
+pub struct SchemaCache {
+    entries: Mutex<CacheEntries>,
+}
+
+impl SchemaCache {
+    fn key(connection_id: &str, schema: Option<&str>) -> (String, String) {
+        (connection_id.to_string(), schema.unwrap_or_default().to_string())
+    }
+
+    pub fn get(&self, connection_id: &str, schema: Option<&str>) -> Result<Option<SchemaSnapshot>, String> {
+        let entries = self.entries.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        Ok(entries.snapshots.get(&Self::key(connection_id, schema)).cloned())
+    }
+
+    /// Current generation of a connection, to be passed back to `insert`
+    pub fn generation(&self, connection_id: &str) -> Result<u64, String> {
+        let entries = self.entries.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        Ok(entries.generations.get(connection_id).copied().unwrap_or(0))
+    }
+
+    /// Store a snapshot unless the connection was invalidated since `generation` was read
+    pub fn insert(&self, connection_id: &str, generation: u64, snapshot: SchemaSnapshot) -> Result<(), String> {
+        let mut entries = self.entries.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        if entries.generations.get(connection_id).copied().unwrap_or(0) == generation {
+            let key = Self::key(connection_id, snapshot.schema.as_deref());
+            entries.snapshots.insert(key, snapshot);
+        }
+        Ok(())
+    }
+
+    /// Drop every cached schema of a connection
+    pub fn invalidate(&self, connection_id: &str) -> Result<(), String> {
+        let mut entries = self.entries.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        entries.snapshots.retain(|(id, _), _| id != connection_id);
+        *entries.generations.entry(connection_id.to_string()).or_insert(0) += 1;
+        Ok(())
+    }
+}
+
+/// Whether a script contains a statement that can change the schema.
+///
+/// Statements are split naively on `;`, so a semicolon inside a string may
+/// produce a false positive; that only costs an extra cache refresh.
+pub fn is_schema_change(sql: &str) -> bool {
+    sql.split(';').any(|statement| {
+        let keyword: String = skip_comments(statement)
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        SCHEMA_CHANGING_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(&keyword))
+    })
+}
+
+/// Strip leading whitespace and comments from a statement
+fn skip_comments(mut sql: &str) -> &str {
+    loop {
+        sql = sql.trim_start();
+        if let Some(rest) = sql.strip_prefix("--") {
+            sql = rest.split_once('\n').map(|(_, rest)| rest).unwrap_or("");
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.split_once("*/").map(|(_, rest)| rest).unwrap_or("");
+        } else {
+            return sql;
+        }
+    }
+}
+
+/// Read a schema's tables, columns, foreign keys and routine names from the catalog
+pub async fn load_snapshot(pool: &SqlPool, schema: Option<&str>) -> Result<SchemaSnapshot, String> {
+    let (tables, routines) = match pool {
+        SqlPool::Postgres(pool) => (
+            introspection::postgres::list_tables(pool, schema).await?,
+            introspection::postgres::list_routines(pool, schema).await?,
+        ),
+        SqlPool::MySql(pool) => (
+            introspection::mysql::list_tables(pool, schema).await?,
+            introspection::mysql::list_routines(pool, schema).await?,
+        ),
+        SqlPool::Sqlite(pool) => (introspection::sqlite::list_tables(pool, schema).await?, Vec::new()),
+    };
+
+    // Per-table lookups run concurrently, in order, a few at a time
+    let tables: Vec<TableSnapshot> = stream::iter(tables)
+        .map(|table| async move {
+            let (columns, foreign_keys) = match pool {
+                SqlPool::Postgres(pool) => (
+                    introspection::postgres::list_columns(pool, schema, &table.name).await?,
+                    introspection::postgres::list_foreign_keys(pool, schema, &table.name).await?,
+                ),
+                SqlPool::MySql(pool) => (
+                    introspection::mysql::list_columns(pool, schema, &table.name).await?,
+                    introspection::mysql::list_foreign_keys(pool, schema, &table.name).await?,
+                ),
+                SqlPool::Sqlite(pool) => (
+                    introspection::sqlite::list_columns(pool, schema, &table.name).await?,
+                    introspection::sqlite::list_foreign_keys(pool, schema, &table.name).await?,
+                ),
+            };
+            Ok::<_, String>(TableSnapshot {
+                name: table.name,
+                table_type: table.table_type,
+                comment: table.comment,
+                columns,
+                foreign_keys,
+            })
+        })
+        .buffered(SNAPSHOT_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let mut routines: Vec<String> = routines.into_iter().map(|r| r.name).collect();
+    routines.dedup();
+
+    Ok(SchemaSnapshot {
+        schema: schema.map(str::to_string),
+        tables,
+        routines,
+        loaded_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default(),
+    })
+}
@@ -0,0 +1,1067 @@
+// Schema comparison
+//
+// `diff_schemas` reads the tables, views and routines of two schemas of the
+// same engine, reports what differs and plans the statements turning the
+// target schema into the source schema. Objects are matched by name. Indexes,
+// constraints, views and routines are compared through the SQL creating them,
+// with the source schema's name in that SQL replaced by the target's, so that
+// two schemas of one database compare equal. SQLite cannot alter most of a
+// table in place, so changed SQLite tables are rebuilt from the source's
+// CREATE TABLE statement.
+
+use futures::future::try_join_all;
+use sqlx::Row;
+
+use crate::db::ddl;
+use crate::db::dialect::Dialect;
+use crate::db::dump;
+use crate::db::dump::mysql::strip_definer;
+use crate::db::introspection;
+use crate::db::types::{
+    ColumnDiff, ColumnInfo, DiffChange, ForeignKeyInfo, MigrationStatement, ObjectDiff, ObjectType, SchemaDiff,
+    SqlPool, TableDiff,
+};
+
+/// An index, constraint, view or routine
+#[derive(Debug, Clone)]
+struct Definition {
+    name: String,
+    kind: String,
+    /// The SQL compared between the two sides
+    sql: String,
+    /// Statement creating the object in the target schema; None when it is
+    /// part of CREATE TABLE (SQLite constraints) or cannot be scripted
+    create: Option<String>,
+    /// Statement dropping the object from the schema it was read from
+    drop: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Column {
+    info: ColumnInfo,
+    /// The column as written in CREATE TABLE / ADD COLUMN
+    sql: String,
+}
+
+#[derive(Debug, Clone)]
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+    indexes: Vec<Definition>,
+    /// Constraints, foreign keys included
+    constraints: Vec<Definition>,
+    /// Statements creating the table in the target schema with its columns
+    /// and constraints; indexes and (except on SQLite) foreign keys follow
+    create: Vec<String>,
+    /// SQLite: the CREATE TABLE statement from the parenthesis on
+    body: Option<String>,
+    /// SQLite: tables its foreign keys point to
+    references: Vec<String>,
+}
+
+struct Schema {
+    tables: Vec<Table>,
+    views: Vec<Definition>,
+    routines: Vec<Definition>,
+}
+
+const FOREIGN_KEY: &str = "FOREIGN KEY";
+
+/// The schema `schema` stands for: the connection's current one when None
+async fn schema_name(pool: &SqlPool, schema: Option<&str>) -> Result<Option<String>, String> {
+    if let Some(schema) = schema {
+        return Ok(Some(schema.to_string()));
+    }
+    match pool {
+        SqlPool::Postgres(pool) => sqlx::query_scalar("SELECT current_schema()::text").fetch_one(pool).await.map(Some),
+        SqlPool::MySql(pool) => sqlx::query_scalar("SELECT DATABASE()").fetch_one(pool).await,
+        SqlPool::Sqlite(_) => Ok(None),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Replace `from.` where it qualifies a name, i.e. not as the end of a longer identifier
+fn replace_qualifier(sql: &str, from: &str, to: &str) -> String {
+    let pattern = format!("{}.", from);
+    let mut result = String::with_capacity(sql.len());
+    let mut last = 0;
+    for (position, _) in sql.match_indices(&pattern) {
+        let qualifies = sql[..position]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !(c.is_alphanumeric() || matches!(c, '_' | '$' | '"' | '`')));
+        if qualifies && position >= last {
+            result.push_str(&sql[last..position]);
+            result.push_str(to);
+            result.push('.');
+            last = position + pattern.len();
+        }
+    }
+    result.push_str(&sql[last..]);
+    result
+}
+
+/// Name in the first backtick-quoted identifier of a MySQL definition line
+fn backtick_name(line: &str) -> Option<String> {
+    let start = line.find('`')? + 1;
+    let mut name = String::new();
+    let mut chars = line[start..].chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '`' {
+            if chars.peek() == Some(&'`') {
+                chars.next();
+            } else {
+                return Some(name);
+            }
+        }
+        name.push(c);
+    }
+    None
+}
+
+/// Sequence of a `nextval('...'::regclass)` default, as SQL
+fn nextval_sequence(default: &str) -> Option<&str> {
+    let rest = default.strip_prefix("nextval('")?;
+    rest.split_once("'::regclass)").map(|(sequence, _)| sequence)
+}
+
+fn referential_clause(keyword: &str, action: &str) -> String {
+    if action.is_empty() || action.eq_ignore_ascii_case("NO ACTION") {
+        String::new()
+    } else {
+        format!(" ON {} {}", keyword, action)
+    }
+}
+
+/// Reads a schema, writing its statements for the target schema
+struct Reader<'a> {
+    pool: &'a SqlPool,
+    dialect: Dialect,
+    /// Schema read from
+    schema: Option<String>,
+    /// Schema the statements are written for
+    output: Option<String>,
+}
+
+impl Reader<'_> {
+    /// SQL of the read schema rewritten for the output schema
+    fn retarget(&self, sql: &str) -> String {
+        match (&self.schema, &self.output) {
+            (Some(from), Some(to)) if from != to && self.dialect != Dialect::Sqlite => {
+                let quoted = self.dialect.quote_ident(to);
+                let sql = sql.replace(&format!("{}.", self.dialect.quote_ident(from)), &format!("{}.", quoted));
+                // Keep the spelling the server uses, so the output compares equal to what it reads back
+                let plain = to.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                    && to.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '$'));
+                replace_qualifier(&sql, from, if plain { to } else { &quoted })
+            }
+            _ => sql.to_string(),
+        }
+    }
+
+    fn qualified(&self, name: &str) -> String {
+        self.dialect.qualified(self.output.as_deref(), name)
+    }
+
+    async fn read(&self) -> Result<Schema, String> {
+        let schema = self.schema.as_deref();
+        let tables = match self.pool {
+            SqlPool::Postgres(pool) => introspection::postgres::list_tables(pool, schema).await?,
+            SqlPool::MySql(pool) => introspection::mysql::list_tables(pool, schema).await?,
+            SqlPool::Sqlite(pool) => introspection::sqlite::list_tables(pool, schema).await?,
+        };
+        let tables = tables
+            .iter()
+            .filter(|t| t.table_type == "TABLE" || t.table_type == "PARTITIONED TABLE")
+            .map(|t| self.table(&t.name));
+        Ok(Schema {
+            tables: try_join_all(tables).await?,
+            views: self.views().await?,
+            routines: self.routines().await?,
+        })
+    }
+
+    async fn table(&self, name: &str) -> Result<Table, String> {
+        match self.pool {
+            SqlPool::Postgres(pool) => self.postgres_table(pool, name).await,
+            SqlPool::MySql(pool) => self.mysql_table(pool, name).await,
+            SqlPool::Sqlite(pool) => self.sqlite_table(pool, name).await,
+        }
+    }
+
+    /// `CONSTRAINT ... FOREIGN KEY ... REFERENCES ...`; references into the
+    /// read schema point to the output schema
+    fn foreign_key(&self, table: &str, fk: &ForeignKeyInfo) -> Definition {
+        let dialect = self.dialect;
+        let columns = |names: &[String]| names.iter().map(|c| dialect.quote_ident(c)).collect::<Vec<_>>().join(", ");
+        let referenced = if fk.referenced_schema == self.schema {
+            self.qualified(&fk.referenced_table)
+        } else {
+            dialect.qualified(fk.referenced_schema.as_deref(), &fk.referenced_table)
+        };
+        let sql = format!(
+            "CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}){}{}",
+            dialect.quote_ident(&fk.name),
+            columns(&fk.columns),
+            referenced,
+            columns(&fk.referenced_columns),
+            referential_clause("UPDATE", &fk.on_update),
+            referential_clause("DELETE", &fk.on_delete),
+        );
+        let qualified = self.qualified(table);
+        Definition {
+            name: fk.name.clone(),
+            kind: FOREIGN_KEY.to_string(),
+            create: Some(format!("ALTER TABLE {} ADD {};", qualified, sql)),
+            drop: Some(format!("ALTER TABLE {} DROP CONSTRAINT {};", qualified, dialect.quote_ident(&fk.name))),
+            sql,
+        }
+    }
+
+    async fn postgres_table(&self, pool: &sqlx::Pool<sqlx::Postgres>, name: &str) -> Result<Table, String> {
+        let dialect = Dialect::Postgres;
+        let schema = self.schema.as_deref();
+        let qualified = self.qualified(name);
+
+        let columns: Vec<Column> = introspection::postgres::list_columns(pool, schema, name)
+            .await?
+            .into_iter()
+            .map(|mut info| {
+                info.default_value = info.default_value.map(|d| self.retarget(&d));
+                info.generated = info.generated.map(|g| self.retarget(&g));
+                Column { sql: ddl::postgres::column_definition(&info), info }
+            })
+            .collect();
+
+        let constraint_infos = introspection::postgres::list_constraints(pool, schema, name).await?;
+        let mut constraints: Vec<Definition> = constraint_infos
+            .iter()
+            .map(|c| {
+                let sql = format!(
+                    "CONSTRAINT {} {}",
+                    dialect.quote_ident(&c.name),
+                    self.retarget(c.definition.as_deref().unwrap_or_default())
+                );
+                Definition {
+                    name: c.name.clone(),
+                    kind: c.constraint_type.clone(),
+                    create: Some(format!("ALTER TABLE {} ADD {};", qualified, sql)),
+                    drop: Some(format!("ALTER TABLE {} DROP CONSTRAINT {};", qualified, dialect.quote_ident(&c.name))),
+                    sql,
+                }
+            })
+            .collect();
+        for fk in introspection::postgres::list_foreign_keys(pool, schema, name).await? {
+            constraints.push(self.foreign_key(name, &fk));
+        }
+
+        // Indexes backing a constraint come with the constraint
+        let indexes = introspection::postgres::list_indexes(pool, schema, name)
+            .await?
+            .into_iter()
+            .filter(|i| !constraint_infos.iter().any(|c| c.name == i.name))
+            .map(|i| {
+                let sql = format!("{};", self.retarget(i.definition.as_deref().unwrap_or_default()));
+                Definition {
+                    kind: if i.is_unique { "UNIQUE INDEX" } else { "INDEX" }.to_string(),
+                    create: Some(sql.clone()),
+                    drop: Some(format!("DROP INDEX {};", dialect.qualified(self.output.as_deref(), &i.name))),
+                    name: i.name,
+                    sql,
+                }
+            })
+            .collect();
+
+        let mut create = Vec::new();
+        let sequences: Vec<(&str, &str)> = columns
+            .iter()
+            .filter_map(|c| Some((nextval_sequence(c.info.default_value.as_deref()?)?, c.info.name.as_str())))
+            .collect();
+        for (sequence, _) in &sequences {
+            create.push(format!("CREATE SEQUENCE IF NOT EXISTS {};", sequence));
+        }
+        let body: Vec<&str> = columns
+            .iter()
+            .map(|c| c.sql.as_str())
+            .chain(constraints.iter().filter(|c| c.kind != FOREIGN_KEY).map(|c| c.sql.as_str()))
+            .collect();
+        create.push(format!("CREATE TABLE {} (\n    {}\n);", qualified, body.join(",\n    ")));
+        for (sequence, column) in &sequences {
+            create.push(format!("ALTER SEQUENCE {} OWNED BY {}.{};", sequence, qualified, dialect.quote_ident(column)));
+        }
+        for column in &columns {
+            if let Some(comment) = &column.info.comment {
+                create.push(format!(
+                    "COMMENT ON COLUMN {}.{} IS {};",
+                    qualified,
+                    dialect.quote_ident(&column.info.name),
+                    dialect.quote_literal(comment)
+                ));
+            }
+        }
+
+        Ok(Table { name: name.to_string(), columns, indexes, constraints, create, body: None, references: Vec::new() })
+    }
+
+    /// MySQL tables are read from SHOW CREATE TABLE, whose lines are the
+    /// column, key and constraint definitions the server would accept back
+    async fn mysql_table(&self, pool: &sqlx::Pool<sqlx::MySql>, name: &str) -> Result<Table, String> {
+        let dialect = Dialect::MySql;
+        let schema = self.schema.as_deref();
+        let qualified = self.qualified(name);
+        let infos = introspection::mysql::list_columns(pool, schema, name).await?;
+        let show = ddl::mysql::generate_ddl(pool, ObjectType::Table, schema, name).await?;
+
+        let mut columns = Vec::new();
+        let mut indexes = Vec::new();
+        let mut constraints = Vec::new();
+        let mut body = Vec::new();
+        let mut options = String::new();
+        for line in show.lines().skip(1) {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix(')') {
+                // Table options, without the counter that only reflects the data
+                options = rest
+                    .trim_end_matches(';')
+                    .split_whitespace()
+                    .filter(|option| !option.starts_with("AUTO_INCREMENT="))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                break;
+            }
+            let sql = line.trim_end_matches(',').to_string();
+            let alter = |action: String| Some(format!("ALTER TABLE {} {};", qualified, action));
+            if sql.starts_with('`') {
+                let column = backtick_name(&sql).unwrap_or_default();
+                if let Some(info) = infos.iter().find(|c| c.name == column) {
+                    body.push(sql.clone());
+                    columns.push(Column { info: info.clone(), sql });
+                }
+            } else if sql.starts_with("PRIMARY KEY") {
+                body.push(sql.clone());
+                constraints.push(Definition {
+                    name: "PRIMARY".to_string(),
+                    kind: "PRIMARY KEY".to_string(),
+                    create: alter(format!("ADD {}", sql)),
+                    drop: alter("DROP PRIMARY KEY".to_string()),
+                    sql,
+                });
+            } else if sql.starts_with("CONSTRAINT") {
+                let constraint = backtick_name(&sql).unwrap_or_default();
+                let is_foreign_key = sql.contains(" FOREIGN KEY ");
+                if !is_foreign_key {
+                    body.push(sql.clone());
+                }
+                constraints.push(Definition {
+                    kind: if is_foreign_key { FOREIGN_KEY } else { "CHECK" }.to_string(),
+                    create: alter(format!("ADD {}", sql)),
+                    drop: alter(format!(
+                        "DROP {} {}",
+                        if is_foreign_key { "FOREIGN KEY" } else { "CONSTRAINT" },
+                        dialect.quote_ident(&constraint)
+                    )),
+                    name: constraint,
+                    sql,
+                });
+            } else if let Some((kind, _)) = sql.split_once("KEY `") {
+                let index = backtick_name(&sql).unwrap_or_default();
+                indexes.push(Definition {
+                    kind: format!("{}INDEX", kind),
+                    create: alter(format!("ADD {}", sql)),
+                    drop: alter(format!("DROP INDEX {}", dialect.quote_ident(&index))),
+                    name: index,
+                    sql,
+                });
+            }
+        }
+
+        let create = vec![format!("CREATE TABLE {} (\n    {}\n) {};", qualified, body.join(",\n    "), options)];
+        Ok(Table { name: name.to_string(), columns, indexes, constraints, create, body: None, references: Vec::new() })
+    }
+
+    /// SQLite constraints carry no names of their own, so they are named by their SQL
+    async fn sqlite_table(&self, pool: &sqlx::Pool<sqlx::Sqlite>, name: &str) -> Result<Table, String> {
+        let dialect = Dialect::Sqlite;
+        let schema = self.schema.as_deref();
+        let qualified = self.qualified(name);
+        let columns = introspection::sqlite::list_columns(pool, schema, name)
+            .await?
+            .into_iter()
+            .map(|info| {
+                let mut sql = format!("{} {}", dialect.quote_ident(&info.name), info.data_type).trim_end().to_string();
+                if let Some(expr) = &info.generated {
+                    sql.push_str(&format!(" GENERATED ALWAYS AS ({})", expr));
+                }
+                if !info.is_nullable {
+                    sql.push_str(" NOT NULL");
+                }
+                if let Some(default) = &info.default_value {
+                    sql.push_str(&format!(" DEFAULT {}", default));
+                }
+                Column { info, sql }
+            })
+            .collect();
+
+        let list = |columns: &[String]| columns.iter().map(|c| dialect.quote_ident(c)).collect::<Vec<_>>().join(", ");
+        let mut constraints: Vec<Definition> = introspection::sqlite::list_constraints(pool, schema, name)
+            .await?
+            .into_iter()
+            .map(|c| {
+                let sql = match c.constraint_type.as_str() {
+                    "CHECK" => format!("CHECK ({})", c.definition.unwrap_or_default()),
+                    kind => format!("{} ({})", kind, list(&c.columns)),
+                };
+                Definition { name: sql.clone(), kind: c.constraint_type, sql, create: None, drop: None }
+            })
+            .collect();
+        let mut references = Vec::new();
+        for fk in introspection::sqlite::list_foreign_keys(pool, schema, name).await? {
+            references.push(fk.referenced_table.clone());
+            let sql = format!(
+                "FOREIGN KEY ({}) REFERENCES {} ({}){}{}",
+                list(&fk.columns),
+                dialect.quote_ident(&fk.referenced_table),
+                list(&fk.referenced_columns),
+                referential_clause("UPDATE", &fk.on_update),
+                referential_clause("DELETE", &fk.on_delete),
+            );
+            constraints.push(Definition { name: sql.clone(), kind: FOREIGN_KEY.to_string(), sql, create: None, drop: None });
+        }
+
+        // Indexes without SQL back PRIMARY KEY / UNIQUE constraints
+        let indexes = introspection::sqlite::list_indexes(pool, schema, name)
+            .await?
+            .into_iter()
+            .filter_map(|i| {
+                let sql = format!("{};", i.definition?.trim_end());
+                Some(Definition {
+                    kind: if i.is_unique { "UNIQUE INDEX" } else { "INDEX" }.to_string(),
+                    create: Some(sql.clone()),
+                    drop: Some(format!("DROP INDEX {};", self.qualified(&i.name))),
+                    name: i.name,
+                    sql,
+                })
+            })
+            .collect();
+
+        let master = format!("{}.sqlite_master", dialect.quote_ident(schema.unwrap_or("main")));
+        let sql: String = sqlx::query_scalar(&format!("SELECT sql FROM {} WHERE type = 'table' AND name = ?1", master))
+            .bind(name)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        let body = sql
+            .find('(')
+            .map(|open| sql[open..].trim_end().to_string())
+            .ok_or_else(|| format!("Cannot read the definition of table '{}'", name))?;
+
+        Ok(Table {
+            name: name.to_string(),
+            columns,
+            indexes,
+            constraints,
+            create: vec![format!("CREATE TABLE {} {};", qualified, body)],
+            body: Some(body),
+            references,
+        })
+    }
+
+    async fn views(&self) -> Result<Vec<Definition>, String> {
+        let schema = self.schema.as_deref();
+        let views = match self.pool {
+            SqlPool::Postgres(pool) => introspection::postgres::list_views(pool, schema).await?,
+            SqlPool::MySql(pool) => introspection::mysql::list_views(pool, schema).await?,
+            SqlPool::Sqlite(pool) => introspection::sqlite::list_views(pool, schema).await?,
+        };
+        Ok(views
+            .into_iter()
+            .map(|view| {
+                let kind = if view.is_materialized { "MATERIALIZED VIEW" } else { "VIEW" };
+                let sql = self.retarget(view.definition.as_deref().unwrap_or_default().trim().trim_end_matches(';'));
+                let qualified = self.qualified(&view.name);
+                Definition {
+                    name: view.name,
+                    kind: kind.to_string(),
+                    create: Some(format!("CREATE {} {} AS\n{};", kind, qualified, sql)),
+                    drop: Some(format!("DROP {} {};", kind, qualified)),
+                    sql,
+                }
+            })
+            .collect())
+    }
+
+    async fn routines(&self) -> Result<Vec<Definition>, String> {
+        let schema = self.schema.as_deref();
+        match self.pool {
+            SqlPool::Postgres(pool) => {
+                let rows = sqlx::query(
+                    r#"
+                    SELECT
+                        p.proname::text AS name,
+                        p.prokind::text AS kind,
+                        pg_get_function_identity_arguments(p.oid) AS arguments,
+                        CASE WHEN p.prokind <> 'a' THEN pg_get_functiondef(p.oid) END AS definition
+                    FROM pg_proc p
+                    JOIN pg_namespace n ON n.oid = p.pronamespace
+                    WHERE n.nspname = $1
+                      AND NOT EXISTS (
+                          SELECT 1 FROM pg_depend d
+                          WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+                      )
+                    ORDER BY p.proname, pg_get_function_identity_arguments(p.oid)
+                    "#,
+                )
+                .bind(schema)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+
+                rows.iter()
+                    .map(|row| {
+                        let name: String = row.try_get("name").map_err(|e| e.to_string())?;
+                        let kind: String = row.try_get("kind").map_err(|e| e.to_string())?;
+                        let arguments: String = row.try_get("arguments").map_err(|e| e.to_string())?;
+                        let definition: Option<String> = row.try_get("definition").map_err(|e| e.to_string())?;
+                        let kind = match kind.as_str() {
+                            "p" => "PROCEDURE",
+                            "a" => "AGGREGATE",
+                            _ => "FUNCTION",
+                        };
+                        let signature = format!("{}({})", name, arguments);
+                        // pg_get_functiondef does not script aggregates
+                        let sql = definition.map_or_else(|| signature.clone(), |d| self.retarget(d.trim_end()));
+                        Ok(Definition {
+                            create: (kind != "AGGREGATE").then(|| format!("{};", sql)),
+                            drop: Some(format!("DROP {} {}({});", kind, self.qualified(&name), arguments)),
+                            name: signature,
+                            kind: kind.to_string(),
+                            sql,
+                        })
+                    })
+                    .collect()
+            }
+            SqlPool::MySql(pool) => {
+                let mut routines = Vec::new();
+                for routine in introspection::mysql::list_routines(pool, schema).await? {
+                    let object_type = if routine.kind == "PROCEDURE" { ObjectType::Procedure } else { ObjectType::Function };
+                    // The body is NULL without privileges on the routine
+                    let create = ddl::mysql::generate_ddl(pool, object_type, schema, &routine.name)
+                        .await
+                        .ok()
+                        .map(|sql| strip_definer(&sql));
+                    routines.push(Definition {
+                        sql: create.clone().unwrap_or_else(|| routine.body.clone().unwrap_or_default()),
+                        drop: Some(format!("DROP {} {};", routine.kind, self.qualified(&routine.name))),
+                        create,
+                        name: routine.name,
+                        kind: routine.kind,
+                    });
+                }
+                Ok(routines)
+            }
+            SqlPool::Sqlite(_) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Objects paired by name, source order first, keeping only those that differ
+fn pair<'a, T>(
+    source: &'a [T],
+    target: &'a [T],
+    name: impl Fn(&T) -> &str,
+    same: impl Fn(&T, &T) -> bool,
+) -> Vec<(Option<&'a T>, Option<&'a T>)> {
+    let find = |items: &'a [T], wanted: &str| items.iter().find(|item| name(item) == wanted);
+    source
+        .iter()
+        .map(|s| (Some(s), find(target, name(s))))
+        .chain(target.iter().filter(|t| find(source, name(t)).is_none()).map(|t| (None, Some(t))))
+        .filter(|pair| match pair {
+            (Some(s), Some(t)) => !same(s, t),
+            _ => true,
+        })
+        .collect()
+}
+
+fn change<T>(source: Option<T>, target: Option<T>) -> DiffChange {
+    match (source, target) {
+        (Some(_), Some(_)) => DiffChange::Changed,
+        (Some(_), None) => DiffChange::Added,
+        (None, _) => DiffChange::Removed,
+    }
+}
+
+fn definition_pairs<'a>(source: &'a [Definition], target: &'a [Definition]) -> Vec<(Option<&'a Definition>, Option<&'a Definition>)> {
+    pair(source, target, |d| d.name.as_str(), |s, t| s.sql == t.sql)
+}
+
+fn object_diffs(pairs: &[(Option<&Definition>, Option<&Definition>)]) -> Vec<ObjectDiff> {
+    pairs
+        .iter()
+        .map(|(s, t)| ObjectDiff {
+            name: s.or(*t).map(|d| d.name.clone()).unwrap_or_default(),
+            kind: s.or(*t).map(|d| d.kind.clone()).unwrap_or_default(),
+            change: change(*s, *t),
+            source_definition: s.map(|d| d.sql.clone()),
+            target_definition: t.map(|d| d.sql.clone()),
+        })
+        .collect()
+}
+
+fn column_differences(source: &Column, target: &Column) -> Vec<String> {
+    let (s, t) = (&source.info, &target.info);
+    let trimmed = |value: &Option<String>| value.as_deref().map(str::trim).map(str::to_string);
+    let mut differences = Vec::new();
+    if !s.data_type.eq_ignore_ascii_case(&t.data_type) {
+        differences.push("type");
+    }
+    if s.is_nullable != t.is_nullable {
+        differences.push("nullable");
+    }
+    if trimmed(&s.default_value) != trimmed(&t.default_value) {
+        differences.push("default");
+    }
+    if s.is_auto_increment != t.is_auto_increment {
+        differences.push("autoIncrement");
+    }
+    if s.identity != t.identity {
+        differences.push("identity");
+    }
+    if trimmed(&s.generated) != trimmed(&t.generated) {
+        differences.push("generated");
+    }
+    if s.comment != t.comment {
+        differences.push("comment");
+    }
+    if differences.is_empty() && source.sql != target.sql {
+        differences.push("definition");
+    }
+    differences.into_iter().map(str::to_string).collect()
+}
+
+/// A table on both sides, with what differs
+struct TableChanges<'a> {
+    source: &'a Table,
+    target: &'a Table,
+    columns: Vec<(Option<&'a Column>, Option<&'a Column>, Vec<String>)>,
+    indexes: Vec<(Option<&'a Definition>, Option<&'a Definition>)>,
+    constraints: Vec<(Option<&'a Definition>, Option<&'a Definition>)>,
+}
+
+impl<'a> TableChanges<'a> {
+    fn new(source: &'a Table, target: &'a Table) -> Self {
+        let columns = pair(&source.columns, &target.columns, |c| c.info.name.as_str(), |s, t| column_differences(s, t).is_empty())
+            .into_iter()
+            .map(|(s, t)| {
+                let differences = match (s, t) {
+                    (Some(s), Some(t)) => column_differences(s, t),
+                    _ => Vec::new(),
+                };
+                (s, t, differences)
+            })
+            .collect();
+        TableChanges {
+            source,
+            target,
+            columns,
+            indexes: definition_pairs(&source.indexes, &target.indexes),
+            constraints: definition_pairs(&source.constraints, &target.constraints),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.columns.is_empty() && self.indexes.is_empty() && self.constraints.is_empty()
+    }
+
+    /// SQLite can only add plain columns in place
+    fn needs_rebuild(&self) -> bool {
+        !self.constraints.is_empty()
+            || self.columns.iter().any(|(s, t, _)| match (s, t) {
+                (Some(s), None) => {
+                    let default = s.info.default_value.as_deref().unwrap_or_default().trim().to_ascii_uppercase();
+                    (!s.info.is_nullable && default.is_empty())
+                        || s.info.generated.is_some()
+                        || default.starts_with('(')
+                        || default.starts_with("CURRENT_")
+                }
+                _ => true,
+            })
+    }
+
+    fn report(&self) -> TableDiff {
+        TableDiff {
+            name: self.source.name.clone(),
+            change: DiffChange::Changed,
+            columns: self
+                .columns
+                .iter()
+                .map(|(s, t, differences)| ColumnDiff {
+                    name: s.or(*t).map(|c| c.info.name.clone()).unwrap_or_default(),
+                    change: change(*s, *t),
+                    source: s.map(|c| c.info.clone()),
+                    target: t.map(|c| c.info.clone()),
+                    differences: differences.clone(),
+                })
+                .collect(),
+            indexes: object_diffs(&self.indexes),
+            constraints: object_diffs(&self.constraints),
+        }
+    }
+}
+
+/// Collects the migration statements for the target schema
+struct Planner {
+    dialect: Dialect,
+    schema: Option<String>,
+    statements: Vec<MigrationStatement>,
+    warnings: Vec<String>,
+}
+
+impl Planner {
+    fn push(&mut self, sql: String) {
+        self.statements.push(MigrationStatement { sql, destructive: false, note: None });
+    }
+
+    fn push_destructive(&mut self, sql: String, note: String) {
+        self.statements.push(MigrationStatement { sql, destructive: true, note: Some(note) });
+    }
+
+    fn qualified(&self, name: &str) -> String {
+        self.dialect.qualified(self.schema.as_deref(), name)
+    }
+
+    fn create(&mut self, definition: &Definition) {
+        match &definition.create {
+            Some(sql) => self.push(sql.clone()),
+            None => self.warnings.push(format!(
+                "{} {} cannot be scripted and has to be created by hand",
+                definition.kind, definition.name
+            )),
+        }
+    }
+
+    fn drop(&mut self, definition: &Definition) {
+        if let Some(sql) = &definition.drop {
+            self.push(sql.clone());
+        }
+    }
+
+    fn postgres_columns(&mut self, changes: &TableChanges) {
+        let dialect = self.dialect;
+        let table = &changes.source.name;
+        let qualified = self.qualified(table);
+        for (_, target, _) in changes.columns.iter().filter(|(s, _, _)| s.is_none()) {
+            let name = &target.map(|c| &c.info.name).cloned().unwrap_or_default();
+            self.push_destructive(
+                format!("ALTER TABLE {} DROP COLUMN {};", qualified, dialect.quote_ident(name)),
+                format!("Drops column {}.{} and its data", table, name),
+            );
+        }
+        for (source, target, differences) in &changes.columns {
+            let (Some(source), Some(target)) = (source, target) else { continue };
+            let (s, t) = (&source.info, &target.info);
+            let column = dialect.quote_ident(&s.name);
+            let alter = |action: String| format!("ALTER TABLE {} ALTER COLUMN {} {};", qualified, column, action);
+            let differs = |what: &str| differences.iter().any(|d| d == what);
+
+            // A generation expression cannot be altered; the column holds no data of its own
+            if differs("generated") {
+                self.push(format!("ALTER TABLE {} DROP COLUMN {};", qualified, column));
+                self.push(format!("ALTER TABLE {} ADD COLUMN {};", qualified, source.sql));
+                continue;
+            }
+            if differs("type") {
+                self.push_destructive(
+                    alter(format!("TYPE {} USING {}::{}", s.data_type, column, s.data_type)),
+                    format!("Changes the type of {}.{} from {} to {}", table, s.name, t.data_type, s.data_type),
+                );
+            }
+            if differs("identity") && s.identity.is_none() {
+                self.push(alter("DROP IDENTITY IF EXISTS".to_string()));
+            }
+            if differs("default") {
+                match &s.default_value {
+                    Some(default) => {
+                        if let Some(sequence) = nextval_sequence(default) {
+                            self.push(format!("CREATE SEQUENCE IF NOT EXISTS {};", sequence));
+                        }
+                        self.push(alter(format!("SET DEFAULT {}", default)));
+                    }
+                    None => self.push(alter("DROP DEFAULT".to_string())),
+                }
+            }
+            if let (true, Some(identity)) = (differs("identity"), &s.identity) {
+                self.push(alter(match t.identity {
+                    Some(_) => format!("SET GENERATED {}", identity),
+                    None => format!("ADD GENERATED {} AS IDENTITY", identity),
+                }));
+            }
+            if differs("nullable") {
+                self.push(alter(if s.is_nullable { "DROP NOT NULL" } else { "SET NOT NULL" }.to_string()));
+            }
+            if differs("comment") {
+                let comment = s.comment.as_deref().map_or_else(|| "NULL".to_string(), |c| dialect.quote_literal(c));
+                self.push(format!("COMMENT ON COLUMN {}.{} IS {};", qualified, column, comment));
+            }
+        }
+        for (source, _, _) in changes.columns.iter().filter(|(_, t, _)| t.is_none()) {
+            let Some(source) = source else { continue };
+            if let Some(sequence) = source.info.default_value.as_deref().and_then(nextval_sequence) {
+                self.push(format!("CREATE SEQUENCE IF NOT EXISTS {};", sequence));
+            }
+            self.push(format!("ALTER TABLE {} ADD COLUMN {};", qualified, source.sql));
+            if let Some(comment) = &source.info.comment {
+                self.push(format!(
+                    "COMMENT ON COLUMN {}.{} IS {};",
+                    qualified,
+                    dialect.quote_ident(&source.info.name),
+                    dialect.quote_literal(comment)
+                ));
+            }
+        }
+    }
+
+    fn mysql_columns(&mut self, changes: &TableChanges) {
+        let table = &changes.source.name;
+        let qualified = self.qualified(table);
+        for (source, target, differences) in &changes.columns {
+            match (source, target) {
+                (None, Some(target)) => self.push_destructive(
+                    format!("ALTER TABLE {} DROP COLUMN {};", qualified, self.dialect.quote_ident(&target.info.name)),
+                    format!("Drops column {}.{} and its data", table, target.info.name),
+                ),
+                (Some(source), Some(target)) => {
+                    let sql = format!("ALTER TABLE {} MODIFY COLUMN {};", qualified, source.sql);
+                    if differences.iter().any(|d| d == "type") {
+                        let note = format!(
+                            "Changes the type of {}.{} from {} to {}",
+                            table, source.info.name, target.info.data_type, source.info.data_type
+                        );
+                        self.push_destructive(sql, note);
+                    } else {
+                        self.push(sql);
+                    }
+                }
+                (Some(source), None) => self.push(format!("ALTER TABLE {} ADD COLUMN {};", qualified, source.sql)),
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// https://www.sqlite.org/lang_altertable.html#otheralter
+    fn sqlite_rebuild(&mut self, changes: &TableChanges) {
+        let dialect = self.dialect;
+        let table = &changes.source.name;
+        let qualified = self.qualified(table);
+        let temp_name = format!("_nebula_new_{}", table);
+        let temp = self.qualified(&temp_name);
+
+        self.push("PRAGMA foreign_keys = OFF;".to_string());
+        self.push("PRAGMA legacy_alter_table = ON;".to_string());
+        self.push(format!("CREATE TABLE {} {};", temp, changes.source.body.as_deref().unwrap_or_default()));
+        let kept: Vec<String> = changes
+            .source
+            .columns
+            .iter()
+            .filter(|s| s.info.generated.is_none())
+            .filter(|s| changes.target.columns.iter().any(|t| t.info.name == s.info.name && t.info.generated.is_none()))
+            .map(|s| dialect.quote_ident(&s.info.name))
+            .collect();
+        if !kept.is_empty() {
+            self.push(format!("INSERT INTO {} ({}) SELECT {} FROM {};", temp, kept.join(", "), kept.join(", "), qualified));
+        }
+
+        let mut losses = Vec::new();
+        for (source, target, differences) in &changes.columns {
+            match (source, target) {
+                (None, Some(target)) => losses.push(format!("drops column {}", target.info.name)),
+                (Some(source), Some(_)) if differences.iter().any(|d| d == "type") => {
+                    losses.push(format!("changes the type of {} to {}", source.info.name, source.info.data_type))
+                }
+                _ => {}
+            }
+        }
+        let drop = format!("DROP TABLE {};", qualified);
+        if losses.is_empty() {
+            self.push(drop);
+        } else {
+            self.push_destructive(drop, format!("Rebuilds table {}: {}", table, losses.join(", ")));
+        }
+        self.push(format!("ALTER TABLE {} RENAME TO {};", temp, dialect.quote_ident(table)));
+        for index in &changes.source.indexes {
+            self.create(index);
+        }
+        self.push("PRAGMA legacy_alter_table = OFF;".to_string());
+        self.push("PRAGMA foreign_keys = ON;".to_string());
+        self.warnings.push(format!(
+            "SQLite cannot alter {} in place; the script rebuilds it (copy, drop and rename), which drops its triggers",
+            table
+        ));
+    }
+}
+
+fn script(statements: &[MigrationStatement]) -> String {
+    statements
+        .iter()
+        .map(|statement| match &statement.note {
+            Some(note) if statement.destructive => format!("-- DESTRUCTIVE: {}\n{}", note, statement.sql),
+            _ => statement.sql.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Compare `source_schema` on `source` with `target_schema` on `target`
+pub async fn diff_schemas(
+    source: &SqlPool,
+    target: &SqlPool,
+    source_schema: Option<&str>,
+    target_schema: Option<&str>,
+) -> Result<SchemaDiff, String> {
+    let dialect = target.dialect();
+    if source.dialect() != dialect {
+        return Err("Schemas can only be compared between connections of the same engine".to_string());
+    }
+    let source_schema = schema_name(source, source_schema).await?;
+    let target_schema = schema_name(target, target_schema).await?;
+    let reader = |pool, schema: &Option<String>| Reader {
+        pool,
+        dialect,
+        schema: schema.clone(),
+        output: target_schema.clone(),
+    };
+    let (source_reader, target_reader) = (reader(source, &source_schema), reader(target, &target_schema));
+    let (source_model, target_model) = futures::try_join!(source_reader.read(), target_reader.read())?;
+
+    let tables = pair(&source_model.tables, &target_model.tables, |t| t.name.as_str(), |_, _| false);
+    let added: Vec<&Table> = tables.iter().filter_map(|(s, t)| s.filter(|_| t.is_none())).collect();
+    let removed: Vec<&Table> = tables.iter().filter_map(|(s, t)| t.filter(|_| s.is_none())).collect();
+    let changed: Vec<TableChanges> = tables
+        .iter()
+        .filter_map(|(s, t)| Some(TableChanges::new((*s)?, (*t)?)))
+        .filter(|changes| !changes.is_empty())
+        .collect();
+    let views = definition_pairs(&source_model.views, &target_model.views);
+    let routines = definition_pairs(&source_model.routines, &target_model.routines);
+
+    let mut planner = Planner { dialect, schema: target_schema.clone(), statements: Vec::new(), warnings: Vec::new() };
+    let rebuilt = |changes: &TableChanges| dialect == Dialect::Sqlite && changes.needs_rebuild();
+
+    // Views and routines may depend on the tables about to change
+    for (_, target) in views.iter().chain(&routines) {
+        if let Some(target) = target {
+            planner.drop(target);
+        }
+    }
+    // Foreign keys first, as they depend on the keys and indexes of other tables
+    // (and those of removed tables, which would block dropping the tables they reference)
+    if dialect != Dialect::Sqlite {
+        for changes in &changed {
+            for target in changes.constraints.iter().filter_map(|(_, t)| *t).filter(|t| t.kind == FOREIGN_KEY) {
+                planner.drop(target);
+            }
+        }
+        for table in &removed {
+            for target in table.constraints.iter().filter(|t| t.kind == FOREIGN_KEY) {
+                planner.drop(target);
+            }
+        }
+    }
+    for changes in changed.iter().filter(|changes| !rebuilt(changes)) {
+        for (_, target) in &changes.indexes {
+            if let Some(target) = target {
+                planner.drop(target);
+            }
+        }
+        for target in changes.constraints.iter().filter_map(|(_, t)| *t).filter(|t| t.kind != FOREIGN_KEY) {
+            planner.drop(target);
+        }
+    }
+    // SQLite keeps its foreign keys, so a referenced table waits for the tables referencing it
+    let names = removed.iter().map(|table| table.name.clone()).collect();
+    let edges: Vec<(String, String)> = removed
+        .iter()
+        .flat_map(|table| table.references.iter().map(|r| (r.clone(), table.name.clone())))
+        .collect();
+    for name in dump::dependency_order(names, &edges) {
+        planner.push_destructive(
+            format!("DROP TABLE {};", planner.qualified(&name)),
+            format!("Drops table {} and all of its rows", name),
+        );
+    }
+
+    for table in &added {
+        for sql in &table.create {
+            planner.push(sql.clone());
+        }
+        for index in &table.indexes {
+            planner.create(index);
+        }
+    }
+    for changes in &changed {
+        match dialect {
+            Dialect::Postgres => planner.postgres_columns(changes),
+            Dialect::MySql => planner.mysql_columns(changes),
+            Dialect::Sqlite if changes.needs_rebuild() => planner.sqlite_rebuild(changes),
+            Dialect::Sqlite => {
+                let qualified = planner.qualified(&changes.source.name);
+                for (source, _, _) in &changes.columns {
+                    if let Some(source) = source {
+                        planner.push(format!("ALTER TABLE {} ADD COLUMN {};", qualified, source.sql));
+                    }
+                }
+            }
+        }
+    }
+    for changes in changed.iter().filter(|changes| !rebuilt(changes)) {
+        for source in changes.constraints.iter().filter_map(|(s, _)| *s).filter(|s| s.kind != FOREIGN_KEY) {
+            planner.create(source);
+        }
+        for (source, _) in &changes.indexes {
+            if let Some(source) = source {
+                planner.create(source);
+            }
+        }
+    }
+    if dialect != Dialect::Sqlite {
+        let added_keys = added.iter().flat_map(|table| table.constraints.iter().filter(|c| c.kind == FOREIGN_KEY));
+        let changed_keys = changed
+            .iter()
+            .flat_map(|changes| changes.constraints.iter().filter_map(|(s, _)| *s))
+            .filter(|c| c.kind == FOREIGN_KEY);
+        for fk in added_keys.chain(changed_keys).collect::<Vec<_>>() {
+            planner.create(fk);
+        }
+    }
+    for (source, _) in routines.iter().chain(&views) {
+        if let Some(source) = source {
+            planner.create(source);
+        }
+    }
+
+    let mut table_diffs: Vec<TableDiff> = tables
+        .iter()
+        .filter(|(s, t)| s.is_none() || t.is_none())
+        .map(|(s, t)| TableDiff {
+            name: s.or(*t).map(|table| table.name.clone()).unwrap_or_default(),
+            change: change(*s, *t),
+            columns: Vec::new(),
+            indexes: Vec::new(),
+            constraints: Vec::new(),
+        })
+        .chain(changed.iter().map(TableChanges::report))
+        .collect();
+    table_diffs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(SchemaDiff {
+        source_schema,
+        target_schema,
+        tables: table_diffs,
+        views: object_diffs(&views),
+        routines: object_diffs(&routines),
+        script: script(&planner.statements),
+        has_destructive_changes: planner.statements.iter().any(|s| s.destructive),
+        statements: planner.statements,
+        warnings: planner.warnings,
+    })
+}
@@ -0,0 +1,314 @@
+// SQL script execution
+//
+// `execute_file` reads a .sql file chunk by chunk, splits it into statements as
+// it goes (see `splitter`) and runs them one at a time on a single connection,
+// so session state (SET, USE, transactions, temporary tables) carries over from
+// one statement to the next. The connection is closed afterwards instead of
+// going back to the pool with that state.
+
+pub mod splitter;
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+use sqlx::pool::PoolConnection;
+use sqlx::{Executor, MySql, PgConnection, Postgres, Sqlite};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::dialect::Dialect;
+use crate::db::export::text::TextEncoding;
+use crate::db::import::reader::DecodingReader;
+use crate::db::script::splitter::{Piece, Splitter};
+use crate::db::tasks::CancelFlag;
+use crate::db::types::{OnError, SqlFileProgress, SqlFileRequest, SqlFileSummary, SqlPool, StatementError};
+
+/// Failed statements returned in the summary; the rest are only counted
+const MAX_ERRORS: usize = 1000;
+/// Characters of a failed statement kept in its error
+const STATEMENT_PREVIEW: usize = 200;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A script file being split into statements
+pub struct ScriptSource {
+    reader: DecodingReader,
+    splitter: Splitter,
+    eof: bool,
+}
+
+impl ScriptSource {
+    pub fn open(path: &Path, encoding: &str, dialect: Dialect) -> Result<ScriptSource, String> {
+        let encoding = TextEncoding::from_label(encoding)?.encoding();
+        Ok(ScriptSource {
+            reader: DecodingReader::open(path, encoding)?,
+            splitter: Splitter::new(dialect),
+            eof: false,
+        })
+    }
+
+    pub fn next_piece(&mut self) -> Result<Option<Piece>, String> {
+        loop {
+            if let Some(piece) = self.splitter.next_piece(self.eof) {
+                return Ok(Some(piece));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            self.eof = !self.splitter.fill(&mut self.reader)?;
+        }
+    }
+
+    /// Skip the rest of a COPY block that is not loaded
+    pub fn skip_copy(&mut self) -> Result<(), String> {
+        while let Some(Piece::CopyData(_)) = self.next_piece()? {}
+        Ok(())
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.reader.bytes_read()
+    }
+}
+
+/// The connection a whole script runs on
+pub enum ScriptConnection {
+    Postgres(PoolConnection<Postgres>),
+    MySql(PoolConnection<MySql>),
+    Sqlite(PoolConnection<Sqlite>),
+}
+
+impl ScriptConnection {
+    /// Take a connection out of the pool; on MySQL, switch to `database` first
+    pub async fn open(pool: &SqlPool, database: Option<&str>) -> Result<ScriptConnection, String> {
+        Ok(match pool {
+            SqlPool::Postgres(pool) => ScriptConnection::Postgres(pool.acquire().await.map_err(|e| e.to_string())?),
+            SqlPool::MySql(pool) => {
+                let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+                if let Some(db) = database {
+                    conn.execute(format!("USE {}", Dialect::MySql.quote_ident(db)).as_str())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+                ScriptConnection::MySql(conn)
+            }
+            SqlPool::Sqlite(pool) => ScriptConnection::Sqlite(pool.acquire().await.map_err(|e| e.to_string())?),
+        })
+    }
+
+    /// Run one statement and return the number of rows it affected
+    pub async fn execute(&mut self, sql: &str) -> Result<u64, sqlx::Error> {
+        let result = match self {
+            ScriptConnection::Postgres(conn) => sqlx::raw_sql(sql).execute(&mut **conn).await?.rows_affected(),
+            ScriptConnection::MySql(conn) => sqlx::raw_sql(sql).execute(&mut **conn).await?.rows_affected(),
+            ScriptConnection::Sqlite(conn) => sqlx::raw_sql(sql).execute(&mut **conn).await?.rows_affected(),
+        };
+        Ok(result)
+    }
+
+    /// Close the connection rather than returning it to the pool
+    pub async fn close(self) {
+        let _ = match self {
+            ScriptConnection::Postgres(conn) => conn.close().await,
+            ScriptConnection::MySql(conn) => conn.close().await,
+            ScriptConnection::Sqlite(conn) => conn.close().await,
+        };
+    }
+}
+
+/// Counters of a running script
+struct ScriptState<'a> {
+    app: &'a AppHandle,
+    task_id: &'a str,
+    total_bytes: u64,
+    summary: SqlFileSummary,
+    line: u64,
+    last_progress: Instant,
+}
+
+impl ScriptState<'_> {
+    fn fail(&mut self, line: u64, sql: &str, message: String) {
+        self.summary.statements_failed += 1;
+        if self.summary.errors.len() < MAX_ERRORS {
+            let mut statement: String = sql.chars().take(STATEMENT_PREVIEW).collect();
+            if statement.len() < sql.len() {
+                statement.push('…');
+            }
+            self.summary.errors.push(StatementError { line, statement, message });
+        }
+    }
+
+    fn emit_progress(&mut self, bytes_read: u64) {
+        self.last_progress = Instant::now();
+        let _ = self.app.emit(
+            "sql-file-progress",
+            SqlFileProgress {
+                task_id: self.task_id.to_string(),
+                statements_executed: self.summary.statements_executed,
+                statements_failed: self.summary.statements_failed,
+                bytes_read,
+                total_bytes: self.total_bytes,
+                line: self.line,
+            },
+        );
+    }
+
+    fn tick(&mut self, bytes_read: u64) {
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            self.emit_progress(bytes_read);
+        }
+    }
+}
+
+fn error_message(e: sqlx::Error) -> String {
+    match e {
+        sqlx::Error::Database(e) => e.message().to_string(),
+        e => e.to_string(),
+    }
+}
+
+/// Feed a COPY block to the server. `Ok(None)` means the script was cancelled
+/// in the middle of the block.
+async fn copy_in(
+    conn: &mut PgConnection,
+    sql: &str,
+    source: &mut ScriptSource,
+    state: &mut ScriptState<'_>,
+    cancel: &CancelFlag,
+) -> Result<Result<Option<u64>, sqlx::Error>, String> {
+    let mut copy = match conn.copy_in_raw(sql).await {
+        Ok(copy) => copy,
+        Err(e) => {
+            source.skip_copy()?;
+            return Ok(Err(e));
+        }
+    };
+    while let Some(Piece::CopyData(data)) = source.next_piece()? {
+        if cancel.is_cancelled() {
+            let _ = copy.abort("Cancelled").await;
+            return Ok(Ok(None));
+        }
+        if let Err(e) = copy.send(data.into_bytes()).await {
+            let _ = copy.abort(e.to_string()).await;
+            source.skip_copy()?;
+            return Ok(Err(e));
+        }
+        state.tick(source.bytes_read());
+    }
+    Ok(copy.finish().await.map(Some))
+}
+
+/// Whether `sql` opens or commits a transaction, as written around a dump
+fn is_transaction_control(sql: &str) -> bool {
+    let words = sql.split_whitespace().collect::<Vec<_>>().join(" ").to_ascii_uppercase();
+    matches!(words.as_str(), "BEGIN" | "BEGIN TRANSACTION" | "START TRANSACTION" | "COMMIT")
+}
+
+/// Run every statement of the script at `request.path`. With `skip_transaction`
+/// the script's own `BEGIN` and `COMMIT` statements are left out, so each
+/// statement commits on its own.
+pub async fn execute_file(
+    app: &AppHandle,
+    pool: &SqlPool,
+    request: &SqlFileRequest,
+    database: Option<&str>,
+    task_id: &str,
+    cancel: &CancelFlag,
+    skip_transaction: bool,
+) -> Result<SqlFileSummary, String> {
+    let start = Instant::now();
+    let path = Path::new(&request.path);
+    let total_bytes = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let mut source = ScriptSource::open(path, &request.encoding, pool.dialect())?;
+    let mut conn = ScriptConnection::open(pool, database).await?;
+    let mut state = ScriptState {
+        app,
+        task_id,
+        total_bytes,
+        summary: SqlFileSummary {
+            statements_executed: 0,
+            statements_failed: 0,
+            rows_affected: 0,
+            errors: Vec::new(),
+            stopped: false,
+            cancelled: false,
+            execution_time_ms: 0,
+        },
+        line: 1,
+        last_progress: Instant::now(),
+    };
+
+    let result = run(&mut conn, &mut source, &mut state, request.on_error, skip_transaction, cancel).await;
+    conn.close().await;
+    result?;
+
+    state.emit_progress(source.bytes_read());
+    state.summary.execution_time_ms = start.elapsed().as_millis();
+    Ok(state.summary)
+}
+
+async fn run(
+    conn: &mut ScriptConnection,
+    source: &mut ScriptSource,
+    state: &mut ScriptState<'_>,
+    on_error: OnError,
+    skip_transaction: bool,
+    cancel: &CancelFlag,
+) -> Result<(), String> {
+    while let Some(piece) = source.next_piece()? {
+        if cancel.is_cancelled() {
+            state.summary.cancelled = true;
+            break;
+        }
+        let (sql, line, result) = match piece {
+            Piece::Statement { sql, line } if skip_transaction && is_transaction_control(&sql) => {
+                state.line = line;
+                continue;
+            }
+            Piece::Statement { sql, line } => {
+                state.line = line;
+                let result = conn.execute(&sql).await;
+                (sql, line, result)
+            }
+            Piece::CopyIn { sql, line } => {
+                state.line = line;
+                let result = match conn {
+                    ScriptConnection::Postgres(conn) => match copy_in(conn, &sql, source, state, cancel).await? {
+                        Ok(None) => {
+                            state.summary.cancelled = true;
+                            break;
+                        }
+                        Ok(Some(rows)) => Ok(rows),
+                        Err(e) => Err(e),
+                    },
+                    ScriptConnection::MySql(_) | ScriptConnection::Sqlite(_) => {
+                        source.skip_copy()?;
+                        Err(sqlx::Error::Protocol("COPY FROM stdin is only supported on PostgreSQL".to_string()))
+                    }
+                };
+                (sql, line, result)
+            }
+            // Data without a COPY statement (the splitter only produces it after one)
+            Piece::CopyData(_) | Piece::CopyEnd => continue,
+        };
+
+        match result {
+            Ok(rows) => {
+                state.summary.statements_executed += 1;
+                state.summary.rows_affected += rows;
+            }
+            // A broken connection fails every remaining statement
+            Err(e @ (sqlx::Error::Io(_) | sqlx::Error::PoolClosed | sqlx::Error::WorkerCrashed)) => {
+                return Err(format!(
+                    "Line {}: {} ({} statements were executed before the error)",
+                    line, e, state.summary.statements_executed
+                ));
+            }
+            Err(e) => {
+                state.fail(line, &sql, error_message(e));
+                if on_error == OnError::Stop {
+                    state.summary.stopped = true;
+                    break;
+                }
+            }
+        }
+        state.tick(source.bytes_read());
+    }
+    Ok(())
+}
@@ -0,0 +1,613 @@
+use crate::db::dialect::Dialect;
+use crate::db::import::reader::DecodingReader;
+
+/// A piece of a SQL script, in file order
+#[derive(Debug, PartialEq)]
+pub enum Piece {
+    /// A statement without its delimiter; `line` is where it starts (1-based)
+    Statement { sql: String, line: u64 },
+    /// `COPY ... FROM stdin`; its data follows as `CopyData` up to `CopyEnd`
+    CopyIn { sql: String, line: u64 },
+    /// Rows of a COPY block, in COPY text format (whole lines)
+    CopyData(String),
+    /// The `\.` line closing a COPY block, or the end of the file
+    CopyEnd,
+}
+
+enum State {
+    Normal,
+    LineComment,
+    /// Postgres block comments nest
+    BlockComment(u32),
+    /// Inside a string or quoted identifier closed by `quote`
+    Quoted { quote: u8, backslash: bool },
+    /// Inside a Postgres `$tag$ ... $tag$` string
+    DollarQuoted(Vec<u8>),
+}
+
+/// Splits a script into statements as it is read, so only the statement
+/// being scanned has to be in memory.
+///
+/// Delimiters inside strings, quoted identifiers, comments and dollar quotes
+/// are ignored. It also understands the MySQL client's `DELIMITER` command,
+/// SQLite trigger bodies (`BEGIN ... END;`), and psql meta-commands and
+/// `COPY ... FROM stdin` blocks as written by pg_dump.
+pub struct Splitter {
+    dialect: Dialect,
+    buf: String,
+    /// Start of the text not returned yet
+    start: usize,
+    /// Scan position
+    pos: usize,
+    /// Line number at `pos`
+    line: u64,
+    state: State,
+    /// Offset and line of the current statement's first token
+    content: Option<(usize, u64)>,
+    delimiter: String,
+    /// First words of the current statement, uppercased
+    head: Vec<String>,
+    trigger: bool,
+    /// BEGIN/CASE ... END nesting inside a trigger body
+    depth: u32,
+    /// End of the last word when it was `E`, so a quote right after it opens an escape string
+    escape_prefix: Option<usize>,
+    /// Inside a COPY block; false until the rest of the COPY statement's line is skipped
+    copy: Option<bool>,
+}
+
+fn is_word_byte(b: u8, dollar: bool) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80 || (dollar && b == b'$')
+}
+
+fn count_lines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|&&b| b == b'\n').count() as u64
+}
+
+fn is_copy_from_stdin(sql: &str) -> bool {
+    let words: Vec<String> = sql.split_whitespace().take(64).map(|w| w.to_ascii_uppercase()).collect();
+    words.first().is_some_and(|w| w == "COPY")
+        && words.windows(2).any(|w| w[0] == "FROM" && w[1].trim_end_matches(';') == "STDIN")
+}
+
+impl Splitter {
+    pub fn new(dialect: Dialect) -> Splitter {
+        Splitter {
+            dialect,
+            buf: String::new(),
+            start: 0,
+            pos: 0,
+            line: 1,
+            state: State::Normal,
+            content: None,
+            delimiter: ";".to_string(),
+            head: Vec::new(),
+            trigger: false,
+            depth: 0,
+            escape_prefix: None,
+            copy: None,
+        }
+    }
+
+    /// Drop the text already returned and append the next chunk of `reader`;
+    /// returns false once the file is exhausted
+    pub fn fill(&mut self, reader: &mut DecodingReader) -> Result<bool, String> {
+        self.compact();
+        reader.read_into(&mut self.buf)
+    }
+
+    fn compact(&mut self) {
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.pos -= self.start;
+            if let Some((offset, _)) = &mut self.content {
+                *offset -= self.start;
+            }
+            self.escape_prefix = self.escape_prefix.and_then(|end| end.checked_sub(self.start));
+            self.start = 0;
+        }
+    }
+
+    /// The next complete piece, or `None` when more text is needed (or, with
+    /// `eof`, when the script is exhausted)
+    pub fn next_piece(&mut self, eof: bool) -> Option<Piece> {
+        if self.copy.is_some() {
+            return self.next_copy(eof);
+        }
+        // Postgres identifiers may contain `$`; MySQL ones can too, but `$$` is a common DELIMITER
+        let dollar = self.dialect == Dialect::Postgres;
+        loop {
+            let bytes = self.buf.as_bytes();
+            let len = bytes.len();
+            let i = self.pos;
+            if i >= len {
+                if !eof {
+                    return None;
+                }
+                self.state = State::Normal;
+                return self.end_statement(len, len);
+            }
+            let c = bytes[i];
+            // Whether the token at `i` may continue past the end of the buffer
+            let partial = |n: usize| i + n > len && !eof;
+
+            match &mut self.state {
+                State::LineComment => {
+                    if c == b'\n' {
+                        self.line += 1;
+                        self.state = State::Normal;
+                    }
+                    self.pos += 1;
+                }
+                State::BlockComment(depth) => {
+                    if (c == b'*' || c == b'/') && partial(2) {
+                        return None;
+                    }
+                    let next = bytes.get(i + 1).copied();
+                    if c == b'*' && next == Some(b'/') {
+                        *depth -= 1;
+                        if *depth == 0 {
+                            self.state = State::Normal;
+                        }
+                        self.pos += 2;
+                    } else if c == b'/' && next == Some(b'*') && self.dialect == Dialect::Postgres {
+                        *depth += 1;
+                        self.pos += 2;
+                    } else {
+                        self.line += u64::from(c == b'\n');
+                        self.pos += 1;
+                    }
+                }
+                State::Quoted { quote, backslash } => {
+                    if *backslash && c == b'\\' {
+                        if partial(2) {
+                            return None;
+                        }
+                        self.line += u64::from(bytes.get(i + 1) == Some(&b'\n'));
+                        self.pos = (i + 2).min(len);
+                    } else if c == *quote {
+                        if partial(2) {
+                            return None;
+                        }
+                        if bytes.get(i + 1) == Some(quote) {
+                            self.pos += 2;
+                        } else {
+                            self.state = State::Normal;
+                            self.pos += 1;
+                        }
+                    } else {
+                        self.line += u64::from(c == b'\n');
+                        self.pos += 1;
+                    }
+                }
+                State::DollarQuoted(tag) => {
+                    let rest = &bytes[i..];
+                    match rest.windows(tag.len()).position(|w| w == tag.as_slice()) {
+                        Some(k) => {
+                            self.line += count_lines(&rest[..k]);
+                            self.pos = i + k + tag.len();
+                            self.state = State::Normal;
+                        }
+                        None if eof => {
+                            self.line += count_lines(rest);
+                            self.pos = len;
+                        }
+                        None => {
+                            // Keep a possible partial closing tag for the next chunk
+                            let skip = rest.len().saturating_sub(tag.len() - 1);
+                            self.line += count_lines(&rest[..skip]);
+                            self.pos = i + skip;
+                            return None;
+                        }
+                    }
+                }
+                State::Normal => {
+                    let delimiter = self.delimiter.as_bytes();
+                    if c == b'\n' {
+                        self.line += 1;
+                        self.pos += 1;
+                    } else if c.is_ascii_whitespace() {
+                        self.pos += 1;
+                    } else if bytes[i..].starts_with(delimiter) {
+                        if self.depth > 0 {
+                            self.pos += delimiter.len();
+                        } else {
+                            let end = i + delimiter.len();
+                            if let Some(piece) = self.end_statement(i, end) {
+                                return Some(piece);
+                            }
+                        }
+                    } else if partial(delimiter.len()) && delimiter.starts_with(&bytes[i..]) {
+                        return None;
+                    } else if self.content.is_none() && c == b'\\' && self.dialect == Dialect::Postgres {
+                        // psql meta-command such as `\connect`: skip the line
+                        match bytes[i..].iter().position(|&b| b == b'\n') {
+                            Some(n) => {
+                                self.pos = i + n + 1;
+                                self.line += 1;
+                            }
+                            None if eof => self.pos = len,
+                            None => return None,
+                        }
+                        self.start = self.pos;
+                    } else if c == b'-' || c == b'/' {
+                        let needed = if c == b'/' && self.dialect == Dialect::MySql { 3 } else { 2 };
+                        if partial(needed) {
+                            return None;
+                        }
+                        let next = bytes.get(i + 1).copied();
+                        if c == b'-' && next == Some(b'-') {
+                            self.state = State::LineComment;
+                            self.pos += 2;
+                        } else if c == b'/' && next == Some(b'*') {
+                            // MySQL `/*! ... */` comments are executed
+                            if self.dialect == Dialect::MySql && bytes.get(i + 2) == Some(&b'!') && self.content.is_none() {
+                                self.content = Some((i, self.line));
+                            }
+                            self.state = State::BlockComment(1);
+                            self.pos += 2;
+                        } else {
+                            self.content.get_or_insert((i, self.line));
+                            self.pos += 1;
+                        }
+                    } else if c == b'#' && self.dialect == Dialect::MySql {
+                        self.state = State::LineComment;
+                        self.pos += 1;
+                    } else if matches!(c, b'\'' | b'"')
+                        || (c == b'`' && self.dialect != Dialect::Postgres)
+                        || (c == b'[' && self.dialect == Dialect::Sqlite)
+                    {
+                        self.content.get_or_insert((i, self.line));
+                        let backslash = (self.dialect == Dialect::MySql && c != b'`')
+                            || (c == b'\'' && self.escape_prefix == Some(i));
+                        let quote = if c == b'[' { b']' } else { c };
+                        self.state = State::Quoted { quote, backslash };
+                        self.pos += 1;
+                    } else if c == b'$' && self.dialect == Dialect::Postgres {
+                        self.content.get_or_insert((i, self.line));
+                        let tag_end = bytes[i + 1..].iter().position(|&b| !is_word_byte(b, false));
+                        match tag_end {
+                            None if !eof => return None,
+                            Some(n) if bytes[i + 1 + n] == b'$' && !bytes[i + 1].is_ascii_digit() => {
+                                self.state = State::DollarQuoted(bytes[i..i + n + 2].to_vec());
+                                self.pos = i + n + 2;
+                            }
+                            // A parameter such as `$1`
+                            _ => self.pos += 1,
+                        }
+                    } else if is_word_byte(c, false) {
+                        let end = match bytes[i..].iter().position(|&b| !is_word_byte(b, dollar)) {
+                            Some(n) => i + n,
+                            None if eof => len,
+                            None => return None,
+                        };
+                        // Only the first words of a statement (and trigger bodies) matter
+                        let interesting = self.content.is_none() || self.head.len() < 3 || self.trigger || end - i == 1;
+                        if !interesting {
+                            self.pos = end;
+                            continue;
+                        }
+                        let word = self.buf[i..end].to_ascii_uppercase();
+                        if self.content.is_none() && word == "DELIMITER" && self.dialect == Dialect::MySql {
+                            let bytes = self.buf.as_bytes();
+                            let line_end = match bytes[i..].iter().position(|&b| b == b'\n') {
+                                Some(n) => i + n,
+                                None if eof => len,
+                                None => return None,
+                            };
+                            let delimiter = self.buf[end..line_end].trim();
+                            if !delimiter.is_empty() {
+                                self.delimiter = delimiter.to_string();
+                            }
+                            self.pos = line_end;
+                            self.start = line_end;
+                            continue;
+                        }
+                        self.content.get_or_insert((i, self.line));
+                        self.escape_prefix = (word == "E").then_some(end);
+                        if self.trigger {
+                            match word.as_str() {
+                                "BEGIN" | "CASE" => self.depth += 1,
+                                "END" => self.depth = self.depth.saturating_sub(1),
+                                _ => {}
+                            }
+                        }
+                        if self.head.len() < 3 {
+                            self.head.push(word);
+                            // Trigger bodies contain `;`; Postgres triggers call a function instead
+                            self.trigger = self.dialect == Dialect::Sqlite
+                                && self.head[0] == "CREATE"
+                                && self.head.iter().any(|w| w == "TRIGGER");
+                        }
+                        self.pos = end;
+                    } else {
+                        self.content.get_or_insert((i, self.line));
+                        self.pos += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Close the current statement at `end` (text) / `next` (after its delimiter)
+    fn end_statement(&mut self, end: usize, next: usize) -> Option<Piece> {
+        let content = self.content.take();
+        self.pos = next;
+        self.start = next;
+        self.head.clear();
+        self.trigger = false;
+        self.depth = 0;
+        self.escape_prefix = None;
+        let (offset, line) = content?;
+        let sql = self.buf[offset..end].trim_end().to_string();
+        if self.dialect == Dialect::Postgres && is_copy_from_stdin(&sql) {
+            self.copy = Some(false);
+            return Some(Piece::CopyIn { sql, line });
+        }
+        Some(Piece::Statement { sql, line })
+    }
+
+    fn next_copy(&mut self, eof: bool) -> Option<Piece> {
+        let bytes = self.buf.as_bytes();
+        let len = bytes.len();
+        if self.copy == Some(false) {
+            // The data starts on the line after the COPY statement
+            match bytes[self.pos..].iter().position(|&b| b == b'\n') {
+                Some(n) => {
+                    self.pos += n + 1;
+                    self.line += 1;
+                }
+                None if eof => self.pos = len,
+                None => return None,
+            }
+            self.start = self.pos;
+            self.copy = Some(true);
+        }
+
+        let mut i = self.pos;
+        let mut lines = 0;
+        let mut end_marker = None;
+        while i < len {
+            let (line_end, next) = match bytes[i..].iter().position(|&b| b == b'\n') {
+                Some(n) => (i + n, i + n + 1),
+                None if eof => (len, len),
+                None => break,
+            };
+            let text = &bytes[i..line_end];
+            if text.strip_suffix(b"\r").unwrap_or(text) == b"\\." {
+                end_marker = Some(next);
+                break;
+            }
+            i = next;
+            lines += 1;
+        }
+        self.line += lines;
+        self.pos = i;
+
+        if i > self.start {
+            let data = self.buf[self.start..i].to_string();
+            self.start = i;
+            return Some(Piece::CopyData(data));
+        }
+        if let Some(next) = end_marker {
+            self.line += u64::from(next > 0 && bytes[next - 1] == b'\n');
+            self.pos = next;
+            self.start = next;
+            self.copy = None;
+            return Some(Piece::CopyEnd);
+        }
+        if eof {
+            self.copy = None;
+            return Some(Piece::CopyEnd);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement(sql: &str, line: u64) -> Piece {
+        Piece::Statement { sql: sql.to_string(), line }
+    }
+
+    fn push(pieces: &mut Vec<Piece>, piece: Piece) {
+        match (pieces.last_mut(), piece) {
+            (Some(Piece::CopyData(data)), Piece::CopyData(more)) => data.push_str(&more),
+            (_, piece) => pieces.push(piece),
+        }
+    }
+
+    /// Split `script` fed `chunk` bytes at a time, merging the `CopyData`
+    /// pieces that chunking breaks up
+    fn split_chunked(dialect: Dialect, script: &str, chunk: usize) -> Vec<Piece> {
+        let mut splitter = Splitter::new(dialect);
+        let mut pieces = Vec::new();
+        for part in script.as_bytes().chunks(chunk) {
+            splitter.compact();
+            splitter.buf.push_str(std::str::from_utf8(part).unwrap());
+            while let Some(piece) = splitter.next_piece(false) {
+                push(&mut pieces, piece);
+            }
+        }
+        while let Some(piece) = splitter.next_piece(true) {
+            push(&mut pieces, piece);
+        }
+        pieces
+    }
+
+    /// Split `script` whole, checking that every chunk size gives the same pieces
+    fn split(dialect: Dialect, script: &str) -> Vec<Piece> {
+        let pieces = split_chunked(dialect, script, script.len().max(1));
+        for chunk in 1..8 {
+            assert_eq!(split_chunked(dialect, script, chunk), pieces, "chunk size {}", chunk);
+        }
+        pieces
+    }
+
+    #[test]
+    fn splits_on_delimiter_with_start_lines() {
+        let pieces = split(Dialect::Postgres, "SELECT 1;\n\n  SELECT\n2 ;SELECT 3");
+        assert_eq!(pieces, vec![statement("SELECT 1", 1), statement("SELECT\n2", 3), statement("SELECT 3", 4)]);
+    }
+
+    #[test]
+    fn skips_empty_statements() {
+        let pieces = split(Dialect::Sqlite, ";;\n ; SELECT 1;;");
+        assert_eq!(pieces, vec![statement("SELECT 1", 2)]);
+    }
+
+    #[test]
+    fn ignores_delimiters_in_quotes() {
+        let pieces = split(Dialect::Postgres, "SELECT 'a;''b', \"c;\"\"d\";\nSELECT 'x\ny;';");
+        assert_eq!(pieces, vec![statement("SELECT 'a;''b', \"c;\"\"d\"", 1), statement("SELECT 'x\ny;'", 2)]);
+    }
+
+    #[test]
+    fn ignores_delimiters_in_backticks_and_brackets() {
+        let pieces = split(Dialect::MySql, "SELECT `a;b` FROM t;SELECT 2;");
+        assert_eq!(pieces, vec![statement("SELECT `a;b` FROM t", 1), statement("SELECT 2", 1)]);
+        let pieces = split(Dialect::Sqlite, "SELECT [a;b], `c;d` FROM t;SELECT 2;");
+        assert_eq!(pieces, vec![statement("SELECT [a;b], `c;d` FROM t", 1), statement("SELECT 2", 1)]);
+    }
+
+    #[test]
+    fn mysql_strings_take_backslash_escapes() {
+        let pieces = split(Dialect::MySql, "SELECT 'a\\';b', \"c\\\";d\";\nSELECT `e\\`;");
+        assert_eq!(pieces, vec![statement("SELECT 'a\\';b', \"c\\\";d\"", 1), statement("SELECT `e\\`", 2)]);
+    }
+
+    #[test]
+    fn postgres_backslashes_escape_only_in_escape_strings() {
+        let pieces = split(Dialect::Postgres, "SELECT 'a\\';SELECT E'b\\';c', e'\\\\';SELECT 'd'");
+        assert_eq!(
+            pieces,
+            vec![statement("SELECT 'a\\'", 1), statement("SELECT E'b\\';c', e'\\\\'", 1), statement("SELECT 'd'", 1)]
+        );
+    }
+
+    #[test]
+    fn ignores_delimiters_in_dollar_quotes() {
+        let script = "CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;\n\
+                      DO $body$ BEGIN RAISE NOTICE '$$;'; END $body$;\n\
+                      SELECT $1, a$b FROM t;";
+        let pieces = split(Dialect::Postgres, script);
+        assert_eq!(
+            pieces,
+            vec![
+                statement("CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql", 1),
+                statement("DO $body$ BEGIN RAISE NOTICE '$$;'; END $body$", 2),
+                statement("SELECT $1, a$b FROM t", 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn dollar_signs_are_not_quotes_outside_postgres() {
+        let pieces = split(Dialect::MySql, "SELECT '$$';SELECT 1 AS $$;");
+        assert_eq!(pieces, vec![statement("SELECT '$$'", 1), statement("SELECT 1 AS $$", 1)]);
+    }
+
+    #[test]
+    fn ignores_delimiters_in_comments() {
+        let script = "-- first; comment\nSELECT 1 /* ; */;\nSELECT 2 -- trailing;\n;";
+        let pieces = split(Dialect::Postgres, script);
+        assert_eq!(pieces, vec![statement("SELECT 1 /* ; */", 2), statement("SELECT 2 -- trailing;", 3)]);
+    }
+
+    #[test]
+    fn mysql_hash_comments() {
+        let pieces = split(Dialect::MySql, "# note; here\nSELECT 1;");
+        assert_eq!(pieces, vec![statement("SELECT 1", 2)]);
+        let pieces = split(Dialect::Postgres, "SELECT a #> b;");
+        assert_eq!(pieces, vec![statement("SELECT a #> b", 1)]);
+    }
+
+    #[test]
+    fn postgres_block_comments_nest() {
+        let pieces = split(Dialect::Postgres, "/* a /* b; */ c; */ SELECT 1;");
+        assert_eq!(pieces, vec![statement("SELECT 1", 1)]);
+        let pieces = split(Dialect::MySql, "/* a /* b; */ SELECT 1;");
+        assert_eq!(pieces, vec![statement("SELECT 1", 1)]);
+    }
+
+    #[test]
+    fn mysql_executable_comments_are_statements() {
+        let pieces = split(Dialect::MySql, "/*!40101 SET NAMES utf8mb4 */;\n/* plain */;");
+        assert_eq!(pieces, vec![statement("/*!40101 SET NAMES utf8mb4 */", 1)]);
+    }
+
+    #[test]
+    fn mysql_delimiter_command() {
+        let script = "DELIMITER $$\n\
+                      CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END$$\n\
+                      DELIMITER ;\n\
+                      CALL p();";
+        let pieces = split(Dialect::MySql, script);
+        assert_eq!(
+            pieces,
+            vec![statement("CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END", 2), statement("CALL p()", 4)]
+        );
+    }
+
+    #[test]
+    fn delimiter_is_only_a_command_at_statement_start() {
+        let pieces = split(Dialect::MySql, "SELECT delimiter FROM t;");
+        assert_eq!(pieces, vec![statement("SELECT delimiter FROM t", 1)]);
+        let pieces = split(Dialect::Postgres, "DELIMITER //\n;");
+        assert_eq!(pieces, vec![statement("DELIMITER //", 1)]);
+    }
+
+    #[test]
+    fn sqlite_trigger_bodies() {
+        let script = "CREATE TRIGGER t AFTER INSERT ON a BEGIN\n\
+                      UPDATE b SET n = CASE WHEN n > 0 THEN n END;\n\
+                      INSERT INTO c VALUES (1);\n\
+                      END;\nSELECT 1;";
+        let pieces = split(Dialect::Sqlite, script);
+        assert_eq!(
+            pieces,
+            vec![
+                statement(
+                    "CREATE TRIGGER t AFTER INSERT ON a BEGIN\n\
+                     UPDATE b SET n = CASE WHEN n > 0 THEN n END;\n\
+                     INSERT INTO c VALUES (1);\n\
+                     END",
+                    1
+                ),
+                statement("SELECT 1", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_psql_meta_commands() {
+        let pieces = split(Dialect::Postgres, "\\connect db\nSELECT 1;\n\\set ON_ERROR_STOP on\nSELECT 2;");
+        assert_eq!(pieces, vec![statement("SELECT 1", 2), statement("SELECT 2", 4)]);
+    }
+
+    #[test]
+    fn copy_blocks() {
+        let script = "COPY t (a, b) FROM stdin;\n1\t;x\n2\t\\N\n\\.\nSELECT 1;\nCOPY t FROM STDIN;\n3\tz\n";
+        let pieces = split(Dialect::Postgres, script);
+        assert_eq!(
+            pieces,
+            vec![
+                Piece::CopyIn { sql: "COPY t (a, b) FROM stdin".to_string(), line: 1 },
+                Piece::CopyData("1\t;x\n2\t\\N\n".to_string()),
+                Piece::CopyEnd,
+                statement("SELECT 1", 5),
+                Piece::CopyIn { sql: "COPY t FROM STDIN".to_string(), line: 6 },
+                Piece::CopyData("3\tz\n".to_string()),
+                Piece::CopyEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn copy_is_a_statement_outside_postgres() {
+        let pieces = split(Dialect::MySql, "COPY t FROM stdin;\n1\n");
+        assert_eq!(pieces, vec![statement("COPY t FROM stdin", 1), statement("1", 2)]);
+    }
+}
@@ -0,0 +1,374 @@
+// Database search
+//
+// `search_database` looks for a value in the text and numeric columns of a
+// schema's tables. Each table is searched with one query whose select list
+// holds, besides the row key, one entry per column: the column's text where
+// it matched and NULL elsewhere, so a single scan tells both which rows and
+// which of their columns matched. Tables are searched a few at a time, and a
+// table's matches are sent as a `search-results` event as soon as it is done.
+// Patterns are written into the queries as literals, as each one is repeated
+// for every column.
+
+use std::time::Instant;
+use futures::stream::{self, StreamExt};
+use serde_json::Map;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::dialect::Dialect;
+use crate::db::edit::Params;
+use crate::db::import::preview_value;
+use crate::db::introspection;
+use crate::db::stream::{fetch_cells, mysql, postgres, sqlite, Cell, RowSink, StreamColumn};
+use crate::db::tasks::CancelFlag;
+use crate::db::transfer::mapping::{self, Family};
+use crate::db::types::{
+    ColumnInfo, SearchFailure, SearchMatch, SearchMode, SearchProgress, SearchRequest, SearchResults, SearchSummary,
+    SqlPool,
+};
+
+/// Characters of a matched value shown in a snippet
+const SNIPPET_CHARS: usize = 120;
+/// Upper bound of `SearchRequest::concurrency`; the pool limits it further
+const MAX_CONCURRENCY: usize = 16;
+/// Escape character of the patterns built for `SearchMode::Contains`, as in browse
+const LIKE_ESCAPE: char = '!';
+
+/// A column the pattern is looked for in
+struct Target<'a> {
+    column: &'a ColumnInfo,
+    numeric: bool,
+}
+
+/// What one table's search found
+struct TableMatches {
+    columns: u64,
+    rows: u64,
+    matches: Vec<SearchMatch>,
+    truncated: bool,
+}
+
+/// Keeps the rows of a table's search query as they stream in
+#[derive(Default)]
+struct Collected(Vec<Vec<Cell>>);
+
+impl RowSink for Collected {
+    fn begin_result(&mut self, _columns: &[StreamColumn]) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: Vec<Cell>) -> Result<(), String> {
+        self.0.push(row);
+        Ok(())
+    }
+}
+
+struct Searcher<'a> {
+    dialect: Dialect,
+    request: &'a SearchRequest,
+}
+
+impl Searcher<'_> {
+    /// Whether the pattern can match a value of the column, and whether the
+    /// column is numeric
+    fn target<'c>(&self, column: &'c ColumnInfo) -> Option<Target<'c>> {
+        let numeric = match mapping::family(self.dialect, &column.data_type) {
+            Family::VarChar(_) | Family::Char(_) | Family::Text | Family::Json | Family::Uuid => false,
+            Family::SmallInt | Family::Int | Family::BigInt | Family::Decimal(_) | Family::Real | Family::Double => true,
+            Family::Bool | Family::Bytes | Family::Date | Family::Time | Family::Timestamp(_) | Family::TimestampTz(_) => {
+                return None
+            }
+        };
+        if numeric {
+            if self.request.text_only {
+                return None;
+            }
+            // Numbers are made of digits, signs, points and exponents only
+            let number_like = |text: &str| text.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+            let possible = match self.request.mode {
+                SearchMode::Contains => number_like(&self.request.pattern),
+                // The pattern goes into the SQL as it is, so it has to be a plain finite number
+                SearchMode::Exact => {
+                    let pattern = self.request.pattern.trim();
+                    number_like(pattern) && pattern.parse::<f64>().is_ok_and(f64::is_finite)
+                }
+                SearchMode::Like | SearchMode::ILike | SearchMode::Regex => true,
+            };
+            if !possible {
+                return None;
+            }
+        }
+        Some(Target { column, numeric })
+    }
+
+    /// The column as text
+    fn text_expr(&self, target: &Target) -> String {
+        let name = self.dialect.quote_ident(&target.column.name);
+        match self.dialect {
+            Dialect::Postgres => format!("{}::text", name),
+            Dialect::MySql if target.numeric => format!("CAST({} AS CHAR)", name),
+            Dialect::Sqlite if target.numeric => format!("CAST({} AS TEXT)", name),
+            Dialect::MySql | Dialect::Sqlite => name,
+        }
+    }
+
+    /// The condition under which the column matches
+    fn condition(&self, target: &Target) -> String {
+        let pattern = &self.request.pattern;
+        let text = self.text_expr(target);
+        let literal = |text: String| Params::inline(self.dialect).push_text(text);
+        match self.request.mode {
+            SearchMode::Contains => {
+                let mut escaped = String::with_capacity(pattern.len() + 2);
+                escaped.push('%');
+                for c in pattern.chars() {
+                    if c == LIKE_ESCAPE || c == '%' || c == '_' {
+                        escaped.push(LIKE_ESCAPE);
+                    }
+                    escaped.push(c);
+                }
+                escaped.push('%');
+                // SQLite's LIKE ignores ASCII case, MySQL's follows the
+                // (usually case-insensitive) collation
+                let like = if self.dialect == Dialect::Postgres { "ILIKE" } else { "LIKE" };
+                format!("{} {} {} ESCAPE '{}'", text, like, literal(escaped), LIKE_ESCAPE)
+            }
+            // Numbers compare as numbers, so that 1.50 is found by 1.5
+            SearchMode::Exact if target.numeric => {
+                format!("{} = {}", self.dialect.quote_ident(&target.column.name), pattern.trim())
+            }
+            SearchMode::Exact => format!("{} = {}", text, literal(pattern.clone())),
+            SearchMode::Like => format!("{} LIKE {}", text, literal(pattern.clone())),
+            SearchMode::ILike => match self.dialect {
+                Dialect::Postgres => format!("{} ILIKE {}", text, literal(pattern.clone())),
+                Dialect::MySql => format!("LOWER({}) LIKE LOWER({})", text, literal(pattern.clone())),
+                Dialect::Sqlite => format!("{} LIKE {}", text, literal(pattern.clone())),
+            },
+            SearchMode::Regex => match self.dialect {
+                Dialect::Postgres => format!("{} ~ {}", text, literal(pattern.clone())),
+                Dialect::MySql | Dialect::Sqlite => format!("{} REGEXP {}", text, literal(pattern.clone())),
+            },
+        }
+    }
+
+    /// Stops between rows once `cancel` is set, returning what was found so far
+    async fn search_table(&self, pool: &SqlPool, table: &str, cancel: &CancelFlag) -> Result<TableMatches, String> {
+        let schema = self.request.schema.as_deref();
+        let columns = introspection::columns(pool, schema, table).await?;
+        if columns.is_empty() {
+            return Err(format!("Table '{}' was not found", table));
+        }
+        let targets: Vec<Target> = columns.iter().filter_map(|c| self.target(c)).collect();
+        if targets.is_empty() {
+            return Ok(TableMatches { columns: 0, rows: 0, matches: Vec::new(), truncated: false });
+        }
+        let key = introspection::row_key(pool, schema, table, &columns).await?.unwrap_or_default();
+
+        let conditions: Vec<String> = targets.iter().map(|t| self.condition(t)).collect();
+        let select: Vec<String> = key
+            .iter()
+            .map(|name| self.dialect.quote_ident(name))
+            .chain(
+                targets
+                    .iter()
+                    .zip(&conditions)
+                    .map(|(target, condition)| format!("CASE WHEN {} THEN {} END", condition, self.text_expr(target))),
+            )
+            .collect();
+        let sql = format!(
+            "SELECT {} FROM {} WHERE {} LIMIT {}",
+            select.join(", "),
+            self.dialect.qualified(schema, table),
+            conditions.iter().map(|c| format!("({})", c)).collect::<Vec<_>>().join(" OR "),
+            // One extra row tells whether there are more
+            self.request.limit_per_table + 1
+        );
+        let mut collected = Collected::default();
+        match pool {
+            SqlPool::Postgres(pool) => postgres::stream_query(pool, &sql, &mut collected, cancel).await?,
+            SqlPool::MySql(pool) => mysql::stream_query(pool, &sql, None, &mut collected, cancel).await?,
+            SqlPool::Sqlite(pool) => sqlite::stream_query(pool, &sql, &mut collected, cancel).await?,
+        };
+        let mut rows = collected.0;
+        let truncated = rows.len() > self.request.limit_per_table;
+        rows.truncate(self.request.limit_per_table);
+
+        let mut matches = Vec::new();
+        for row in &rows {
+            let key: Map<String, serde_json::Value> =
+                key.iter().zip(row).map(|(name, cell)| (name.clone(), preview_value(cell))).collect();
+            for (target, cell) in targets.iter().zip(&row[key.len()..]) {
+                let text = match cell {
+                    Cell::Null => continue,
+                    Cell::Text(text) | Cell::Decimal(text) => text.clone(),
+                    other => preview_value(other).to_string(),
+                };
+                matches.push(SearchMatch {
+                    column: target.column.name.clone(),
+                    key: key.clone(),
+                    snippet: snippet(&text, &self.needle()),
+                });
+            }
+        }
+        Ok(TableMatches { columns: targets.len() as u64, rows: rows.len() as u64, matches, truncated })
+    }
+
+    /// Literal text a match most likely contains, to center snippets on
+    fn needle(&self) -> String {
+        let pattern = &self.request.pattern;
+        match self.request.mode {
+            SearchMode::Contains | SearchMode::Exact => pattern.clone(),
+            // The longest run free of wildcards / regex syntax
+            SearchMode::Like | SearchMode::ILike => longest_run(pattern, |c| c != '%' && c != '_'),
+            SearchMode::Regex => longest_run(pattern, |c| c.is_alphanumeric() || c == ' '),
+        }
+    }
+
+    /// Fail early, instead of once per table, on a pattern the engine rejects
+    async fn check_pattern(&self, pool: &SqlPool) -> Result<(), String> {
+        if self.request.pattern.is_empty() {
+            return Err("Enter a value to search for".to_string());
+        }
+        if self.request.mode != SearchMode::Regex {
+            return Ok(());
+        }
+        let pattern = Params::inline(self.dialect).push_text(self.request.pattern.clone());
+        let operator = if self.dialect == Dialect::Postgres { "~" } else { "REGEXP" };
+        fetch_cells(pool, &format!("SELECT '' {} {}", operator, pattern))
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Invalid regular expression: {}", e))
+    }
+}
+
+fn longest_run(text: &str, keep: impl Fn(char) -> bool) -> String {
+    text.split(|c: char| !keep(c))
+        .max_by_key(|run| run.chars().count())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// The value, or a window of it around the first (case-insensitive)
+/// occurrence of `needle`, with ellipses where it was cut
+fn snippet(text: &str, needle: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= SNIPPET_CHARS {
+        return text.to_string();
+    }
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    let found = (!needle.is_empty())
+        .then(|| {
+            chars.windows(needle.len()).position(|window| {
+                window.iter().zip(&needle).all(|(c, n)| c.to_lowercase().eq(std::iter::once(*n)))
+            })
+        })
+        .flatten();
+    let start = match found {
+        Some(position) => position.saturating_sub(SNIPPET_CHARS.saturating_sub(needle.len()) / 2),
+        None => 0,
+    }
+    .min(chars.len() - SNIPPET_CHARS);
+    let end = start + SNIPPET_CHARS;
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(&chars[start..end]);
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Tables searched when the request names none: every table of the schema
+async fn all_tables(pool: &SqlPool, schema: Option<&str>) -> Result<Vec<String>, String> {
+    let tables = match pool {
+        SqlPool::Postgres(pool) => introspection::postgres::list_tables(pool, schema).await?,
+        SqlPool::MySql(pool) => introspection::mysql::list_tables(pool, schema).await?,
+        SqlPool::Sqlite(pool) => introspection::sqlite::list_tables(pool, schema).await?,
+    };
+    // Partitioned parents are left out; their partitions are tables themselves
+    Ok(tables.into_iter().filter(|t| t.table_type == "TABLE").map(|t| t.name).collect())
+}
+
+/// Search the tables of `request` for its pattern
+pub async fn search_database(
+    app: &AppHandle,
+    pool: &SqlPool,
+    request: &SearchRequest,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<SearchSummary, String> {
+    let start = Instant::now();
+    let searcher = Searcher { dialect: pool.dialect(), request };
+    searcher.check_pattern(pool).await?;
+    let tables = if request.tables.is_empty() {
+        all_tables(pool, request.schema.as_deref()).await?
+    } else {
+        request.tables.clone()
+    };
+
+    let mut summary = SearchSummary {
+        tables_searched: 0,
+        columns_searched: 0,
+        matching_rows: 0,
+        matches: 0,
+        truncated_tables: Vec::new(),
+        failed_tables: Vec::new(),
+        cancelled: false,
+        execution_time_ms: 0,
+    };
+    let emit_progress = |summary: &SearchSummary| {
+        let _ = app.emit(
+            "search-progress",
+            SearchProgress {
+                task_id: task_id.to_string(),
+                tables_searched: summary.tables_searched,
+                total_tables: tables.len() as u64,
+                matches: summary.matches,
+            },
+        );
+    };
+    emit_progress(&summary);
+
+    let mut searches = stream::iter(&tables)
+        .map(|table| {
+            let searcher = &searcher;
+            async move { (table, searcher.search_table(pool, table, cancel).await) }
+        })
+        .buffer_unordered(request.concurrency.clamp(1, MAX_CONCURRENCY));
+    while let Some((table, result)) = searches.next().await {
+        // Dropping the stream abandons the searches still running
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        summary.tables_searched += 1;
+        match result {
+            Ok(found) => {
+                summary.columns_searched += found.columns;
+                summary.matching_rows += found.rows;
+                summary.matches += found.matches.len() as u64;
+                if found.truncated {
+                    summary.truncated_tables.push(table.clone());
+                }
+                if !found.matches.is_empty() {
+                    let _ = app.emit(
+                        "search-results",
+                        SearchResults {
+                            task_id: task_id.to_string(),
+                            table: table.clone(),
+                            matches: found.matches,
+                            truncated: found.truncated,
+                        },
+                    );
+                }
+            }
+            Err(message) => summary.failed_tables.push(SearchFailure { table: table.clone(), message }),
+        }
+        emit_progress(&summary);
+    }
+
+    summary.execution_time_ms = start.elapsed().as_millis();
+    Ok(summary)
+}
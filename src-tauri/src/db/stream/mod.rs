@@ -0,0 +1,275 @@
+// Streaming result sets
+//
+// Scripts are run with `sqlx::raw_sql`, so rows are handed to a `RowSink` as
+// they arrive instead of being collected first. Postgres and MySQL return every
+// value in text form over the simple/text protocol; each backend turns those
+// (or SQLite's typed values) into `Cell`s using the column's declared type.
+
+pub mod postgres;
+pub mod mysql;
+pub mod sqlite;
+
+use futures::TryStreamExt;
+use sqlx::{Database, Either, Executor, Row};
+
+use crate::db::dialect::Dialect;
+use crate::db::tasks::CancelFlag;
+use crate::db::types::SqlPool;
+
+/// Broad type of a result column, derived from the driver's type name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Bool,
+    Int,
+    Float,
+    Decimal,
+    Text,
+    Bytes,
+    Json,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Other,
+}
+
+impl ColumnKind {
+    pub fn from_type_name(dialect: Dialect, type_name: &str) -> ColumnKind {
+        let name = type_name.to_ascii_uppercase();
+        match dialect {
+            Dialect::Postgres => match name.as_str() {
+                "BOOL" => ColumnKind::Bool,
+                "INT2" | "INT4" | "INT8" | "OID" => ColumnKind::Int,
+                "FLOAT4" | "FLOAT8" => ColumnKind::Float,
+                "NUMERIC" => ColumnKind::Decimal,
+                "TEXT" | "VARCHAR" | "BPCHAR" | "CHAR" | "NAME" | "CITEXT" | "UUID" => ColumnKind::Text,
+                "BYTEA" => ColumnKind::Bytes,
+                "JSON" | "JSONB" => ColumnKind::Json,
+                "DATE" => ColumnKind::Date,
+                "TIME" => ColumnKind::Time,
+                "TIMESTAMP" => ColumnKind::Timestamp,
+                "TIMESTAMPTZ" => ColumnKind::TimestampTz,
+                _ => ColumnKind::Other,
+            },
+            Dialect::MySql => {
+                let base = name.trim_end_matches(" UNSIGNED");
+                match base {
+                    "BOOLEAN" => ColumnKind::Bool,
+                    "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => ColumnKind::Int,
+                    "FLOAT" | "DOUBLE" => ColumnKind::Float,
+                    "DECIMAL" => ColumnKind::Decimal,
+                    "CHAR" | "VARCHAR" | "TEXT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET" => {
+                        ColumnKind::Text
+                    }
+                    "BINARY" | "VARBINARY" | "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BIT" | "GEOMETRY" => {
+                        ColumnKind::Bytes
+                    }
+                    "JSON" => ColumnKind::Json,
+                    "DATE" => ColumnKind::Date,
+                    "TIME" => ColumnKind::Time,
+                    "DATETIME" => ColumnKind::Timestamp,
+                    // MySQL returns TIMESTAMP values in the session time zone without an offset
+                    "TIMESTAMP" => ColumnKind::Timestamp,
+                    _ => ColumnKind::Other,
+                }
+            }
+            // Declared types follow SQLite's affinity rules
+            Dialect::Sqlite => {
+                if name.contains("INT") {
+                    ColumnKind::Int
+                } else if name.contains("BOOL") {
+                    ColumnKind::Bool
+                } else if name.contains("CHAR") || name.contains("CLOB") || name.contains("TEXT") {
+                    ColumnKind::Text
+                } else if name.contains("BLOB") {
+                    ColumnKind::Bytes
+                } else if name.contains("REAL") || name.contains("FLOA") || name.contains("DOUB") {
+                    ColumnKind::Float
+                } else if name.contains("JSON") {
+                    ColumnKind::Json
+                } else if name.contains("DATETIME") || name.contains("TIMESTAMP") {
+                    ColumnKind::Timestamp
+                } else if name.contains("DATE") {
+                    ColumnKind::Date
+                } else if name.contains("TIME") {
+                    ColumnKind::Time
+                } else if name.contains("DEC") || name.contains("NUMERIC") {
+                    ColumnKind::Decimal
+                } else {
+                    ColumnKind::Other
+                }
+            }
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, ColumnKind::Int | ColumnKind::Float | ColumnKind::Decimal)
+    }
+}
+
+/// A result column as reported by the driver
+#[derive(Debug, Clone)]
+pub struct StreamColumn {
+    pub name: String,
+    /// Driver type name, e.g. `INT4`, `VARCHAR` or the declared SQLite type
+    pub type_name: String,
+    pub kind: ColumnKind,
+}
+
+/// A single value of a streamed row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Exact numeric in its textual form (NUMERIC/DECIMAL, or integers beyond i64)
+    Decimal(String),
+    /// Text, and anything without a closer representation (dates, UUIDs, arrays...)
+    Text(String),
+    Bytes(Vec<u8>),
+    Json(serde_json::Value),
+}
+
+impl Cell {
+    /// Interpret a value received in text form according to its column kind
+    pub fn from_text(kind: ColumnKind, text: &str) -> Cell {
+        match kind {
+            ColumnKind::Bool => match text {
+                "t" | "true" | "1" => Cell::Bool(true),
+                "f" | "false" | "0" => Cell::Bool(false),
+                _ => Cell::Text(text.to_string()),
+            },
+            ColumnKind::Int => text
+                .parse()
+                .map(Cell::Int)
+                .unwrap_or_else(|_| Cell::Decimal(text.to_string())),
+            ColumnKind::Float => text
+                .parse()
+                .map(Cell::Float)
+                .unwrap_or_else(|_| Cell::Text(text.to_string())),
+            ColumnKind::Decimal => Cell::Decimal(text.to_string()),
+            ColumnKind::Bytes => match text.strip_prefix("\\x").and_then(decode_hex) {
+                Some(bytes) => Cell::Bytes(bytes),
+                None => Cell::Bytes(text.as_bytes().to_vec()),
+            },
+            ColumnKind::Json => serde_json::from_str(text)
+                .map(Cell::Json)
+                .unwrap_or_else(|_| Cell::Text(text.to_string())),
+            _ => Cell::Text(text.to_string()),
+        }
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Lowercase hex digits of a byte string
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Receives result sets as they are streamed
+pub trait RowSink {
+    /// Called when a result set starts, before its first row
+    fn begin_result(&mut self, columns: &[StreamColumn]) -> Result<(), String>;
+    fn write_row(&mut self, row: Vec<Cell>) -> Result<(), String>;
+}
+
+/// What happened while streaming a script
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    pub rows: u64,
+    /// Result sets that produced columns (statements without rows are not counted)
+    pub result_sets: usize,
+    pub cancelled: bool,
+}
+
+/// A backend's conversion of one row to cells
+type DecodeRow<DB> = fn(&<DB as Database>::Row, &[StreamColumn]) -> Result<Vec<Cell>, String>;
+
+/// Run a script on `conn` and feed every row of every result set to `sink`,
+/// turning rows into cells with the backend's `stream_column` / `decode_row`
+async fn stream_rows<DB: Database>(
+    conn: &mut DB::Connection,
+    query: &str,
+    sink: &mut (dyn RowSink + Send),
+    cancel: &CancelFlag,
+    stream_column: fn(&DB::Column) -> StreamColumn,
+    decode_row: DecodeRow<DB>,
+) -> Result<StreamStats, String>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let mut stats = StreamStats::default();
+    let mut current: Option<Vec<StreamColumn>> = None;
+    let mut stream = sqlx::raw_sql(query).fetch_many(&mut *conn);
+
+    while let Some(item) = stream.try_next().await.map_err(|e| e.to_string())? {
+        if cancel.is_cancelled() {
+            stats.cancelled = true;
+            return Ok(stats);
+        }
+        match item {
+            // End of a statement
+            Either::Left(_) => current = None,
+            Either::Right(row) => {
+                let columns = match &current {
+                    Some(columns) => columns,
+                    None => {
+                        let columns: Vec<StreamColumn> = row.columns().iter().map(stream_column).collect();
+                        sink.begin_result(&columns)?;
+                        stats.result_sets += 1;
+                        current.insert(columns)
+                    }
+                };
+                sink.write_row(decode_row(&row, columns)?)?;
+                stats.rows += 1;
+            }
+        }
+    }
+    drop(stream);
+
+    // A single SELECT without rows still gets its columns, via describe
+    if stats.result_sets == 0 {
+        if let Ok(describe) = (&mut *conn).describe(query).await {
+            let columns: Vec<StreamColumn> = describe.columns().iter().map(stream_column).collect();
+            if !columns.is_empty() {
+                sink.begin_result(&columns)?;
+                stats.result_sets = 1;
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Run a query over the text protocol and decode its rows
+pub async fn fetch_cells(pool: &SqlPool, sql: &str) -> Result<Vec<Vec<Cell>>, String> {
+    match pool {
+        SqlPool::Postgres(pool) => {
+            let rows = sqlx::raw_sql(sql).fetch_all(pool).await.map_err(|e| e.to_string())?;
+            let Some(first) = rows.first() else { return Ok(Vec::new()) };
+            let columns: Vec<_> = first.columns().iter().map(postgres::stream_column).collect();
+            rows.iter().map(|row| postgres::decode_row(row, &columns)).collect()
+        }
+        SqlPool::MySql(pool) => {
+            let rows = sqlx::raw_sql(sql).fetch_all(pool).await.map_err(|e| e.to_string())?;
+            let Some(first) = rows.first() else { return Ok(Vec::new()) };
+            let columns: Vec<_> = first.columns().iter().map(mysql::stream_column).collect();
+            rows.iter().map(|row| mysql::decode_row(row, &columns)).collect()
+        }
+        SqlPool::Sqlite(pool) => {
+            let rows = sqlx::raw_sql(sql).fetch_all(pool).await.map_err(|e| e.to_string())?;
+            let Some(first) = rows.first() else { return Ok(Vec::new()) };
+            let columns: Vec<_> = first.columns().iter().map(sqlite::stream_column).collect();
+            rows.iter().map(|row| sqlite::decode_row(row, &columns)).collect()
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use sqlx::mysql::{MySqlColumn, MySqlRow};
+use sqlx::{Pool, MySql, Row, Column, TypeInfo, Executor};
+use crate::db::dialect::Dialect;
+use crate::db::stream::{stream_rows, Cell, ColumnKind, RowSink, StreamColumn, StreamStats};
+use crate::db::tasks::CancelFlag;
+
+pub fn stream_column(col: &MySqlColumn) -> StreamColumn {
+    StreamColumn {
+        name: col.name().to_string(),
+        type_name: col.type_info().name().to_string(),
+        kind: ColumnKind::from_type_name(Dialect::MySql, col.type_info().name()),
+    }
+}
+
+/// Rows of the text protocol arrive as raw bytes
+pub fn decode_row(row: &MySqlRow, columns: &[StreamColumn]) -> Result<Vec<Cell>, String> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let bytes: Option<Vec<u8>> = row.try_get_unchecked(i).map_err(|e| e.to_string())?;
+            Ok(match bytes {
+                None => Cell::Null,
+                Some(bytes) if column.kind == ColumnKind::Bytes => Cell::Bytes(bytes),
+                Some(bytes) => Cell::from_text(column.kind, &String::from_utf8_lossy(&bytes)),
+            })
+        })
+        .collect()
+}
+
+/// Run a script and feed every row of every result set to `sink`
+pub async fn stream_query(
+    pool: &Pool<MySql>,
+    query: &str,
+    database: Option<&str>,
+    sink: &mut (dyn RowSink + Send),
+    cancel: &CancelFlag,
+) -> Result<StreamStats, String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    if let Some(db) = database {
+        conn.execute(format!("USE {}", Dialect::MySql.quote_ident(db)).as_str())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    stream_rows::<MySql>(&mut conn, query, sink, cancel, stream_column, decode_row).await
+}
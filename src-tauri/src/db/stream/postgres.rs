@@ -0,0 +1,40 @@
+use sqlx::postgres::{PgColumn, PgRow};
+use sqlx::{Pool, Postgres, Row, Column, TypeInfo, ValueRef};
+use crate::db::dialect::Dialect;
+use crate::db::stream::{stream_rows, Cell, ColumnKind, RowSink, StreamColumn, StreamStats};
+use crate::db::tasks::CancelFlag;
+
+pub fn stream_column(col: &PgColumn) -> StreamColumn {
+    StreamColumn {
+        name: col.name().to_string(),
+        type_name: col.type_info().name().to_string(),
+        kind: ColumnKind::from_type_name(Dialect::Postgres, col.type_info().name()),
+    }
+}
+
+/// Rows of the simple query protocol arrive in text format
+pub fn decode_row(row: &PgRow, columns: &[StreamColumn]) -> Result<Vec<Cell>, String> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let value = row.try_get_raw(i).map_err(|e| e.to_string())?;
+            if value.is_null() {
+                return Ok(Cell::Null);
+            }
+            let text = value.as_str().map_err(|e| e.to_string())?;
+            Ok(Cell::from_text(column.kind, text))
+        })
+        .collect()
+}
+
+/// Run a script and feed every row of every result set to `sink`
+pub async fn stream_query(
+    pool: &Pool<Postgres>,
+    query: &str,
+    sink: &mut (dyn RowSink + Send),
+    cancel: &CancelFlag,
+) -> Result<StreamStats, String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    stream_rows::<Postgres>(&mut conn, query, sink, cancel, stream_column, decode_row).await
+}
@@ -0,0 +1,54 @@
+use sqlx::sqlite::{SqliteColumn, SqliteRow};
+use sqlx::{Pool, Sqlite, Row, Column, TypeInfo, ValueRef};
+use crate::db::dialect::Dialect;
+use crate::db::stream::{stream_rows, Cell, ColumnKind, RowSink, StreamColumn, StreamStats};
+use crate::db::tasks::CancelFlag;
+
+pub fn stream_column(col: &SqliteColumn) -> StreamColumn {
+    StreamColumn {
+        name: col.name().to_string(),
+        type_name: col.type_info().name().to_string(),
+        kind: ColumnKind::from_type_name(Dialect::Sqlite, col.type_info().name()),
+    }
+}
+
+/// SQLite values carry their own storage class, whatever the declared type
+pub fn decode_row(row: &SqliteRow, columns: &[StreamColumn]) -> Result<Vec<Cell>, String> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let value = row.try_get_raw(i).map_err(|e| e.to_string())?;
+            if value.is_null() {
+                return Ok(Cell::Null);
+            }
+            let storage = value.type_info().name().to_string();
+            Ok(match storage.as_str() {
+                "INTEGER" if column.kind == ColumnKind::Bool => {
+                    Cell::Bool(row.try_get_unchecked::<i64, _>(i).map_err(|e| e.to_string())? != 0)
+                }
+                "INTEGER" => Cell::Int(row.try_get_unchecked(i).map_err(|e| e.to_string())?),
+                "REAL" => Cell::Float(row.try_get_unchecked(i).map_err(|e| e.to_string())?),
+                "BLOB" => Cell::Bytes(row.try_get_unchecked(i).map_err(|e| e.to_string())?),
+                _ => {
+                    let text: String = row.try_get_unchecked(i).map_err(|e| e.to_string())?;
+                    match column.kind {
+                        ColumnKind::Json => Cell::from_text(ColumnKind::Json, &text),
+                        _ => Cell::Text(text),
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+/// Run a script and feed every row of every result set to `sink`
+pub async fn stream_query(
+    pool: &Pool<Sqlite>,
+    query: &str,
+    sink: &mut (dyn RowSink + Send),
+    cancel: &CancelFlag,
+) -> Result<StreamStats, String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    stream_rows::<Sqlite>(&mut conn, query, sink, cancel, stream_column, decode_row).await
+}
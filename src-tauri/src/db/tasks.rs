@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Cancellation flag shared between a long-running command and `cancel_task`
+#[derive(Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Long-running commands (exports, imports, ...) currently in flight, by task id.
+///
+/// The frontend picks the task id, so it can cancel a task and match its
+/// progress events before the command returns.
+#[derive(Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<HashMap<String, CancelFlag>>,
+}
+
+impl TaskRegistry {
+    pub fn start(&self, task_id: &str) -> Result<CancelFlag, String> {
+        let mut tasks = self.tasks.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        if tasks.contains_key(task_id) {
+            return Err(format!("Task {} is already running", task_id));
+        }
+        let flag = CancelFlag::default();
+        tasks.insert(task_id.to_string(), flag.clone());
+        Ok(flag)
+    }
+
+    /// Request cancellation; returns false if no such task is running
+    pub fn cancel(&self, task_id: &str) -> Result<bool, String> {
+        let tasks = self.tasks.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        Ok(tasks.get(task_id).map(CancelFlag::cancel).is_some())
+    }
+
+    pub fn finish(&self, task_id: &str) {
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.remove(task_id);
+        }
+    }
+}
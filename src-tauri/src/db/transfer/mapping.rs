@@ -0,0 +1,236 @@
+// Column type mapping between engines
+//
+// A source column type is classified into a `Family`, keeping its length or
+// precision, and the family is then spelled in the target dialect. Types
+// without a counterpart (arrays, enums, ranges, network types...) become text,
+// which every engine can hold. Between two connections of the same engine the
+// type is kept as declared.
+
+use crate::db::dialect::Dialect;
+
+/// What a column type holds, independent of the engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Family {
+    Bool,
+    SmallInt,
+    Int,
+    BigInt,
+    /// Precision and scale as written, e.g. `10,2`
+    Decimal(Option<String>),
+    Real,
+    Double,
+    VarChar(Option<u32>),
+    Char(Option<u32>),
+    Text,
+    Bytes,
+    Date,
+    Time,
+    /// Fractional second digits
+    Timestamp(Option<u32>),
+    TimestampTz(Option<u32>),
+    Json,
+    Uuid,
+}
+
+/// Lowercase type name without its modifiers, and the modifiers:
+/// `timestamp(3) with time zone` is `("timestamp with time zone", Some("3"))`
+fn split(data_type: &str) -> (String, Option<String>) {
+    let lower = data_type.trim().to_ascii_lowercase();
+    match (lower.find('('), lower.find(')')) {
+        (Some(open), Some(close)) if open < close => {
+            let base = format!("{} {}", &lower[..open], &lower[close + 1..]);
+            let args = lower[open + 1..close].replace(' ', "");
+            (base.split_whitespace().collect::<Vec<_>>().join(" "), Some(args))
+        }
+        _ => (lower.split_whitespace().collect::<Vec<_>>().join(" "), None),
+    }
+}
+
+fn postgres_family(base: &str, args: Option<String>) -> Family {
+    let length = args.as_deref().and_then(|a| a.parse().ok());
+    match base {
+        "boolean" => Family::Bool,
+        "smallint" => Family::SmallInt,
+        "integer" => Family::Int,
+        "bigint" => Family::BigInt,
+        "numeric" => Family::Decimal(args),
+        "money" => Family::Decimal(Some("19,2".to_string())),
+        "real" => Family::Real,
+        "double precision" => Family::Double,
+        "character varying" => Family::VarChar(length),
+        "character" => Family::Char(length),
+        "bytea" => Family::Bytes,
+        "date" => Family::Date,
+        "time without time zone" | "time with time zone" => Family::Time,
+        "timestamp without time zone" => Family::Timestamp(length),
+        "timestamp with time zone" => Family::TimestampTz(length),
+        "json" | "jsonb" => Family::Json,
+        "uuid" => Family::Uuid,
+        _ => Family::Text,
+    }
+}
+
+fn mysql_family(base: &str, args: Option<String>) -> Family {
+    let unsigned = base.contains("unsigned");
+    let name = base.split(' ').next().unwrap_or_default();
+    let length = args.as_deref().and_then(|a| a.parse().ok());
+    match name {
+        "tinyint" if args.as_deref() == Some("1") => Family::Bool,
+        "bool" | "boolean" => Family::Bool,
+        "tinyint" | "year" => Family::SmallInt,
+        "smallint" if unsigned => Family::Int,
+        "smallint" => Family::SmallInt,
+        "mediumint" => Family::Int,
+        "int" | "integer" if unsigned => Family::BigInt,
+        "int" | "integer" => Family::Int,
+        "bigint" if unsigned => Family::Decimal(Some("20,0".to_string())),
+        "bigint" => Family::BigInt,
+        "decimal" | "numeric" | "dec" | "fixed" => Family::Decimal(args),
+        "float" => Family::Real,
+        "double" | "real" => Family::Double,
+        "varchar" => Family::VarChar(length),
+        "char" => Family::Char(length),
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" | "bit" => Family::Bytes,
+        "date" => Family::Date,
+        "time" => Family::Time,
+        // TIMESTAMP values are read in the session time zone, without an offset
+        "datetime" | "timestamp" => Family::Timestamp(length),
+        "json" => Family::Json,
+        _ => Family::Text,
+    }
+}
+
+/// SQLite's declared types are free-form; classify them the way SQLite
+/// derives column affinity, refined with the common type names
+fn sqlite_family(base: &str, args: Option<String>) -> Family {
+    let name = base.to_ascii_uppercase();
+    let length = args.as_deref().and_then(|a| a.parse().ok());
+    if name.contains("INT") {
+        Family::BigInt
+    } else if name.contains("BOOL") {
+        Family::Bool
+    } else if name.starts_with("VARCHAR") || name.starts_with("CHARACTER VARYING") || name.starts_with("NVARCHAR") {
+        Family::VarChar(length)
+    } else if name.starts_with("CHAR") || name.starts_with("NCHAR") {
+        Family::Char(length)
+    } else if name.contains("CHAR") || name.contains("CLOB") || name.contains("TEXT") {
+        Family::Text
+    } else if name.contains("BLOB") {
+        Family::Bytes
+    } else if name.contains("REAL") || name.contains("FLOA") || name.contains("DOUB") {
+        Family::Double
+    } else if name.contains("JSON") {
+        Family::Json
+    } else if name.contains("DATETIME") || name.contains("TIMESTAMP") {
+        Family::Timestamp(None)
+    } else if name.contains("DATE") {
+        Family::Date
+    } else if name.contains("TIME") {
+        Family::Time
+    } else if name.contains("DEC") || name.contains("NUMERIC") {
+        Family::Decimal(args)
+    } else if name.contains("UUID") {
+        Family::Uuid
+    } else {
+        Family::Text
+    }
+}
+
+fn postgres_type(family: Family) -> String {
+    match family {
+        Family::Bool => "boolean".to_string(),
+        Family::SmallInt => "smallint".to_string(),
+        Family::Int => "integer".to_string(),
+        Family::BigInt => "bigint".to_string(),
+        Family::Decimal(Some(args)) => format!("numeric({})", args),
+        Family::Decimal(None) => "numeric".to_string(),
+        Family::Real => "real".to_string(),
+        Family::Double => "double precision".to_string(),
+        Family::VarChar(Some(n)) => format!("varchar({})", n),
+        Family::Char(Some(n)) => format!("char({})", n),
+        Family::VarChar(None) | Family::Char(None) | Family::Text => "text".to_string(),
+        Family::Bytes => "bytea".to_string(),
+        Family::Date => "date".to_string(),
+        Family::Time => "time".to_string(),
+        Family::Timestamp(Some(p)) => format!("timestamp({})", p),
+        Family::Timestamp(None) => "timestamp".to_string(),
+        Family::TimestampTz(Some(p)) => format!("timestamp({}) with time zone", p),
+        Family::TimestampTz(None) => "timestamptz".to_string(),
+        Family::Json => "jsonb".to_string(),
+        Family::Uuid => "uuid".to_string(),
+    }
+}
+
+/// `key` columns must be indexable, which MySQL TEXT columns are not
+fn mysql_type(family: Family, key: bool) -> String {
+    let text = if key { "varchar(255)" } else { "longtext" };
+    match family {
+        Family::Bool => "tinyint(1)".to_string(),
+        Family::SmallInt => "smallint".to_string(),
+        Family::Int => "int".to_string(),
+        Family::BigInt => "bigint".to_string(),
+        Family::Decimal(Some(args)) => format!("decimal({})", args),
+        // An unconstrained numeric keeps as many digits as MySQL allows
+        Family::Decimal(None) => "decimal(65,30)".to_string(),
+        Family::Real => "float".to_string(),
+        Family::Double => "double".to_string(),
+        Family::VarChar(Some(n)) if n <= 16383 => format!("varchar({})", n),
+        Family::Char(Some(n)) if n <= 255 => format!("char({})", n),
+        Family::Char(Some(n)) if n <= 16383 => format!("varchar({})", n),
+        Family::VarChar(_) | Family::Char(_) | Family::Text => text.to_string(),
+        Family::Bytes if key => "varbinary(255)".to_string(),
+        Family::Bytes => "longblob".to_string(),
+        Family::Date => "date".to_string(),
+        Family::Time => "time(6)".to_string(),
+        // Other engines keep microseconds by default; MySQL would drop them
+        Family::Timestamp(p) | Family::TimestampTz(p) => format!("datetime({})", p.unwrap_or(6).min(6)),
+        Family::Json => "json".to_string(),
+        Family::Uuid => "char(36)".to_string(),
+    }
+}
+
+/// Type names that the app (and other tools) recognise, for SQLite's affinity rules
+fn sqlite_type(family: Family) -> String {
+    match family {
+        Family::Bool => "BOOLEAN".to_string(),
+        Family::SmallInt | Family::Int | Family::BigInt => "INTEGER".to_string(),
+        Family::Decimal(Some(args)) => format!("DECIMAL({})", args),
+        Family::Decimal(None) => "NUMERIC".to_string(),
+        Family::Real | Family::Double => "REAL".to_string(),
+        Family::VarChar(Some(n)) => format!("VARCHAR({})", n),
+        Family::Char(Some(n)) => format!("CHAR({})", n),
+        Family::VarChar(None) | Family::Char(None) | Family::Text | Family::Uuid => "TEXT".to_string(),
+        Family::Bytes => "BLOB".to_string(),
+        Family::Date => "DATE".to_string(),
+        Family::Time => "TIME".to_string(),
+        Family::Timestamp(_) | Family::TimestampTz(_) => "DATETIME".to_string(),
+        Family::Json => "JSON".to_string(),
+    }
+}
+
+/// Family of a column declared as `data_type` on `dialect`
+pub fn family(dialect: Dialect, data_type: &str) -> Family {
+    let (base, args) = split(data_type);
+    if base.ends_with("[]") {
+        return Family::Text;
+    }
+    match dialect {
+        Dialect::Postgres => postgres_family(&base, args),
+        Dialect::MySql => mysql_family(&base, args),
+        Dialect::Sqlite => sqlite_family(&base, args),
+    }
+}
+
+/// The type a column declared as `data_type` on `from` gets on `to`.
+/// `key` is set for primary key columns.
+pub fn map_type(from: Dialect, to: Dialect, data_type: &str, key: bool) -> String {
+    if from == to {
+        return data_type.to_string();
+    }
+    let family = family(from, data_type);
+    match to {
+        Dialect::Postgres => postgres_type(family),
+        Dialect::MySql => mysql_type(family, key),
+        Dialect::Sqlite => sqlite_type(family),
+    }
+}
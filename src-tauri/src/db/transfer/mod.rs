@@ -0,0 +1,394 @@
+// Table transfer
+//
+// `transfer_table` copies the rows of a table on one connection into a table
+// on another, which may run a different engine. The source is read with one
+// streaming SELECT over the text protocol, so the row filter is written into
+// it with literals instead of parameters. Rows are coerced to the target
+// columns' types and loaded in batches by the import `Loader`. The target
+// table can be created from the source columns, with their types mapped by
+// `mapping`.
+
+pub mod mapping;
+
+use std::time::{Duration, Instant};
+use futures::TryStreamExt;
+use sqlx::Row;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::alter;
+use crate::db::browse;
+use crate::db::dialect::Dialect;
+use crate::db::edit::Params;
+use crate::db::import::bulk::{Batch, Loader};
+use crate::db::import::coerce::{coerce, TargetKind};
+use crate::db::introspection;
+use crate::db::stream::{self, Cell};
+use crate::db::tasks::CancelFlag;
+use crate::db::types::{
+    ColumnDef, ColumnInfo, FilterJoin, FilterNode, LoadMethod, RejectedRow, RowStatus, SqlPool, TableChangePlan,
+    TableChangeRequest, TableDesign, TransferColumn, TransferMode, TransferProgress, TransferRequest, TransferSummary,
+};
+
+/// Rejected rows returned in the summary; the rest are only counted
+const MAX_REJECTED: usize = 1000;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Create the target table with the source columns' types mapped to the target engine
+async fn create_table_plan(
+    source: &SqlPool,
+    target: &SqlPool,
+    request: &TransferRequest,
+    table: &str,
+    columns: &[&ColumnInfo],
+) -> Result<TableChangePlan, String> {
+    let dialect = target.dialect();
+    // Postgres tells `Id` from `id`; MySQL and SQLite would refuse the table
+    if dialect != Dialect::Postgres {
+        for (i, column) in columns.iter().enumerate() {
+            if let Some(other) = columns[..i].iter().find(|c| c.name.eq_ignore_ascii_case(&column.name)) {
+                return Err(format!(
+                    "Columns {} and {} differ only in case, which the target does not allow",
+                    other.name, column.name
+                ));
+            }
+        }
+    }
+    let key = introspection::primary_key(source, request.source_schema.as_deref(), &request.source_table)
+        .await?
+        .unwrap_or_default();
+    // A key made of columns that are not copied would not be a key any more
+    let key_copied = key.iter().all(|k| columns.iter().any(|c| &c.name == k));
+    let definitions = columns
+        .iter()
+        .map(|column| {
+            let is_primary_key = key_copied && key.contains(&column.name);
+            ColumnDef {
+                id: column.name.clone(),
+                status: RowStatus::Added,
+                original_name: None,
+                name: column.name.clone(),
+                data_type: mapping::map_type(source.dialect(), dialect, &column.data_type, is_primary_key),
+                length: String::new(),
+                is_primary_key,
+                is_not_null: !column.is_nullable,
+                is_auto_increment: false,
+                default_value: String::new(),
+                comment: column.comment.clone().unwrap_or_default(),
+            }
+        })
+        .collect();
+    let change = TableChangeRequest {
+        schema: request.target_schema.clone(),
+        table: table.to_string(),
+        original: None,
+        design: TableDesign { columns: definitions, indexes: Vec::new(), foreign_keys: Vec::new() },
+    };
+    alter::plan(dialect, &change, &alter::PlanContext::default())
+}
+
+async fn apply_plan(target: &SqlPool, plan: &TableChangePlan) -> Result<(), String> {
+    match target {
+        SqlPool::Postgres(pool) => alter::apply_postgres(pool, plan).await,
+        SqlPool::MySql(pool) => alter::apply_mysql(pool, plan).await,
+        SqlPool::Sqlite(pool) => alter::apply_sqlite(pool, plan).await,
+    }
+}
+
+/// Run a statement without results on the target
+async fn execute(pool: &SqlPool, sql: &str) -> Result<(), String> {
+    match pool {
+        SqlPool::Postgres(pool) => sqlx::raw_sql(sql).execute(pool).await.map(|_| ()),
+        SqlPool::MySql(pool) => sqlx::raw_sql(sql).execute(pool).await.map(|_| ()),
+        SqlPool::Sqlite(pool) => sqlx::raw_sql(sql).execute(pool).await.map(|_| ()),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Loads the streamed source rows into the target
+struct Copier<'a> {
+    app: &'a AppHandle,
+    task_id: &'a str,
+    cancel: &'a CancelFlag,
+    loader: Loader,
+    /// Target column kinds, in select order
+    kinds: Vec<(String, TargetKind)>,
+    batch: Batch,
+    batch_size: usize,
+    total_rows: u64,
+    summary: TransferSummary,
+    last_progress: Instant,
+}
+
+impl Copier<'_> {
+    fn reject(&mut self, line: u64, reason: String) {
+        self.summary.rows_rejected += 1;
+        if self.summary.rejected.len() < MAX_REJECTED {
+            self.summary.rejected.push(RejectedRow { line, reason });
+        }
+    }
+
+    fn emit_progress(&mut self) {
+        self.last_progress = Instant::now();
+        let _ = self.app.emit(
+            "transfer-progress",
+            TransferProgress {
+                task_id: self.task_id.to_string(),
+                rows_read: self.summary.rows_read,
+                rows_written: self.summary.rows_written,
+                rows_rejected: self.summary.rows_rejected,
+                total_rows: self.total_rows,
+            },
+        );
+    }
+
+    /// Take one source row; returns false once the task is cancelled
+    async fn row(&mut self, cells: Vec<Cell>) -> Result<bool, String> {
+        if self.cancel.is_cancelled() {
+            self.summary.cancelled = true;
+            return Ok(false);
+        }
+        self.summary.rows_read += 1;
+        let line = self.summary.rows_read;
+        let mut values = Vec::with_capacity(cells.len());
+        for ((name, kind), cell) in self.kinds.iter().zip(cells) {
+            match coerce(*kind, cell) {
+                Ok(cell) => values.push(cell),
+                Err(e) => {
+                    let reason = format!("{}: {}", name, e);
+                    self.reject(line, reason);
+                    return Ok(true);
+                }
+            }
+        }
+        self.batch.push((line, values));
+        if self.batch.len() >= self.batch_size {
+            self.flush().await?;
+        }
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            self.emit_progress();
+        }
+        Ok(true)
+    }
+
+    async fn flush(&mut self) -> Result<(), String> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let rejected = self
+            .loader
+            .load(&self.batch)
+            .await
+            .map_err(|e| self.loader.describe_error(e, self.summary.rows_written, "transfer"))?;
+        self.summary.rows_written += (self.batch.len() - rejected.len()) as u64;
+        for row in rejected {
+            self.reject(row.line, row.reason);
+        }
+        self.batch.clear();
+        Ok(())
+    }
+}
+
+/// Copy the rows of `request.source_table` on `source` into the target table on `target`
+pub async fn transfer_table(
+    app: &AppHandle,
+    source: &SqlPool,
+    target: &SqlPool,
+    same_connection: bool,
+    request: &TransferRequest,
+    task_id: &str,
+    cancel: &CancelFlag,
+) -> Result<TransferSummary, String> {
+    let start = Instant::now();
+    let source_schema = request.source_schema.as_deref();
+    let target_schema = request.target_schema.as_deref();
+    let table = request.target_table.as_deref().unwrap_or(&request.source_table);
+    if table.trim().is_empty() {
+        return Err("A target table is required".to_string());
+    }
+    if same_connection && source_schema == target_schema && table == request.source_table {
+        return Err("The source and the target are the same table".to_string());
+    }
+
+    let source_columns = introspection::columns(source, source_schema, &request.source_table).await?;
+    if source_columns.is_empty() {
+        return Err(format!("Table {} not found", request.source_table));
+    }
+    let columns: Vec<&ColumnInfo> = if request.columns.is_empty() {
+        source_columns.iter().collect()
+    } else {
+        request
+            .columns
+            .iter()
+            .map(|name| {
+                source_columns
+                    .iter()
+                    .find(|c| &c.name == name)
+                    .ok_or_else(|| format!("Column {} does not exist in table {}", name, request.source_table))
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    // The source query, built first so a bad filter fails before the target is touched
+    let source_dialect = source.dialect();
+    let filter = request
+        .filter
+        .clone()
+        .unwrap_or(FilterNode::Group { join: FilterJoin::And, children: Vec::new() });
+    let total_rows = browse::count_rows(source, source_schema, &request.source_table, &filter).await?;
+    let mut params = Params::inline(source_dialect);
+    let condition =
+        browse::filter_condition(source_dialect, source_schema, &request.source_table, &source_columns, &filter, &mut params)?;
+    let select = format!(
+        "SELECT {} FROM {} WHERE {}",
+        columns.iter().map(|c| source_dialect.quote_ident(&c.name)).collect::<Vec<_>>().join(", "),
+        source_dialect.qualified(source_schema, &request.source_table),
+        condition
+    );
+
+    // Prepare the target table
+    let target_dialect = target.dialect();
+    let qualified = target_dialect.qualified(target_schema, table);
+    let exists = !introspection::columns(target, target_schema, table).await?.is_empty();
+    let mut table_created = false;
+    match request.mode {
+        TransferMode::Replace => {
+            // The new definition is planned before the old table goes; on
+            // Postgres and SQLite the drop also rolls back with a failed create
+            let mut plan = create_table_plan(source, target, request, table, &columns).await?;
+            if exists {
+                plan.statements.insert(0, format!("DROP TABLE {}", qualified));
+            }
+            apply_plan(target, &plan).await?;
+            table_created = true;
+        }
+        _ if !exists && request.create_table => {
+            let plan = create_table_plan(source, target, request, table, &columns).await?;
+            apply_plan(target, &plan).await?;
+            table_created = true;
+        }
+        _ if !exists => return Err(format!("Table {} does not exist in the target", table)),
+        TransferMode::Truncate => {
+            let sql = match target {
+                SqlPool::Sqlite(_) => format!("DELETE FROM {}", qualified),
+                SqlPool::Postgres(_) | SqlPool::MySql(_) => format!("TRUNCATE TABLE {}", qualified),
+            };
+            execute(target, &sql).await?;
+        }
+        TransferMode::Append => {}
+    }
+
+    // Match the copied columns to the target's
+    let target_columns = introspection::columns(target, target_schema, table).await?;
+    let mut kinds = Vec::with_capacity(columns.len());
+    let mut mapped = Vec::with_capacity(columns.len());
+    for column in &columns {
+        let target_column = target_columns
+            .iter()
+            .find(|c| c.name == column.name)
+            .or_else(|| target_columns.iter().find(|c| c.name.eq_ignore_ascii_case(&column.name)))
+            .ok_or_else(|| format!("Column {} does not exist in the target table {}", column.name, table))?;
+        if target_column.generated.is_some() {
+            return Err(format!("Column {} is generated in the target table and cannot be written", target_column.name));
+        }
+        kinds.push((target_column.name.clone(), TargetKind::from_type(&target_column.data_type)));
+        mapped.push(TransferColumn {
+            name: target_column.name.clone(),
+            source_type: column.data_type.clone(),
+            target_type: target_column.data_type.clone(),
+        });
+    }
+
+    let names: Vec<String> = kinds.iter().map(|(name, _)| name.clone()).collect();
+    let mut summary = TransferSummary {
+        rows_read: 0,
+        rows_written: 0,
+        rows_rejected: 0,
+        rejected: Vec::new(),
+        columns: mapped,
+        table_created,
+        method: LoadMethod::Insert,
+        cancelled: false,
+        execution_time_ms: 0,
+    };
+
+    // Within one SQLite database a pending read blocks the loader's transaction
+    // once it has to spill to the file, so the copy is a single statement
+    if let (true, SqlPool::Sqlite(pool)) = (same_connection, target) {
+        let sql = format!(
+            "INSERT INTO {} ({}) {}",
+            qualified,
+            names.iter().map(|name| target_dialect.quote_ident(name)).collect::<Vec<_>>().join(", "),
+            select
+        );
+        let result = sqlx::raw_sql(&sql).execute(pool).await.map_err(|e| e.to_string())?;
+        summary.rows_read = result.rows_affected();
+        summary.rows_written = result.rows_affected();
+        summary.execution_time_ms = start.elapsed().as_millis();
+        return Ok(summary);
+    }
+
+    let loader = Loader::new(target, target_schema, table, &names).await?;
+    let batch_size = request.batch_size.max(1);
+    summary.method = loader.method();
+    let mut copier = Copier {
+        app,
+        task_id,
+        cancel,
+        summary,
+        loader,
+        kinds,
+        batch: Vec::with_capacity(batch_size),
+        batch_size,
+        total_rows,
+        last_progress: Instant::now(),
+    };
+    copier.emit_progress();
+
+    match source {
+        SqlPool::Postgres(pool) => {
+            let mut rows = sqlx::raw_sql(&select).fetch(pool);
+            let mut columns = None;
+            while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+                let columns = columns.get_or_insert_with(|| {
+                    row.columns().iter().map(stream::postgres::stream_column).collect::<Vec<_>>()
+                });
+                if !copier.row(stream::postgres::decode_row(&row, columns)?).await? {
+                    break;
+                }
+            }
+        }
+        SqlPool::MySql(pool) => {
+            let mut rows = sqlx::raw_sql(&select).fetch(pool);
+            let mut columns = None;
+            while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+                let columns = columns.get_or_insert_with(|| {
+                    row.columns().iter().map(stream::mysql::stream_column).collect::<Vec<_>>()
+                });
+                if !copier.row(stream::mysql::decode_row(&row, columns)?).await? {
+                    break;
+                }
+            }
+        }
+        SqlPool::Sqlite(pool) => {
+            let mut rows = sqlx::raw_sql(&select).fetch(pool);
+            let mut columns = None;
+            while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+                let columns = columns.get_or_insert_with(|| {
+                    row.columns().iter().map(stream::sqlite::stream_column).collect::<Vec<_>>()
+                });
+                if !copier.row(stream::sqlite::decode_row(&row, columns)?).await? {
+                    break;
+                }
+            }
+        }
+    }
+    if !copier.summary.cancelled {
+        copier.flush().await?;
+    }
+    copier.summary.method = copier.loader.method();
+    copier.emit_progress();
+    let mut summary = copier.summary;
+    copier.loader.finish().await?;
+    summary.execution_time_ms = start.elapsed().as_millis();
+    Ok(summary)
+}
@@ -3,6 +3,10 @@ use sqlx::{Pool, Postgres, MySql, Sqlite};
 use std::sync::Mutex;
 use std::collections::HashMap;
 
+use crate::db::dialect::Dialect;
+use crate::db::schema_cache::SchemaCache;
+use crate::db::tasks::TaskRegistry;
+
 /// Application state holding all database connection pools
 #[derive(Default)]
 pub struct AppState {
@@ -10,6 +14,66 @@ pub struct AppState {
     pub mysql_pools: Mutex<HashMap<String, Pool<MySql>>>,
     pub sqlite_pools: Mutex<HashMap<String, Pool<Sqlite>>>,
     pub redis_clients: Mutex<HashMap<String, redis::Client>>,
+    pub schema_cache: SchemaCache,
+    pub tasks: TaskRegistry,
+}
+
+/// A cloned handle to one of the SQL connection pools
+#[derive(Clone)]
+pub enum SqlPool {
+    Postgres(Pool<Postgres>),
+    MySql(Pool<MySql>),
+    Sqlite(Pool<Sqlite>),
+}
+
+impl SqlPool {
+    pub fn dialect(&self) -> Dialect {
+        match self {
+            SqlPool::Postgres(_) => Dialect::Postgres,
+            SqlPool::MySql(_) => Dialect::MySql,
+            SqlPool::Sqlite(_) => Dialect::Sqlite,
+        }
+    }
+}
+
+impl AppState {
+    /// Look up the SQL pool registered under `connection_id`, whatever its engine
+    pub fn sql_pool(&self, connection_id: &str) -> Result<SqlPool, String> {
+        {
+            let pools = self.pg_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+            if let Some(pool) = pools.get(connection_id) {
+                return Ok(SqlPool::Postgres(pool.clone()));
+            }
+        }
+        {
+            let pools = self.mysql_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+            if let Some(pool) = pools.get(connection_id) {
+                return Ok(SqlPool::MySql(pool.clone()));
+            }
+        }
+        let pools = self.sqlite_pools.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        if let Some(pool) = pools.get(connection_id) {
+            return Ok(SqlPool::Sqlite(pool.clone()));
+        }
+        Err("Connection not found".to_string())
+    }
+
+    /// Look up a SQLite pool, for the commands that only apply to SQLite
+    pub fn sqlite_pool(&self, connection_id: &str) -> Result<Pool<Sqlite>, String> {
+        match self.sql_pool(connection_id)? {
+            SqlPool::Sqlite(pool) => Ok(pool),
+            _ => Err("This operation is only available for SQLite connections".to_string()),
+        }
+    }
+
+    /// Look up the Redis client registered under `connection_id`
+    pub fn redis_client(&self, connection_id: &str) -> Result<redis::Client, String> {
+        let clients = self.redis_clients.lock().map_err(|_| "Failed to lock mutex".to_string())?;
+        clients
+            .get(connection_id)
+            .cloned()
+            .ok_or_else(|| "Redis connection not found".to_string())
+    }
 }
 
 /// Result of a database query
@@ -20,3 +84,1828 @@ pub struct QueryResult {
     pub execution_time_ms: u128,
     pub affected_rows: u64,
 }
+
+/// A table column, as read from the engine's catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub ordinal: i64,
+    /// Full type including length/precision, e.g. `character varying(20)` or `int(10) unsigned`
+    pub data_type: String,
+    pub is_nullable: bool,
+    /// Default expression as SQL text
+    pub default_value: Option<String>,
+    pub is_auto_increment: bool,
+    /// `ALWAYS` or `BY DEFAULT` for Postgres identity columns
+    pub identity: Option<String>,
+    /// Expression of a generated (computed) column
+    pub generated: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// An index on a table, as read from the engine's catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub name: String,
+    pub table: String,
+    /// Key columns in index order; expression keys are reported as their SQL text
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    /// Access method, e.g. `btree`, `gin`, `FULLTEXT`
+    pub index_type: String,
+    /// WHERE clause of a partial index
+    pub predicate: Option<String>,
+    /// Full CREATE INDEX statement when the engine can provide one
+    pub definition: Option<String>,
+}
+
+/// A table-level constraint other than a foreign key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstraintInfo {
+    pub name: String,
+    pub table: String,
+    /// One of `PRIMARY KEY`, `UNIQUE`, `CHECK` or `EXCLUDE`
+    pub constraint_type: String,
+    pub columns: Vec<String>,
+    /// Constraint body, e.g. the CHECK expression
+    pub definition: Option<String>,
+}
+
+/// A foreign key with its referential actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyInfo {
+    pub name: String,
+    /// Schema of the referencing `table`
+    pub schema: Option<String>,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub referenced_schema: Option<String>,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+/// A table, view or other relation of a schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub schema: Option<String>,
+    pub name: String,
+    /// `TABLE`, `VIEW`, `MATERIALIZED VIEW`, `PARTITIONED TABLE` or `FOREIGN TABLE`
+    pub table_type: String,
+    pub comment: Option<String>,
+    /// Row count from catalog statistics, when the engine keeps one
+    pub estimated_rows: Option<i64>,
+}
+
+/// A view or materialized view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewInfo {
+    pub schema: Option<String>,
+    pub name: String,
+    pub is_materialized: bool,
+    /// The view's SELECT statement
+    pub definition: Option<String>,
+}
+
+/// A stored function, procedure or aggregate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutineInfo {
+    pub schema: Option<String>,
+    pub name: String,
+    /// `FUNCTION`, `PROCEDURE`, `AGGREGATE` or `WINDOW`
+    pub kind: String,
+    /// Argument list as it appears in the signature, without parentheses
+    pub arguments: String,
+    pub return_type: Option<String>,
+    pub language: Option<String>,
+    pub body: Option<String>,
+}
+
+/// A trigger attached to a table or view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerInfo {
+    pub schema: Option<String>,
+    pub name: String,
+    pub table: String,
+    /// `BEFORE`, `AFTER` or `INSTEAD OF`
+    pub timing: String,
+    /// Any of `INSERT`, `UPDATE`, `DELETE`, `TRUNCATE`
+    pub events: Vec<String>,
+    /// `ROW` or `STATEMENT`
+    pub orientation: String,
+    /// Full CREATE TRIGGER statement when the engine can provide one
+    pub definition: Option<String>,
+    /// Trigger body, or the source of the trigger function on Postgres
+    pub body: Option<String>,
+}
+
+/// A sequence, or an AUTO_INCREMENT counter on engines without sequences
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceInfo {
+    pub schema: Option<String>,
+    pub name: String,
+    pub data_type: Option<String>,
+    /// Last value handed out, `None` if the sequence was never used
+    pub current_value: Option<i64>,
+    pub increment: i64,
+    pub start_value: Option<i64>,
+    pub min_value: Option<i64>,
+    pub max_value: Option<i64>,
+    pub cycle: bool,
+    /// `table.column` the sequence belongs to, if any
+    pub owned_by: Option<String>,
+}
+
+/// A Postgres enum type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumTypeInfo {
+    pub schema: Option<String>,
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+/// A Postgres domain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainInfo {
+    pub schema: Option<String>,
+    pub name: String,
+    pub base_type: String,
+    pub not_null: bool,
+    pub default_value: Option<String>,
+    /// CHECK constraint definitions
+    pub checks: Vec<String>,
+}
+
+/// An installed Postgres extension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionInfo {
+    pub name: String,
+    pub version: String,
+    pub schema: String,
+    pub description: Option<String>,
+}
+
+/// Kind of database object, used to select what to script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectType {
+    Table,
+    View,
+    Function,
+    Procedure,
+    Sequence,
+    Trigger,
+}
+
+impl ObjectType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObjectType::Table => "table",
+            ObjectType::View => "view",
+            ObjectType::Function => "function",
+            ObjectType::Procedure => "procedure",
+            ObjectType::Sequence => "sequence",
+            ObjectType::Trigger => "trigger",
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Table designer definitions
+//
+// These mirror the TableDesigner types on the frontend (camelCase) so the
+// designer state can be passed to the backend as-is.
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowStatus {
+    Clean,
+    Modified,
+    Added,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnDef {
+    pub id: String,
+    pub status: RowStatus,
+    #[serde(default)]
+    pub original_name: Option<String>,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: String,
+    #[serde(default)]
+    pub length: String,
+    #[serde(default)]
+    pub is_primary_key: bool,
+    #[serde(default)]
+    pub is_not_null: bool,
+    #[serde(default)]
+    pub is_auto_increment: bool,
+    /// Raw SQL default expression; empty for none
+    #[serde(default)]
+    pub default_value: String,
+    #[serde(default)]
+    pub comment: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexDef {
+    pub id: String,
+    pub status: RowStatus,
+    pub name: String,
+    /// `NORMAL`, `UNIQUE` or `FULLTEXT`
+    #[serde(rename = "type")]
+    pub index_type: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyDef {
+    pub id: String,
+    pub status: RowStatus,
+    pub name: String,
+    pub source_column: String,
+    pub ref_table: String,
+    pub ref_column: String,
+    pub on_delete: String,
+    pub on_update: String,
+}
+
+/// Full designer state of a table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableDesign {
+    pub columns: Vec<ColumnDef>,
+    #[serde(default)]
+    pub indexes: Vec<IndexDef>,
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKeyDef>,
+}
+
+/// Request to turn `original` into `design`; a missing `original` means a new table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableChangeRequest {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    #[serde(default)]
+    pub original: Option<TableDesign>,
+    pub design: TableDesign,
+}
+
+/// Statements produced by `plan_table_changes`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableChangePlan {
+    /// Statements that must run before the transaction (e.g. SQLite pragmas)
+    pub pre_statements: Vec<String>,
+    /// Statements run inside a single transaction
+    pub statements: Vec<String>,
+    /// Statements that must run after the transaction, whether it succeeded or not
+    pub post_statements: Vec<String>,
+    /// Destructive or non-transactional steps the user should be told about
+    pub warnings: Vec<String>,
+    pub applied: bool,
+}
+
+// ----------------------------------------------------------------------------
+// Schema snapshot
+// ----------------------------------------------------------------------------
+
+/// A table or view with the metadata needed for autocompletion and AI context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSnapshot {
+    pub name: String,
+    pub table_type: String,
+    pub comment: Option<String>,
+    pub columns: Vec<ColumnInfo>,
+    pub foreign_keys: Vec<ForeignKeyInfo>,
+}
+
+/// Everything cached for one schema of a connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshot {
+    pub schema: Option<String>,
+    pub tables: Vec<TableSnapshot>,
+    /// Function and procedure names
+    pub routines: Vec<String>,
+    /// When the snapshot was read from the catalog, in milliseconds since the Unix epoch
+    pub loaded_at_ms: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Export
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    /// A single JSON array of row objects
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Batched INSERT statements
+    Sql,
+    /// Apache Parquet, typed from the result's column types
+    Parquet,
+    /// Arrow IPC file format (Feather v2)
+    Arrow,
+    /// Excel workbook, one worksheet per result set
+    Xlsx,
+    Markdown,
+    Html,
+}
+
+/// Text table style for `render_table` and the Markdown/HTML exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    Markdown,
+    Html,
+}
+
+/// When CSV fields are wrapped in quote characters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvQuoting {
+    /// Only fields containing the delimiter, quote character or a line break
+    #[default]
+    Necessary,
+    Always,
+    /// Every field except numbers and booleans
+    NonNumeric,
+    Never,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub quote: char,
+    pub quoting: CsvQuoting,
+    pub header: bool,
+    /// Text written for NULL values
+    pub null_token: String,
+    /// Use `\r\n` instead of `\n` between records
+    pub crlf: bool,
+    /// `utf-8`, `utf-8-bom`, `utf-16le`, `utf-16be` or any WHATWG encoding label
+    pub encoding: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            quote: '"',
+            quoting: CsvQuoting::Necessary,
+            header: true,
+            null_token: String::new(),
+            crlf: false,
+            encoding: "utf-8".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SqlExportOptions {
+    /// Target table of the generated INSERT statements
+    pub table: String,
+    pub schema: Option<String>,
+    /// Dialect to write; defaults to the source connection's
+    pub dialect: Option<Dialect>,
+    /// Rows per INSERT statement
+    pub batch_size: usize,
+}
+
+impl Default for SqlExportOptions {
+    fn default() -> Self {
+        SqlExportOptions {
+            table: String::new(),
+            schema: None,
+            dialect: None,
+            batch_size: 100,
+        }
+    }
+}
+
+/// Column chunk compression codec of a Parquet export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParquetCompression {
+    None,
+    #[default]
+    Snappy,
+    Gzip,
+    Lz4,
+    Zstd,
+    Brotli,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ParquetOptions {
+    pub compression: ParquetCompression,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub csv: CsvOptions,
+    #[serde(default)]
+    pub sql: SqlExportOptions,
+    #[serde(default)]
+    pub parquet: ParquetOptions,
+}
+
+/// Result of `export_query`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub rows: u64,
+    pub bytes: u64,
+    /// The export was cancelled and the partial file removed
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `export-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub task_id: String,
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Import
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    /// A JSON array of objects (or of arrays)
+    Json,
+    /// One JSON object (or array) per line
+    Ndjson,
+}
+
+/// How a CSV file is laid out; detected by `preview_import` and editable by the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub quote: char,
+    /// The first record holds column names
+    pub header: bool,
+    /// Unquoted fields equal to this text are NULL (empty: empty fields are NULL)
+    pub null_token: String,
+    pub encoding: String,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: ',',
+            quote: '"',
+            header: true,
+            null_token: String::new(),
+            encoding: "utf-8".to_string(),
+        }
+    }
+}
+
+/// Type inferred for a source column from the previewed values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InferredType {
+    Boolean,
+    Integer,
+    Decimal,
+    Date,
+    Timestamp,
+    Json,
+    Text,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportColumnPreview {
+    pub name: String,
+    pub inferred_type: InferredType,
+    /// Suggested column type for a new table on the previewed connection
+    pub sql_type: String,
+    pub nullable: bool,
+}
+
+/// Result of `preview_import`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub format: ImportFormat,
+    /// Detected dialect, for CSV files
+    pub csv: Option<CsvDialect>,
+    pub columns: Vec<ImportColumnPreview>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub file_size: u64,
+}
+
+/// Where a target column's values come from. Source columns that are not
+/// mapped (or mapped with `skip`) are not imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ColumnMapping {
+    /// Source column `source` (by position) goes to `target`
+    #[serde(rename_all = "camelCase")]
+    Column {
+        source: usize,
+        target: String,
+        /// Column type when the table is created; defaults to the inferred type
+        #[serde(default)]
+        data_type: Option<String>,
+    },
+    /// Every row gets `value` in `target`
+    #[serde(rename_all = "camelCase")]
+    Constant {
+        target: String,
+        value: serde_json::Value,
+        #[serde(default)]
+        data_type: Option<String>,
+    },
+    Skip { source: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRequest {
+    pub path: String,
+    pub format: ImportFormat,
+    #[serde(default)]
+    pub csv: CsvDialect,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    /// Create `table` from the mapping instead of importing into an existing table
+    #[serde(default)]
+    pub create_table: bool,
+    pub columns: Vec<ColumnMapping>,
+    /// Rows per batch (per transaction, except on SQLite where the whole
+    /// import runs in one transaction)
+    #[serde(default = "default_import_batch_size")]
+    pub batch_size: usize,
+}
+
+fn default_import_batch_size() -> usize {
+    1000
+}
+
+/// A source row that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedRow {
+    /// Line of the file where the row starts
+    pub line: u64,
+    pub reason: String,
+}
+
+/// How `import_file` loaded the rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadMethod {
+    /// Postgres `COPY ... FROM STDIN`
+    Copy,
+    /// MySQL `LOAD DATA LOCAL INFILE`
+    #[serde(rename = "load_data")]
+    LoadData,
+    /// Multi-row INSERT statements
+    Insert,
+    /// One prepared INSERT per row in a single transaction (SQLite)
+    Prepared,
+}
+
+/// Result of `import_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub rows_read: u64,
+    pub rows_imported: u64,
+    pub rows_rejected: u64,
+    /// The first rejected rows, with the reason each was rejected
+    pub rejected: Vec<RejectedRow>,
+    pub table_created: bool,
+    /// Falls back to `insert` when COPY could not be used
+    pub method: LoadMethod,
+    /// The import was cancelled; batches committed before that are kept
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `import-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub task_id: String,
+    pub rows_read: u64,
+    pub rows_imported: u64,
+    pub rows_rejected: u64,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+}
+
+// ----------------------------------------------------------------------------
+// SQL scripts
+// ----------------------------------------------------------------------------
+
+/// What `execute_sql_file` does when a statement fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    #[default]
+    Stop,
+    /// Log the error and run the next statement
+    Continue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlFileRequest {
+    pub path: String,
+    /// Any WHATWG encoding label; UTF-8 by default
+    #[serde(default)]
+    pub encoding: String,
+    #[serde(default)]
+    pub on_error: OnError,
+}
+
+/// A statement of a script that failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementError {
+    /// Line where the statement starts
+    pub line: u64,
+    /// The statement, shortened for display
+    pub statement: String,
+    pub message: String,
+}
+
+/// Result of `execute_sql_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlFileSummary {
+    pub statements_executed: u64,
+    pub statements_failed: u64,
+    pub rows_affected: u64,
+    /// The first failed statements
+    pub errors: Vec<StatementError>,
+    /// The script stopped at a failed statement (`OnError::Stop`)
+    pub stopped: bool,
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `sql-file-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlFileProgress {
+    pub task_id: String,
+    pub statements_executed: u64,
+    pub statements_failed: u64,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    /// Line being executed
+    pub line: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Dump
+// ----------------------------------------------------------------------------
+
+/// What `dump_database` writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpContent {
+    /// Schema and data
+    #[default]
+    All,
+    Schema,
+    Data,
+}
+
+/// How table rows are written in a dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpDataFormat {
+    /// Multi-row INSERT statements
+    Insert,
+    /// `COPY ... FROM stdin` blocks (Postgres only)
+    Copy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpOptions {
+    pub path: String,
+    /// Postgres schema or MySQL database to dump. Every user schema on
+    /// Postgres and the connection's database on MySQL when omitted; SQLite
+    /// dumps `main` unless an attached schema is named.
+    #[serde(default)]
+    pub schema: Option<String>,
+    /// Glob patterns (`*`, `?`) matched against `name` or `schema.name` of
+    /// tables and views; everything is dumped when empty
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub content: DumpContent,
+    /// COPY on Postgres and INSERT elsewhere when omitted
+    #[serde(default)]
+    pub data_format: Option<DumpDataFormat>,
+    /// Drop existing objects before creating them
+    #[serde(default)]
+    pub drop_objects: bool,
+    /// Rows per INSERT statement
+    #[serde(default = "default_import_batch_size")]
+    pub batch_size: usize,
+}
+
+/// Result of `dump_database`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpSummary {
+    pub path: String,
+    /// Tables whose rows were written
+    pub tables: usize,
+    pub rows: u64,
+    pub bytes: u64,
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `dump-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpProgress {
+    pub task_id: String,
+    /// Table whose rows are being written
+    pub table: Option<String>,
+    pub tables_done: usize,
+    pub tables_total: usize,
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+// ----------------------------------------------------------------------------
+// SQLite maintenance
+// ----------------------------------------------------------------------------
+
+/// Result of `backup_sqlite`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSummary {
+    pub path: String,
+    pub bytes: u64,
+    pub execution_time_ms: u128,
+}
+
+/// One problem reported by `PRAGMA integrity_check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub message: String,
+    /// Table, index or `table.column` the message names
+    pub object: Option<String>,
+    /// B-tree page the message refers to
+    pub page: Option<i64>,
+}
+
+/// Result of `sqlite_integrity_check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    pub issues: Vec<IntegrityIssue>,
+    /// The check stopped after `max_errors` issues
+    pub truncated: bool,
+}
+
+/// A row whose foreign key has no parent row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    /// None for WITHOUT ROWID tables
+    pub rowid: Option<i64>,
+    pub parent: String,
+    pub columns: Vec<String>,
+    /// Empty when the key references the parent's primary key implicitly
+    pub parent_columns: Vec<String>,
+}
+
+/// Pages used by one table or index, from the dbstat virtual table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub name: String,
+    /// `table`, `index` or `schema` (the sqlite_schema table itself)
+    pub object_type: String,
+    /// Table an index belongs to
+    pub table: Option<String>,
+    pub pages: i64,
+    pub size_bytes: i64,
+    pub payload_bytes: i64,
+    pub unused_bytes: i64,
+}
+
+/// Result of `sqlite_storage_usage`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageReport {
+    pub page_size: i64,
+    pub page_count: i64,
+    pub freelist_pages: i64,
+    pub size_bytes: i64,
+    /// Largest first
+    pub objects: Vec<StorageUsage>,
+}
+
+/// Result of `optimize_sqlite`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizeSummary {
+    pub size_before: i64,
+    pub size_after: i64,
+    pub execution_time_ms: u128,
+}
+
+// ----------------------------------------------------------------------------
+// SQLite connection options
+// ----------------------------------------------------------------------------
+
+/// A database file attached under `alias`, listed as a schema next to `main`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteAttachment {
+    pub alias: String,
+    pub path: String,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// A loadable extension such as spatialite or sqlean
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteExtension {
+    pub path: String,
+    /// Derived from the file name by SQLite when omitted
+    #[serde(default)]
+    pub entry_point: Option<String>,
+}
+
+/// Key and cipher settings of a SQLCipher database. Settings left out use
+/// the defaults of the bundled SQLCipher (4.x) unless `compatibility` names
+/// an older major version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteCipher {
+    /// Passphrase, or a raw key written as `x'<64 hex digits>'`
+    pub key: String,
+    #[serde(default)]
+    pub kdf_iter: Option<u32>,
+    #[serde(default)]
+    pub page_size: Option<u32>,
+    /// SQLCipher major version (1 to 4) whose settings the file was written with
+    #[serde(default)]
+    pub compatibility: Option<u8>,
+}
+
+/// Optional settings of `connect_sqlite`, applied to every pooled connection.
+/// Omitted pragmas keep the driver's defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteOptions {
+    #[serde(default)]
+    pub attach: Vec<SqliteAttachment>,
+    /// `delete`, `truncate`, `persist`, `memory`, `wal` or `off`
+    #[serde(default)]
+    pub journal_mode: Option<String>,
+    #[serde(default)]
+    pub foreign_keys: Option<bool>,
+    #[serde(default)]
+    pub busy_timeout_ms: Option<u64>,
+    /// Pages when positive, KiB when negative, as in `PRAGMA cache_size`
+    #[serde(default)]
+    pub cache_size: Option<i64>,
+    #[serde(default)]
+    pub extensions: Vec<SqliteExtension>,
+    /// Opens a SQLCipher-encrypted database
+    #[serde(default)]
+    pub cipher: Option<SqliteCipher>,
+}
+
+// ----------------------------------------------------------------------------
+// Row edits
+// ----------------------------------------------------------------------------
+
+/// An edited grid row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowUpdate {
+    /// Values as read: the key columns, plus any other columns to check for
+    /// concurrent changes (usually the ones being edited)
+    pub original: serde_json::Map<String, serde_json::Value>,
+    /// New values by column
+    pub values: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Pending grid edits of one table, applied by `apply_row_changes`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowChanges {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    #[serde(default)]
+    pub updates: Vec<RowUpdate>,
+    /// New rows by column; omitted columns take their defaults
+    #[serde(default)]
+    pub inserts: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// Original values of deleted rows, as for `RowUpdate::original`
+    #[serde(default)]
+    pub deletes: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// Edit a table without a primary or unique key, matching rows on every
+    /// original value given; a change that matches several rows is refused
+    #[serde(default)]
+    pub allow_keyless: bool,
+}
+
+/// Result of `apply_row_changes`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowChangeSummary {
+    pub updated: u64,
+    pub inserted: u64,
+    pub deleted: u64,
+    /// Columns rows were identified by; empty for a keyless table
+    pub key_columns: Vec<String>,
+    pub execution_time_ms: u128,
+}
+
+// ----------------------------------------------------------------------------
+// Table browsing
+// ----------------------------------------------------------------------------
+
+/// Comparison of a filter condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    /// SQL LIKE with the value as the pattern
+    Like,
+    NotLike,
+    /// Case-insensitive substring match; `%` and `_` in the value match themselves
+    Contains,
+    StartsWith,
+    EndsWith,
+    /// The value is an array
+    In,
+    NotIn,
+    /// The value is a `[low, high]` array; both ends are included
+    Between,
+    IsNull,
+    IsNotNull,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterJoin {
+    And,
+    Or,
+}
+
+/// A node of a filter tree: a condition on one column, or a group of nodes
+/// joined with AND or OR
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FilterNode {
+    Group {
+        join: FilterJoin,
+        children: Vec<FilterNode>,
+    },
+    Condition {
+        column: String,
+        operator: FilterOperator,
+        #[serde(default)]
+        value: serde_json::Value,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SortKey {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// How `browse_table` finds the page. NULLs sort after every value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Paging {
+    /// Skip `offset` rows
+    Offset {
+        #[serde(default)]
+        offset: u64,
+    },
+    /// Continue after the row whose ordering columns had the values in
+    /// `after` (the `next_cursor` of the previous page); the first page has none
+    Keyset {
+        #[serde(default)]
+        after: Option<serde_json::Map<String, serde_json::Value>>,
+    },
+}
+
+impl Default for Paging {
+    fn default() -> Self {
+        Paging::Offset { offset: 0 }
+    }
+}
+
+/// Whether `browse_table` counts the matching rows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    None,
+    #[default]
+    Exact,
+    /// The planner's estimate where the engine has one (Postgres, and MySQL
+    /// without a filter); an exact count otherwise
+    Estimate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowseRequest {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    #[serde(default)]
+    pub filter: Option<FilterNode>,
+    /// Ordering; the table's key is appended so that pages are stable
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+    #[serde(default)]
+    pub paging: Paging,
+    #[serde(default)]
+    pub count: CountMode,
+}
+
+fn default_page_size() -> u64 {
+    100
+}
+
+/// A page of `browse_table`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowseResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Rows matching the filter, unless counting was turned off
+    pub total_rows: Option<u64>,
+    pub total_is_estimate: bool,
+    pub has_more: bool,
+    /// The ordering actually used, including the appended key columns
+    pub order_by: Vec<SortKey>,
+    /// `after` for the next page when paging by keyset
+    pub next_cursor: Option<serde_json::Map<String, serde_json::Value>>,
+    pub execution_time_ms: u128,
+}
+
+// ----------------------------------------------------------------------------
+// Foreign-key navigation
+// ----------------------------------------------------------------------------
+
+/// A row and one of its foreign key columns, for `follow_reference`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceRequest {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    pub column: String,
+    /// The row's values by column; must include every column of the foreign key
+    pub row: serde_json::Map<String, serde_json::Value>,
+    /// Foreign key to follow when the column is part of several
+    #[serde(default)]
+    pub constraint: Option<String>,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+}
+
+/// The rows a foreign key points to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferencedRows {
+    pub constraint: String,
+    pub schema: Option<String>,
+    pub table: String,
+    /// Filter selecting the rows, for opening them in a table view
+    pub filter: FilterNode,
+    pub page: BrowseResult,
+}
+
+/// Rows of one table that reference a row through one foreign key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferencingTable {
+    pub constraint: String,
+    pub schema: Option<String>,
+    pub table: String,
+    /// Referencing columns
+    pub columns: Vec<String>,
+    /// Filter selecting the rows; None when the row's key has a NULL and so
+    /// cannot be referenced
+    pub filter: Option<FilterNode>,
+    pub row_count: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Table transfer
+// ----------------------------------------------------------------------------
+
+/// What `transfer_table` does with the rows already in the target table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferMode {
+    /// Add the rows to the existing ones
+    #[default]
+    Append,
+    /// Delete the existing rows first, keeping the table
+    Truncate,
+    /// Drop the target table and create it again from the source columns
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRequest {
+    #[serde(default)]
+    pub source_schema: Option<String>,
+    pub source_table: String,
+    #[serde(default)]
+    pub target_schema: Option<String>,
+    /// Defaults to the source table's name
+    #[serde(default)]
+    pub target_table: Option<String>,
+    /// Source columns to copy, all when empty; they go to target columns of the same name
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Only copy the source rows matching this filter
+    #[serde(default)]
+    pub filter: Option<FilterNode>,
+    #[serde(default)]
+    pub mode: TransferMode,
+    /// Create the target table from the source columns when it does not exist
+    #[serde(default)]
+    pub create_table: bool,
+    /// Rows per batch, as for `import_file`
+    #[serde(default = "default_import_batch_size")]
+    pub batch_size: usize,
+}
+
+/// A copied column with its type on both sides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferColumn {
+    pub name: String,
+    pub source_type: String,
+    pub target_type: String,
+}
+
+/// Result of `transfer_table`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferSummary {
+    pub rows_read: u64,
+    pub rows_written: u64,
+    pub rows_rejected: u64,
+    /// The first rejected rows; `line` is the row's position in the source
+    pub rejected: Vec<RejectedRow>,
+    pub columns: Vec<TransferColumn>,
+    pub table_created: bool,
+    pub method: LoadMethod,
+    /// Batches written before the cancellation are kept
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `transfer-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferProgress {
+    pub task_id: String,
+    pub rows_read: u64,
+    pub rows_written: u64,
+    pub rows_rejected: u64,
+    /// Source rows matching the filter
+    pub total_rows: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Schema diff
+// ----------------------------------------------------------------------------
+
+/// How an object of the source schema compares to the target schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffChange {
+    /// Only in the source
+    Added,
+    /// Only in the target
+    Removed,
+    /// In both, defined differently
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDiff {
+    pub name: String,
+    pub change: DiffChange,
+    pub source: Option<ColumnInfo>,
+    pub target: Option<ColumnInfo>,
+    /// What differs for a changed column: `type`, `nullable`, `default`,
+    /// `autoIncrement`, `identity`, `generated`, `comment` or `definition`
+    pub differences: Vec<String>,
+}
+
+/// An index, constraint, view or routine that differs, with the SQL defining it on each side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDiff {
+    pub name: String,
+    /// e.g. `INDEX`, `PRIMARY KEY`, `FOREIGN KEY`, `VIEW` or `FUNCTION`
+    pub kind: String,
+    pub change: DiffChange,
+    pub source_definition: Option<String>,
+    pub target_definition: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDiff {
+    pub name: String,
+    pub change: DiffChange,
+    /// Details of a changed table; empty for added and removed ones
+    pub columns: Vec<ColumnDiff>,
+    pub indexes: Vec<ObjectDiff>,
+    pub constraints: Vec<ObjectDiff>,
+}
+
+/// One statement of a migration script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatement {
+    pub sql: String,
+    /// The statement drops or rewrites data that the target holds
+    pub destructive: bool,
+    /// What a destructive statement loses
+    pub note: Option<String>,
+}
+
+/// Result of `diff_schemas`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub source_schema: Option<String>,
+    pub target_schema: Option<String>,
+    pub tables: Vec<TableDiff>,
+    pub views: Vec<ObjectDiff>,
+    pub routines: Vec<ObjectDiff>,
+    /// Statements turning the target schema into the source schema, in order
+    pub statements: Vec<MigrationStatement>,
+    /// The statements as one script, with destructive ones flagged by a comment
+    pub script: String,
+    pub has_destructive_changes: bool,
+    pub warnings: Vec<String>,
+}
+
+// ----------------------------------------------------------------------------
+// Data diff
+// ----------------------------------------------------------------------------
+
+fn default_diff_chunk_size() -> usize {
+    10000
+}
+
+fn default_diff_max_rows() -> usize {
+    1000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDiffRequest {
+    #[serde(default)]
+    pub left_schema: Option<String>,
+    pub left_table: String,
+    #[serde(default)]
+    pub right_schema: Option<String>,
+    /// Defaults to the left table's name
+    #[serde(default)]
+    pub right_table: Option<String>,
+    /// Columns rows are matched by; defaults to the left table's primary key.
+    /// Rows with NULL in a key column are not compared.
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+    /// Columns compared besides the key; defaults to every column of both tables
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Rows of the left table per compared chunk
+    #[serde(default = "default_diff_chunk_size")]
+    pub chunk_size: usize,
+    /// Rows listed (and scripted) per kind of difference; the rest are only counted
+    #[serde(default = "default_diff_max_rows")]
+    pub max_rows: usize,
+}
+
+/// A column whose value differs between the two sides of a row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueDiff {
+    pub column: String,
+    pub left: serde_json::Value,
+    pub right: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedRow {
+    pub key: serde_json::Map<String, serde_json::Value>,
+    pub differences: Vec<ValueDiff>,
+}
+
+/// Result of `diff_table_data`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataDiff {
+    pub key_columns: Vec<String>,
+    /// Compared columns besides the key
+    pub columns: Vec<String>,
+    pub left_rows: u64,
+    pub right_rows: u64,
+    pub matching_rows: u64,
+    pub left_only_count: u64,
+    pub right_only_count: u64,
+    pub changed_count: u64,
+    /// Rows only in the left table, key and compared columns
+    pub left_only: Vec<serde_json::Map<String, serde_json::Value>>,
+    pub right_only: Vec<serde_json::Map<String, serde_json::Value>>,
+    pub changed: Vec<ChangedRow>,
+    /// More differences than `max_rows` were found; the lists and the script are partial
+    pub truncated: bool,
+    pub chunks: u64,
+    /// Chunks found equal by their checksums, without reading their rows
+    pub chunks_skipped: u64,
+    /// DELETE / UPDATE / INSERT statements making the right table match the left one
+    pub script: String,
+    pub warnings: Vec<String>,
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `data-diff-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataDiffProgress {
+    pub task_id: String,
+    pub chunks_done: u64,
+    pub chunks_total: u64,
+    pub rows_compared: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Test data generation
+// ----------------------------------------------------------------------------
+
+fn default_null_ratio() -> f64 {
+    0.1
+}
+
+/// Overrides of how one column is filled
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnGenerator {
+    /// Leave the column to its default
+    #[serde(default)]
+    pub skip: bool,
+    /// Pick from these values
+    #[serde(default)]
+    pub values: Vec<serde_json::Value>,
+    /// Range of numbers, or of dates and timestamps as ISO text
+    #[serde(default)]
+    pub min: Option<serde_json::Value>,
+    #[serde(default)]
+    pub max: Option<serde_json::Value>,
+    /// Share of NULLs in a nullable column, in place of the request's
+    #[serde(default)]
+    pub null_ratio: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateDataRequest {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    pub row_count: u64,
+    /// The same seed generates the same rows; a random one is used (and returned) when None
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Share of NULLs in nullable columns
+    #[serde(default = "default_null_ratio")]
+    pub null_ratio: f64,
+    /// Per-column overrides, by column name
+    #[serde(default)]
+    pub columns: HashMap<String, ColumnGenerator>,
+    /// Rows per batch, as for `import_file`
+    #[serde(default = "default_import_batch_size")]
+    pub batch_size: usize,
+}
+
+/// A filled column and what it was filled with, e.g. `email` or `foreign key orders.id`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedColumn {
+    pub name: String,
+    pub generator: String,
+}
+
+/// Result of `generate_test_data`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateDataSummary {
+    pub rows_inserted: u64,
+    pub rows_rejected: u64,
+    /// The first rows the database refused; `line` is the generated row's number
+    pub rejected: Vec<RejectedRow>,
+    pub seed: u64,
+    pub columns: Vec<GeneratedColumn>,
+    /// Auto-increment, identity, generated and skipped columns, left to the database
+    pub skipped_columns: Vec<String>,
+    pub method: LoadMethod,
+    /// Batches inserted before the cancellation are kept
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `generate-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateProgress {
+    pub task_id: String,
+    pub rows_inserted: u64,
+    pub rows_rejected: u64,
+    pub total_rows: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Database search
+// ----------------------------------------------------------------------------
+
+fn default_search_limit() -> usize {
+    100
+}
+
+fn default_search_concurrency() -> usize {
+    4
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Case-insensitive substring match; `%` and `_` in the pattern match themselves
+    #[default]
+    Contains,
+    /// The whole value equals the pattern
+    Exact,
+    /// SQL LIKE pattern
+    Like,
+    /// Case-insensitive LIKE pattern
+    ILike,
+    /// Regular expression, in the engine's syntax (Rust's `regex` on SQLite)
+    Regex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchRequest {
+    pub pattern: String,
+    #[serde(default)]
+    pub mode: SearchMode,
+    #[serde(default)]
+    pub schema: Option<String>,
+    /// Tables to search; every table of the schema (but not views) when empty
+    #[serde(default)]
+    pub tables: Vec<String>,
+    /// Leave numeric columns out
+    #[serde(default)]
+    pub text_only: bool,
+    /// Matching rows listed per table
+    #[serde(default = "default_search_limit")]
+    pub limit_per_table: usize,
+    /// Tables searched at the same time
+    #[serde(default = "default_search_concurrency")]
+    pub concurrency: usize,
+}
+
+/// A column of a row whose value matched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub column: String,
+    /// Row key values; empty for tables without a primary or unique key
+    pub key: serde_json::Map<String, serde_json::Value>,
+    /// The value, shortened around the match
+    pub snippet: String,
+}
+
+/// Payload of the `search-results` event, sent for each table with matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub task_id: String,
+    pub table: String,
+    pub matches: Vec<SearchMatch>,
+    /// More rows matched than `limit_per_table`
+    pub truncated: bool,
+}
+
+/// A table that could not be searched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchFailure {
+    pub table: String,
+    pub message: String,
+}
+
+/// Result of `search_database`; the matches themselves arrive as events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSummary {
+    pub tables_searched: u64,
+    pub columns_searched: u64,
+    pub matching_rows: u64,
+    pub matches: u64,
+    /// Tables with more matching rows than were listed
+    pub truncated_tables: Vec<String>,
+    pub failed_tables: Vec<SearchFailure>,
+    pub cancelled: bool,
+    pub execution_time_ms: u128,
+}
+
+/// Payload of the `search-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchProgress {
+    pub task_id: String,
+    pub tables_searched: u64,
+    pub total_tables: u64,
+    pub matches: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Redis key browser
+// ----------------------------------------------------------------------------
+
+fn default_scan_cursor() -> String {
+    "0".to_string()
+}
+
+fn default_scan_count() -> u32 {
+    1000
+}
+
+fn default_scan_page_size() -> usize {
+    500
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedisScanRequest {
+    /// Cursor returned by the previous page; "0" starts a new scan
+    #[serde(default = "default_scan_cursor")]
+    pub cursor: String,
+    /// Glob-style pattern (SCAN's MATCH); defaults to every key, or every key under `prefix`
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Only keys of this type: string, hash, list, set, zset or stream (Redis 6+)
+    #[serde(default)]
+    pub key_type: Option<String>,
+    /// SCAN's COUNT hint: keys looked at per round trip
+    #[serde(default = "default_scan_count")]
+    pub count: u32,
+    /// Rounds of SCAN stop once this many keys were found
+    #[serde(default = "default_scan_page_size")]
+    pub page_size: usize,
+    /// Group keys into namespaces split by this separator, e.g. ":"
+    #[serde(default)]
+    pub separator: Option<String>,
+    /// Namespace being browsed, ending with the separator, e.g. "user:"
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisKeyInfo {
+    pub key: String,
+    /// string, hash, list, set, zset or stream
+    pub key_type: String,
+    /// None for keys without an expiry
+    pub ttl_ms: Option<i64>,
+    /// MEMORY USAGE; None where the server does not allow it
+    pub memory_bytes: Option<u64>,
+}
+
+/// Keys sharing the next segment below the browsed prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisNamespace {
+    pub name: String,
+    /// The namespace's prefix, separator included, to browse it with
+    pub prefix: String,
+    /// Keys of this page in the namespace, at any depth
+    pub key_count: u64,
+}
+
+/// Result of `redis_scan_keys`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisScanResult {
+    /// Cursor of the next page; "0" once the scan went through the keyspace
+    pub cursor: String,
+    pub complete: bool,
+    /// The keys found, or with a separator only those directly under the prefix
+    pub keys: Vec<RedisKeyInfo>,
+    pub namespaces: Vec<RedisNamespace>,
+    /// Keys in the database (DBSIZE)
+    pub total_keys: u64,
+    pub execution_time_ms: u128,
+}
+
+// ----------------------------------------------------------------------------
+// Redis values
+// ----------------------------------------------------------------------------
+
+fn default_value_page_size() -> usize {
+    200
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedisValueRequest {
+    pub key: String,
+    /// Where the page starts: the `cursor` of the previous page, none for the first
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Glob-style pattern for hash fields and set members (HSCAN / SSCAN MATCH)
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Fields, items, members or entries per page
+    #[serde(default = "default_value_page_size")]
+    pub count: usize,
+    /// Sorted set score bounds, such as "1.5", "(1.5" (exclusive) or "-inf"
+    #[serde(default)]
+    pub min_score: Option<String>,
+    #[serde(default)]
+    pub max_score: Option<String>,
+    /// Highest scores / newest stream entries first
+    #[serde(default)]
+    pub reverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueFormat {
+    Text,
+    Json,
+    /// Not UTF-8 text; the value is given in hex
+    Binary,
+}
+
+/// A field of a hash or of a stream entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisField {
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisScoredMember {
+    pub member: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisStreamEntry {
+    pub id: String,
+    pub fields: Vec<RedisField>,
+}
+
+/// A page of a value, by type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RedisContent {
+    String {
+        value: String,
+        format: ValueFormat,
+        /// Only the beginning of a large value was read
+        truncated: bool,
+    },
+    Hash {
+        fields: Vec<RedisField>,
+    },
+    List {
+        /// Index of the first item
+        start: u64,
+        items: Vec<String>,
+    },
+    Set {
+        members: Vec<String>,
+    },
+    ZSet {
+        members: Vec<RedisScoredMember>,
+    },
+    Stream {
+        entries: Vec<RedisStreamEntry>,
+    },
+}
+
+/// Result of `redis_get_value`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisValue {
+    pub key: String,
+    pub key_type: String,
+    pub ttl_ms: Option<i64>,
+    /// Bytes of a string, or fields / items / members / entries of the others
+    pub length: u64,
+    pub content: RedisContent,
+    /// Cursor of the next page; None on the last one
+    pub cursor: Option<String>,
+}
+
+/// A change to one field, item, member or entry of a value. Operations that
+/// add to a missing key create it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RedisEdit {
+    #[serde(rename_all = "camelCase")]
+    SetString {
+        value: String,
+        /// Keep the key's expiry, which SET otherwise clears
+        #[serde(default)]
+        keep_ttl: bool,
+    },
+    /// Add or update a field; with `previous_field`, rename that one
+    #[serde(rename_all = "camelCase")]
+    SetHashField {
+        field: String,
+        value: String,
+        #[serde(default)]
+        previous_field: Option<String>,
+        /// Fail instead of overwriting an existing field
+        #[serde(default)]
+        only_new: bool,
+    },
+    RemoveHashFields {
+        fields: Vec<String>,
+    },
+    /// Append items, or prepend them with `head`
+    PushListItems {
+        items: Vec<String>,
+        #[serde(default)]
+        head: bool,
+    },
+    /// Replace the item at `index`, provided it still is `current`
+    SetListItem {
+        index: i64,
+        current: String,
+        value: String,
+    },
+    /// Remove the item at `index`, provided it still is `current`
+    RemoveListItem {
+        index: i64,
+        current: String,
+    },
+    /// Add a member, or replace `previous` with it
+    SetSetMember {
+        member: String,
+        #[serde(default)]
+        previous: Option<String>,
+    },
+    RemoveSetMembers {
+        members: Vec<String>,
+    },
+    /// Add a member or change its score; with `previous`, replace that member
+    SetZSetMember {
+        member: String,
+        score: f64,
+        #[serde(default)]
+        previous: Option<String>,
+    },
+    RemoveZSetMembers {
+        members: Vec<String>,
+    },
+    /// Add an entry, with an id chosen by the server unless one is given
+    AddStreamEntry {
+        #[serde(default)]
+        id: Option<String>,
+        fields: Vec<RedisField>,
+    },
+    RemoveStreamEntries {
+        ids: Vec<String>,
+    },
+}
+
+/// Result of `redis_edit_value`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisEditResult {
+    /// Fields, items, members or entries added, changed or removed
+    pub changed: u64,
+    /// Id of an added stream entry
+    pub entry_id: Option<String>,
+}
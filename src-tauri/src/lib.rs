@@ -10,7 +10,53 @@ pub fn run() {
         db::connect_sqlite,
         db::connect_redis,
         db::execute_redis_command,
-        db::execute_query
+        db::execute_query,
+        db::list_schemas,
+        db::list_tables,
+        db::list_columns,
+        db::list_indexes,
+        db::list_constraints,
+        db::list_foreign_keys,
+        db::list_views,
+        db::list_routines,
+        db::list_triggers,
+        db::list_sequences,
+        db::list_enums,
+        db::list_domains,
+        db::list_extensions,
+        db::generate_ddl,
+        db::plan_table_changes,
+        db::get_schema_snapshot,
+        db::invalidate_schema_cache,
+        db::export_query,
+        db::render_table,
+        db::preview_import,
+        db::import_file,
+        db::execute_sql_file,
+        db::dump_database,
+        db::restore_dump,
+        db::backup_sqlite,
+        db::sqlite_integrity_check,
+        db::sqlite_foreign_key_check,
+        db::sqlite_storage_usage,
+        db::optimize_sqlite,
+        db::rekey_sqlite,
+        db::apply_row_changes,
+        db::browse_table,
+        db::follow_reference,
+        db::referencing_rows,
+        db::transfer_table,
+        db::diff_schemas,
+        db::diff_table_data,
+        db::generate_test_data,
+        db::search_database,
+        db::redis_scan_keys,
+        db::redis_get_value,
+        db::redis_edit_value,
+        db::redis_set_ttl,
+        db::redis_rename_key,
+        db::redis_delete_keys,
+        db::cancel_task
     ])
     .setup(|app| {
       if cfg!(debug_assertions) {